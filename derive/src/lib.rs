@@ -0,0 +1,225 @@
+//! # Derive Macro for `core_memo`
+//!
+//! This crate provides `#[derive(Memoize)]` and `#[memo]`. Use them through
+//! `core_memo`, with the `derive` feature, which re-exports them next to the
+//! `Memoize` trait.
+
+extern crate proc_macro;
+extern crate proc_macro2;
+#[macro_use]
+extern crate quote;
+extern crate syn;
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use syn::spanned::Spanned;
+use syn::{Data, DeriveInput, Error, Expr, Fields, FnArg, Ident, ItemFn, ReturnType, Type};
+
+/// Derive `Memoize` for a struct which is computed field by field
+///
+/// The parameter type is given with `#[memoize(param = Type)]` on the
+/// struct, and every field is given the expression which computes it with
+/// `#[memoize(expression)]`. The expressions can use the parameter, as `p`.
+///
+/// See the `Memoize` trait in `core_memo` for an example.
+#[proc_macro_derive(Memoize, attributes(memoize))]
+pub fn derive_memoize(input: TokenStream) -> TokenStream {
+    let input = syn::parse_macro_input!(input as DeriveInput);
+    expand(&input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+/// Memoize a function
+///
+/// On a function taking the parameter by reference, like
+/// `fn summarize(config: &Config) -> Summary`, this generates a `Memoize`
+/// newtype around the return type, named after the function in CamelCase
+/// (`Summarize`), and a `Memo` for it, with `Memo` appended (`SummarizeMemo`).
+/// The function itself is left as it is. The return value is the public field
+/// of the newtype.
+///
+/// The name of the newtype can be chosen with `#[memo(name = Name)]`.
+///
+/// See the `Memoize` trait in `core_memo` for an example.
+#[proc_macro_attribute]
+pub fn memo(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let mut name = None;
+    let parser = syn::meta::parser(|meta| {
+        if meta.path.is_ident("name") {
+            name = Some(meta.value()?.parse()?);
+            Ok(())
+        } else {
+            Err(meta.error("expected `name = Name`"))
+        }
+    });
+    syn::parse_macro_input!(attr with parser);
+    let item = syn::parse_macro_input!(item as ItemFn);
+
+    expand_memo(name, &item)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+fn expand(input: &DeriveInput) -> Result<TokenStream2, Error> {
+    let param = param_type(input)?;
+
+    let fields = match input.data {
+        Data::Struct(ref data) => &data.fields,
+        _ => {
+            return Err(Error::new(
+                input.ident.span(),
+                "#[derive(Memoize)] is only supported for structs",
+            ))
+        }
+    };
+
+    let exprs = fields
+        .iter()
+        .map(field_expr)
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let value = match *fields {
+        Fields::Named(_) => {
+            let names = fields.iter().map(|field| &field.ident);
+            quote!(Self { #(#names: #exprs,)* })
+        }
+        Fields::Unnamed(_) => quote!(Self(#(#exprs,)*)),
+        Fields::Unit => quote!(Self),
+    };
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics ::core_memo::Memoize for #name #ty_generics #where_clause {
+            type Param = #param;
+
+            #[allow(unused_variables)]
+            fn memoize(p: &#param) -> Self {
+                #value
+            }
+        }
+    })
+}
+
+/// Get the type in `#[memoize(param = Type)]` on the struct
+fn param_type(input: &DeriveInput) -> Result<Type, Error> {
+    let mut param = None;
+    for attr in input
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("memoize"))
+    {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("param") {
+                param = Some(meta.value()?.parse()?);
+                Ok(())
+            } else {
+                Err(meta.error("expected `param = Type`"))
+            }
+        })?;
+    }
+    param.ok_or_else(|| {
+        Error::new(
+            input.ident.span(),
+            "#[derive(Memoize)] needs the parameter type, as #[memoize(param = Type)]",
+        )
+    })
+}
+
+/// Get the expression in `#[memoize(expression)]` on a field
+fn field_expr(field: &syn::Field) -> Result<Expr, Error> {
+    let mut attrs = field
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("memoize"));
+    let attr = attrs.next().ok_or_else(|| {
+        Error::new(
+            field.span(),
+            "#[derive(Memoize)] needs the expression which computes this field, \
+             as #[memoize(expression)]",
+        )
+    })?;
+    if let Some(extra) = attrs.next() {
+        return Err(Error::new(extra.span(), "duplicate #[memoize] attribute"));
+    }
+    attr.parse_args()
+}
+
+fn expand_memo(name: Option<Ident>, item: &ItemFn) -> Result<TokenStream2, Error> {
+    let sig = &item.sig;
+    if !sig.generics.params.is_empty() || sig.generics.where_clause.is_some() {
+        return Err(Error::new(
+            sig.generics.span(),
+            "#[memo] does not support generic functions",
+        ));
+    }
+    if let Some(ref asyncness) = sig.asyncness {
+        return Err(Error::new(
+            asyncness.span(),
+            "#[memo] does not support async functions",
+        ));
+    }
+
+    let param = match (sig.inputs.len(), sig.inputs.first()) {
+        (1, Some(FnArg::Typed(arg))) => match *arg.ty {
+            Type::Reference(ref r) if r.mutability.is_none() => &r.elem,
+            _ => {
+                return Err(Error::new(
+                    arg.ty.span(),
+                    "#[memo] needs the parameter to be taken by shared reference",
+                ))
+            }
+        },
+        _ => {
+            return Err(Error::new(
+                sig.inputs.span(),
+                "#[memo] needs a function with exactly one argument, the parameter",
+            ))
+        }
+    };
+    let output = match sig.output {
+        ReturnType::Default => quote!(()),
+        ReturnType::Type(_, ref ty) => quote!(#ty),
+    };
+
+    let vis = &item.vis;
+    let func = &sig.ident;
+    let name = name.unwrap_or_else(|| Ident::new(&camel_case(&func.to_string()), func.span()));
+    let alias = Ident::new(&format!("{}Memo", name), name.span());
+    let doc_name = format!("The memoized result of `{}()`", func);
+    let doc_alias = format!("A `Memo` of `{}()`", func);
+
+    Ok(quote! {
+        #item
+
+        #[doc = #doc_name]
+        #vis struct #name(#vis #output);
+
+        impl ::core_memo::Memoize for #name {
+            type Param = #param;
+
+            fn memoize(p: &#param) -> Self {
+                #name(#func(p))
+            }
+        }
+
+        #[doc = #doc_alias]
+        #vis type #alias<P = #param> = ::core_memo::Memo<#name, P>;
+    })
+}
+
+/// Convert a function name, in snake_case, to CamelCase
+fn camel_case(name: &str) -> String {
+    name.split('_')
+        .flat_map(|word| {
+            let mut chars = word.chars();
+            chars
+                .next()
+                .into_iter()
+                .flat_map(char::to_uppercase)
+                .chain(chars)
+        })
+        .collect()
+}
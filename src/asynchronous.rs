@@ -0,0 +1,256 @@
+#[cfg(feature = "alloc")]
+use alloc::boxed::Box;
+use core::borrow::Borrow;
+use core::future::{self, Future, IntoFuture, Ready};
+use core::pin::Pin;
+use core::task::{Context, Poll};
+
+use {Invalidate, Memo, Memoize};
+
+/// Represents an asynchronous computation that is to be memoized
+///
+/// This is like `Memoize`, but the computation returns a future, for things
+/// like network or database calls. Implement it with an `async fn`, and
+/// memoize it with `MemoAsync`.
+///
+/// ## Example
+///
+/// ```edition2018
+/// use core_memo::{MemoizeAsync, MemoAsync};
+/// # fn block_on<F: std::future::Future>(f: F) -> F::Output {
+/// #     let mut f = std::pin::pin!(f);
+/// #     let mut cx = std::task::Context::from_waker(std::task::Waker::noop());
+/// #     loop {
+/// #         if let std::task::Poll::Ready(v) = f.as_mut().poll(&mut cx) {
+/// #             return v;
+/// #         }
+/// #     }
+/// # }
+///
+/// struct User {
+///     name: String,
+/// }
+///
+/// # async fn fetch_name(id: u64) -> String {
+/// #     format!("user{}", id)
+/// # }
+/// impl MemoizeAsync for User {
+///     type Param = u64;
+///
+///     async fn memoize_async(id: &u64) -> Self {
+///         User { name: fetch_name(*id).await }
+///     }
+/// }
+///
+/// block_on(async {
+///     let mut user: MemoAsync<User> = MemoAsync::new(42);
+///
+///     // fetched once...
+///     assert_eq!(user.get().await.name, "user42");
+///
+///     // ...and then returned immediately
+///     assert!(user.is_ready());
+///     assert_eq!(user.get().await.name, "user42");
+/// });
+/// ```
+///
+pub trait MemoizeAsync: Sized {
+    /// The input type for the computation
+    type Param: ?Sized;
+
+    /// The function to compute the value
+    fn memoize_async(p: &Self::Param) -> impl Future<Output = Self>;
+}
+
+/// Memoized value of an asynchronous computation
+///
+/// Like `Memo`, this holds ownership over the parameter for the computation,
+/// but the computation is a `MemoizeAsync`, and `get()` returns a future.
+/// Awaiting it computes the value the first time, and returns the cached
+/// value immediately afterwards.
+///
+/// If the future is dropped before it completes, nothing is cached, and the
+/// next call to `get()` starts the computation over.
+///
+/// See `MemoizeAsync` for an example.
+#[derive(Debug)]
+pub struct MemoAsync<T, P = <T as MemoizeAsync>::Param> {
+    value: Option<T>,
+    param: P,
+}
+
+impl<T, P> MemoAsync<T, P> {
+    /// Creates a new `MemoAsync` instance
+    pub fn new(p: P) -> Self {
+        Self {
+            value: None,
+            param: p,
+        }
+    }
+
+    /// Clears any cached value
+    ///
+    /// The value will be reevaluated the next time it is needed.
+    pub fn clear(&mut self) {
+        self.value = None;
+    }
+
+    /// Check if there is a cached value
+    ///
+    /// If this method returns `true`, the future returned by the next call to
+    /// `get()` will complete immediately.
+    pub fn is_ready(&self) -> bool {
+        self.value.is_some()
+    }
+
+    /// Get the value if it is available
+    ///
+    /// If there is a cached value, returns it. If the value needs to be
+    /// computed, returns `None`.
+    pub fn try_get(&self) -> Option<&T> {
+        self.value.as_ref()
+    }
+
+    /// Get a reference to the parameter used for the computation
+    pub fn param(&self) -> &P {
+        &self.param
+    }
+
+    /// Get a mutable reference to the parameter used for the computation
+    ///
+    /// This clears any cached value.
+    pub fn param_mut(&mut self) -> &mut P {
+        self.clear();
+        &mut self.param
+    }
+
+    /// Get the parameter back, dropping the memo
+    pub fn into_param(self) -> P {
+        self.param
+    }
+}
+
+impl<T: MemoizeAsync, P: Borrow<T::Param>> MemoAsync<T, P> {
+    /// Get the value
+    ///
+    /// If the value has already been computed, the returned future completes
+    /// immediately with the cached value. If not, it runs the computation,
+    /// and caches its result.
+    pub fn get(&mut self) -> impl Future<Output = &T> {
+        let MemoAsync {
+            ref mut value,
+            ref param,
+        } = *self;
+        let future = match *value {
+            Some(_) => None,
+            None => Some(T::memoize_async(param.borrow())),
+        };
+        Get {
+            value: Some(value),
+            future,
+        }
+    }
+}
+
+/// Await the value of a `MemoAsync`
+///
+/// Requires the `alloc` feature.
+///
+/// Same as awaiting `get()`, so a memo can be passed around as a value which
+/// will exist, and awaited where it is needed. The future is boxed, because
+/// the type of the future of the computation cannot be named.
+///
+/// ## Example
+///
+/// ```edition2018
+/// use core_memo::{MemoizeAsync, MemoAsync};
+/// # fn block_on<F: std::future::Future>(f: F) -> F::Output {
+/// #     let mut f = std::pin::pin!(f);
+/// #     let mut cx = std::task::Context::from_waker(std::task::Waker::noop());
+/// #     loop {
+/// #         if let std::task::Poll::Ready(v) = f.as_mut().poll(&mut cx) {
+/// #             return v;
+/// #         }
+/// #     }
+/// # }
+///
+/// struct Greeting(String);
+///
+/// impl MemoizeAsync for Greeting {
+///     type Param = str;
+///     async fn memoize_async(name: &str) -> Self {
+///         Greeting(format!("hello, {}", name))
+///     }
+/// }
+///
+/// async fn greet(greeting: &mut MemoAsync<Greeting, String>) -> usize {
+///     greeting.await.0.len()
+/// }
+///
+/// let mut greeting: MemoAsync<Greeting, String> = MemoAsync::new("world".into());
+/// assert_eq!(block_on(greet(&mut greeting)), 12);
+/// assert!(greeting.is_ready());
+/// ```
+///
+#[cfg(feature = "alloc")]
+impl<'a, T: MemoizeAsync, P: Borrow<T::Param>> IntoFuture for &'a mut MemoAsync<T, P> {
+    type Output = &'a T;
+    type IntoFuture = Pin<Box<dyn Future<Output = &'a T> + 'a>>;
+
+    fn into_future(self) -> Self::IntoFuture {
+        Box::pin(self.get())
+    }
+}
+
+/// Await the value of a `Memo`
+///
+/// This bridges a synchronous memo to async code, which can then await it like
+/// a `MemoAsync`. The value is computed (blocking the task, if it has not been
+/// cached yet) when it is awaited.
+impl<'a, T: Memoize, P: Borrow<T::Param>> IntoFuture for &'a mut Memo<T, P> {
+    type Output = &'a T;
+    type IntoFuture = Ready<&'a T>;
+
+    fn into_future(self) -> Ready<&'a T> {
+        future::ready(self.get())
+    }
+}
+
+impl<T, P> Invalidate for MemoAsync<T, P> {
+    fn invalidate(&mut self) {
+        self.clear()
+    }
+}
+
+/// The future returned by `MemoAsync::get()`
+struct Get<'a, T, F> {
+    /// The value slot of the memo, until the future completes
+    value: Option<&'a mut Option<T>>,
+    /// The computation, if the value is not cached
+    future: Option<F>,
+}
+
+impl<'a, T, F: Future<Output = T>> Future for Get<'a, T, F> {
+    type Output = &'a T;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<&'a T> {
+        // SAFETY: `future` is pinned along with `self`: it is never moved, and
+        // only dropped in place
+        let this = unsafe { self.get_unchecked_mut() };
+
+        if let Some(ref mut future) = this.future {
+            let future = unsafe { Pin::new_unchecked(future) };
+            let value = match future.poll(cx) {
+                Poll::Ready(value) => value,
+                Poll::Pending => return Poll::Pending,
+            };
+            this.future = None;
+            if let Some(ref mut slot) = this.value {
+                **slot = Some(value);
+            }
+        }
+
+        let slot = this.value.take().expect("polled after completion");
+        Poll::Ready(slot.as_ref().unwrap())
+    }
+}
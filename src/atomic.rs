@@ -0,0 +1,200 @@
+use core::borrow::Borrow;
+use core::fmt;
+use core::mem;
+
+use sync::{spin_loop, AtomicU8, Ordering, UnsafeCell};
+use {Invalidate, Memoize};
+
+/// There is no value, and nobody is computing it
+const EMPTY: u8 = 0;
+/// A thread is computing the value
+const RUNNING: u8 = 1;
+/// The value is cached
+const READY: u8 = 2;
+
+/// Memoized value which can be shared between threads, without locks
+///
+/// Requires the `atomic` feature.
+///
+/// Like `MemoShared`, `get()` only needs `&self`, and the value is computed
+/// once and then read by all the threads. But this does not need `std`: the
+/// state of the value is an atomic flag. The first thread which needs the
+/// value claims it and computes it, and the others spin until it is ready.
+/// Reading the cached value is a single atomic load.
+///
+/// This is for `no_std` programs with atomics, like a kernel or firmware with
+/// several cores. On targets without compare-and-swap, enable the
+/// `portable-atomic` feature. Spinning wastes time if the computation is
+/// slow, so with `std`, prefer `MemoShared`, which blocks the waiting threads
+/// instead.
+///
+/// If the computation panics, the value goes back to not being computed, and
+/// the next call to `get()` computes it again.
+///
+/// ## Example
+///
+#[cfg_attr(not(feature = "loom"), doc = "```")]
+#[cfg_attr(feature = "loom", doc = "```ignore")]
+/// use core_memo::{Memoize, MemoAtomic};
+///
+/// struct Crc(u32);
+///
+/// impl Memoize for Crc {
+///     type Param = [u8];
+///     fn memoize(data: &[u8]) -> Self {
+///         Crc(data.iter().fold(0, |crc, &b| crc.rotate_left(5) ^ b as u32))
+///     }
+/// }
+///
+/// static FIRMWARE: MemoAtomic<Crc, &[u8]> = MemoAtomic::new(b"\x7fELF...");
+///
+/// let crc = FIRMWARE.get().0;
+/// std::thread::spawn(move || assert_eq!(FIRMWARE.get().0, crc))
+///     .join()
+///     .unwrap();
+/// ```
+///
+pub struct MemoAtomic<T, P = <T as Memoize>::Param> {
+    state: AtomicU8,
+    value: UnsafeCell<Option<T>>,
+    param: P,
+}
+
+// SAFETY: the value is only written by the thread which claimed it (moving
+// `state` from `EMPTY` to `RUNNING`), and only read once `state` is `READY`,
+// after which it is never written through a shared reference. So it is
+// shared like a `&T`, and sent like a `T`.
+unsafe impl<T: Send + Sync, P: Sync> Sync for MemoAtomic<T, P> {}
+
+impl<T, P> MemoAtomic<T, P> {
+    /// Creates a new `MemoAtomic` instance
+    #[cfg(not(feature = "loom"))]
+    pub const fn new(p: P) -> Self {
+        Self {
+            state: AtomicU8::new(EMPTY),
+            value: UnsafeCell::new(None),
+            param: p,
+        }
+    }
+
+    /// Creates a new `MemoAtomic` instance
+    #[cfg(feature = "loom")]
+    pub fn new(p: P) -> Self {
+        Self {
+            state: AtomicU8::new(EMPTY),
+            value: UnsafeCell::new(None),
+            param: p,
+        }
+    }
+
+    /// Clears any cached value
+    ///
+    /// The value will be reevaluated the next time it is needed.
+    pub fn clear(&mut self) {
+        // SAFETY: `&mut self` means nobody else is reading or writing it
+        self.value.with_mut(|value| unsafe { *value = None });
+        self.state.store(EMPTY, Ordering::Relaxed);
+    }
+
+    /// Check if there is a cached value
+    ///
+    /// If this method returns `true`, the next call to `get()` will return the
+    /// stored memoized value.
+    pub fn is_ready(&self) -> bool {
+        self.state.load(Ordering::Acquire) == READY
+    }
+
+    /// Get the value if it is available
+    ///
+    /// If there is a cached value, returns it. If the value needs to be
+    /// computed, or another thread is computing it, returns `None`.
+    pub fn try_get(&self) -> Option<&T> {
+        if !self.is_ready() {
+            return None;
+        }
+        // SAFETY: the value is never written while it is `READY`, and the
+        // `Acquire` load synchronizes with the `Release` store which made it
+        // `READY`, after it was written
+        self.value.with(|value| unsafe { (*value).as_ref() })
+    }
+
+    /// Get a reference to the parameter used for the computation
+    pub fn param(&self) -> &P {
+        &self.param
+    }
+
+    /// Get a mutable reference to the parameter used for the computation
+    ///
+    /// This clears any cached value.
+    pub fn param_mut(&mut self) -> &mut P {
+        self.clear();
+        &mut self.param
+    }
+
+    /// Get the parameter back, dropping the memo
+    pub fn into_param(self) -> P {
+        self.param
+    }
+}
+
+impl<T: Memoize, P: Borrow<T::Param>> MemoAtomic<T, P> {
+    /// Get the value
+    ///
+    /// If the value has already been computed, this function returns the
+    /// cached value. If not, and no other thread is computing it, it is
+    /// computed and cached for future use. If another thread is computing it,
+    /// this spins until it is done.
+    pub fn get(&self) -> &T {
+        loop {
+            if let Some(value) = self.try_get() {
+                return value;
+            }
+
+            let claimed = self.state.compare_exchange_weak(
+                EMPTY,
+                RUNNING,
+                Ordering::Acquire,
+                Ordering::Acquire,
+            );
+            match claimed {
+                Ok(_) => self.compute(),
+                Err(_) => spin_loop(),
+            }
+        }
+    }
+
+    /// Compute the value, after claiming it
+    fn compute(&self) {
+        /// Gives up the claim if the computation panics
+        struct Unclaim<'a>(&'a AtomicU8);
+
+        impl<'a> Drop for Unclaim<'a> {
+            fn drop(&mut self) {
+                self.0.store(EMPTY, Ordering::Release);
+            }
+        }
+
+        let unclaim = Unclaim(&self.state);
+        let value = T::memoize(self.param.borrow());
+        // SAFETY: this thread claimed the value, so nobody else is reading or
+        // writing it until it is `READY`
+        self.value.with_mut(|slot| unsafe { *slot = Some(value) });
+        mem::forget(unclaim);
+        self.state.store(READY, Ordering::Release);
+    }
+}
+
+impl<T, P> Invalidate for MemoAtomic<T, P> {
+    fn invalidate(&mut self) {
+        self.clear()
+    }
+}
+
+impl<T: fmt::Debug, P: fmt::Debug> fmt::Debug for MemoAtomic<T, P> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("MemoAtomic")
+            .field("value", &self.try_get())
+            .field("param", &self.param)
+            .finish()
+    }
+}
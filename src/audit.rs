@@ -0,0 +1,72 @@
+use core::fmt;
+use core::hash::Hash;
+use core::mem;
+
+use hash::stable_hash;
+
+/// Checks that a `MemoExt` is always given the same parameter
+///
+/// Once enabled, this remembers a hash of the parameter the cached value was
+/// computed from, and panics if the value is then requested with a parameter
+/// which hashes differently. It is a field of the memo with the `paranoid`
+/// feature, and compiled out otherwise.
+#[derive(Clone, Copy, Default)]
+pub(crate) struct Audit {
+    /// The `fn(&Q) -> u64` which hashes the parameters, for the parameter type
+    /// `Q` of the memo, if auditing is enabled
+    hash: Option<fn()>,
+    /// The hash of the parameter of the cached value, if it is known
+    computed: Option<u64>,
+}
+
+impl Audit {
+    /// Start checking parameters of type `Q`
+    pub(crate) fn enable<Q: Hash + ?Sized>(&mut self) {
+        let hash: fn(&Q) -> u64 = stable_hash::<Q>;
+        // SAFETY: only transmuted back to the same type in `check()`, which
+        // the memo calls with its parameter type
+        self.hash = Some(unsafe { mem::transmute::<fn(&Q) -> u64, fn()>(hash) });
+        self.computed = None;
+    }
+
+    /// Check the parameter for a cached value, or remember it for a value
+    /// which is about to be computed
+    ///
+    /// `memo` is the name of the memo type, for the panic message.
+    pub(crate) fn check<Q: ?Sized>(&mut self, p: &Q, cached: bool, memo: &str) {
+        let hash = match self.hash {
+            // SAFETY: see `enable()`
+            Some(hash) => unsafe { mem::transmute::<fn(), fn(&Q) -> u64>(hash) },
+            None => return,
+        };
+        let hash = hash(p);
+
+        if !cached {
+            self.computed = Some(hash);
+            return;
+        }
+
+        if let Some(computed) = self.computed {
+            assert!(
+                computed == hash,
+                "core_memo: {} was given a different parameter than its cached \
+                 value was computed from (call `clear()` when the parameter changes)",
+                memo
+            );
+        }
+    }
+
+    /// Forget the parameter, for a value which was not computed by the memo
+    pub(crate) fn forget(&mut self) {
+        self.computed = None;
+    }
+}
+
+impl fmt::Debug for Audit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Audit")
+            .field("enabled", &self.hash.is_some())
+            .field("computed", &self.computed)
+            .finish()
+    }
+}
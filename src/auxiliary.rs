@@ -0,0 +1,156 @@
+use core::borrow::Borrow;
+use core::fmt;
+use core::ops::Deref;
+
+use {Memo, MemoExt, MemoMut, MemoOnce, Memoize};
+
+/// Represents a computation which also produces auxiliary data
+///
+/// This is like `Memoize`, but the computation returns side-products (like
+/// warnings or statistics) together with the value. Memoize it by using
+/// `WithAux<T>` as the value type of any of the memoization types. The
+/// side-products are stored next to the value, and can be taken out with
+/// `take_aux()`, without having to make them part of the value.
+///
+/// ## Example
+///
+/// ```
+/// use core_memo::{Memo, MemoizeAux, WithAux};
+///
+/// struct Layout {
+///     width: u32,
+/// }
+///
+/// impl MemoizeAux for Layout {
+///     type Param = [u32];
+///     type Aux = Vec<String>;
+///
+///     fn memoize_aux(p: &[u32]) -> (Self, Vec<String>) {
+///         let warnings = p
+///             .iter()
+///             .filter(|&&w| w == 0)
+///             .map(|_| String::from("empty column"))
+///             .collect();
+///         (Layout { width: p.iter().sum() }, warnings)
+///     }
+/// }
+///
+/// let mut memo: Memo<WithAux<Layout>, Vec<u32>> = Memo::new(vec![10, 0, 5]);
+///
+/// assert_eq!(memo.get().width, 15);
+/// assert_eq!(memo.take_aux(), Some(vec![String::from("empty column")]));
+///
+/// // taken already, but the value stays cached
+/// assert_eq!(memo.take_aux(), None);
+/// assert!(memo.is_ready());
+/// ```
+///
+pub trait MemoizeAux: Sized {
+    /// The input type for the computation
+    type Param: ?Sized;
+
+    /// The type of the side-products
+    type Aux;
+
+    /// The function to compute the value, and the side-products
+    fn memoize_aux(p: &Self::Param) -> (Self, Self::Aux);
+}
+
+/// A value computed with `MemoizeAux`, with its side-products
+///
+/// This implements `Memoize` for any `MemoizeAux` type, and derefs to the
+/// value.
+pub struct WithAux<T: MemoizeAux> {
+    value: T,
+    aux: Option<T::Aux>,
+}
+
+impl<T: MemoizeAux> WithAux<T> {
+    /// Get the side-products, if they have not been taken out yet
+    pub fn aux(&self) -> Option<&T::Aux> {
+        self.aux.as_ref()
+    }
+
+    /// Take the side-products out, leaving `None` behind
+    pub fn take_aux(&mut self) -> Option<T::Aux> {
+        self.aux.take()
+    }
+
+    /// Get the value back
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+}
+
+impl<T: MemoizeAux> Memoize for WithAux<T> {
+    type Param = T::Param;
+
+    fn memoize(p: &T::Param) -> Self {
+        let (value, aux) = T::memoize_aux(p);
+        WithAux {
+            value,
+            aux: Some(aux),
+        }
+    }
+}
+
+impl<T: MemoizeAux> Deref for WithAux<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+impl<T> fmt::Debug for WithAux<T>
+where
+    T: MemoizeAux + fmt::Debug,
+    T::Aux: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("WithAux")
+            .field("value", &self.value)
+            .field("aux", &self.aux)
+            .finish()
+    }
+}
+
+impl<T: MemoizeAux> MemoExt<WithAux<T>> {
+    /// Take the side-products of the cached value out, if there are any
+    ///
+    /// Returns `None` if there is no cached value, or if the side-products
+    /// were already taken out since it was computed.
+    pub fn take_aux(&mut self) -> Option<T::Aux> {
+        self.value.as_mut().and_then(WithAux::take_aux)
+    }
+}
+
+impl<T: MemoizeAux, P: Borrow<T::Param>> Memo<WithAux<T>, P> {
+    /// Take the side-products of the cached value out, if there are any
+    ///
+    /// Returns `None` if there is no cached value, or if the side-products
+    /// were already taken out since it was computed.
+    pub fn take_aux(&mut self) -> Option<T::Aux> {
+        self.value.as_mut().and_then(WithAux::take_aux)
+    }
+}
+
+impl<'p, T: MemoizeAux, R> MemoOnce<'p, WithAux<T>, R> {
+    /// Take the side-products of the cached value out, if there are any
+    ///
+    /// Returns `None` if there is no cached value, or if the side-products
+    /// were already taken out since it was computed.
+    pub fn take_aux(&mut self) -> Option<T::Aux> {
+        self.value.as_mut().and_then(WithAux::take_aux)
+    }
+}
+
+impl<'p, T: MemoizeAux> MemoMut<'p, WithAux<T>> {
+    /// Take the side-products of the cached value out, if there are any
+    ///
+    /// Returns `None` if there is no cached value, or if the side-products
+    /// were already taken out since it was computed.
+    pub fn take_aux(&mut self) -> Option<T::Aux> {
+        self.value.as_mut().and_then(WithAux::take_aux)
+    }
+}
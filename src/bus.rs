@@ -0,0 +1,169 @@
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+use core::borrow::Borrow;
+
+use Invalidate;
+
+/// Broadcasts invalidations to all memos subscribed to a topic
+///
+/// Requires the `alloc` feature.
+///
+/// This lets you decouple the code that changes some state from the (possibly
+/// many) memos whose values are derived from it. The producer only needs to
+/// `publish()` the topic that has changed; it does not need to know anything
+/// about the memos that depend on it.
+///
+/// The bus does not hold the memos themselves. Instead, each memo is wrapped in
+/// a `Subscriber`, which remembers the topics it is interested in. Publishing
+/// a topic is a single cheap operation, regardless of how many subscribers it
+/// has. Each `Subscriber` will notice that it has been invalidated the next
+/// time you call `sync()` on it, and clear its memo.
+///
+/// ## Example
+///
+/// ```
+/// use core_memo::{Memoize, Memo, InvalidationBus};
+///
+/// struct Sum(i32);
+///
+/// impl Memoize for Sum {
+///     type Param = [i32];
+///     fn memoize(p: &[i32]) -> Self {
+///         Sum(p.iter().sum())
+///     }
+/// }
+///
+/// let mut bus = InvalidationBus::new();
+///
+/// let mut a = bus.subscribe(Memo::<Sum, _>::new(vec![1, 2]), vec!["numbers"]);
+/// let mut b = bus.subscribe(Memo::<Sum, _>::new(vec![3, 4]), vec!["numbers"]);
+///
+/// assert_eq!(a.sync(&bus).get().0, 3);
+/// assert_eq!(b.sync(&bus).get().0, 7);
+///
+/// // one call invalidates every subscriber of the topic
+/// bus.publish("numbers");
+///
+/// assert!(a.is_stale(&bus));
+/// assert!(b.is_stale(&bus));
+///
+/// // the memos are cleared the next time they are synced
+/// assert!(!a.sync(&bus).is_ready());
+/// assert!(!b.sync(&bus).is_ready());
+/// ```
+///
+#[derive(Debug)]
+pub struct InvalidationBus<K: Ord> {
+    generations: BTreeMap<K, u64>,
+}
+
+/// Memo wrapper which is invalidated through an `InvalidationBus`
+///
+/// Requires the `alloc` feature.
+///
+/// Created by `InvalidationBus::subscribe()`. See the documentation of
+/// `InvalidationBus` for more info.
+///
+/// You must call `sync()` to access the memo mutably. This makes sure that
+/// any invalidations published since the last sync are applied first.
+#[derive(Debug)]
+pub struct Subscriber<K, M> {
+    memo: M,
+    topics: Vec<(K, u64)>,
+}
+
+impl<K: Ord> InvalidationBus<K> {
+    /// Creates a new `InvalidationBus` instance
+    pub fn new() -> Self {
+        Self {
+            generations: BTreeMap::new(),
+        }
+    }
+
+    /// Invalidates all subscribers of a topic
+    ///
+    /// The subscribers will clear their memos the next time they are synced.
+    pub fn publish(&mut self, topic: K) {
+        *self.generations.entry(topic).or_insert(0) += 1;
+    }
+
+    /// Get the number of times a topic has been published
+    pub fn generation<Q>(&self, topic: &Q) -> u64
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.generations.get(topic).cloned().unwrap_or(0)
+    }
+
+    /// Subscribe a memo to the given topics
+    ///
+    /// The memo is assumed to be up to date with respect to everything that
+    /// has been published so far.
+    pub fn subscribe<M, I>(&self, memo: M, topics: I) -> Subscriber<K, M>
+    where
+        I: IntoIterator<Item = K>,
+    {
+        let topics = topics
+            .into_iter()
+            .map(|topic| {
+                let generation = self.generation(&topic);
+                (topic, generation)
+            })
+            .collect();
+
+        Subscriber { memo, topics }
+    }
+}
+
+impl<K: Ord> Default for InvalidationBus<K> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: Ord, M: Invalidate> Subscriber<K, M> {
+    /// Check if any of the subscribed topics have been published since the
+    /// last sync
+    pub fn is_stale(&self, bus: &InvalidationBus<K>) -> bool {
+        self.topics
+            .iter()
+            .any(|(topic, seen)| bus.generation(topic) != *seen)
+    }
+
+    /// Apply any pending invalidations and get the memo
+    ///
+    /// If any of the subscribed topics have been published since the last
+    /// sync, the memo is cleared.
+    pub fn sync(&mut self, bus: &InvalidationBus<K>) -> &mut M {
+        let mut stale = false;
+
+        for (topic, seen) in self.topics.iter_mut() {
+            let generation = bus.generation(topic);
+            if generation != *seen {
+                *seen = generation;
+                stale = true;
+            }
+        }
+
+        if stale {
+            self.memo.invalidate();
+        }
+
+        &mut self.memo
+    }
+
+    /// Get a reference to the memo without syncing
+    ///
+    /// The memo may hold a stale value, if there are pending invalidations.
+    pub fn memo(&self) -> &M {
+        &self.memo
+    }
+
+    /// Unsubscribe and get the memo back
+    ///
+    /// Any pending invalidations are discarded.
+    pub fn into_inner(self) -> M {
+        self.memo
+    }
+}
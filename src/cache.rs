@@ -0,0 +1,2189 @@
+use alloc::borrow::ToOwned;
+use alloc::collections::{BTreeMap, BTreeSet};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::borrow::Borrow;
+use core::fmt;
+use core::hash::Hash;
+use core::iter;
+use core::mem;
+use core::ops::{Bound, RangeBounds};
+#[cfg(feature = "std")]
+use std::time::{Duration, Instant};
+
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+
+#[cfg(feature = "serde")]
+use serde::de::{Deserialize, Deserializer};
+#[cfg(feature = "serde")]
+use serde::ser::{Serialize, SerializeSeq, Serializer};
+
+use hash::stable_hash;
+use sketch::{BloomFilter, FrequencySketch};
+use {CacheError, Invalidate, Lookup, MemSize, Memoize, Params, Storage};
+
+/// Marks the absence of a node in the linked list
+const NIL: usize = usize::MAX;
+
+/// Keyed memoization cache, with optional LRU eviction
+///
+/// Requires the `alloc` feature.
+///
+/// This type caches one computed value per parameter. The parameters are used
+/// as keys: `get()` computes the value for a key on a miss and returns the
+/// cached value on a hit.
+///
+/// The cache can be unbounded (created with `new()`) or bounded (created with
+/// `bounded()`). A bounded cache evicts the least recently used entry whenever
+/// it needs room for a new one.
+///
+/// A bounded cache can also be given an admission policy, with
+/// `with_admission()`, to protect frequently used entries from being evicted
+/// by keys which are only used once.
+///
+/// If most lookups are for keys without a meaningful value (like checking if
+/// an override exists for an ID), `with_negative_filter()` lets the cache
+/// remember such misses compactly, without an entry for each of them.
+///
+/// If the computation is so cheap that caching it is not worth the memory,
+/// `with_min_cost()` (with the `std` feature) stops caching after timing it.
+/// If you don't know how cheap it is, or how often keys repeat (like when the
+/// computation is provided by your users), `with_adaptive()` keeps measuring,
+/// and turns caching off and on again as needed.
+///
+/// If your values vary a lot in size, bound the cache by their total weight
+/// instead (created with `weighted()`). You provide a function that measures
+/// the weight of a value (like its size in bytes), and the least recently used
+/// entries are evicted until the new one fits.
+///
+/// Expensive computations can declare their cost (see `Memoize::cost()`).
+/// Each doubling of the cost gives an entry one more chance to stay in the
+/// cache: when it is the least recently used entry and would be evicted, it is
+/// moved to the front instead (until it runs out of chances, or is used again).
+/// With the default cost, this is plain LRU eviction.
+///
+/// If `Param` is unsized (like `str`), specify an owned type for the key (like
+/// `String`). The keys are stored in a `Storage` (of type `S`), which is a
+/// `BTreeMap` by default, so `K` must implement `Ord`. With a `BTreeMap`, the
+/// entries are kept in key order, so you can iterate over them in order and
+/// invalidate whole ranges of keys at once with `invalidate_range()`. This is
+/// useful for keys with a meaningful ordering, like timestamps. Other kinds of
+/// storage (like a `HashMap`) can be used instead, but without these ordered
+/// operations.
+///
+/// Recency is tracked with a doubly-linked list threaded through the entries,
+/// so the cache only requires `alloc` and not `std`.
+///
+/// With the `std` feature, entries can also be given a time-to-live, with
+/// `with_ttl()`. Expired entries are not returned, and are recomputed when they
+/// are next needed. Use `gc()` to drop the ones which are not needed anymore.
+///
+/// Entries can also carry tags (of type `G`), attached with `get_tagged()` or
+/// `tag()`. Then, `invalidate_tag()` drops all entries carrying a given tag.
+/// Use this when the things your entries depend on don't follow the structure
+/// of the keys (for example, all meshes using a given material).
+///
+/// ## Example
+///
+/// ```
+/// use core_memo::{Memoize, MemoCache};
+///
+/// struct Len(usize);
+///
+/// impl Memoize for Len {
+///     type Param = str;
+///     fn memoize(p: &str) -> Self {
+///         Len(p.len())
+///     }
+/// }
+///
+/// let mut cache: MemoCache<Len, String> = MemoCache::bounded(2);
+///
+/// assert_eq!(cache.get("a").0, 1);
+/// assert_eq!(cache.get("bb").0, 2);
+///
+/// // "a" is used more recently than "bb" now
+/// cache.get("a");
+///
+/// // so "bb" is evicted to make room for "ccc"
+/// cache.get("ccc");
+///
+/// assert!(cache.is_ready("a"));
+/// assert!(!cache.is_ready("bb"));
+/// assert!(cache.is_ready("ccc"));
+/// ```
+///
+pub struct MemoCache<
+    T: Memoize,
+    K = <T as Memoize>::Param,
+    G = &'static str,
+    S = BTreeMap<K, usize>,
+> {
+    index: S,
+    tags: BTreeMap<G, BTreeSet<usize>>,
+    nodes: Vec<Option<Node<K, T, G>>>,
+    free: Vec<usize>,
+    head: usize,
+    tail: usize,
+    capacity: Option<usize>,
+    weigher: Option<Weigher<T>>,
+    weight: usize,
+    admission: Option<Admission<K>>,
+    rejected: Vec<usize>,
+    negative: Option<NegativeFilter<T, S>>,
+    #[cfg(feature = "std")]
+    min_cost: Option<Duration>,
+    #[cfg(feature = "std")]
+    adaptive: Option<Adaptive<K>>,
+    #[cfg(feature = "std")]
+    ttl: Option<Duration>,
+    bypass: bool,
+    #[cfg(feature = "stats")]
+    clock: u64,
+}
+
+/// Map from parameters to memoized values
+///
+/// Requires the `alloc` feature.
+///
+/// This is a `MemoCache`, with the type parameters in map order: the key type
+/// first, then the computation. Use it to memoize a computation over many
+/// different parameters, with one cached value per parameter.
+///
+/// ## Example
+///
+/// ```
+/// use core_memo::{Memoize, MemoMap};
+///
+/// struct Length(usize);
+///
+/// impl Memoize for Length {
+///     type Param = str;
+///     fn memoize(p: &str) -> Self {
+///         Length(p.chars().count())
+///     }
+/// }
+///
+/// let mut map: MemoMap<String, Length> = MemoMap::new();
+///
+/// let key_param = String::from("héllo");
+/// assert_eq!(map.get(&key_param).0, 5);
+/// assert!(map.is_ready("héllo"));
+/// ```
+///
+pub type MemoMap<K, T> = MemoCache<T, K>;
+
+/// Access statistics for an entry in a `MemoCache`
+///
+/// Requires the `stats` feature.
+///
+/// Time is measured by a logical clock, which counts the accesses to the whole
+/// cache, so that it works without `std`. Compare the `last_access()` of two
+/// entries to see which was used more recently.
+#[cfg(feature = "stats")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EntryStats {
+    hits: u64,
+    last_access: u64,
+}
+
+#[cfg(feature = "stats")]
+impl EntryStats {
+    /// Get the number of times the cached value was used, without computing it
+    pub fn hits(&self) -> u64 {
+        self.hits
+    }
+
+    /// Get the time of the last access to the entry, on the cache's clock
+    pub fn last_access(&self) -> u64 {
+        self.last_access
+    }
+}
+
+/// The maximum total weight, and the function to measure values with
+struct Weigher<T> {
+    max: usize,
+    weigh: fn(&T) -> usize,
+}
+
+/// The access frequencies of keys, and the function to hash keys with
+struct Admission<K> {
+    sketch: FrequencySketch,
+    hash: fn(&K) -> u64,
+}
+
+/// Number of lookups between decisions of the adaptive mode
+#[cfg(feature = "std")]
+const ADAPTIVE_WINDOW: u32 = 64;
+
+/// Measurements of the adaptive mode, to decide whether caching pays off
+#[cfg(feature = "std")]
+struct Adaptive<K> {
+    threshold: Duration,
+    cost: Duration,
+    lookups: u32,
+    repeats: u32,
+    seen: FrequencySketch,
+    hash: fn(&K) -> u64,
+}
+
+#[cfg(feature = "std")]
+impl<K> Adaptive<K> {
+    /// Update the average cost of the computation with a new measurement
+    fn measure(&mut self, elapsed: Duration) {
+        self.cost = if self.cost.is_zero() {
+            elapsed
+        } else {
+            self.cost - self.cost / 8 + elapsed / 8
+        };
+    }
+
+    /// Check if a key was looked up recently, and record the lookup
+    fn seen(&mut self, key: &K) -> bool {
+        let hash = (self.hash)(key);
+        let seen = self.seen.estimate(hash) > 0;
+        self.seen.increment(hash);
+        seen
+    }
+
+    /// Record a lookup, and decide whether the cache should be bypassed
+    ///
+    /// Caching is turned off when the time it saves per lookup drops below the
+    /// threshold, and only turned on again when it would save twice as much.
+    fn record(&mut self, repeat: bool, bypass: bool) -> bool {
+        self.lookups += 1;
+        if repeat {
+            self.repeats += 1;
+        }
+        if self.lookups < ADAPTIVE_WINDOW {
+            return bypass;
+        }
+
+        let saved = self.cost * self.repeats / self.lookups;
+        self.lookups = 0;
+        self.repeats = 0;
+        if bypass {
+            saved < self.threshold * 2
+        } else {
+            saved < self.threshold
+        }
+    }
+}
+
+/// The keys known to be misses, with their values
+struct NegativeFilter<T: Memoize, S> {
+    /// Approximate set of the keys in `misses`, so that most of the keys which
+    /// are not misses don't have to be looked up there
+    filter: BloomFilter,
+    hash: fn(&T::Param) -> u64,
+    is_miss: fn(&T) -> bool,
+    /// The node holding the value of each miss, by key
+    misses: S,
+    /// No more misses are recorded once there are this many
+    limit: usize,
+}
+
+struct Node<K, T, G> {
+    key: K,
+    value: T,
+    weight: usize,
+    chances: u8,
+    chances_left: u8,
+    tags: Vec<G>,
+    #[cfg(feature = "stats")]
+    stats: EntryStats,
+    #[cfg(feature = "std")]
+    expires: Option<Instant>,
+    prev: usize,
+    next: usize,
+}
+
+impl<T: Memoize, K: Clone, G: Ord + Clone, S: Storage<K, usize>> MemoCache<T, K, G, S> {
+    /// Creates a new unbounded `MemoCache` instance
+    pub fn new() -> Self
+    where
+        S: Default,
+    {
+        Self {
+            index: S::default(),
+            tags: BTreeMap::new(),
+            nodes: Vec::new(),
+            free: Vec::new(),
+            head: NIL,
+            tail: NIL,
+            capacity: None,
+            weigher: None,
+            weight: 0,
+            admission: None,
+            rejected: Vec::new(),
+            negative: None,
+            #[cfg(feature = "std")]
+            min_cost: None,
+            #[cfg(feature = "std")]
+            adaptive: None,
+            #[cfg(feature = "std")]
+            ttl: None,
+            bypass: false,
+            #[cfg(feature = "stats")]
+            clock: 0,
+        }
+    }
+
+    /// Creates a new `MemoCache` instance, holding at most `capacity` entries
+    ///
+    /// ## Panics
+    ///
+    /// Panics if `capacity` is zero.
+    pub fn bounded(capacity: usize) -> Self
+    where
+        S: Default,
+    {
+        assert!(capacity > 0, "MemoCache capacity must be non-zero");
+        Self {
+            capacity: Some(capacity),
+            ..Self::new()
+        }
+    }
+
+    /// Creates a new `MemoCache` instance, holding entries up to a total weight
+    ///
+    /// The weight of each value is measured with `weigh` once, when it is
+    /// computed. If a single value weighs more than `max_weight`, it is still
+    /// cached (since `get()` needs to return it), but everything else is
+    /// evicted.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// use core_memo::{Memoize, MemoCache};
+    ///
+    /// struct Bytes(Vec<u8>);
+    ///
+    /// impl Memoize for Bytes {
+    ///     type Param = usize;
+    ///     fn memoize(len: &usize) -> Self {
+    ///         Bytes(vec![0; *len])
+    ///     }
+    /// }
+    ///
+    /// let mut cache: MemoCache<Bytes> = MemoCache::weighted(1000, |b| b.0.len());
+    ///
+    /// cache.get(&100);
+    /// cache.get(&500);
+    /// assert_eq!(cache.weight(), 600);
+    ///
+    /// // 100 is the least recently used entry, so it is evicted to make room
+    /// cache.get(&450);
+    /// assert!(!cache.is_ready(&100));
+    /// assert_eq!(cache.weight(), 950);
+    /// ```
+    ///
+    pub fn weighted(max_weight: usize, weigh: fn(&T) -> usize) -> Self
+    where
+        S: Default,
+    {
+        Self {
+            weigher: Some(Weigher {
+                max: max_weight,
+                weigh,
+            }),
+            ..Self::new()
+        }
+    }
+
+    /// Enables the TinyLFU admission policy
+    ///
+    /// The cache keeps an approximate count of recent accesses to every key,
+    /// including keys which are not cached. When `get()` computes a value for
+    /// a new key, but the cache is full, the value is only cached if its key
+    /// has been used more often than the key of the entry that would be
+    /// evicted. Otherwise, the value is returned without being cached.
+    ///
+    /// This way, keys which are only used once (like in a scan over a large
+    /// data set) don't push the frequently used entries out of the cache.
+    ///
+    /// Only `get()` and `get_tagged()` apply the policy. Values which are
+    /// inserted explicitly (like with `warm()` or `entry()`) are always
+    /// cached. The policy has no effect in an unbounded cache.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// use core_memo::{Memoize, MemoCache};
+    ///
+    /// struct Double(u32);
+    ///
+    /// impl Memoize for Double {
+    ///     type Param = u32;
+    ///     fn memoize(p: &u32) -> Self {
+    ///         Double(p * 2)
+    ///     }
+    /// }
+    ///
+    /// let mut cache: MemoCache<Double> = MemoCache::bounded(2).with_admission();
+    ///
+    /// for _ in 0..3 {
+    ///     cache.get(&1);
+    ///     cache.get(&2);
+    /// }
+    ///
+    /// // a one-off key is computed, but it does not evict anything
+    /// assert_eq!(cache.get(&100).0, 200);
+    /// assert!(!cache.is_ready(&100));
+    /// assert!(cache.is_ready(&1));
+    /// assert!(cache.is_ready(&2));
+    /// ```
+    ///
+    pub fn with_admission(self) -> Self
+    where
+        K: Hash,
+    {
+        let size = self.capacity.unwrap_or(0).max(64);
+        Self {
+            admission: Some(Admission {
+                sketch: FrequencySketch::new(size),
+                hash: stable_hash::<K>,
+            }),
+            ..self
+        }
+    }
+
+    /// Enables remembering misses, behind a Bloom filter
+    ///
+    /// Use this when the value for most keys is a "miss" (like `None`), as
+    /// determined by `is_miss`. Misses are not stored as entries in the cache,
+    /// so they are not counted by `len()`, and don't push the other entries
+    /// out of a bounded cache. Instead, they are kept aside, for up to
+    /// `expected_misses` keys, and `get()` returns them without computing
+    /// them again. Once that many are known, further misses are computed
+    /// every time, until the known misses are cleared.
+    ///
+    /// Their keys are also recorded in a Bloom filter, so that for most of the
+    /// keys which are not known misses, the misses don't have to be looked up
+    /// at all. The filter only decides whether to look: the value returned
+    /// for a key is always its own.
+    ///
+    /// Known misses are dropped by `remove()` and the other invalidation
+    /// methods, like entries, or all at once with `clear_misses()`. They also
+    /// expire like entries, if the cache has a time-to-live (see `with_ttl()`).
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// use core_memo::{Memoize, MemoCache};
+    ///
+    /// struct Override(Option<&'static str>);
+    ///
+    /// impl Memoize for Override {
+    ///     type Param = u32;
+    ///     fn memoize(id: &u32) -> Self {
+    ///         // some expensive lookup, which usually finds nothing
+    ///         Override(if *id == 7 { Some("seven") } else { None })
+    ///     }
+    /// }
+    ///
+    /// let mut cache: MemoCache<Override> =
+    ///     MemoCache::new().with_negative_filter(1000, |o| o.0.is_none());
+    ///
+    /// assert_eq!(cache.get(&7).0, Some("seven"));
+    /// assert_eq!(cache.get(&8).0, None);
+    ///
+    /// // the miss is remembered, but not cached as an entry
+    /// assert!(cache.is_known_miss(&8));
+    /// assert_eq!(cache.len(), 1);
+    /// ```
+    ///
+    pub fn with_negative_filter(self, expected_misses: usize, is_miss: fn(&T) -> bool) -> Self
+    where
+        T::Param: Hash,
+        S: Default,
+    {
+        let misses = S::default();
+        let limit = expected_misses.min(misses.capacity().unwrap_or(usize::MAX));
+        Self {
+            negative: Some(NegativeFilter {
+                filter: BloomFilter::new(expected_misses),
+                hash: stable_hash::<T::Param>,
+                is_miss,
+                misses,
+                limit,
+            }),
+            ..self
+        }
+    }
+
+    /// Forget all known misses
+    pub fn clear_misses(&mut self) {
+        if let Some(ref mut negative) = self.negative {
+            negative.filter.clear();
+            for (_, &i) in negative.misses.iter() {
+                self.nodes[i] = None;
+                self.free.push(i);
+            }
+            negative.misses.clear();
+        }
+    }
+
+    /// Stops caching if the computation turns out to be cheap
+    ///
+    /// Requires the `std` feature.
+    ///
+    /// The first value computed by `get()` is timed. If it took less than
+    /// `threshold`, the cache stops caching: from then on, `get()` always
+    /// computes the value and returns it without caching it. Use this for
+    /// computations that may be trivial, with keys that change constantly, so
+    /// that memory isn't wasted on values that are cheaper to compute again.
+    ///
+    /// Values which are inserted explicitly (like with `warm()` or `entry()`)
+    /// are still cached.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use core_memo::{Memoize, MemoCache};
+    ///
+    /// struct Double(u32);
+    ///
+    /// impl Memoize for Double {
+    ///     type Param = u32;
+    ///     fn memoize(p: &u32) -> Self {
+    ///         Double(p * 2)
+    ///     }
+    /// }
+    ///
+    /// let mut cache: MemoCache<Double> =
+    ///     MemoCache::new().with_min_cost(Duration::from_secs(1));
+    ///
+    /// assert_eq!(cache.get(&1).0, 2);
+    /// assert!(cache.is_bypassed());
+    /// assert!(!cache.is_ready(&1));
+    /// ```
+    ///
+    #[cfg(feature = "std")]
+    pub fn with_min_cost(mut self, threshold: Duration) -> Self {
+        self.min_cost = Some(threshold);
+        self
+    }
+
+    /// Turns caching off and on automatically, depending on whether it pays off
+    ///
+    /// Requires the `std` feature.
+    ///
+    /// The cache measures how long the computation takes, and how often keys
+    /// are looked up again. Every 64 lookups with `get()`, it estimates the
+    /// time saved per lookup by caching. If that is less than `threshold`,
+    /// the cache stops caching new values (like with `with_min_cost()`). While
+    /// caching is off, the cache keeps measuring, and turns caching back on
+    /// once it would save at least twice the threshold. The gap between the
+    /// two limits keeps the cache from switching back and forth.
+    ///
+    /// Entries which are already cached stay in the cache either way.
+    #[cfg(feature = "std")]
+    pub fn with_adaptive(mut self, threshold: Duration) -> Self
+    where
+        K: Hash,
+    {
+        self.adaptive = Some(Adaptive {
+            threshold,
+            cost: Duration::ZERO,
+            lookups: 0,
+            repeats: 0,
+            seen: FrequencySketch::new(ADAPTIVE_WINDOW as usize),
+            hash: stable_hash::<K>,
+        });
+        self
+    }
+
+    /// Makes the cached values expire after a time-to-live
+    ///
+    /// Requires the `std` feature.
+    ///
+    /// Every entry (and every known miss, see `with_negative_filter()`) expires
+    /// `ttl` after its value was computed (or inserted). From then on, it
+    /// counts as not ready: `try_get()` returns `None` for it, and `get()`
+    /// computes the value again.
+    ///
+    /// Expiry is lazy: an expired entry stays in memory until it is looked up
+    /// again, or until the next call to `gc()`. Call `gc()` periodically if
+    /// many keys are never looked up again, to reclaim their memory.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// use std::thread;
+    /// use std::time::Duration;
+    /// use core_memo::{Memoize, MemoCache};
+    ///
+    /// struct Double(u32);
+    ///
+    /// impl Memoize for Double {
+    ///     type Param = u32;
+    ///     fn memoize(p: &u32) -> Self {
+    ///         Double(p * 2)
+    ///     }
+    /// }
+    ///
+    /// let mut cache: MemoCache<Double> =
+    ///     MemoCache::new().with_ttl(Duration::from_millis(10));
+    ///
+    /// cache.get(&1);
+    /// cache.get(&2);
+    /// thread::sleep(Duration::from_millis(20));
+    ///
+    /// assert!(!cache.is_ready(&1));
+    /// assert_eq!(cache.gc(), 2);
+    /// assert!(cache.is_empty());
+    /// ```
+    ///
+    #[cfg(feature = "std")]
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = Some(ttl);
+        self
+    }
+
+    /// Get the time-to-live of the entries, if they expire
+    ///
+    /// Requires the `std` feature.
+    #[cfg(feature = "std")]
+    pub fn ttl(&self) -> Option<Duration> {
+        self.ttl
+    }
+
+    /// Drops all expired entries, and expired known misses
+    ///
+    /// Returns the number of entries and known misses that were removed.
+    ///
+    /// Expired entries are dropped lazily when they are looked up, but this
+    /// sweeps the whole cache, so that memory is also reclaimed for keys which
+    /// are never looked up again. Without a time-to-live (see `with_ttl()`),
+    /// nothing expires, and this does nothing.
+    pub fn gc(&mut self) -> usize {
+        let doomed: Vec<usize> = self
+            .index
+            .iter()
+            .map(|(_, &i)| i)
+            .filter(|&i| self.is_expired(i))
+            .collect();
+
+        for &i in &doomed {
+            self.remove_node(i);
+        }
+
+        let misses: Vec<K> = match self.negative {
+            Some(ref negative) => negative
+                .misses
+                .iter()
+                .filter(|&(_, &i)| self.is_expired(i))
+                .map(|(key, _)| key.clone())
+                .collect(),
+            None => Vec::new(),
+        };
+        for key in &misses {
+            self.remove_miss::<K>(key);
+        }
+
+        doomed.len() + misses.len()
+    }
+
+    /// Check if the cache has stopped caching, because the computation was
+    /// cheaper than the threshold given to `with_min_cost()` or
+    /// `with_adaptive()`
+    ///
+    /// Requires the `std` feature.
+    #[cfg(feature = "std")]
+    pub fn is_bypassed(&self) -> bool {
+        self.bypass
+    }
+
+    /// Get the approximate number of bytes of memory used by the cache
+    ///
+    /// This counts the entries (with the heap memory of their keys and values,
+    /// as reported by `MemSize`), and an estimate for the index. The overhead
+    /// of the index itself depends on the kind of `Storage`, and is not
+    /// counted.
+    pub fn memory_used(&self) -> usize
+    where
+        K: MemSize,
+        T: MemSize,
+    {
+        let slots = self.nodes.capacity() * mem::size_of::<Option<Node<K, T, G>>>();
+        let index = self.index.len() * mem::size_of::<(K, usize)>();
+        let heap: usize = self
+            .nodes
+            .iter()
+            .flatten()
+            .map(|n| {
+                n.key.heap_size() + n.value.heap_size() + n.tags.capacity() * mem::size_of::<G>()
+            })
+            .sum();
+        slots + index + heap
+    }
+
+    /// Get the maximum number of entries, if the cache is bounded
+    pub fn capacity(&self) -> Option<usize> {
+        self.capacity
+    }
+
+    /// Get the maximum total weight, if the cache is bounded by weight
+    pub fn max_weight(&self) -> Option<usize> {
+        self.weigher.as_ref().map(|w| w.max)
+    }
+
+    /// Get the total weight of the cached entries
+    ///
+    /// This is always zero, unless the cache is bounded by weight.
+    pub fn weight(&self) -> usize {
+        self.weight
+    }
+
+    /// Get the number of cached entries
+    pub fn len(&self) -> usize {
+        self.index.len()
+    }
+
+    /// Check if there are no cached entries
+    pub fn is_empty(&self) -> bool {
+        self.index.is_empty()
+    }
+
+    /// Reserve memory for at least `additional` more entries
+    ///
+    /// Returns `CacheError::AllocFailed` if the memory cannot be allocated,
+    /// instead of aborting like an insertion would. This covers the entries
+    /// themselves; the storage of the index may still allocate as they are
+    /// inserted.
+    pub fn try_reserve(&mut self, additional: usize) -> Result<(), CacheError> {
+        let needed = additional.saturating_sub(self.free.len());
+        self.nodes
+            .try_reserve(needed)
+            .map_err(|_| CacheError::AllocFailed)
+    }
+
+    /// Get the entry for a key, for in-place manipulation
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// use core_memo::{Memoize, MemoCache};
+    ///
+    /// struct Words(Vec<String>);
+    ///
+    /// impl Memoize for Words {
+    ///     type Param = str;
+    ///     fn memoize(p: &str) -> Self {
+    ///         Words(p.split_whitespace().map(String::from).collect())
+    ///     }
+    /// }
+    ///
+    /// let mut cache: MemoCache<Words, String> = MemoCache::new();
+    ///
+    /// let words = cache
+    ///     .entry("hello world".to_owned())
+    ///     .and_modify(|w| w.0.clear())
+    ///     .or_memoize();
+    ///
+    /// // the entry was vacant, so it was computed, and not modified
+    /// assert_eq!(words.0, ["hello", "world"]);
+    /// words.0.sort_by(|a, b| b.cmp(a));
+    ///
+    /// // the entry is occupied now, so the computation doesn't happen again
+    /// let words = cache.entry("hello world".to_owned()).or_memoize();
+    /// assert_eq!(words.0, ["world", "hello"]);
+    /// ```
+    ///
+    pub fn entry(&mut self, key: K) -> CacheEntry<'_, T, K, G, S> {
+        let mut index = self.index.get(&key).cloned();
+        if let Some(i) = index.filter(|&i| self.is_expired(i)) {
+            self.remove_node(i);
+            index = None;
+        }
+        CacheEntry {
+            cache: self,
+            key,
+            index,
+        }
+    }
+
+    /// Iterate over the cached entries, in the order of the storage
+    ///
+    /// With a `BTreeMap` (the default), this is key order.
+    pub fn iter(&self) -> CacheIter<'_, T, K, G, S> {
+        CacheIter {
+            inner: self.index.iter(),
+            nodes: &self.nodes,
+        }
+    }
+
+    /// Iterate over the keys of the cached entries, in the order of the storage
+    pub fn keys(&self) -> impl Iterator<Item = &K> {
+        self.index.iter().map(|(k, _)| k)
+    }
+
+    /// Iterate over the cached values, in the order of the storage
+    pub fn values(&self) -> impl Iterator<Item = &T> {
+        self.index.iter().map(move |(_, &i)| &self.node(i).value)
+    }
+
+    /// Iterate over the keys of the cached entries, most recently used first
+    ///
+    /// This is useful for recording which keys are hot, to `warm()` another
+    /// cache with them later.
+    pub fn keys_by_recency(&self) -> impl Iterator<Item = &K> {
+        let head = Some(self.head).filter(|&i| i != NIL);
+        iter::successors(head, move |&i| {
+            Some(self.node(i).next).filter(|&i| i != NIL)
+        })
+        .map(move |i| &self.node(i).key)
+    }
+
+    /// Iterate over the nodes, least recently used first
+    #[cfg(feature = "serde")]
+    fn nodes_by_recency_rev(&self) -> impl Iterator<Item = &Node<K, T, G>> {
+        let tail = Some(self.tail).filter(|&i| i != NIL);
+        iter::successors(tail, move |&i| {
+            Some(self.node(i).prev).filter(|&i| i != NIL)
+        })
+        .map(move |i| self.node(i))
+    }
+
+    /// Get the access statistics for all entries, in the order of the storage
+    ///
+    /// Requires the `stats` feature.
+    #[cfg(feature = "stats")]
+    pub fn stats(&self) -> impl Iterator<Item = (&K, EntryStats)> {
+        self.index
+            .iter()
+            .map(move |(k, &i)| (k, self.node(i).stats))
+    }
+
+    /// Get the keys of the (at most) `n` most frequently used entries
+    ///
+    /// Requires the `stats` feature.
+    ///
+    /// The keys are ordered from the most to the least hits. Entries with the
+    /// same number of hits are ordered from the most to the least recent.
+    #[cfg(feature = "stats")]
+    pub fn hottest(&self, n: usize) -> Vec<&K> {
+        let mut all: Vec<_> = self.stats().collect();
+        all.sort_by_key(|&(_, s)| core::cmp::Reverse((s.hits, s.last_access)));
+        all.into_iter().take(n).map(|(k, _)| k).collect()
+    }
+
+    /// Get the keys of the (at most) `n` least frequently used entries
+    ///
+    /// Requires the `stats` feature.
+    ///
+    /// The keys are ordered from the least to the most hits. Entries with the
+    /// same number of hits are ordered from the least to the most recent.
+    #[cfg(feature = "stats")]
+    pub fn coldest(&self, n: usize) -> Vec<&K> {
+        let mut all: Vec<_> = self.stats().collect();
+        all.sort_by_key(|&(_, s)| (s.hits, s.last_access));
+        all.into_iter().take(n).map(|(k, _)| k).collect()
+    }
+
+    /// Retains only the entries for which the predicate returns `true`
+    ///
+    /// The other entries are removed, as if by `remove()`.
+    pub fn retain<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&K, &mut T) -> bool,
+    {
+        self.invalidate_where(|k, v| !f(k, v));
+    }
+
+    /// Clears the cached values for which the predicate returns `true`
+    ///
+    /// Returns the number of entries that were removed.
+    pub fn invalidate_where<F>(&mut self, mut f: F) -> usize
+    where
+        F: FnMut(&K, &mut T) -> bool,
+    {
+        let mut doomed = Vec::new();
+        for (_, &i) in self.index.iter() {
+            let node = self.nodes[i].as_mut().unwrap();
+            if f(&node.key, &mut node.value) {
+                doomed.push(i);
+            }
+        }
+
+        for &i in &doomed {
+            self.remove_node(i);
+        }
+
+        doomed.len() + self.remove_misses_where(f)
+    }
+
+    /// Clears the cached values for all entries carrying a tag
+    ///
+    /// Returns the number of entries that were removed.
+    pub fn invalidate_tag(&mut self, tag: &G) -> usize {
+        let doomed = match self.tags.remove(tag) {
+            Some(set) => set,
+            None => return 0,
+        };
+
+        for &i in &doomed {
+            self.remove_node(i);
+        }
+
+        doomed.len()
+    }
+
+    /// Clears all cached values
+    pub fn clear(&mut self) {
+        self.index.clear();
+        self.tags.clear();
+        self.nodes.clear();
+        self.free.clear();
+        self.head = NIL;
+        self.tail = NIL;
+        self.weight = 0;
+        self.rejected.clear();
+        if let Some(ref mut negative) = self.negative {
+            negative.filter.clear();
+            negative.misses.clear();
+        }
+    }
+
+    fn node(&self, i: usize) -> &Node<K, T, G> {
+        self.nodes[i].as_ref().unwrap()
+    }
+
+    fn node_mut(&mut self, i: usize) -> &mut Node<K, T, G> {
+        self.nodes[i].as_mut().unwrap()
+    }
+
+    /// Check if the value of a node has outlived the time-to-live
+    #[cfg(feature = "std")]
+    fn is_expired(&self, i: usize) -> bool {
+        self.node(i)
+            .expires
+            .is_some_and(|expires| Instant::now() >= expires)
+    }
+
+    #[cfg(not(feature = "std"))]
+    fn is_expired(&self, _i: usize) -> bool {
+        false
+    }
+
+    /// Get the time at which a value computed now expires, if it does
+    #[cfg(feature = "std")]
+    fn deadline(&self) -> Option<Instant> {
+        self.ttl.map(|ttl| Instant::now() + ttl)
+    }
+
+    /// Remove a node from the linked list
+    fn unlink(&mut self, i: usize) {
+        let (prev, next) = {
+            let node = self.node(i);
+            (node.prev, node.next)
+        };
+
+        if prev == NIL {
+            self.head = next;
+        } else {
+            self.node_mut(prev).next = next;
+        }
+
+        if next == NIL {
+            self.tail = prev;
+        } else {
+            self.node_mut(next).prev = prev;
+        }
+    }
+
+    /// Insert a node at the front (most recently used end) of the linked list
+    fn push_front(&mut self, i: usize) {
+        let head = self.head;
+        {
+            let node = self.node_mut(i);
+            node.prev = NIL;
+            node.next = head;
+        }
+
+        if head == NIL {
+            self.tail = i;
+        } else {
+            self.node_mut(head).prev = i;
+        }
+        self.head = i;
+    }
+
+    /// Mark a node as the most recently used
+    fn touch(&mut self, i: usize) {
+        if self.head != i {
+            self.unlink(i);
+            self.push_front(i);
+        }
+    }
+
+    /// Mark a node as used, because its cached value was needed
+    fn hit(&mut self, i: usize) {
+        self.touch(i);
+        {
+            let node = self.node_mut(i);
+            node.chances_left = node.chances;
+        }
+
+        if let Some(ref mut admission) = self.admission {
+            let key = &self.nodes[i].as_ref().unwrap().key;
+            admission.sketch.increment((admission.hash)(key));
+        }
+
+        #[cfg(feature = "stats")]
+        {
+            let now = self.tick();
+            let stats = &mut self.node_mut(i).stats;
+            stats.hits += 1;
+            stats.last_access = now;
+        }
+    }
+
+    /// Advance the logical clock
+    #[cfg(feature = "stats")]
+    fn tick(&mut self) -> u64 {
+        self.clock += 1;
+        self.clock
+    }
+
+    /// Remove a node entirely, returning its key and value
+    fn remove_node(&mut self, i: usize) -> (K, T) {
+        self.unlink(i);
+        let node = self.nodes[i].take().unwrap();
+        self.free.push(i);
+        self.weight -= node.weight;
+        self.index.remove(&node.key);
+        for tag in &node.tags {
+            self.untag_node(i, tag);
+        }
+        (node.key, node.value)
+    }
+
+    /// Attach a tag to a node, if it does not have it already
+    fn tag_node(&mut self, i: usize, tag: G) {
+        let node = self.nodes[i].as_mut().unwrap();
+        if !node.tags.contains(&tag) {
+            node.tags.push(tag.clone());
+            self.tags.entry(tag).or_default().insert(i);
+        }
+    }
+
+    /// Remove a node from the index of a tag
+    fn untag_node(&mut self, i: usize, tag: &G) {
+        if let Some(set) = self.tags.get_mut(tag) {
+            set.remove(&i);
+            if set.is_empty() {
+                self.tags.remove(tag);
+            }
+        }
+    }
+
+    /// Add a new entry with the given cost, evicting the least recently used
+    /// ones if needed (but keeping at least the `keep` most recently used ones)
+    fn insert_node(&mut self, key: K, value: T, cost: u32, keep: usize) -> usize {
+        // an entry supersedes a known miss of the same key
+        self.remove_miss::<K>(&key);
+
+        let weight = self.weigh(&value);
+        self.evict(1, weight, keep);
+        self.weight += weight;
+
+        // one more chance for each doubling of the cost
+        let chances = (u32::BITS - 1 - cost.max(1).leading_zeros()) as u8;
+        let node = Node {
+            key: key.clone(),
+            value,
+            weight,
+            chances,
+            chances_left: chances,
+            tags: Vec::new(),
+            #[cfg(feature = "stats")]
+            stats: EntryStats {
+                hits: 0,
+                last_access: self.tick(),
+            },
+            #[cfg(feature = "std")]
+            expires: self.deadline(),
+            prev: NIL,
+            next: NIL,
+        };
+
+        let i = self.alloc_node(node);
+        self.push_front(i);
+        self.index.insert(key, i);
+        i
+    }
+
+    /// Put a node in a free slot
+    fn alloc_node(&mut self, node: Node<K, T, G>) -> usize {
+        match self.free.pop() {
+            Some(i) => {
+                self.nodes[i] = Some(node);
+                i
+            }
+            None => {
+                self.nodes.push(Some(node));
+                self.nodes.len() - 1
+            }
+        }
+    }
+
+    /// Check if a new entry should be cached, according to the admission
+    /// policy (and record the access to its key)
+    fn admits(&mut self, key: &K, weight: usize) -> bool {
+        let full = self.is_over(1, weight);
+        let admission = match self.admission {
+            Some(ref mut admission) => admission,
+            None => return true,
+        };
+
+        let hash = (admission.hash)(key);
+        admission.sketch.increment(hash);
+
+        if !full || self.tail == NIL {
+            return true;
+        }
+
+        let victim = &self.nodes[self.tail].as_ref().unwrap().key;
+        admission.sketch.estimate(hash) > admission.sketch.estimate((admission.hash)(victim))
+    }
+
+    /// Hold a value which was not cached, so that a reference to it can be
+    /// returned
+    ///
+    /// It is not part of the cache, and is dropped on the next lookup.
+    fn reject(&mut self, key: K, value: T) -> usize {
+        let node = self.detached_node(key, value);
+        let i = self.alloc_node(node);
+        self.rejected.push(i);
+        i
+    }
+
+    /// Create a node which is not part of the cache
+    fn detached_node(&mut self, key: K, value: T) -> Node<K, T, G> {
+        Node {
+            key,
+            value,
+            weight: 0,
+            chances: 0,
+            chances_left: 0,
+            tags: Vec::new(),
+            #[cfg(feature = "stats")]
+            stats: EntryStats {
+                hits: 0,
+                last_access: self.tick(),
+            },
+            #[cfg(feature = "std")]
+            expires: self.deadline(),
+            prev: NIL,
+            next: NIL,
+        }
+    }
+
+    /// Check if a node is not part of the cache (rejected, or a miss)
+    fn is_detached(&self, i: usize) -> bool {
+        self.rejected.contains(&i)
+            || self
+                .negative
+                .as_ref()
+                .is_some_and(|n| Lookup::<K, usize>::get(&n.misses, &self.node(i).key) == Some(&i))
+    }
+
+    /// Check if a value is a miss, which should be recorded in the filter
+    fn is_miss(&self, value: &T) -> bool {
+        self.negative.as_ref().is_some_and(|n| (n.is_miss)(value))
+    }
+
+    /// Forget a known miss, returning its value
+    fn remove_miss<Q: ?Sized>(&mut self, key: &Q) -> Option<T>
+    where
+        S: Lookup<Q, usize>,
+    {
+        let i = self.negative.as_mut()?.misses.remove(key)?;
+        self.free.push(i);
+        self.nodes[i].take().map(|node| node.value)
+    }
+
+    /// Forget the known misses for which the predicate returns `true`,
+    /// returning how many there were
+    fn remove_misses_where<F>(&mut self, mut f: F) -> usize
+    where
+        F: FnMut(&K, &mut T) -> bool,
+    {
+        let misses: Vec<usize> = match self.negative {
+            Some(ref negative) => negative.misses.iter().map(|(_, &i)| i).collect(),
+            None => return 0,
+        };
+
+        let mut doomed = Vec::new();
+        for i in misses {
+            let node = self.nodes[i].as_mut().unwrap();
+            if f(&node.key, &mut node.value) {
+                doomed.push(node.key.clone());
+            }
+        }
+
+        for key in &doomed {
+            self.remove_miss::<K>(key);
+        }
+        doomed.len()
+    }
+
+    /// Drop the values which were not cached, if any
+    fn drop_rejected(&mut self) {
+        for i in mem::take(&mut self.rejected) {
+            self.nodes[i] = None;
+            self.free.push(i);
+        }
+    }
+
+    /// Replace the value of a node, and mark it as the most recently used
+    fn replace_node(&mut self, i: usize, value: T) {
+        let weight = self.weigh(&value);
+        self.weight = self.weight - self.node(i).weight + weight;
+        #[cfg(feature = "std")]
+        {
+            let expires = self.deadline();
+            self.node_mut(i).expires = expires;
+        }
+        {
+            let node = self.node_mut(i);
+            node.value = value;
+            node.weight = weight;
+        }
+        self.touch(i);
+        self.evict(0, 0, 1);
+    }
+
+    /// Measure the weight of a value, if the cache is bounded by weight
+    fn weigh(&self, value: &T) -> usize {
+        self.weigher.as_ref().map_or(0, |w| (w.weigh)(value))
+    }
+
+    /// Evict the least recently used entries, until there is room for `count`
+    /// more entries weighing `weight` in total
+    ///
+    /// The `keep` most recently used entries are never evicted. Otherwise,
+    /// entries with chances left are spared, and moved to the front instead.
+    fn evict(&mut self, count: usize, weight: usize, keep: usize) {
+        while self.index.len() > keep && self.is_over(count, weight) {
+            let tail = self.tail;
+            if keep == 0 && self.node(tail).chances_left > 0 {
+                self.node_mut(tail).chances_left -= 1;
+                self.touch(tail);
+            } else {
+                self.remove_node(tail);
+            }
+        }
+    }
+
+    /// Check if adding `count` entries weighing `weight` in total would exceed
+    /// the bounds of the cache
+    fn is_over(&self, count: usize, weight: usize) -> bool {
+        let full = self
+            .capacity
+            .into_iter()
+            .chain(self.index.capacity())
+            .any(|capacity| self.index.len() + count > capacity);
+        let heavy = self
+            .weigher
+            .as_ref()
+            .is_some_and(|w| self.weight + weight > w.max);
+        full || heavy
+    }
+}
+
+impl<T: Memoize, K, G, S> MemoCache<T, K, G, S>
+where
+    K: Clone + Borrow<T::Param>,
+    G: Ord + Clone,
+    S: Storage<K, usize> + Lookup<T::Param, usize>,
+{
+    /// Compute a value, timing it if the cache has a minimum cost, or is
+    /// adaptive
+    fn compute(&mut self, key: &K, p: &T::Param) -> T {
+        #[cfg(feature = "std")]
+        {
+            if let Some(threshold) = self.min_cost.take() {
+                let start = Instant::now();
+                let value = T::memoize(p);
+                self.bypass = start.elapsed() < threshold;
+                return value;
+            }
+
+            if let Some(ref mut adaptive) = self.adaptive {
+                let start = Instant::now();
+                let value = T::memoize(p);
+                adaptive.measure(start.elapsed());
+                let repeat = self.bypass && adaptive.seen(key);
+                self.bypass = adaptive.record(repeat, self.bypass);
+                return value;
+            }
+        }
+        #[cfg(not(feature = "std"))]
+        let _ = key;
+
+        T::memoize(p)
+    }
+
+    /// Find the node for a key, or for its known miss, and mark it as used
+    fn lookup(&mut self, p: &T::Param) -> Option<usize> {
+        match self.find_or_expire(p) {
+            Some(i) => {
+                self.hit(i);
+                #[cfg(feature = "std")]
+                {
+                    if let Some(ref mut adaptive) = self.adaptive {
+                        self.bypass = adaptive.record(true, self.bypass);
+                    }
+                }
+                Some(i)
+            }
+            None => self.known_miss(p),
+        }
+    }
+
+    /// Store a computed value, returning its node
+    ///
+    /// A miss is recorded in the negative filter. Otherwise the value is
+    /// cached, unless the cache is bypassed, the admission policy turns it
+    /// down, or (in a batch) it does not fit without evicting the `keep` most
+    /// recently used entries; then it is rejected.
+    fn store(&mut self, key: K, value: T, cost: u32, keep: usize) -> usize {
+        if self.is_miss(&value) {
+            return self.record_miss(key, value);
+        }
+
+        let weight = self.weigh(&value);
+        if self.bypass || !self.admits(&key, weight) {
+            return self.reject(key, value);
+        }
+        if keep > 0 {
+            self.evict(1, weight, keep);
+            if self.is_over(1, weight) {
+                return self.reject(key, value);
+            }
+        }
+        self.insert_node(key, value, cost, keep)
+    }
+
+    /// Record a key as a known miss, holding its value aside
+    ///
+    /// If there are too many known misses already, the value is rejected
+    /// instead.
+    fn record_miss(&mut self, key: K, value: T) -> usize {
+        // an expired miss of the same key is replaced
+        self.remove_miss::<K>(&key);
+
+        let negative = self.negative.as_ref().unwrap();
+        if negative.misses.len() >= negative.limit {
+            return self.reject(key, value);
+        }
+
+        let hash = (negative.hash)(key.borrow());
+        let node = self.detached_node(key.clone(), value);
+        let i = self.alloc_node(node);
+        let negative = self.negative.as_mut().unwrap();
+        negative.filter.insert(hash);
+        negative.misses.insert(key, i);
+        i
+    }
+
+    /// Find the node holding the value of a known miss, if it has not expired
+    fn known_miss(&self, p: &T::Param) -> Option<usize> {
+        let negative = self.negative.as_ref()?;
+        if !negative.filter.contains((negative.hash)(p)) {
+            return None;
+        }
+        Lookup::<T::Param, usize>::get(&negative.misses, p)
+            .cloned()
+            .filter(|&i| !self.is_expired(i))
+    }
+
+    /// Check if a key is known to be a miss
+    ///
+    /// See `with_negative_filter()`.
+    pub fn is_known_miss(&self, p: &T::Param) -> bool {
+        self.known_miss(p).is_some()
+    }
+
+    /// Find the node for a key
+    fn find(&self, p: &T::Param) -> Option<usize> {
+        Lookup::<T::Param, usize>::get(&self.index, p).cloned()
+    }
+
+    /// Find the node for a key, if it has not expired
+    fn find_live(&self, p: &T::Param) -> Option<usize> {
+        self.find(p).filter(|&i| !self.is_expired(i))
+    }
+
+    /// Find the node for a key, dropping it if it has expired
+    fn find_or_expire(&mut self, p: &T::Param) -> Option<usize> {
+        let i = self.find(p)?;
+        if self.is_expired(i) {
+            self.remove_node(i);
+            return None;
+        }
+        Some(i)
+    }
+
+    /// Check if there is a cached value for a key
+    ///
+    /// This is `false` for expired entries.
+    pub fn is_ready(&self, p: &T::Param) -> bool {
+        self.find_live(p).is_some()
+    }
+
+    /// Get the value for a key if it is available
+    ///
+    /// This does not count as a use of the entry for the purposes of LRU
+    /// eviction.
+    pub fn try_get(&self, p: &T::Param) -> Option<&T> {
+        self.find_live(p).map(|i| &self.node(i).value)
+    }
+
+    /// Get the value for a key if it is available, or why it is not
+    ///
+    /// Same as `try_get()`, but returns `CacheError::Expired` if the entry has
+    /// outlived the time-to-live, and `CacheError::NotReady` if there is no
+    /// entry.
+    pub fn try_get_checked(&self, p: &T::Param) -> Result<&T, CacheError> {
+        let i = self.find(p).ok_or(CacheError::NotReady)?;
+        if self.is_expired(i) {
+            return Err(CacheError::Expired);
+        }
+        Ok(&self.node(i).value)
+    }
+
+    /// Clears the cached value for a key
+    ///
+    /// Returns the value, if there was one.
+    pub fn remove(&mut self, p: &T::Param) -> Option<T> {
+        match self.find_or_expire(p) {
+            Some(i) => Some(self.remove_node(i).1),
+            None => self.remove_miss(p),
+        }
+    }
+
+    /// Compute and cache the values for many keys in advance
+    ///
+    /// Keys which already have a cached value are not computed again. Returns
+    /// the number of values that were computed.
+    ///
+    /// Use this to prefill a cache with hot keys at startup (for example, from
+    /// a recorded access log). In a bounded cache, the keys that come later
+    /// count as more recently used, so list them from coldest to hottest.
+    pub fn warm<I: IntoIterator<Item = K>>(&mut self, keys: I) -> usize {
+        let mut computed = 0;
+        for key in keys {
+            match self.find_or_expire(key.borrow()) {
+                Some(i) => self.touch(i),
+                None => {
+                    let value = T::memoize(key.borrow());
+                    let cost = T::cost(key.borrow());
+                    self.insert_node(key, value, cost, 0);
+                    computed += 1;
+                }
+            }
+        }
+        computed
+    }
+
+    /// Compute and cache the values for many keys in advance, in parallel
+    ///
+    /// Requires the `rayon` feature.
+    ///
+    /// Same as `warm()`, but the missing values are computed on the `rayon`
+    /// thread pool, so that prefilling a large cache at startup scales with
+    /// the number of cores. Every missing key is computed once, even if it is
+    /// listed several times.
+    ///
+    /// This is for prefilling a cache you have exclusive access to. It takes
+    /// the cache by `&mut`, so nothing else can look up or compute the same
+    /// keys meanwhile; it does not coordinate with other computations, like
+    /// a cache shared between threads would.
+    ///
+    /// The values are inserted after all of them have been computed, in the
+    /// order of the keys, so list them from coldest to hottest. (With a
+    /// fixed-size storage, like `FixedMap`, the keys are computed in batches
+    /// of at most the capacity of the cache.)
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// use core_memo::{Memoize, MemoCache};
+    ///
+    /// struct Fib(u64);
+    ///
+    /// impl Memoize for Fib {
+    ///     type Param = u64;
+    ///     fn memoize(n: &u64) -> Self {
+    ///         let (mut a, mut b) = (0, 1);
+    ///         for _ in 0..*n {
+    ///             let next = a + b;
+    ///             a = b;
+    ///             b = next;
+    ///         }
+    ///         Fib(a)
+    ///     }
+    /// }
+    ///
+    /// let mut cache: MemoCache<Fib> = MemoCache::new();
+    /// cache.get(&10);
+    ///
+    /// // 10 is cached already, and 20 is listed twice
+    /// assert_eq!(cache.warm_par(vec![10, 20, 30, 20]), 2);
+    /// assert_eq!(cache.try_get(&30).unwrap().0, 832040);
+    /// ```
+    ///
+    #[cfg(feature = "rayon")]
+    pub fn warm_par<I: IntoIterator<Item = K>>(&mut self, keys: I) -> usize
+    where
+        T: Send,
+        K: Send + Sync,
+        S: Default,
+    {
+        // the distinct missing keys, found with a map like the index
+        let mut seen = S::default();
+        let mut missing = Vec::new();
+        let mut computed = 0;
+        for key in keys {
+            match self.find_or_expire(key.borrow()) {
+                Some(i) => self.touch(i),
+                None if Lookup::<T::Param, usize>::contains_key(&seen, key.borrow()) => {}
+                None => {
+                    // insert the batch so far, so that the map has room again
+                    if seen.capacity().is_some_and(|cap| seen.len() >= cap) {
+                        computed += self.insert_par(mem::take(&mut missing));
+                        seen.clear();
+                    }
+                    seen.insert(key.clone(), missing.len());
+                    missing.push(key);
+                }
+            }
+        }
+        computed + self.insert_par(missing)
+    }
+
+    /// Compute the values for keys on the `rayon` thread pool, and insert
+    /// them in order, returning how many there were
+    #[cfg(feature = "rayon")]
+    fn insert_par(&mut self, keys: Vec<K>) -> usize
+    where
+        T: Send,
+        K: Send + Sync,
+    {
+        let computed: Vec<(K, T, u32)> = keys
+            .into_par_iter()
+            .map(|key| {
+                let value = T::memoize(key.borrow());
+                let cost = T::cost(key.borrow());
+                (key, value, cost)
+            })
+            .collect();
+
+        let count = computed.len();
+        for (key, value, cost) in computed {
+            self.insert_node(key, value, cost, 0);
+        }
+        count
+    }
+
+    /// Get the values for many keys at once
+    ///
+    /// The values are returned in the same order as the keys. Values which are
+    /// not cached yet are computed, once for every distinct key, after all the
+    /// keys have been looked up. The keys count as used in order, so the last
+    /// ones are the most recently used.
+    ///
+    /// The computed values are stored like `get()` stores them, except that
+    /// the entries of the batch are not evicted to make room for each other:
+    /// values which do not fit within the bounds of the cache are returned
+    /// without being cached. (With a fixed-size storage, like `FixedMap`, the
+    /// missing keys are computed in batches of at most the capacity of the
+    /// cache, and a key listed again after its batch is computed again if its
+    /// value was not cached.)
+    pub fn get_many(&mut self, keys: &[K]) -> Vec<&T>
+    where
+        S: Default,
+    {
+        self.get_many_with(keys, |cache, missing| {
+            missing
+                .iter()
+                .map(|key| (cache.compute(key, key.borrow()), T::cost(key.borrow())))
+                .collect()
+        })
+    }
+
+    /// Get the values for many keys at once, computing the missing ones in
+    /// parallel
+    ///
+    /// Requires the `rayon` feature.
+    ///
+    /// Same as `get_many()`, but the missing values are computed on the
+    /// `rayon` thread pool.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// use core_memo::{Memoize, MemoCache};
+    ///
+    /// struct Square(u64);
+    ///
+    /// impl Memoize for Square {
+    ///     type Param = u64;
+    ///     fn memoize(n: &u64) -> Self {
+    ///         Square(n * n)
+    ///     }
+    /// }
+    ///
+    /// let mut cache: MemoCache<Square> = MemoCache::new();
+    ///
+    /// let values: Vec<u64> = cache.get_many_par(&[3, 1, 3]).iter().map(|s| s.0).collect();
+    /// assert_eq!(values, [9, 1, 9]);
+    /// ```
+    ///
+    #[cfg(feature = "rayon")]
+    pub fn get_many_par(&mut self, keys: &[K]) -> Vec<&T>
+    where
+        T: Send,
+        K: Sync,
+        S: Default,
+    {
+        self.get_many_with(keys, |_, missing| {
+            missing
+                .par_iter()
+                .map(|key| (T::memoize(key.borrow()), T::cost(key.borrow())))
+                .collect()
+        })
+    }
+
+    /// Get the values for many keys at once, computing the values and costs
+    /// of the missing ones with `compute`
+    fn get_many_with<F>(&mut self, keys: &[K], mut compute: F) -> Vec<&T>
+    where
+        F: FnMut(&mut Self, &[K]) -> Vec<(T, u32)>,
+        S: Default,
+    {
+        self.drop_rejected();
+
+        // the node of each key, once it is known
+        let mut nodes = vec![NIL; keys.len()];
+        // the distinct cached entries in the batch, which are the most
+        // recently used
+        let mut batch = BTreeSet::new();
+        // the distinct missing keys, found with a map like the index, and the
+        // keys listed again before they were computed
+        let mut seen = S::default();
+        let mut missing = Vec::new();
+        let mut repeated = Vec::new();
+        for (n, key) in keys.iter().enumerate() {
+            if let Some(i) = self.lookup(key.borrow()) {
+                if !self.is_detached(i) {
+                    batch.insert(i);
+                }
+                nodes[n] = i;
+            } else if let Some(&first) = Lookup::<T::Param, usize>::get(&seen, key.borrow()) {
+                repeated.push((n, first));
+            } else {
+                // compute the batch so far, so that the map has room again
+                if seen.capacity().is_some_and(|cap| seen.len() >= cap) {
+                    self.compute_batch(keys, &missing, &mut nodes, &mut batch, &mut compute);
+                    seen.clear();
+                    missing.clear();
+                }
+                seen.insert(key.clone(), n);
+                missing.push(n);
+            }
+        }
+        self.compute_batch(keys, &missing, &mut nodes, &mut batch, &mut compute);
+
+        for (n, first) in repeated {
+            nodes[n] = nodes[first];
+        }
+        // in the order of the keys, now that they are all stored
+        for &i in &nodes {
+            if !self.is_detached(i) {
+                self.touch(i);
+            }
+        }
+
+        let this = &*self;
+        nodes.into_iter().map(|i| &this.node(i).value).collect()
+    }
+
+    /// Compute the values of the keys at the positions in `missing`, and store
+    /// them without evicting the entries of the batch
+    fn compute_batch<F>(
+        &mut self,
+        keys: &[K],
+        missing: &[usize],
+        nodes: &mut [usize],
+        batch: &mut BTreeSet<usize>,
+        compute: &mut F,
+    ) where
+        F: FnMut(&mut Self, &[K]) -> Vec<(T, u32)>,
+    {
+        let missing_keys: Vec<K> = missing.iter().map(|&n| keys[n].clone()).collect();
+        let values = compute(self, &missing_keys);
+        for ((&n, key), (value, cost)) in missing.iter().zip(missing_keys).zip(values) {
+            let i = self.store(key, value, cost, batch.len());
+            if !self.is_detached(i) {
+                batch.insert(i);
+            }
+            nodes[n] = i;
+        }
+    }
+
+    /// Get the access statistics for the entry for a key
+    ///
+    /// Requires the `stats` feature.
+    #[cfg(feature = "stats")]
+    pub fn stats_for(&self, p: &T::Param) -> Option<EntryStats> {
+        self.find_live(p).map(|i| self.node(i).stats)
+    }
+
+    /// Attach a tag to the cached entry for a key
+    ///
+    /// Returns `false` if there is no cached value for the key.
+    pub fn tag(&mut self, p: &T::Param, tag: G) -> bool {
+        match self.find_or_expire(p) {
+            Some(i) => {
+                self.tag_node(i, tag);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Get the tags attached to the cached entry for a key
+    pub fn tags(&self, p: &T::Param) -> &[G] {
+        match self.find_live(p) {
+            Some(i) => &self.node(i).tags,
+            None => &[],
+        }
+    }
+}
+
+impl<T: Memoize, K, G, S> MemoCache<T, K, G, S>
+where
+    K: Clone + Borrow<T::Param>,
+    T::Param: ToOwned<Owned = K>,
+    G: Ord + Clone,
+    S: Storage<K, usize> + Lookup<T::Param, usize>,
+{
+    /// If the value for a key is not ready, compute it and cache it
+    pub fn ready(&mut self, p: &T::Param) {
+        self.get(p);
+    }
+
+    /// Force the value for a key to be recomputed
+    pub fn update(&mut self, p: &T::Param) -> &T {
+        let value = T::memoize(p);
+        let i = match self.index.get(p) {
+            Some(&i) => {
+                self.replace_node(i, value);
+                i
+            }
+            None => self.insert_node(p.to_owned(), value, T::cost(p), 0),
+        };
+        &self.node(i).value
+    }
+
+    /// Get the value for a key
+    ///
+    /// If the value has already been computed, this function returns the cached
+    /// value. If not, it is computed and cached for future use.
+    ///
+    /// In a bounded cache, this marks the entry as the most recently used.
+    pub fn get(&mut self, p: &T::Param) -> &T {
+        let i = self.get_node(p);
+        &self.node(i).value
+    }
+
+    /// Get the value for a key, passing the parts of a tuple key individually
+    ///
+    /// Same as `get()`, but the key is assembled from the arguments (see
+    /// `Params`), so you don't have to build it yourself.
+    pub fn get_with<A: Params<T::Param>>(&mut self, args: A) -> &T
+    where
+        T::Param: Sized,
+    {
+        self.get(&args.into_param())
+    }
+
+    /// Get the value for a key, and attach tags to its entry
+    ///
+    /// Same as `get()`, but the tags are also attached to the entry (whether
+    /// it was already cached or not).
+    pub fn get_tagged<I>(&mut self, p: &T::Param, tags: I) -> &T
+    where
+        I: IntoIterator<Item = G>,
+    {
+        let i = self.get_node(p);
+        if !self.is_detached(i) {
+            for tag in tags {
+                self.tag_node(i, tag);
+            }
+        }
+        &self.node(i).value
+    }
+
+    /// Find the node for a key, computing it if needed, and mark it as used
+    fn get_node(&mut self, p: &T::Param) -> usize {
+        self.drop_rejected();
+
+        if let Some(i) = self.lookup(p) {
+            return i;
+        }
+
+        let key = p.to_owned();
+        let value = self.compute(&key, p);
+        self.store(key, value, T::cost(p), 0)
+    }
+}
+
+impl<T: Memoize, K, G> MemoCache<T, K, G, BTreeMap<K, usize>>
+where
+    K: Ord + Clone + Borrow<T::Param>,
+    T::Param: Ord,
+    G: Ord + Clone,
+{
+    /// Iterate over the cached entries with keys in a range, in key order
+    pub fn range<R>(&self, range: R) -> impl DoubleEndedIterator<Item = (&K, &T)>
+    where
+        R: RangeBounds<T::Param>,
+    {
+        self.index
+            .range::<T::Param, R>(range)
+            .map(move |(k, &i)| (k, &self.node(i).value))
+    }
+
+    /// Clears the cached values for all keys in a range
+    ///
+    /// Returns the number of entries that were removed.
+    pub fn invalidate_range<R>(&mut self, range: R) -> usize
+    where
+        R: RangeBounds<T::Param>,
+    {
+        let doomed: Vec<usize> = self
+            .index
+            .range::<T::Param, _>((range.start_bound(), range.end_bound()))
+            .map(|(_, &i)| i)
+            .collect();
+
+        for &i in &doomed {
+            self.remove_node(i);
+        }
+
+        let misses: Vec<K> = match self.negative {
+            Some(ref negative) => negative
+                .misses
+                .range::<T::Param, _>((range.start_bound(), range.end_bound()))
+                .map(|(k, _)| k.clone())
+                .collect(),
+            None => Vec::new(),
+        };
+        for key in &misses {
+            self.remove_miss::<K>(key);
+        }
+
+        doomed.len() + misses.len()
+    }
+}
+
+impl<T: Memoize, K, G> MemoCache<T, K, G, BTreeMap<K, usize>>
+where
+    K: Ord + Clone + Borrow<str>,
+    G: Ord + Clone,
+{
+    /// Clears the cached values for all keys starting with a prefix
+    ///
+    /// Returns the number of entries that were removed.
+    ///
+    /// This is useful for caches keyed by paths or URLs, to invalidate a whole
+    /// subtree (like `"assets/textures/"`) at once. Thanks to the ordering of
+    /// the keys, only the matching entries are visited.
+    pub fn invalidate_prefix(&mut self, prefix: &str) -> usize {
+        let doomed: Vec<usize> = self
+            .index
+            .range::<str, _>((Bound::Included(prefix), Bound::Unbounded))
+            .take_while(|(k, _)| (*k).borrow().starts_with(prefix))
+            .map(|(_, &i)| i)
+            .collect();
+
+        for &i in &doomed {
+            self.remove_node(i);
+        }
+
+        let misses: Vec<K> = match self.negative {
+            Some(ref negative) => negative
+                .misses
+                .range::<str, _>((Bound::Included(prefix), Bound::Unbounded))
+                .take_while(|(k, _)| (*k).borrow().starts_with(prefix))
+                .map(|(k, _)| k.clone())
+                .collect(),
+            None => Vec::new(),
+        };
+        for key in &misses {
+            self.remove_miss::<K>(key);
+        }
+
+        doomed.len() + misses.len()
+    }
+}
+
+/// Iterator over the entries of a `MemoCache`
+///
+/// Requires the `alloc` feature.
+///
+/// Created by `MemoCache::iter()`.
+pub struct CacheIter<'a, T: Memoize + 'a, K: 'a, G: 'a, S: Storage<K, usize> + 'a> {
+    inner: S::Iter<'a>,
+    nodes: &'a [Option<Node<K, T, G>>],
+}
+
+impl<'a, T: Memoize, K, G, S: Storage<K, usize>> CacheIter<'a, T, K, G, S> {
+    fn entry(&self, (k, &i): (&'a K, &'a usize)) -> (&'a K, &'a T) {
+        (k, &self.nodes[i].as_ref().unwrap().value)
+    }
+}
+
+impl<'a, T: Memoize, K, G, S: Storage<K, usize>> Iterator for CacheIter<'a, T, K, G, S> {
+    type Item = (&'a K, &'a T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|e| self.entry(e))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<'a, T: Memoize, K, G, S> DoubleEndedIterator for CacheIter<'a, T, K, G, S>
+where
+    S: Storage<K, usize>,
+    S::Iter<'a>: DoubleEndedIterator,
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner.next_back().map(|e| self.entry(e))
+    }
+}
+
+/// An entry in a `MemoCache`, which may be occupied or vacant
+///
+/// Requires the `alloc` feature.
+///
+/// Created by `MemoCache::entry()`.
+///
+/// If you modify a value through the returned references, its weight (in a
+/// cache bounded by weight) is not measured again.
+pub struct CacheEntry<'a, T: Memoize + 'a, K: 'a, G: 'a, S: 'a> {
+    cache: &'a mut MemoCache<T, K, G, S>,
+    key: K,
+    index: Option<usize>,
+}
+
+impl<'a, T: Memoize, K: Clone, G: Ord + Clone, S: Storage<K, usize>> CacheEntry<'a, T, K, G, S> {
+    /// Get the key of the entry
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+
+    /// Check if there is a cached value for the entry
+    pub fn is_occupied(&self) -> bool {
+        self.index.is_some()
+    }
+
+    /// Modify the cached value, if there is one
+    pub fn and_modify<F: FnOnce(&mut T)>(self, f: F) -> Self {
+        if let Some(i) = self.index {
+            f(&mut self.cache.node_mut(i).value);
+        }
+        self
+    }
+}
+
+impl<'a, T: Memoize, K, G, S> CacheEntry<'a, T, K, G, S>
+where
+    K: Clone + Borrow<T::Param>,
+    G: Ord + Clone,
+    S: Storage<K, usize>,
+{
+    /// Get the cached value, or insert `value` if there is none
+    pub fn or_insert(self, value: T) -> &'a mut T {
+        self.or_insert_with(|| value)
+    }
+
+    /// Get the cached value, or insert the result of `f` if there is none
+    ///
+    /// This marks the entry as the most recently used.
+    pub fn or_insert_with<F: FnOnce() -> T>(self, f: F) -> &'a mut T {
+        let cache = self.cache;
+        let i = match self.index {
+            Some(i) => {
+                cache.hit(i);
+                i
+            }
+            None => {
+                let cost = T::cost(self.key.borrow());
+                cache.insert_node(self.key, f(), cost, 0)
+            }
+        };
+        &mut cache.node_mut(i).value
+    }
+
+    /// Get the cached value, or compute it if there is none
+    ///
+    /// This marks the entry as the most recently used.
+    pub fn or_memoize(self) -> &'a mut T {
+        let cache = self.cache;
+        let i = match self.index {
+            Some(i) => {
+                cache.hit(i);
+                i
+            }
+            None => {
+                let value = T::memoize(self.key.borrow());
+                let cost = T::cost(self.key.borrow());
+                cache.insert_node(self.key, value, cost, 0)
+            }
+        };
+        &mut cache.node_mut(i).value
+    }
+}
+
+impl<'a, T: Memoize, K: fmt::Debug, G, S> fmt::Debug for CacheEntry<'a, T, K, G, S> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("CacheEntry")
+            .field("key", &self.key)
+            .field("occupied", &self.index.is_some())
+            .finish()
+    }
+}
+
+impl<T, K, G, S> Extend<(K, T)> for MemoCache<T, K, G, S>
+where
+    T: Memoize,
+    K: Clone + Borrow<T::Param>,
+    G: Ord + Clone,
+    S: Storage<K, usize>,
+{
+    /// Insert entries into the cache
+    ///
+    /// Existing entries for the same keys are replaced. In a bounded cache,
+    /// the entries that come later count as more recently used.
+    fn extend<I: IntoIterator<Item = (K, T)>>(&mut self, entries: I) {
+        for (key, value) in entries {
+            match self.index.get(&key) {
+                Some(&i) => self.replace_node(i, value),
+                None => {
+                    let cost = T::cost(key.borrow());
+                    self.insert_node(key, value, cost, 0);
+                }
+            }
+        }
+    }
+}
+
+impl<T, K, G, S> iter::FromIterator<(K, T)> for MemoCache<T, K, G, S>
+where
+    T: Memoize,
+    K: Clone + Borrow<T::Param>,
+    G: Ord + Clone,
+    S: Storage<K, usize> + Default,
+{
+    /// Creates a new unbounded `MemoCache` instance, with the given entries
+    fn from_iter<I: IntoIterator<Item = (K, T)>>(entries: I) -> Self {
+        let mut cache = Self::new();
+        cache.extend(entries);
+        cache
+    }
+}
+
+/// Serializes the entries as a sequence of `(key, value)` pairs
+///
+/// Requires the `serde` feature.
+///
+/// The entries are ordered from the least to the most recently used, so that
+/// inserting them in order (like with `extend()`) restores their recency. The
+/// configuration of the cache (bounds, policies, tags) is not serialized.
+#[cfg(feature = "serde")]
+impl<T, K, G, M> Serialize for MemoCache<T, K, G, M>
+where
+    T: Memoize + Serialize,
+    K: Clone + Serialize,
+    G: Ord + Clone,
+    M: Storage<K, usize>,
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut seq = serializer.serialize_seq(Some(self.len()))?;
+        for node in self.nodes_by_recency_rev() {
+            seq.serialize_element(&(&node.key, &node.value))?;
+        }
+        seq.end()
+    }
+}
+
+/// Deserializes an unbounded cache, from a sequence of `(key, value)` pairs
+///
+/// Requires the `serde` feature.
+///
+/// To restore the entries into a cache with bounds or policies, deserialize
+/// them as a `Vec<(K, T)>`, and insert them with `extend()`.
+#[cfg(feature = "serde")]
+impl<'de, T, K, G, S> Deserialize<'de> for MemoCache<T, K, G, S>
+where
+    T: Memoize + Deserialize<'de>,
+    K: Clone + Borrow<T::Param> + Deserialize<'de>,
+    G: Ord + Clone,
+    S: Storage<K, usize> + Default,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let entries: Vec<(K, T)> = Vec::deserialize(deserializer)?;
+        Ok(entries.into_iter().collect())
+    }
+}
+
+impl<T, K, G, S> Default for MemoCache<T, K, G, S>
+where
+    T: Memoize,
+    K: Clone,
+    G: Ord + Clone,
+    S: Storage<K, usize> + Default,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, K, G, S> Invalidate for MemoCache<T, K, G, S>
+where
+    T: Memoize,
+    K: Clone,
+    G: Ord + Clone,
+    S: Storage<K, usize>,
+{
+    fn invalidate(&mut self) {
+        self.clear()
+    }
+}
+
+impl<T, K, G, S> fmt::Debug for MemoCache<T, K, G, S>
+where
+    T: Memoize + fmt::Debug,
+    K: fmt::Debug,
+    S: Storage<K, usize>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let iter: CacheIter<T, K, G, S> = CacheIter {
+            inner: self.index.iter(),
+            nodes: &self.nodes,
+        };
+        f.debug_map().entries(iter).finish()
+    }
+}
@@ -0,0 +1,186 @@
+use alloc::boxed::Box;
+use core::any::{Any, TypeId};
+use core::ffi::c_void;
+use core::ptr;
+
+use {DynMemoize, MemoDyn};
+
+/// Computes a value from the parameter, for the C API
+///
+/// Requires the `capi` feature.
+///
+/// Receives the parameter and the context pointer given to
+/// `core_memo_create()`, and returns a pointer to the new value.
+pub type CoreMemoComputeFn =
+    unsafe extern "C" fn(param: *const c_void, ctx: *mut c_void) -> *mut c_void;
+
+/// Frees a value returned by a `CoreMemoComputeFn`, for the C API
+///
+/// Requires the `capi` feature.
+pub type CoreMemoFreeFn = unsafe extern "C" fn(value: *mut c_void, ctx: *mut c_void);
+
+/// Opaque handle to a memoized value, for the C API
+///
+/// Requires the `capi` feature.
+///
+/// This is a `MemoDyn` whose computation is a pair of C callbacks, together
+/// with the parameter (an opaque pointer, which is passed to the callbacks).
+/// Create it with `core_memo_create()` and destroy it with
+/// `core_memo_destroy()`.
+///
+/// A header for the C API can be generated with `cbindgen`, using the
+/// `cbindgen.toml` at the root of the repository. To link it into a C/C++
+/// program, build the crate as a static or dynamic library with the `capi`
+/// feature, like `cargo rustc --release --features capi --crate-type staticlib`.
+pub struct CoreMemo {
+    memo: MemoDyn<*const c_void>,
+    param: *const c_void,
+}
+
+struct Callbacks {
+    compute: CoreMemoComputeFn,
+    free: Option<CoreMemoFreeFn>,
+    ctx: *mut c_void,
+}
+
+/// A value owned by a `CoreMemo`, freed with the callback
+struct Value {
+    ptr: *mut c_void,
+    free: Option<CoreMemoFreeFn>,
+    ctx: *mut c_void,
+}
+
+impl Drop for Value {
+    fn drop(&mut self) {
+        if let Some(free) = self.free {
+            unsafe { free(self.ptr, self.ctx) }
+        }
+    }
+}
+
+impl DynMemoize<*const c_void> for Callbacks {
+    fn memoize_dyn(&self, p: &*const c_void) -> Box<dyn Any> {
+        let ptr = unsafe { (self.compute)(*p, self.ctx) };
+        Box::new(Value {
+            ptr,
+            free: self.free,
+            ctx: self.ctx,
+        })
+    }
+
+    fn output_type(&self) -> TypeId {
+        TypeId::of::<Value>()
+    }
+}
+
+/// Creates a new memo, for the C API
+///
+/// Requires the `capi` feature.
+///
+/// `compute` is called to compute the value from `param`, when it is needed.
+/// If `free` is not null, it is called to free every value which is discarded.
+/// Both receive `ctx`.
+///
+/// # Safety
+///
+/// `param` and `ctx` must stay valid for as long as the callbacks may use
+/// them. The returned handle must be destroyed with `core_memo_destroy()`.
+#[no_mangle]
+pub unsafe extern "C" fn core_memo_create(
+    compute: CoreMemoComputeFn,
+    free: Option<CoreMemoFreeFn>,
+    ctx: *mut c_void,
+    param: *const c_void,
+) -> *mut CoreMemo {
+    let callbacks = Callbacks { compute, free, ctx };
+    Box::into_raw(Box::new(CoreMemo {
+        memo: MemoDyn::new(Box::new(callbacks)),
+        param,
+    }))
+}
+
+/// Destroys a memo, freeing any cached value, for the C API
+///
+/// Requires the `capi` feature.
+///
+/// # Safety
+///
+/// `memo` must be null, or a handle from `core_memo_create()`, which has not
+/// been destroyed yet.
+#[no_mangle]
+pub unsafe extern "C" fn core_memo_destroy(memo: *mut CoreMemo) {
+    if !memo.is_null() {
+        drop(Box::from_raw(memo));
+    }
+}
+
+/// Gets the value, computing it if needed, for the C API
+///
+/// Requires the `capi` feature.
+///
+/// The value is owned by the memo, and stays valid until it is cleared, or
+/// the memo is destroyed.
+///
+/// # Safety
+///
+/// `memo` must be a valid handle from `core_memo_create()`.
+#[no_mangle]
+pub unsafe extern "C" fn core_memo_get(memo: *mut CoreMemo) -> *const c_void {
+    let memo = &mut *memo;
+    let param = memo.param;
+    memo.memo.get_as::<Value>(&param).unwrap().ptr
+}
+
+/// Gets the value if it is available, or null, for the C API
+///
+/// Requires the `capi` feature.
+///
+/// # Safety
+///
+/// `memo` must be a valid handle from `core_memo_create()`.
+#[no_mangle]
+pub unsafe extern "C" fn core_memo_try_get(memo: *const CoreMemo) -> *const c_void {
+    match (*memo).memo.try_get_as::<Value>() {
+        Some(value) => value.ptr,
+        None => ptr::null(),
+    }
+}
+
+/// Checks if there is a cached value, for the C API
+///
+/// Requires the `capi` feature.
+///
+/// # Safety
+///
+/// `memo` must be a valid handle from `core_memo_create()`.
+#[no_mangle]
+pub unsafe extern "C" fn core_memo_is_ready(memo: *const CoreMemo) -> bool {
+    (*memo).memo.is_ready()
+}
+
+/// Clears any cached value, freeing it, for the C API
+///
+/// Requires the `capi` feature.
+///
+/// # Safety
+///
+/// `memo` must be a valid handle from `core_memo_create()`.
+#[no_mangle]
+pub unsafe extern "C" fn core_memo_clear(memo: *mut CoreMemo) {
+    (*memo).memo.clear();
+}
+
+/// Replaces the parameter, clearing any cached value, for the C API
+///
+/// Requires the `capi` feature.
+///
+/// # Safety
+///
+/// `memo` must be a valid handle from `core_memo_create()`, and `param` must
+/// stay valid for as long as the callbacks may use it.
+#[no_mangle]
+pub unsafe extern "C" fn core_memo_set_param(memo: *mut CoreMemo, param: *const c_void) {
+    let memo = &mut *memo;
+    memo.memo.clear();
+    memo.param = param;
+}
@@ -0,0 +1,382 @@
+use core::borrow::Borrow;
+use core::cell::{Cell, OnceCell, Ref, RefCell};
+use core::fmt;
+
+use {Invalidate, Memoize};
+
+/// Memoized value which can be computed through a shared reference
+///
+/// Like `Memo`, this holds ownership over the parameter for the computation.
+/// But the value is in a `OnceCell`, so `get()` only needs `&self`. This is
+/// for single-threaded code, like methods which only have `&self`, but want
+/// to cache something derived from it. It is not `Sync`; use `MemoShared` to
+/// share a memo between threads.
+///
+/// The value can only be cleared through `&mut self`. Use `MemoRefCell` if
+/// you need to clear it through a shared reference.
+///
+/// ## Example
+///
+/// ```
+/// use core_memo::{Memoize, MemoCell};
+///
+/// struct WordCount(usize);
+///
+/// impl Memoize for WordCount {
+///     type Param = String;
+///     fn memoize(text: &String) -> Self {
+///         WordCount(text.split_whitespace().count())
+///     }
+/// }
+///
+/// struct Document {
+///     words: MemoCell<WordCount>,
+/// }
+///
+/// impl Document {
+///     fn word_count(&self) -> usize {
+///         self.words.get().0
+///     }
+/// }
+///
+/// let doc = Document { words: MemoCell::new("the quick brown fox".into()) };
+/// assert_eq!(doc.word_count(), 4);
+/// ```
+///
+#[derive(Debug, Clone)]
+pub struct MemoCell<T, P = <T as Memoize>::Param> {
+    value: OnceCell<T>,
+    param: P,
+}
+
+impl<T, P> MemoCell<T, P> {
+    /// Creates a new `MemoCell` instance
+    pub const fn new(p: P) -> Self {
+        Self {
+            value: OnceCell::new(),
+            param: p,
+        }
+    }
+
+    /// Clears any cached value
+    ///
+    /// The value will be reevaluated the next time it is needed.
+    pub fn clear(&mut self) {
+        self.value.take();
+    }
+
+    /// Check if there is a cached value
+    ///
+    /// If this method returns `true`, the next call to `get()` will return the
+    /// stored memoized value.
+    pub fn is_ready(&self) -> bool {
+        self.value.get().is_some()
+    }
+
+    /// Get the value if it is available
+    ///
+    /// If there is a cached value, returns it. If the value needs to be
+    /// computed, returns `None`.
+    pub fn try_get(&self) -> Option<&T> {
+        self.value.get()
+    }
+
+    /// Get a reference to the parameter used for the computation
+    pub fn param(&self) -> &P {
+        &self.param
+    }
+
+    /// Get a mutable reference to the parameter used for the computation
+    ///
+    /// This clears any cached value.
+    pub fn param_mut(&mut self) -> &mut P {
+        self.clear();
+        &mut self.param
+    }
+
+    /// Get the parameter back, dropping the memo
+    pub fn into_param(self) -> P {
+        self.param
+    }
+}
+
+impl<T: Memoize, P: Borrow<T::Param>> MemoCell<T, P> {
+    /// Get the value
+    ///
+    /// If the value has already been computed, this function returns the
+    /// cached value. If not, it is computed and cached for future use.
+    ///
+    /// ## Panics
+    ///
+    /// If the computation calls `get()` on the same memo.
+    pub fn get(&self) -> &T {
+        self.value.get_or_init(|| T::memoize(self.param.borrow()))
+    }
+}
+
+impl<T, P> Invalidate for MemoCell<T, P> {
+    fn invalidate(&mut self) {
+        self.clear()
+    }
+}
+
+/// Memoized value which can be computed and cleared through a shared
+/// reference
+///
+/// Like `MemoCell`, but the value is in a `RefCell`, so it can also be
+/// cleared through `&self`, like in graph or tree structures where the nodes
+/// can only be reached by shared references. `get()` returns a `Ref` guard,
+/// like `RefCell::borrow()`.
+///
+/// This is for single-threaded code. As with `RefCell`, the borrows are
+/// checked at runtime: clearing the value while a guard is alive panics.
+///
+/// ## Example
+///
+/// ```
+/// use core_memo::{Memoize, MemoRefCell};
+///
+/// struct Depth(usize);
+///
+/// impl Memoize for Depth {
+///     type Param = Vec<u32>;
+///     fn memoize(path: &Vec<u32>) -> Self {
+///         Depth(path.len())
+///     }
+/// }
+///
+/// struct Node {
+///     depth: MemoRefCell<Depth>,
+/// }
+///
+/// impl Node {
+///     fn depth(&self) -> usize {
+///         self.depth.get().0
+///     }
+///
+///     fn on_reparent(&self) {
+///         self.depth.clear();
+///     }
+/// }
+///
+/// let node = Node { depth: MemoRefCell::new(vec![0, 3]) };
+/// assert_eq!(node.depth(), 2);
+///
+/// node.on_reparent();
+/// assert!(!node.depth.is_ready());
+/// ```
+///
+#[derive(Debug, Clone)]
+pub struct MemoRefCell<T, P = <T as Memoize>::Param> {
+    value: RefCell<Option<T>>,
+    param: P,
+}
+
+impl<T, P> MemoRefCell<T, P> {
+    /// Creates a new `MemoRefCell` instance
+    pub const fn new(p: P) -> Self {
+        Self {
+            value: RefCell::new(None),
+            param: p,
+        }
+    }
+
+    /// Clears any cached value
+    ///
+    /// The value will be reevaluated the next time it is needed.
+    ///
+    /// ## Panics
+    ///
+    /// If the value is borrowed, by a guard returned by `get()` or
+    /// `try_get()`.
+    pub fn clear(&self) {
+        *self.value.borrow_mut() = None;
+    }
+
+    /// Check if there is a cached value
+    ///
+    /// If this method returns `true`, the next call to `get()` will return the
+    /// stored memoized value.
+    pub fn is_ready(&self) -> bool {
+        self.value.borrow().is_some()
+    }
+
+    /// Get the value if it is available
+    ///
+    /// If there is a cached value, returns it. If the value needs to be
+    /// computed, returns `None`.
+    pub fn try_get(&self) -> Option<Ref<'_, T>> {
+        Ref::filter_map(self.value.borrow(), Option::as_ref).ok()
+    }
+
+    /// Get a reference to the parameter used for the computation
+    pub fn param(&self) -> &P {
+        &self.param
+    }
+
+    /// Get a mutable reference to the parameter used for the computation
+    ///
+    /// This clears any cached value.
+    pub fn param_mut(&mut self) -> &mut P {
+        *self.value.get_mut() = None;
+        &mut self.param
+    }
+
+    /// Get the parameter back, dropping the memo
+    pub fn into_param(self) -> P {
+        self.param
+    }
+}
+
+impl<T: Memoize, P: Borrow<T::Param>> MemoRefCell<T, P> {
+    /// Get the value
+    ///
+    /// If the value has already been computed, this function returns the
+    /// cached value. If not, it is computed and cached for future use.
+    pub fn get(&self) -> Ref<'_, T> {
+        if !self.is_ready() {
+            // computed before borrowing, so that `try_get()` and `is_ready()`
+            // still work while the computation runs
+            let value = T::memoize(self.param.borrow());
+            *self.value.borrow_mut() = Some(value);
+        }
+        Ref::map(self.value.borrow(), |value| value.as_ref().unwrap())
+    }
+}
+
+impl<T, P> Invalidate for MemoRefCell<T, P> {
+    fn invalidate(&mut self) {
+        *self.value.get_mut() = None;
+    }
+}
+
+/// Memoized `Copy` value which can be computed and cleared through a shared
+/// reference
+///
+/// Like `MemoRefCell`, but for small values which are `Copy`, like lengths,
+/// hashes, or bounding boxes. The value is in a `Cell`, and `get()` returns it
+/// by value, so there are no guards, and nothing can panic.
+///
+/// This is for single-threaded code.
+///
+/// ## Example
+///
+/// ```
+/// use core_memo::{Memoize, MemoCopy};
+///
+/// #[derive(Clone, Copy)]
+/// struct Bounds(f32, f32);
+///
+/// impl Memoize for Bounds {
+///     type Param = Vec<f32>;
+///     fn memoize(xs: &Vec<f32>) -> Self {
+///         let min = xs.iter().copied().fold(f32::INFINITY, f32::min);
+///         let max = xs.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+///         Bounds(min, max)
+///     }
+/// }
+///
+/// let memo: MemoCopy<Bounds> = MemoCopy::new(vec![2.0, -1.0, 5.0]);
+/// let Bounds(min, max) = memo.get();
+/// assert_eq!((min, max), (-1.0, 5.0));
+///
+/// memo.clear();
+/// assert!(memo.try_get().is_none());
+/// ```
+///
+pub struct MemoCopy<T, P = <T as Memoize>::Param> {
+    value: Cell<Option<T>>,
+    param: P,
+}
+
+impl<T: Copy, P> MemoCopy<T, P> {
+    /// Creates a new `MemoCopy` instance
+    pub const fn new(p: P) -> Self {
+        Self {
+            value: Cell::new(None),
+            param: p,
+        }
+    }
+
+    /// Clears any cached value
+    ///
+    /// The value will be reevaluated the next time it is needed.
+    pub fn clear(&self) {
+        self.value.set(None);
+    }
+
+    /// Check if there is a cached value
+    ///
+    /// If this method returns `true`, the next call to `get()` will return the
+    /// stored memoized value.
+    pub fn is_ready(&self) -> bool {
+        self.value.get().is_some()
+    }
+
+    /// Get the value if it is available
+    ///
+    /// If there is a cached value, returns it. If the value needs to be
+    /// computed, returns `None`.
+    pub fn try_get(&self) -> Option<T> {
+        self.value.get()
+    }
+
+    /// Get a reference to the parameter used for the computation
+    pub fn param(&self) -> &P {
+        &self.param
+    }
+
+    /// Get a mutable reference to the parameter used for the computation
+    ///
+    /// This clears any cached value.
+    pub fn param_mut(&mut self) -> &mut P {
+        self.clear();
+        &mut self.param
+    }
+
+    /// Get the parameter back, dropping the memo
+    pub fn into_param(self) -> P {
+        self.param
+    }
+}
+
+impl<T: Memoize + Copy, P: Borrow<T::Param>> MemoCopy<T, P> {
+    /// Get the value
+    ///
+    /// If the value has already been computed, this function returns the
+    /// cached value. If not, it is computed and cached for future use.
+    pub fn get(&self) -> T {
+        match self.value.get() {
+            Some(value) => value,
+            None => {
+                let value = T::memoize(self.param.borrow());
+                self.value.set(Some(value));
+                value
+            }
+        }
+    }
+}
+
+impl<T: Copy, P> Invalidate for MemoCopy<T, P> {
+    fn invalidate(&mut self) {
+        self.clear()
+    }
+}
+
+impl<T: Copy, P: Clone> Clone for MemoCopy<T, P> {
+    fn clone(&self) -> Self {
+        Self {
+            value: self.value.clone(),
+            param: self.param.clone(),
+        }
+    }
+}
+
+impl<T: Copy + fmt::Debug, P: fmt::Debug> fmt::Debug for MemoCopy<T, P> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("MemoCopy")
+            .field("value", &self.value)
+            .field("param", &self.param)
+            .finish()
+    }
+}
@@ -0,0 +1,160 @@
+use std::sync::mpsc::Receiver;
+
+use Invalidate;
+
+/// Memo wrapper which is invalidated by messages received over a channel
+///
+/// Requires the `std` feature.
+///
+/// This lets other threads request invalidation of a memo, without needing
+/// mutable access to it. They only need a `Sender` for the channel.
+///
+/// Any pending messages are drained every time you call `sync()`, which
+/// gives you mutable access to the memo. By default, every message
+/// invalidates the memo. Use `with_filter()` to only react to some messages
+/// (for example, to specific topics), or `with_handler()` to handle each
+/// message with mutable access to the memo (for example, to remove only the
+/// key named by the message from a `MemoCache`).
+///
+/// ## Example
+///
+/// ```
+/// use core_memo::{Memoize, Memo, Listener};
+/// use std::sync::mpsc::channel;
+/// use std::thread;
+///
+/// struct Double(u32);
+///
+/// impl Memoize for Double {
+///     type Param = u32;
+///     fn memoize(p: &u32) -> Self {
+///         Double(p * 2)
+///     }
+/// }
+///
+/// let (tx, rx) = channel();
+/// let mut memo = Listener::new(Memo::<Double>::new(21), rx);
+///
+/// assert_eq!(memo.sync().get().0, 42);
+///
+/// thread::spawn(move || tx.send(()).unwrap()).join().unwrap();
+///
+/// // the message is received and the memo is cleared
+/// assert!(!memo.sync().is_ready());
+/// ```
+///
+#[derive(Debug)]
+pub struct Listener<M, Msg, H = fn(&mut M, Msg)> {
+    memo: M,
+    receiver: Receiver<Msg>,
+    handler: H,
+}
+
+fn invalidate_all<M: Invalidate, Msg>(memo: &mut M, _msg: Msg) {
+    memo.invalidate();
+}
+
+impl<M: Invalidate, Msg> Listener<M, Msg> {
+    /// Creates a new `Listener` instance
+    ///
+    /// Every message received over the channel invalidates the memo.
+    pub fn new(memo: M, receiver: Receiver<Msg>) -> Self {
+        Self {
+            memo,
+            receiver,
+            handler: invalidate_all::<M, Msg>,
+        }
+    }
+
+    /// Creates a new `Listener` instance, which only reacts to some messages
+    ///
+    /// The memo is invalidated only by messages for which `filter` returns
+    /// `true`. Other messages are discarded.
+    pub fn with_filter<F>(
+        memo: M,
+        receiver: Receiver<Msg>,
+        mut filter: F,
+    ) -> Listener<M, Msg, impl FnMut(&mut M, Msg)>
+    where
+        F: FnMut(&Msg) -> bool,
+    {
+        Listener::with_handler(memo, receiver, move |memo: &mut M, msg| {
+            if filter(&msg) {
+                memo.invalidate();
+            }
+        })
+    }
+}
+
+impl<M, Msg, H: FnMut(&mut M, Msg)> Listener<M, Msg, H> {
+    /// Creates a new `Listener` instance, which handles every message with
+    /// `handler`
+    ///
+    /// The handler is given mutable access to the memo, and the message, so
+    /// it can decide what the message means for the memo (like a new value for
+    /// its parameter).
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// use core_memo::{Memoize, Memo, Listener};
+    /// use std::sync::mpsc::channel;
+    ///
+    /// struct Len(usize);
+    ///
+    /// impl Memoize for Len {
+    ///     type Param = String;
+    ///     fn memoize(p: &String) -> Self {
+    ///         Len(p.len())
+    ///     }
+    /// }
+    ///
+    /// let (tx, rx) = channel();
+    /// let mut memo = Listener::with_handler(Memo::<Len>::new("hi".into()), rx, |memo, name| {
+    ///     if *memo.param() != name {
+    ///         *memo.param_mut() = name;
+    ///     }
+    /// });
+    /// assert_eq!(memo.sync().get().0, 2);
+    ///
+    /// // the same name again keeps the cached value
+    /// tx.send("hi".to_string()).unwrap();
+    /// assert!(memo.sync().is_ready());
+    ///
+    /// tx.send("hello".to_string()).unwrap();
+    /// assert_eq!(memo.sync().get().0, 5);
+    /// ```
+    pub fn with_handler(memo: M, receiver: Receiver<Msg>, handler: H) -> Self {
+        Self {
+            memo,
+            receiver,
+            handler,
+        }
+    }
+
+    /// Apply any pending invalidations and get the memo
+    ///
+    /// Drains all messages currently in the channel, and handles them in
+    /// order, before returning the memo.
+    ///
+    /// If all senders have been dropped, the memo is left as it is.
+    pub fn sync(&mut self) -> &mut M {
+        while let Ok(msg) = self.receiver.try_recv() {
+            (self.handler)(&mut self.memo, msg);
+        }
+
+        &mut self.memo
+    }
+
+    /// Get a reference to the memo without syncing
+    ///
+    /// The memo may hold a stale value, if there are pending messages.
+    pub fn memo(&self) -> &M {
+        &self.memo
+    }
+
+    /// Stop listening and get the memo and the receiver back
+    pub fn into_inner(self) -> (M, Receiver<Msg>) {
+        (self.memo, self.receiver)
+    }
+}
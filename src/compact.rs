@@ -0,0 +1,144 @@
+#[cfg(feature = "alloc")]
+use alloc::boxed::Box;
+#[cfg(feature = "alloc")]
+use alloc::collections::{BinaryHeap, VecDeque};
+#[cfg(feature = "alloc")]
+use alloc::string::String;
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use std::collections::{HashMap, HashSet};
+#[cfg(feature = "std")]
+use std::hash::{BuildHasher, Hash};
+
+use {Memo, MemoExt, MemoMut, MemoOnce, Memoize};
+
+/// Values which can release memory they don't need, without changing
+///
+/// Implement this for your cached values, to free their spare capacity (like
+/// with `Vec::shrink_to_fit()`) during memory-pressure events, without
+/// invalidating them. It is implemented for the collections in `alloc` and
+/// `std`, for `Option` and `Box`, and for the memoization types (compacting
+/// the cached value, if there is one).
+///
+/// Call `compact()` on a memo directly, or register it in a `Registry` with
+/// `enable_compact()`, to compact it in `Registry::compact_all()`.
+///
+/// ## Example
+///
+/// ```
+/// use core_memo::{Compact, Memo, Memoize};
+///
+/// struct Primes(Vec<u32>);
+///
+/// impl Memoize for Primes {
+///     type Param = u32;
+///     fn memoize(n: &u32) -> Self {
+///         let mut primes = Vec::with_capacity(*n as usize);
+///         for i in 2..*n {
+///             if primes.iter().all(|p| i % p != 0) {
+///                 primes.push(i);
+///             }
+///         }
+///         Primes(primes)
+///     }
+/// }
+///
+/// impl Compact for Primes {
+///     fn compact(&mut self) {
+///         self.0.shrink_to_fit();
+///     }
+/// }
+///
+/// let mut memo: Memo<Primes> = Memo::new(100);
+/// assert_eq!(memo.get().0.len(), 25);
+///
+/// memo.compact();
+/// assert_eq!(memo.try_get().unwrap().0.capacity(), 25);
+/// ```
+///
+pub trait Compact {
+    /// Release memory which is not needed for the value
+    fn compact(&mut self);
+}
+
+impl<T: Compact> Compact for Option<T> {
+    fn compact(&mut self) {
+        if let Some(ref mut value) = *self {
+            value.compact();
+        }
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<T: Compact + ?Sized> Compact for Box<T> {
+    fn compact(&mut self) {
+        (**self).compact();
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl Compact for String {
+    fn compact(&mut self) {
+        self.shrink_to_fit();
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<T> Compact for Vec<T> {
+    fn compact(&mut self) {
+        self.shrink_to_fit();
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<T> Compact for VecDeque<T> {
+    fn compact(&mut self) {
+        self.shrink_to_fit();
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<T: Ord> Compact for BinaryHeap<T> {
+    fn compact(&mut self) {
+        self.shrink_to_fit();
+    }
+}
+
+#[cfg(feature = "std")]
+impl<K: Eq + Hash, V, S: BuildHasher> Compact for HashMap<K, V, S> {
+    fn compact(&mut self) {
+        self.shrink_to_fit();
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T: Eq + Hash, S: BuildHasher> Compact for HashSet<T, S> {
+    fn compact(&mut self) {
+        self.shrink_to_fit();
+    }
+}
+
+impl<T: Compact> Compact for MemoExt<T> {
+    fn compact(&mut self) {
+        self.value.compact();
+    }
+}
+
+impl<T: Compact, P> Compact for Memo<T, P> {
+    fn compact(&mut self) {
+        self.value.compact();
+    }
+}
+
+impl<'p, T: Compact, R> Compact for MemoOnce<'p, T, R> {
+    fn compact(&mut self) {
+        self.value.compact();
+    }
+}
+
+impl<'p, T: Memoize + Compact> Compact for MemoMut<'p, T> {
+    fn compact(&mut self) {
+        self.value.compact();
+    }
+}
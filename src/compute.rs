@@ -0,0 +1,115 @@
+use core::fmt;
+use core::ops::Deref;
+
+use Memoize;
+
+/// Represents a computation whose output can be any type
+///
+/// `Memoize` is implemented on the output of the computation, so the output
+/// has to be a type of your own (typically a newtype around the actual value).
+/// This trait is implemented on a separate type (typically an empty marker
+/// struct) instead, so the output can be anything, like a `String` or a
+/// `Vec<f32>`. Memoize it by using `Output<C>` as the value type of any of the
+/// memoization types.
+///
+/// Every `Memoize` type is also a `Compute` type, with itself as the output.
+///
+/// ## Example
+///
+/// ```
+/// use core_memo::{Compute, Memo, Output};
+///
+/// struct Lines;
+///
+/// impl Compute for Lines {
+///     type Param = str;
+///     type Output = Vec<String>;
+///     fn compute(p: &str) -> Vec<String> {
+///         p.lines().map(String::from).collect()
+///     }
+/// }
+///
+/// let mut memo: Memo<Output<Lines>, String> = Memo::new("a\nb".into());
+///
+/// // `Output` derefs to the actual output
+/// let lines: &Vec<String> = memo.get();
+/// assert_eq!(lines, &["a", "b"]);
+/// ```
+///
+pub trait Compute {
+    /// The input type for the computation
+    type Param: ?Sized;
+
+    /// The type of the computed values
+    type Output;
+
+    /// The function to compute the value
+    fn compute(p: &Self::Param) -> Self::Output;
+}
+
+impl<T: Memoize> Compute for T {
+    type Param = T::Param;
+    type Output = T;
+
+    fn compute(p: &T::Param) -> T {
+        T::memoize(p)
+    }
+}
+
+/// The output of a `Compute` type, which can be memoized
+///
+/// This implements `Memoize` for any `Compute` type, and derefs to the actual
+/// output. See `Compute` for an example.
+pub struct Output<C: Compute>(C::Output);
+
+impl<C: Compute> Output<C> {
+    /// Get the actual output
+    pub fn into_inner(self) -> C::Output {
+        self.0
+    }
+}
+
+impl<C: Compute> Memoize for Output<C> {
+    type Param = C::Param;
+
+    fn memoize(p: &C::Param) -> Self {
+        Output(C::compute(p))
+    }
+}
+
+impl<C: Compute> Deref for Output<C> {
+    type Target = C::Output;
+
+    fn deref(&self) -> &C::Output {
+        &self.0
+    }
+}
+
+impl<C: Compute> Clone for Output<C>
+where
+    C::Output: Clone,
+{
+    fn clone(&self) -> Self {
+        Output(self.0.clone())
+    }
+}
+
+impl<C: Compute> PartialEq for Output<C>
+where
+    C::Output: PartialEq,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+}
+
+impl<C: Compute> Eq for Output<C> where C::Output: Eq {}
+
+impl<C: Compute> fmt::Debug for Output<C>
+where
+    C::Output: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
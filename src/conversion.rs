@@ -0,0 +1,84 @@
+use core::fmt;
+use core::marker::PhantomData;
+use core::ops::Deref;
+
+use Memoize;
+
+/// A value converted from the parameter, which can be memoized
+///
+/// This implements `Memoize` for any type which implements `From<&P>`, so
+/// that such types can be used with `Memo` and the other memoization types,
+/// without writing a `Memoize` impl which only calls `from()`. It derefs to
+/// the value.
+///
+/// (`Memoize` can not be implemented for those types directly, with a
+/// blanket impl: the parameter type would not be determined by the type of
+/// the value.)
+///
+/// ## Example
+///
+/// ```
+/// use core_memo::{FromMemo, Memo};
+///
+/// struct Initials(String);
+///
+/// impl From<&str> for Initials {
+///     fn from(name: &str) -> Self {
+///         Initials(name.split(' ').filter_map(|w| w.chars().next()).collect())
+///     }
+/// }
+///
+/// let mut memo: Memo<FromMemo<Initials, str>, &str> = Memo::new("Ada King Lovelace");
+///
+/// assert_eq!(memo.get().0, "AKL");
+/// ```
+///
+pub struct FromMemo<T, P: ?Sized>(T, PhantomData<fn(&P)>);
+
+impl<T, P: ?Sized> FromMemo<T, P> {
+    /// Get the value back
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T, P: ?Sized> Deref for FromMemo<T, P> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T, P: ?Sized> Memoize for FromMemo<T, P>
+where
+    T: for<'a> From<&'a P>,
+{
+    type Param = P;
+
+    fn memoize(p: &P) -> Self {
+        FromMemo(T::from(p), PhantomData)
+    }
+}
+
+// not derived, as that would require the parameter type to implement them too
+
+impl<T: fmt::Debug, P: ?Sized> fmt::Debug for FromMemo<T, P> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("FromMemo").field(&self.0).finish()
+    }
+}
+
+impl<T: Clone, P: ?Sized> Clone for FromMemo<T, P> {
+    fn clone(&self) -> Self {
+        FromMemo(self.0.clone(), PhantomData)
+    }
+}
+
+impl<T: PartialEq, P: ?Sized> PartialEq for FromMemo<T, P> {
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+}
+
+impl<T: Eq, P: ?Sized> Eq for FromMemo<T, P> {}
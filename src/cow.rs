@@ -0,0 +1,101 @@
+use alloc::borrow::Cow;
+use core::borrow::Borrow;
+use core::ops::Deref;
+
+use {Memo, MemoExt, MemoMut, MemoOnce, Memoize};
+
+impl<T: Memoize + Clone> MemoExt<T> {
+    /// Get the value, without caching it
+    ///
+    /// Requires the `alloc` feature.
+    ///
+    /// If there is a cached value, it is borrowed. If not, it is computed and
+    /// returned owned, without being cached. This only needs `&self`, so it
+    /// works where the memo cannot be mutated, at the cost of computing the
+    /// value every time until something calls `get()` or `ready()`.
+    pub fn get_cow<B: Borrow<T::Param> + ?Sized>(&self, p: &B) -> Cow<'_, T> {
+        match self.try_get() {
+            Some(value) => Cow::Borrowed(value),
+            None => Cow::Owned(T::memoize(p.borrow())),
+        }
+    }
+}
+
+impl<T: Memoize + Clone, P: Borrow<T::Param>> Memo<T, P> {
+    /// Get the value, without caching it
+    ///
+    /// Requires the `alloc` feature.
+    ///
+    /// If there is a cached value, it is borrowed. If not, it is computed and
+    /// returned owned, without being cached. This only needs `&self`, so it
+    /// works where the memo cannot be mutated, at the cost of computing the
+    /// value every time until something calls `get()` or `ready()`.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// use std::borrow::Cow;
+    /// use core_memo::{Memoize, Memo};
+    ///
+    /// #[derive(Clone)]
+    /// struct Sum(i32);
+    ///
+    /// impl Memoize for Sum {
+    ///     type Param = [i32];
+    ///     fn memoize(p: &[i32]) -> Self {
+    ///         Sum(p.iter().sum())
+    ///     }
+    /// }
+    ///
+    /// fn show(memo: &Memo<Sum, Vec<i32>>) -> String {
+    ///     format!("total: {}", memo.get_cow().0)
+    /// }
+    ///
+    /// let mut memo: Memo<Sum, Vec<i32>> = Memo::new(vec![1, 2, 3]);
+    /// assert_eq!(show(&memo), "total: 6");
+    /// assert!(matches!(memo.get_cow(), Cow::Owned(_)));
+    ///
+    /// memo.ready();
+    /// assert!(matches!(memo.get_cow(), Cow::Borrowed(_)));
+    /// ```
+    ///
+    pub fn get_cow(&self) -> Cow<'_, T> {
+        match self.try_get() {
+            Some(value) => Cow::Borrowed(value),
+            None => Cow::Owned(T::memoize(self.param.borrow())),
+        }
+    }
+}
+
+impl<'p, T: Memoize + Clone, R: Deref> MemoOnce<'p, T, R>
+where
+    R::Target: Borrow<T::Param>,
+{
+    /// Get the value, without caching it
+    ///
+    /// Requires the `alloc` feature.
+    ///
+    /// If there is a cached value, it is borrowed. If not, it is computed and
+    /// returned owned, without being cached.
+    pub fn get_cow(&self) -> Cow<'_, T> {
+        match self.try_get() {
+            Some(value) => Cow::Borrowed(value),
+            None => Cow::Owned(T::memoize(self.param())),
+        }
+    }
+}
+
+impl<'p, T: Memoize + Clone> MemoMut<'p, T> {
+    /// Get the value, without caching it
+    ///
+    /// Requires the `alloc` feature.
+    ///
+    /// If there is a cached value, it is borrowed. If not, it is computed and
+    /// returned owned, without being cached.
+    pub fn get_cow(&self) -> Cow<'_, T> {
+        match self.try_get() {
+            Some(value) => Cow::Borrowed(value),
+            None => Cow::Owned(T::memoize(self.param())),
+        }
+    }
+}
@@ -0,0 +1,149 @@
+use core::borrow::Borrow;
+use core::cell::UnsafeCell;
+use core::fmt;
+
+use {Invalidate, Memoize};
+
+/// Memoized value which can be shared with interrupt handlers
+///
+/// Requires the `critical-section` feature.
+///
+/// Like `Memo`, this holds ownership over the parameter for the computation.
+/// But `get()` only needs `&self`, so it can be used from a `static`, by the
+/// main program and by interrupt handlers (or other cores) alike. Every access
+/// checks the value in a short critical section, using whichever
+/// `critical-section` implementation the program links in.
+///
+/// The computation itself runs outside of the critical section, so that
+/// interrupts are not held up by it. If an interrupt handler needs the value
+/// while it is being computed, it computes it too, and the first computation
+/// to finish is cached.
+///
+/// ## Example
+///
+/// ```
+/// use core_memo::{Memoize, MemoCritical};
+///
+/// struct Divider(u32);
+///
+/// impl Memoize for Divider {
+///     type Param = u32;
+///     fn memoize(clock_hz: &u32) -> Self {
+///         Divider(clock_hz / 115_200)
+///     }
+/// }
+///
+/// static UART_DIVIDER: MemoCritical<Divider> = MemoCritical::new(48_000_000);
+///
+/// fn on_uart_interrupt() -> u32 {
+///     UART_DIVIDER.get().0
+/// }
+///
+/// assert_eq!(on_uart_interrupt(), 416);
+/// ```
+///
+pub struct MemoCritical<T, P = <T as Memoize>::Param> {
+    value: UnsafeCell<Option<T>>,
+    param: P,
+}
+
+// SAFETY: the value is only written inside a critical section, while it is
+// `None`, so nobody can have a reference to it. Once it is `Some`, it is only
+// read, until it is cleared through `&mut self`. So it is shared like a `&T`,
+// and sent like a `T`.
+unsafe impl<T: Send + Sync, P: Sync> Sync for MemoCritical<T, P> {}
+
+impl<T, P> MemoCritical<T, P> {
+    /// Creates a new `MemoCritical` instance
+    pub const fn new(p: P) -> Self {
+        Self {
+            value: UnsafeCell::new(None),
+            param: p,
+        }
+    }
+
+    /// Clears any cached value
+    ///
+    /// The value will be reevaluated the next time it is needed.
+    pub fn clear(&mut self) {
+        *self.value.get_mut() = None;
+    }
+
+    /// Check if there is a cached value
+    ///
+    /// If this method returns `true`, the next call to `get()` will return the
+    /// stored memoized value.
+    pub fn is_ready(&self) -> bool {
+        self.try_get().is_some()
+    }
+
+    /// Get the value if it is available
+    ///
+    /// If there is a cached value, returns it. If the value needs to be
+    /// computed, returns `None`.
+    pub fn try_get(&self) -> Option<&T> {
+        // SAFETY: it is only written while it is `None`, in a critical
+        // section, which this waits for
+        critical_section::with(|_| unsafe { (*self.value.get()).as_ref() })
+    }
+
+    /// Get a reference to the parameter used for the computation
+    pub fn param(&self) -> &P {
+        &self.param
+    }
+
+    /// Get a mutable reference to the parameter used for the computation
+    ///
+    /// This clears any cached value.
+    pub fn param_mut(&mut self) -> &mut P {
+        self.clear();
+        &mut self.param
+    }
+
+    /// Get the parameter back, dropping the memo
+    pub fn into_param(self) -> P {
+        self.param
+    }
+}
+
+impl<T: Memoize, P: Borrow<T::Param>> MemoCritical<T, P> {
+    /// Get the value
+    ///
+    /// If the value has already been computed, this function returns the
+    /// cached value. If not, it is computed and cached for future use, unless
+    /// it is cached by someone else (like an interrupt handler) in the
+    /// meantime.
+    pub fn get(&self) -> &T {
+        if let Some(value) = self.try_get() {
+            return value;
+        }
+
+        let value = T::memoize(self.param.borrow());
+        critical_section::with(|_| {
+            // SAFETY: nobody else can access it during the critical section,
+            // and nobody has a reference to it while it is `None`
+            let slot = self.value.get();
+            unsafe {
+                if (*slot).is_none() {
+                    *slot = Some(value);
+                }
+                (*slot).as_ref().unwrap()
+            }
+        })
+    }
+}
+
+impl<T, P> Invalidate for MemoCritical<T, P> {
+    fn invalidate(&mut self) {
+        self.clear()
+    }
+}
+
+impl<T: fmt::Debug, P: fmt::Debug> fmt::Debug for MemoCritical<T, P> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("MemoCritical")
+            .field("value", &self.try_get())
+            .field("param", &self.param)
+            .finish()
+    }
+}
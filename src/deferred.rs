@@ -0,0 +1,90 @@
+use core::any;
+
+use defmt::{Format, Formatter};
+
+use {Memo, MemoExt, MemoMut, MemoOnce, Memoize};
+
+/// Formats the cached value, if there is one
+///
+/// Requires the `defmt` feature.
+///
+/// ## Example
+///
+/// ```
+/// extern crate defmt;
+/// # extern crate core_memo;
+///
+/// use core_memo::{Memoize, Memo};
+///
+/// #[derive(defmt::Format)]
+/// struct Reading(u16);
+///
+/// impl Memoize for Reading {
+///     type Param = u16;
+///     fn memoize(p: &u16) -> Self {
+///         Reading(p * 4)
+///     }
+/// }
+///
+/// fn log(memo: &Memo<Reading>) {
+///     // like `Memo { value: Some(Reading(2048)), param: 512 }`
+///     defmt::info!("{}", memo);
+/// }
+/// # fn main() {}
+/// ```
+///
+impl<T: Format> Format for MemoExt<T> {
+    fn format(&self, f: Formatter) {
+        defmt::write!(f, "MemoExt {{ value: {} }}", self.value)
+    }
+}
+
+/// Formats the cached value, if there is one, and the parameter
+///
+/// Requires the `defmt` feature.
+impl<T: Format, P: Format> Format for Memo<T, P> {
+    fn format(&self, f: Formatter) {
+        defmt::write!(f, "Memo {{ value: {}, param: {} }}", self.value, self.param)
+    }
+}
+
+/// Formats the cached value, if there is one, and the parameter
+///
+/// Requires the `defmt` feature.
+impl<'p, T: Format, R: Format> Format for MemoOnce<'p, T, R> {
+    fn format(&self, f: Formatter) {
+        defmt::write!(
+            f,
+            "MemoOnce {{ value: {}, param: {} }}",
+            self.value,
+            self.param
+        )
+    }
+}
+
+/// Formats the cached value, if there is one, and the parameter
+///
+/// Requires the `defmt` feature.
+impl<'p, T: Memoize + Format> Format for MemoMut<'p, T>
+where
+    T::Param: Format,
+{
+    fn format(&self, f: Formatter) {
+        defmt::write!(
+            f,
+            "MemoMut {{ value: {}, param: {} }}",
+            self.value,
+            &*self.param
+        )
+    }
+}
+
+/// Emit the event for a value of type `T` being computed
+pub(crate) fn computing<T>() {
+    defmt::trace!("core_memo: computing {=str}", any::type_name::<T>());
+}
+
+/// Emit the event for a cached value of type `T` being cleared
+pub(crate) fn cleared<T>() {
+    defmt::trace!("core_memo: cleared {=str}", any::type_name::<T>());
+}
@@ -0,0 +1,562 @@
+#[cfg(feature = "std")]
+use alloc::boxed::Box;
+#[cfg(feature = "std")]
+use alloc::collections::VecDeque;
+use core::any;
+#[cfg(feature = "std")]
+use core::borrow::Borrow;
+use core::fmt;
+#[cfg(feature = "std")]
+use core::hash::Hash;
+use core::mem;
+#[cfg(feature = "std")]
+use core::ops::Deref;
+use core::ptr;
+use core::sync::atomic::{AtomicPtr, Ordering};
+#[cfg(feature = "std")]
+use core::time::Duration;
+#[cfg(feature = "std")]
+use std::time::Instant;
+
+#[cfg(feature = "std")]
+use hash::stable_hash;
+use sync::plain::AtomicU8;
+#[cfg(feature = "std")]
+use {Memo, MemoExt, MemoMut, MemoOnce, Memoize};
+
+/// Some methods of the memo were called
+const USED: u8 = 1;
+/// The memo computed a value
+const COMPUTED: u8 = 2;
+/// The value was read after it was computed
+const READ: u8 = 4;
+
+/// The hook set with `set_waste_hook()`, or null
+static HOOK: AtomicPtr<()> = AtomicPtr::new(ptr::null_mut());
+/// The hook set with `set_slow_hook()`, or null
+#[cfg(feature = "std")]
+static SLOW_HOOK: AtomicPtr<()> = AtomicPtr::new(ptr::null_mut());
+
+/// Kind of wasted work found by the `diagnostics` feature
+///
+/// Requires the `diagnostics` feature.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Waste {
+    /// The memo was dropped without ever being used
+    NeverUsed,
+    /// The memo computed a value which was never read
+    NeverRead,
+}
+
+/// Report of wasted work, for a memo which was just dropped
+///
+/// Requires the `diagnostics` feature.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WasteReport {
+    /// What was wasted
+    pub waste: Waste,
+    /// The type of the memo
+    pub memo: &'static str,
+}
+
+impl fmt::Display for WasteReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.waste {
+            Waste::NeverUsed => write!(f, "{} was dropped without being used", self.memo),
+            Waste::NeverRead => write!(f, "{} computed a value which was never read", self.memo),
+        }
+    }
+}
+
+/// Set the function which is called with reports of wasted work
+///
+/// Requires the `diagnostics` feature.
+///
+/// In debug builds, `Memo`, `MemoExt`, `MemoOnce`, and `MemoMut` check how they were used
+/// when they are dropped. If they were never used, or if they computed a value
+/// which was never read, they report it to this hook. Use this to find wasted
+/// computations and dead caches.
+///
+/// Without a hook, reports are printed to stderr (with the `std` feature), or
+/// ignored (without it).
+///
+/// ## Example
+///
+/// ```
+/// use core_memo::{set_waste_hook, Memo, Memoize, Waste, WasteReport};
+///
+/// struct Answer(i32);
+///
+/// impl Memoize for Answer {
+///     type Param = ();
+///     fn memoize(_: &()) -> Self {
+///         Answer(42)
+///     }
+/// }
+///
+/// fn panic_on_waste(report: &WasteReport) {
+///     if report.waste == Waste::NeverRead {
+///         panic!("{}", report);
+///     }
+/// }
+///
+/// set_waste_hook(panic_on_waste);
+///
+/// let mut memo: Memo<Answer> = Memo::new(());
+/// assert_eq!(memo.get().0, 42);
+/// drop(memo); // fine, the value was read
+/// ```
+///
+pub fn set_waste_hook(hook: fn(&WasteReport)) {
+    HOOK.store(hook as *mut (), Ordering::Release);
+}
+
+/// Call the hook with a report, or print it
+fn report(report: &WasteReport) {
+    let hook = HOOK.load(Ordering::Acquire);
+    if !hook.is_null() {
+        // SAFETY: only `set_waste_hook()` stores non-null pointers, which come
+        // from a function pointer of this type
+        let hook = unsafe { mem::transmute::<*mut (), fn(&WasteReport)>(hook) };
+        hook(report);
+    } else {
+        #[cfg(feature = "std")]
+        std::eprintln!("core_memo: {}", report);
+    }
+}
+
+/// Report of a computation which took longer than the budget of its memo
+///
+/// Requires the `diagnostics` and `std` features.
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SlowReport {
+    /// The type of the memo
+    pub memo: &'static str,
+    /// How long the computation took
+    pub elapsed: Duration,
+    /// The budget set with `set_budget()`
+    pub budget: Duration,
+}
+
+#[cfg(feature = "std")]
+impl fmt::Display for SlowReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} took {:?} to compute its value, over its budget of {:?}",
+            self.memo, self.elapsed, self.budget
+        )
+    }
+}
+
+/// Set the function which is called with reports of slow computations
+///
+/// Requires the `diagnostics` and `std` features.
+///
+/// In debug builds, memos which were given a budget with `set_budget()` time
+/// their computations, and report the ones which exceed it to this hook. Use
+/// this to find computations which are too slow to be repeated where they are
+/// (like on a UI thread). Forward the reports to your logging library, or
+/// panic, to make them impossible to miss.
+///
+/// Without a hook, reports are printed to stderr.
+///
+/// ## Example
+///
+/// ```
+/// use std::time::Duration;
+/// use core_memo::{set_slow_hook, Memo, Memoize, SlowReport};
+///
+/// struct Frame(u64);
+///
+/// impl Memoize for Frame {
+///     type Param = u64;
+///     fn memoize(p: &u64) -> Self {
+///         Frame(p + 1)
+///     }
+/// }
+///
+/// fn fail(report: &SlowReport) {
+///     panic!("{}", report);
+/// }
+///
+/// set_slow_hook(fail);
+///
+/// let mut memo: Memo<Frame> = Memo::new(0);
+/// memo.set_budget(Duration::from_millis(16));
+/// assert_eq!(memo.get().0, 1);
+/// ```
+///
+#[cfg(feature = "std")]
+pub fn set_slow_hook(hook: fn(&SlowReport)) {
+    SLOW_HOOK.store(hook as *mut (), Ordering::Release);
+}
+
+/// Call the slow computation hook with a report, or print it
+#[cfg(feature = "std")]
+fn report_slow(report: &SlowReport) {
+    let hook = SLOW_HOOK.load(Ordering::Acquire);
+    if !hook.is_null() {
+        // SAFETY: only `set_slow_hook()` stores non-null pointers, which come
+        // from a function pointer of this type
+        let hook = unsafe { mem::transmute::<*mut (), fn(&SlowReport)>(hook) };
+        hook(report);
+    } else {
+        std::eprintln!("core_memo: {}", report);
+    }
+}
+
+/// A computation recorded in the history of a memo
+///
+/// Requires the `diagnostics` and `std` features.
+///
+/// See `record_history()` on the memoization types. Replaying the events of a
+/// memo shows why it thrashed: the same fingerprints coming back over and over
+/// mean that the value is invalidated although the parameter does not change,
+/// and slow computations show what that costs.
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ComputeEvent {
+    /// The number of values the memo computed before this one, since recording
+    /// started
+    pub generation: u64,
+    /// A hash of the parameter, which is the same for equal parameters (even
+    /// across runs)
+    pub fingerprint: u64,
+    /// How long the computation took
+    pub elapsed: Duration,
+}
+
+#[cfg(feature = "std")]
+impl fmt::Display for ComputeEvent {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "#{}: computed for {:016x} in {:?}",
+            self.generation, self.fingerprint, self.elapsed
+        )
+    }
+}
+
+/// The most recent computations of a memo
+#[cfg(feature = "std")]
+struct History {
+    events: VecDeque<ComputeEvent>,
+    capacity: usize,
+    generation: u64,
+    /// The `fn(&Q) -> u64` which computes the fingerprints, for the parameter
+    /// type `Q` of the memo
+    fingerprint: fn(),
+}
+
+/// When a computation started, if it is being timed
+#[cfg(feature = "std")]
+pub(crate) type Start = Option<Instant>;
+#[cfg(not(feature = "std"))]
+pub(crate) type Start = Option<core::convert::Infallible>;
+
+/// Tracks how a memo is used, to report wasted work when it is dropped
+///
+/// This is a field of the memo, so that the memo itself does not need to
+/// implement `Drop` (which would prevent moving its fields out).
+pub(crate) struct Usage {
+    flags: AtomicU8,
+    memo: &'static str,
+    #[cfg(feature = "std")]
+    budget: Option<Duration>,
+    #[cfg(feature = "std")]
+    history: Option<Box<History>>,
+}
+
+impl Usage {
+    /// Track the usage of a memo of type `M`
+    pub(crate) fn new<M: ?Sized>() -> Self {
+        Usage {
+            flags: AtomicU8::new(0),
+            memo: any::type_name::<M>(),
+            #[cfg(feature = "std")]
+            budget: None,
+            #[cfg(feature = "std")]
+            history: None,
+        }
+    }
+
+    fn set(&self, flags: u8) {
+        self.flags.fetch_or(flags, Ordering::Relaxed);
+    }
+
+    /// Record that the memo was used
+    pub(crate) fn used(&self) {
+        self.set(USED);
+    }
+
+    /// Record that the memo computed a new value
+    pub(crate) fn computed(&mut self) {
+        let flags = self.flags.get_mut();
+        *flags = (*flags | USED | COMPUTED) & !READ;
+    }
+
+    /// Record that the value was read
+    pub(crate) fn read(&self) {
+        self.set(USED | READ);
+    }
+
+    /// Set how long computations may take, before they are reported
+    #[cfg(feature = "std")]
+    pub(crate) fn set_budget(&mut self, budget: Duration) {
+        self.budget = Some(budget);
+    }
+
+    /// Start recording the last `capacity` computations, with fingerprints of
+    /// parameters of type `Q`, or stop if `capacity` is zero
+    #[cfg(feature = "std")]
+    pub(crate) fn record_history<Q: Hash + ?Sized>(&mut self, capacity: usize) {
+        if capacity == 0 {
+            self.history = None;
+            return;
+        }
+
+        let fingerprint: fn(&Q) -> u64 = stable_hash::<Q>;
+        self.history = Some(Box::new(History {
+            events: VecDeque::with_capacity(capacity),
+            capacity,
+            generation: 0,
+            // SAFETY: only transmuted back to the same type in
+            // `computed_since()`, which the memo calls with its parameter
+            // type, like this method
+            fingerprint: unsafe { mem::transmute::<fn(&Q) -> u64, fn()>(fingerprint) },
+        }));
+    }
+
+    /// Iterate over the recorded computations, oldest first
+    #[cfg(feature = "std")]
+    pub(crate) fn history(&self) -> impl Iterator<Item = ComputeEvent> + '_ {
+        self.history.iter().flat_map(|h| h.events.iter().cloned())
+    }
+
+    /// Start timing a computation, if it needs to be
+    #[cfg(feature = "std")]
+    pub(crate) fn start(&self) -> Start {
+        let budget = cfg!(debug_assertions) && self.budget.is_some();
+        if budget || self.history.is_some() {
+            Some(Instant::now())
+        } else {
+            None
+        }
+    }
+
+    #[cfg(not(feature = "std"))]
+    pub(crate) fn start(&self) -> Start {
+        None
+    }
+
+    /// Record that the memo computed a new value from `p`, in a computation
+    /// which started at `start`, and report it if it was too slow
+    ///
+    /// `Q` must be the parameter type of the memo.
+    pub(crate) fn computed_since<Q: ?Sized>(&mut self, start: Start, p: &Q) {
+        self.computed();
+        #[cfg(feature = "std")]
+        if let Some(start) = start {
+            let elapsed = start.elapsed();
+            match self.budget {
+                Some(budget) if cfg!(debug_assertions) && elapsed > budget => {
+                    report_slow(&SlowReport {
+                        memo: self.memo,
+                        elapsed,
+                        budget,
+                    });
+                }
+                _ => {}
+            }
+
+            if let Some(ref mut history) = self.history {
+                // SAFETY: `record_history()` stored a function of this type
+                let fingerprint =
+                    unsafe { mem::transmute::<fn(), fn(&Q) -> u64>(history.fingerprint) };
+                if history.events.len() == history.capacity {
+                    history.events.pop_front();
+                }
+                history.events.push_back(ComputeEvent {
+                    generation: history.generation,
+                    fingerprint: fingerprint(p),
+                    elapsed,
+                });
+                history.generation += 1;
+            }
+        }
+        #[cfg(not(feature = "std"))]
+        let _ = (start, p);
+    }
+}
+
+impl Default for Usage {
+    /// Usage which is not tracked (like for a deserialized memo)
+    fn default() -> Self {
+        Usage {
+            flags: AtomicU8::new(USED),
+            memo: "",
+            #[cfg(feature = "std")]
+            budget: None,
+            #[cfg(feature = "std")]
+            history: None,
+        }
+    }
+}
+
+impl Drop for Usage {
+    /// Report wasted work, in debug builds
+    fn drop(&mut self) {
+        if !cfg!(debug_assertions) {
+            return;
+        }
+
+        let flags = *self.flags.get_mut();
+        let waste = if flags & USED == 0 {
+            Waste::NeverUsed
+        } else if flags & COMPUTED != 0 && flags & READ == 0 {
+            Waste::NeverRead
+        } else {
+            return;
+        };
+
+        report(&WasteReport {
+            waste,
+            memo: self.memo,
+        });
+    }
+}
+
+impl fmt::Debug for Usage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let flags = self.flags.load(Ordering::Relaxed);
+        f.debug_struct("Usage")
+            .field("used", &(flags & USED != 0))
+            .field("computed", &(flags & COMPUTED != 0))
+            .field("read", &(flags & READ != 0))
+            .finish()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T: Memoize> MemoExt<T>
+where
+    T::Param: Hash,
+{
+    /// Start recording the last `capacity` computations, or stop if it is zero
+    ///
+    /// Requires the `diagnostics` and `std` features.
+    ///
+    /// Every value computed from then on is recorded as a `ComputeEvent`, with
+    /// a fingerprint of the parameter and how long it took. Get them with
+    /// `history()`. Starting again clears the history.
+    pub fn record_history(&mut self, capacity: usize) {
+        self.usage.record_history::<T::Param>(capacity);
+    }
+
+    /// Iterate over the recorded computations, oldest first
+    ///
+    /// Requires the `diagnostics` and `std` features.
+    pub fn history(&self) -> impl Iterator<Item = ComputeEvent> + '_ {
+        self.usage.history()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T: Memoize, P: Borrow<T::Param>> Memo<T, P>
+where
+    T::Param: Hash,
+{
+    /// Start recording the last `capacity` computations, or stop if it is zero
+    ///
+    /// Requires the `diagnostics` and `std` features.
+    ///
+    /// Every value computed from then on is recorded as a `ComputeEvent`, with
+    /// a fingerprint of the parameter and how long it took. Get them with
+    /// `history()`. Starting again clears the history.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// use core_memo::{Memo, Memoize};
+    ///
+    /// struct Len(usize);
+    ///
+    /// impl Memoize for Len {
+    ///     type Param = str;
+    ///     fn memoize(p: &str) -> Self {
+    ///         Len(p.len())
+    ///     }
+    /// }
+    ///
+    /// let mut memo: Memo<Len, String> = Memo::new("a".into());
+    /// memo.record_history(16);
+    ///
+    /// for _ in 0..3 {
+    ///     memo.get();
+    ///     // oops, invalidated without any change
+    ///     memo.param_mut();
+    /// }
+    ///
+    /// let events: Vec<_> = memo.history().collect();
+    /// assert_eq!(events.len(), 3);
+    /// assert!(events.iter().all(|e| e.fingerprint == events[0].fingerprint));
+    /// ```
+    ///
+    pub fn record_history(&mut self, capacity: usize) {
+        self.usage.record_history::<T::Param>(capacity);
+    }
+
+    /// Iterate over the recorded computations, oldest first
+    ///
+    /// Requires the `diagnostics` and `std` features.
+    pub fn history(&self) -> impl Iterator<Item = ComputeEvent> + '_ {
+        self.usage.history()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<'p, T: Memoize, R: Deref> MemoOnce<'p, T, R>
+where
+    T::Param: Hash,
+    R::Target: Borrow<T::Param>,
+{
+    /// Start recording the last `capacity` computations, or stop if it is zero
+    ///
+    /// Requires the `diagnostics` and `std` features.
+    ///
+    /// See `Memo::record_history()`.
+    pub fn record_history(&mut self, capacity: usize) {
+        self.usage.record_history::<T::Param>(capacity);
+    }
+
+    /// Iterate over the recorded computations, oldest first
+    ///
+    /// Requires the `diagnostics` and `std` features.
+    pub fn history(&self) -> impl Iterator<Item = ComputeEvent> + '_ {
+        self.usage.history()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<'p, T: Memoize> MemoMut<'p, T>
+where
+    T::Param: Hash,
+{
+    /// Start recording the last `capacity` computations, or stop if it is zero
+    ///
+    /// Requires the `diagnostics` and `std` features.
+    ///
+    /// See `Memo::record_history()`.
+    pub fn record_history(&mut self, capacity: usize) {
+        self.usage.record_history::<T::Param>(capacity);
+    }
+
+    /// Iterate over the recorded computations, oldest first
+    ///
+    /// Requires the `diagnostics` and `std` features.
+    pub fn history(&self) -> impl Iterator<Item = ComputeEvent> + '_ {
+        self.usage.history()
+    }
+}
@@ -0,0 +1,229 @@
+use core::hash::Hash;
+use core::marker::PhantomData;
+use core::sync::atomic::{AtomicUsize, Ordering};
+use std::ffi::OsString;
+use std::format;
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+
+use bincode;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use hash::second_hash;
+use persist::Persisted;
+use Memoize;
+
+/// Memoization to files in a directory
+///
+/// Requires the `disk` feature.
+///
+/// Each cached value is stored in its own file, named after a hash of the
+/// parameter it was computed from (`<hash>.bin`). On a hit, the value is
+/// loaded from the file. On a miss, it is computed using `Memoize` and written
+/// to the file, so it can be loaded next time (even by another process).
+///
+/// Values are stored in a `Persisted` container, encoded with `bincode`. If
+/// a file is corrupted, or was written by an incompatible version, it is
+/// treated as a miss and overwritten. The container also holds a second hash
+/// of the parameter, computed differently than the file name, so a file
+/// which belongs to a different parameter is also treated as a miss, unless
+/// both hashes collide.
+///
+/// Files are written to a temporary file first, with a name unique to the
+/// writer, and then renamed, so that readers and concurrent writers never
+/// see a partially-written file.
+///
+/// Unlike the other memoization types, `DiskCache` does not keep the value in
+/// memory. Every call to `get()` returns a freshly loaded (or computed) value.
+///
+/// ## Example
+///
+/// ```no_run
+/// # extern crate core_memo;
+/// extern crate serde;
+///
+/// use core_memo::{Memoize, DiskCache};
+/// use serde::{Deserialize, Serialize};
+///
+/// #[derive(Serialize, Deserialize)]
+/// struct Thumbnail(Vec<u8>);
+///
+/// impl Memoize for Thumbnail {
+///     type Param = str;
+///     fn memoize(path: &str) -> Self {
+///         // some expensive asset processing
+///         Thumbnail(path.bytes().rev().collect())
+///     }
+/// }
+///
+/// # fn main() -> std::io::Result<()> {
+/// let cache: DiskCache<Thumbnail> = DiskCache::new("target/thumbnails")?;
+///
+/// // computed and written to disk the first time, loaded afterwards
+/// let thumb = cache.get("assets/logo.png")?;
+/// # Ok(())
+/// # }
+/// ```
+///
+#[derive(Debug)]
+pub struct DiskCache<T> {
+    dir: PathBuf,
+    marker: PhantomData<fn() -> T>,
+}
+
+impl<T: Memoize> DiskCache<T>
+where
+    T::Param: Hash,
+{
+    /// Creates a new `DiskCache` instance, storing files in `dir`
+    ///
+    /// The directory is created if it does not exist.
+    pub fn new<D: Into<PathBuf>>(dir: D) -> io::Result<Self> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)?;
+        Ok(Self {
+            dir,
+            marker: PhantomData,
+        })
+    }
+
+    /// Get the directory where the files are stored
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Get the path of the file for a parameter
+    ///
+    /// The file may or may not exist.
+    pub fn path(&self, p: &T::Param) -> PathBuf {
+        self.file(p, "bin")
+    }
+
+    /// Get the path of a file for a parameter, with the given extension
+    pub(crate) fn file(&self, p: &T::Param, ext: &str) -> PathBuf {
+        self.dir.join(format!("{:016x}.{}", second_hash(p), ext))
+    }
+
+    /// Check if there is a file for the parameter
+    ///
+    /// This does not check if the file is valid.
+    pub fn contains(&self, p: &T::Param) -> bool {
+        self.path(p).is_file()
+    }
+
+    /// Delete the files for a parameter, if they exist
+    ///
+    /// This deletes both the file for `get()` and the one for `get_mapped()`.
+    pub fn remove(&self, p: &T::Param) -> io::Result<()> {
+        for ext in ["bin", "map"].iter() {
+            match fs::remove_file(self.file(p, ext)) {
+                Err(ref e) if e.kind() == io::ErrorKind::NotFound => {}
+                r => r?,
+            }
+        }
+        Ok(())
+    }
+
+    /// Delete all cached files in the directory
+    ///
+    /// This includes the temporary files left behind by writes which failed.
+    /// Other files in the directory are left alone.
+    pub fn clear(&self) -> io::Result<()> {
+        for entry in fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            let name = path.file_name().and_then(|name| name.to_str());
+            if name.is_some_and(is_cache_file) {
+                match fs::remove_file(path) {
+                    // a temporary file may have been renamed in the meantime
+                    Err(ref e) if e.kind() == io::ErrorKind::NotFound => {}
+                    r => r?,
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<T> DiskCache<T>
+where
+    T: Memoize + Serialize + DeserializeOwned,
+    T::Param: Hash,
+{
+    /// Get the value
+    ///
+    /// If there is a valid file for the parameter, the value is loaded from it.
+    /// If not, it is computed and written to the file.
+    ///
+    /// Returns an error only if writing the file fails.
+    pub fn get(&self, p: &T::Param) -> io::Result<T> {
+        if let Some(value) = self.try_get(p) {
+            return Ok(value);
+        }
+
+        let value = T::memoize(p);
+        self.store(p, &value)?;
+        Ok(value)
+    }
+
+    /// Get the value if it is available
+    ///
+    /// If there is a valid file for the parameter, the value is loaded from it.
+    /// If not, returns `None`.
+    pub fn try_get(&self, p: &T::Param) -> Option<T> {
+        let file = File::open(self.path(p)).ok()?;
+        let persisted: Persisted<T> = bincode::deserialize_from(BufReader::new(file)).ok()?;
+        persisted.restore(p)
+    }
+
+    /// Force the value to be recomputed and written to the file
+    pub fn update(&self, p: &T::Param) -> io::Result<T> {
+        let value = T::memoize(p);
+        self.store(p, &value)?;
+        Ok(value)
+    }
+
+    fn store(&self, p: &T::Param, value: &T) -> io::Result<()> {
+        let path = self.path(p);
+        let tmp = temp_path(&path);
+
+        // write to a temporary file first, so that readers never see a
+        // partially-written file
+        {
+            let mut writer = BufWriter::new(File::create(&tmp)?);
+            bincode::serialize_into(&mut writer, &Persisted::new(value, p))
+                .map_err(io::Error::other)?;
+            writer.flush()?;
+        }
+
+        fs::rename(tmp, path)
+    }
+}
+
+/// Counter for the names of temporary files, so that writers in the same
+/// process don't share them
+static TEMP_FILES: AtomicUsize = AtomicUsize::new(0);
+
+/// Get a path for a temporary file, to be renamed to `path` once written
+///
+/// It is unique to this process and call, like `<hash>.bin.<pid>.<n>.tmp`.
+pub(crate) fn temp_path(path: &Path) -> PathBuf {
+    let n = TEMP_FILES.fetch_add(1, Ordering::Relaxed);
+    let mut name = OsString::from(path.file_name().unwrap());
+    name.push(format!(".{}.{}.tmp", process::id(), n));
+    path.with_file_name(name)
+}
+
+/// Check if a file name is one of ours: a cached value, or a temporary file
+fn is_cache_file(name: &str) -> bool {
+    let mut parts = name.split('.');
+    let hash = parts.next().unwrap_or("");
+    if hash.len() != 16 || !hash.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return false;
+    }
+    let ext = parts.next();
+    let tmp = parts.next_back();
+    (ext == Some("bin") || ext == Some("map")) && (tmp.is_none() || tmp == Some("tmp"))
+}
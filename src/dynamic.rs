@@ -0,0 +1,207 @@
+use alloc::boxed::Box;
+use core::any::{self, Any, TypeId};
+use core::fmt;
+use core::marker::PhantomData;
+
+use {Invalidate, Memoize};
+
+/// Object-safe version of `Memoize`, for computations chosen at runtime
+///
+/// Requires the `alloc` feature.
+///
+/// `Memoize` cannot be used as a trait object, because the computation is
+/// identified by the type of its output. This trait describes a computation
+/// by a value instead, which produces a type-erased output. Use it when the
+/// computations are not known at compile time (like when they are provided by
+/// plugins), and store them in a `MemoDyn`.
+///
+/// Any `Memoize` type can be turned into a `DynMemoize` with `Computation`.
+pub trait DynMemoize<P: ?Sized> {
+    /// Compute the value
+    fn memoize_dyn(&self, p: &P) -> Box<dyn Any>;
+
+    /// Get the type of the values produced by `memoize_dyn()`
+    fn output_type(&self) -> TypeId;
+
+    /// Get the name of the type of the values, for diagnostics
+    fn output_type_name(&self) -> &'static str {
+        "<unknown>"
+    }
+}
+
+/// Adapter implementing `DynMemoize` for a `Memoize` type
+///
+/// Requires the `alloc` feature.
+pub struct Computation<T>(PhantomData<fn() -> T>);
+
+impl<T> Computation<T> {
+    /// Creates a new `Computation` instance
+    pub fn new() -> Self {
+        Computation(PhantomData)
+    }
+}
+
+impl<T> Default for Computation<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> fmt::Debug for Computation<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Computation<{}>", any::type_name::<T>())
+    }
+}
+
+impl<T: Memoize + Any> DynMemoize<T::Param> for Computation<T> {
+    fn memoize_dyn(&self, p: &T::Param) -> Box<dyn Any> {
+        Box::new(T::memoize(p))
+    }
+
+    fn output_type(&self) -> TypeId {
+        TypeId::of::<T>()
+    }
+
+    fn output_type_name(&self) -> &'static str {
+        any::type_name::<T>()
+    }
+}
+
+/// Memoized value of a computation chosen at runtime
+///
+/// Requires the `alloc` feature.
+///
+/// Like `MemoExt`, but the computation is a `DynMemoize` trait object, and the
+/// value is type-erased. All `MemoDyn`s with the same parameter type have the
+/// same type, so they can be stored together. Use `get_as()` to access the
+/// value as its concrete type.
+///
+/// ## Example
+///
+/// ```
+/// use core_memo::{Memoize, MemoDyn};
+///
+/// struct Double(i32);
+///
+/// impl Memoize for Double {
+///     type Param = i32;
+///     fn memoize(p: &i32) -> Self {
+///         Double(p * 2)
+///     }
+/// }
+///
+/// struct Describe(String);
+///
+/// impl Memoize for Describe {
+///     type Param = i32;
+///     fn memoize(p: &i32) -> Self {
+///         Describe(format!("the number {}", p))
+///     }
+/// }
+///
+/// let mut memos: Vec<MemoDyn<i32>> = vec![MemoDyn::of::<Double>(), MemoDyn::of::<Describe>()];
+///
+/// for memo in &mut memos {
+///     memo.ready(&21);
+/// }
+///
+/// assert_eq!(memos[0].try_get_as::<Double>().unwrap().0, 42);
+/// assert_eq!(memos[1].try_get_as::<Describe>().unwrap().0, "the number 21");
+///
+/// // wrong type
+/// assert!(memos[0].try_get_as::<Describe>().is_none());
+/// ```
+///
+pub struct MemoDyn<P: ?Sized> {
+    computation: Box<dyn DynMemoize<P>>,
+    value: Option<Box<dyn Any>>,
+}
+
+impl<P: ?Sized> MemoDyn<P> {
+    /// Creates a new `MemoDyn` instance for a computation
+    pub fn new(computation: Box<dyn DynMemoize<P>>) -> Self {
+        Self {
+            computation,
+            value: None,
+        }
+    }
+
+    /// Creates a new `MemoDyn` instance for a `Memoize` type
+    pub fn of<T: Memoize<Param = P> + Any>() -> Self {
+        Self::new(Box::new(Computation::<T>::new()))
+    }
+
+    /// Get the computation
+    pub fn computation(&self) -> &dyn DynMemoize<P> {
+        &*self.computation
+    }
+
+    /// Clears any cached value
+    ///
+    /// The value will be reevaluated the next time it is needed.
+    pub fn clear(&mut self) {
+        self.value = None
+    }
+
+    /// Check if there is a cached value
+    pub fn is_ready(&self) -> bool {
+        self.value.is_some()
+    }
+
+    /// If the value is not ready, compute it and cache it
+    pub fn ready(&mut self, p: &P) {
+        if self.value.is_none() {
+            self.value = Some(self.computation.memoize_dyn(p));
+        }
+    }
+
+    /// Force the value to be recomputed
+    pub fn update(&mut self, p: &P) {
+        self.value = Some(self.computation.memoize_dyn(p));
+    }
+
+    /// Get the value
+    ///
+    /// If the value has already been computed, this function returns the cached
+    /// value. If not, it is computed and cached for future use.
+    pub fn get(&mut self, p: &P) -> &dyn Any {
+        self.ready(p);
+        self.try_get().unwrap()
+    }
+
+    /// Get the value if it is available
+    pub fn try_get(&self) -> Option<&dyn Any> {
+        self.value.as_deref()
+    }
+
+    /// Get the value as a concrete type
+    ///
+    /// Returns `None` if the computation does not produce values of type `U`.
+    /// In that case, nothing is computed.
+    pub fn get_as<U: Any>(&mut self, p: &P) -> Option<&U> {
+        if self.computation.output_type() != TypeId::of::<U>() {
+            return None;
+        }
+        self.get(p).downcast_ref()
+    }
+
+    /// Get the value as a concrete type, if it is available
+    pub fn try_get_as<U: Any>(&self) -> Option<&U> {
+        self.try_get()?.downcast_ref()
+    }
+}
+
+impl<P: ?Sized> Invalidate for MemoDyn<P> {
+    fn invalidate(&mut self) {
+        self.clear()
+    }
+}
+
+impl<P: ?Sized> fmt::Debug for MemoDyn<P> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("MemoDyn")
+            .field("output", &self.computation.output_type_name())
+            .field("ready", &self.is_ready())
+            .finish()
+    }
+}
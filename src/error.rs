@@ -0,0 +1,99 @@
+use core::convert::Infallible;
+use core::error::Error;
+use core::fmt;
+
+/// Error returned by the checked and fallible operations of this library
+///
+/// All of them use this one type, so that errors can be handled the same way,
+/// whichever memoization type they come from. Only some of the kinds can be
+/// returned by any given operation, as documented on the operation.
+///
+/// `E` is the error type of a fallible computation, for `ComputeFailed`. It is
+/// `Infallible` for operations which do not run one.
+///
+/// ## Example
+///
+/// ```
+/// use core_memo::CacheError;
+///
+/// fn describe(e: &CacheError<String>) -> String {
+///     match e {
+///         CacheError::ComputeFailed(reason) => format!("failed: {}", reason),
+///         other => other.to_string(),
+///     }
+/// }
+///
+/// assert_eq!(describe(&CacheError::ComputeFailed("bad input".into())), "failed: bad input");
+/// assert_eq!(describe(&CacheError::NotReady), "the value is not ready");
+/// ```
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum CacheError<E = Infallible> {
+    /// There is no cached value, and the operation would not compute one
+    NotReady,
+    /// The cached value is older than its time-to-live
+    Expired,
+    /// A thread panicked while holding a lock on the value
+    Poisoned,
+    /// The computation returned an error
+    ComputeFailed(E),
+    /// Waiting for the value took too long
+    TimedOut,
+    /// Memory could not be allocated for the value
+    AllocFailed,
+}
+
+impl<E> CacheError<E> {
+    /// Get the error of the computation, if that is what this is
+    pub fn compute_error(&self) -> Option<&E> {
+        match self {
+            CacheError::ComputeFailed(e) => Some(e),
+            _ => None,
+        }
+    }
+
+    /// Convert the error of the computation, leaving the other kinds as they
+    /// are
+    pub fn map<F, O: FnOnce(E) -> F>(self, op: O) -> CacheError<F> {
+        match self {
+            CacheError::NotReady => CacheError::NotReady,
+            CacheError::Expired => CacheError::Expired,
+            CacheError::Poisoned => CacheError::Poisoned,
+            CacheError::ComputeFailed(e) => CacheError::ComputeFailed(op(e)),
+            CacheError::TimedOut => CacheError::TimedOut,
+            CacheError::AllocFailed => CacheError::AllocFailed,
+        }
+    }
+}
+
+impl CacheError {
+    /// Convert into an error with any computation error type
+    ///
+    /// This can never be `ComputeFailed`, so it fits anywhere.
+    pub fn widen<E>(self) -> CacheError<E> {
+        self.map(|never| match never {})
+    }
+}
+
+impl<E: fmt::Display> fmt::Display for CacheError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CacheError::NotReady => f.write_str("the value is not ready"),
+            CacheError::Expired => f.write_str("the value has expired"),
+            CacheError::Poisoned => f.write_str("the lock on the value is poisoned"),
+            CacheError::ComputeFailed(e) => write!(f, "the computation failed: {}", e),
+            CacheError::TimedOut => f.write_str("timed out waiting for the value"),
+            CacheError::AllocFailed => f.write_str("memory allocation for the value failed"),
+        }
+    }
+}
+
+impl<E: Error + 'static> Error for CacheError<E> {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            CacheError::ComputeFailed(e) => Some(e),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,141 @@
+use core::any::TypeId;
+use core::cell::RefCell;
+use core::mem;
+use core::ops::Deref;
+use std::vec::Vec;
+
+use Memoize;
+
+/// A type-erased fake computation, with the type it computes
+type Entry = (TypeId, fn());
+
+std::thread_local! {
+    /// The computations overridden with `override_with()`
+    static OVERRIDES: RefCell<Vec<Entry>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Find the innermost override for `T`
+fn overridden<T: Memoize + 'static>() -> Option<fn(&T::Param) -> T> {
+    let id = TypeId::of::<T>();
+    OVERRIDES.with(|o| {
+        o.borrow()
+            .iter()
+            .rev()
+            .find(|&&(i, _)| i == id)
+            // SAFETY: it was registered by `override_with::<T>()`, for the
+            // same `TypeId`, so it is a `fn(&T::Param) -> T`
+            .map(|&(_, f)| unsafe { mem::transmute::<fn(), fn(&T::Param) -> T>(f) })
+    })
+}
+
+/// Replace a computation with a fake one, for all `Overridable` memos on the
+/// current thread
+///
+/// Requires the `test-util` and `std` features.
+///
+/// Until the returned guard is dropped, `Overridable<T>` computes its values
+/// with `fake`, instead of `T::memoize()`. Values which are already cached are
+/// not affected.
+///
+/// The override only applies to the current thread, so that tests running in
+/// parallel don't see each other's fakes. Overrides can be nested: the last
+/// one wins, until its guard is dropped. See `Overridable` for an example.
+pub fn override_with<T: Memoize + 'static>(fake: fn(&T::Param) -> T) -> Override {
+    let id = TypeId::of::<T>();
+    // SAFETY: only transmuted back by `overridden::<T>()`, for the same `T`
+    let fake = unsafe { mem::transmute::<fn(&T::Param) -> T, fn()>(fake) };
+    OVERRIDES.with(|o| o.borrow_mut().push((id, fake)));
+    Override { id }
+}
+
+/// Guard for a computation overridden with `override_with()`
+///
+/// Requires the `test-util` and `std` features.
+///
+/// The override is removed when this is dropped.
+#[derive(Debug)]
+#[must_use = "the override is removed when this is dropped"]
+pub struct Override {
+    id: TypeId,
+}
+
+impl Drop for Override {
+    fn drop(&mut self) {
+        OVERRIDES.with(|o| {
+            let mut o = o.borrow_mut();
+            if let Some(i) = o.iter().rposition(|&(i, _)| i == self.id) {
+                o.remove(i);
+            }
+        });
+    }
+}
+
+/// Wraps any computation, so that it can be replaced with a fake one in tests
+///
+/// Requires the `test-util` and `std` features.
+///
+/// The computation is the same as for `T`, unless it is overridden with
+/// `override_with()` on the current thread. Use it in place of `T` in the
+/// memos which your tests need to stub out (like behind a type alias, which
+/// is only `Overridable` under `cfg(test)`), so that the other memos of `T`,
+/// and your production builds, are not affected. The value can be accessed
+/// with `Deref`.
+///
+/// To stub out the value of a single memo, there is no need for this: give it
+/// the value with `set()`.
+///
+/// ## Example
+///
+/// ```
+/// use core_memo::{override_with, Memoize, Memo, Overridable};
+///
+/// struct Forecast(&'static str);
+///
+/// impl Memoize for Forecast {
+///     type Param = String;
+///     fn memoize(city: &String) -> Self {
+///         // imagine a slow call to a weather service here
+///         Forecast("rain")
+///     }
+/// }
+///
+/// let _fake = override_with::<Forecast>(|_| Forecast("sunny"));
+///
+/// let mut memo: Memo<Overridable<Forecast>> = Memo::new("Paris".into());
+/// assert_eq!(memo.get().0, "sunny");
+/// ```
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Overridable<T>(T);
+
+impl<T> Overridable<T> {
+    /// Get the value back
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> Deref for Overridable<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T: Memoize + 'static> Memoize for Overridable<T> {
+    type Param = T::Param;
+
+    const COST: u32 = T::COST;
+
+    fn memoize(p: &T::Param) -> Self {
+        match overridden::<T>() {
+            Some(fake) => Overridable(fake(p)),
+            None => Overridable(T::memoize(p)),
+        }
+    }
+
+    fn cost(p: &T::Param) -> u32 {
+        T::cost(p)
+    }
+}
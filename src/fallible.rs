@@ -0,0 +1,336 @@
+use core::borrow::Borrow;
+use core::fmt;
+use core::ops::Deref;
+
+use {CacheError, Memo, MemoExt, MemoMut, MemoOnce, Memoize};
+
+/// Represents a computation which can fail
+///
+/// This is like `Memoize`, but the computation returns a `Result`, for things
+/// like parsing or validation. Memoize it by using `Fallible<T>` as the value
+/// type of any of the memoization types.
+///
+/// Then, `try_get_or_compute()` returns the value, or the error if the
+/// computation failed. By default, errors are not cached: the next call runs
+/// the computation again. This can be changed with `ERROR_POLICY`. (`get()`
+/// and `ready()` still work as usual, and cache the `Fallible` whether it
+/// holds a value or an error.)
+///
+/// ## Example
+///
+/// ```
+/// use core_memo::{CacheError, Fallible, Memo, TryMemoize};
+///
+/// struct Port(u16);
+///
+/// impl TryMemoize for Port {
+///     type Param = str;
+///     type Error = std::num::ParseIntError;
+///
+///     fn try_memoize(p: &str) -> Result<Self, Self::Error> {
+///         p.parse().map(Port)
+///     }
+/// }
+///
+/// let mut memo: Memo<Fallible<Port>, String> = Memo::new("80a".into());
+/// assert!(matches!(memo.try_get_or_compute(), Err(CacheError::ComputeFailed(_))));
+/// assert!(!memo.is_ready());
+///
+/// memo.param_mut().pop();
+/// assert_eq!(memo.try_get_or_compute().unwrap().0, 80);
+/// assert!(memo.is_ready());
+/// ```
+///
+pub trait TryMemoize: Sized {
+    /// The input type for the computation
+    type Param: ?Sized;
+
+    /// The error returned when the computation fails
+    type Error;
+
+    /// Whether errors are cached, when the value is requested with
+    /// `try_get_or_compute()`
+    ///
+    /// By default, they are not. See `ErrorPolicy`.
+    const ERROR_POLICY: ErrorPolicy = ErrorPolicy::NeverCache;
+
+    /// The function to compute the value
+    fn try_memoize(p: &Self::Param) -> Result<Self, Self::Error>;
+}
+
+/// Whether the memoization types cache errors of a `TryMemoize` computation
+///
+/// Set with `TryMemoize::ERROR_POLICY`. This is about `try_get_or_compute()`
+/// and `try_ready()`: `get()` and `ready()` cache the result, whatever it is.
+///
+/// Cached errors are returned as clones, without running the computation
+/// again, until the memo is cleared (like when its parameter is modified).
+///
+/// ## Example
+///
+/// ```
+/// use std::cell::Cell;
+/// use core_memo::{ErrorPolicy, Fallible, Memo, TryMemoize};
+///
+/// struct Config(String);
+///
+/// impl TryMemoize for Config {
+///     type Param = Cell<u32>;
+///     type Error = String;
+///
+///     // a flaky source: give it one more chance, then stop asking
+///     const ERROR_POLICY: ErrorPolicy = ErrorPolicy::RetryUpTo(1);
+///
+///     fn try_memoize(attempts: &Cell<u32>) -> Result<Self, String> {
+///         attempts.set(attempts.get() + 1);
+///         Err(String::from("unreachable"))
+///     }
+/// }
+///
+/// let mut memo: Memo<Fallible<Config>> = Memo::new(Cell::new(0));
+/// for _ in 0..5 {
+///     assert!(memo.try_get_or_compute().is_err());
+/// }
+/// assert_eq!(memo.param().get(), 2);
+/// ```
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum ErrorPolicy {
+    /// Errors are not cached: every call runs the computation again
+    #[default]
+    NeverCache,
+    /// Errors are cached, like values
+    Cache,
+    /// The computation runs again on the next calls, until it has failed
+    /// this many more times in a row; then the error is cached
+    RetryUpTo(u32),
+}
+
+impl ErrorPolicy {
+    /// Check if a computation which failed `failures` times in a row should
+    /// run again
+    fn retries(self, failures: u32) -> bool {
+        match self {
+            ErrorPolicy::NeverCache => true,
+            ErrorPolicy::Cache => false,
+            ErrorPolicy::RetryUpTo(n) => failures <= n,
+        }
+    }
+}
+
+/// The result of a `TryMemoize` computation, which can be memoized
+///
+/// This implements `Memoize` for any `TryMemoize` type. See `TryMemoize` for
+/// an example.
+pub struct Fallible<T: TryMemoize> {
+    result: Result<T, T::Error>,
+    failures: u32,
+}
+
+impl<T: TryMemoize> Fallible<T> {
+    /// Check if the computation succeeded
+    pub fn is_ok(&self) -> bool {
+        self.result.is_ok()
+    }
+
+    /// Get the number of times in a row the computation failed, as far as the
+    /// memo has seen
+    pub fn failures(&self) -> u32 {
+        self.failures
+    }
+
+    /// Get a reference to the value, or the error
+    pub fn as_result(&self) -> Result<&T, &T::Error> {
+        self.result.as_ref()
+    }
+
+    /// Get the value, or the error, back
+    pub fn into_result(self) -> Result<T, T::Error> {
+        self.result
+    }
+}
+
+impl<T: TryMemoize> Memoize for Fallible<T> {
+    type Param = T::Param;
+
+    fn memoize(p: &T::Param) -> Self {
+        let result = T::try_memoize(p);
+        Fallible {
+            failures: result.is_err() as u32,
+            result,
+        }
+    }
+}
+
+impl<T> fmt::Debug for Fallible<T>
+where
+    T: TryMemoize + fmt::Debug,
+    T::Error: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Fallible")
+            .field("result", &self.result)
+            .field("failures", &self.failures)
+            .finish()
+    }
+}
+
+/// Check if the computation needs to run, given the cached result
+///
+/// Returns the number of times in a row the computation failed so far, if it
+/// does.
+fn stale<T: TryMemoize>(value: &Option<Fallible<T>>) -> Option<u32> {
+    match *value {
+        None => Some(0),
+        Some(ref fallible) if fallible.is_ok() => None,
+        Some(ref fallible) if T::ERROR_POLICY.retries(fallible.failures) => Some(fallible.failures),
+        Some(_) => None,
+    }
+}
+
+/// Get the cached value, or the error
+///
+/// If the error was just computed (after `failures` failures in a row), count
+/// it, and take it out of the memo, unless it should be kept.
+fn settle<T: TryMemoize>(
+    value: &mut Option<Fallible<T>>,
+    failures: Option<u32>,
+) -> Result<&T, CacheError<T::Error>>
+where
+    T::Error: Clone,
+{
+    let failed = value.as_ref().is_some_and(|fallible| !fallible.is_ok());
+    if let (Some(failures), true) = (failures, failed) {
+        if T::ERROR_POLICY == ErrorPolicy::NeverCache {
+            if let Some(Fallible { result: Err(e), .. }) = value.take() {
+                return Err(CacheError::ComputeFailed(e));
+            }
+        } else if let Some(ref mut fallible) = *value {
+            fallible.failures = failures + 1;
+        }
+    }
+
+    match *value {
+        Some(Fallible {
+            result: Ok(ref value),
+            ..
+        }) => Ok(value),
+        Some(Fallible {
+            result: Err(ref e), ..
+        }) => Err(CacheError::ComputeFailed(e.clone())),
+        None => Err(CacheError::NotReady),
+    }
+}
+
+impl<T: TryMemoize> MemoExt<Fallible<T>>
+where
+    T::Error: Clone,
+{
+    /// If the value is not ready, try to compute it and cache it
+    ///
+    /// Returns `CacheError::ComputeFailed` if the computation fails. Whether
+    /// the error is cached depends on `T::ERROR_POLICY`.
+    pub fn try_ready<B>(&mut self, p: &B) -> Result<(), CacheError<T::Error>>
+    where
+        B: Borrow<T::Param> + ?Sized,
+    {
+        self.try_get_or_compute(p).map(|_| ())
+    }
+
+    /// Get the value, trying to compute it if needed
+    ///
+    /// Returns `CacheError::ComputeFailed` if the computation fails. Whether
+    /// the error is cached, or the next call tries again, depends on
+    /// `T::ERROR_POLICY`. This also applies to an error cached by `get()` or
+    /// `ready()`.
+    pub fn try_get_or_compute<B>(&mut self, p: &B) -> Result<&T, CacheError<T::Error>>
+    where
+        B: Borrow<T::Param> + ?Sized,
+    {
+        let failures = stale(&self.value);
+        if failures.is_some() {
+            self.update(p);
+        }
+        settle(&mut self.value, failures)
+    }
+}
+
+impl<T: TryMemoize, P: Borrow<T::Param>> Memo<Fallible<T>, P>
+where
+    T::Error: Clone,
+{
+    /// If the value is not ready, try to compute it and cache it
+    ///
+    /// Returns `CacheError::ComputeFailed` if the computation fails. Whether
+    /// the error is cached depends on `T::ERROR_POLICY`.
+    pub fn try_ready(&mut self) -> Result<(), CacheError<T::Error>> {
+        self.try_get_or_compute().map(|_| ())
+    }
+
+    /// Get the value, trying to compute it if needed
+    ///
+    /// Returns `CacheError::ComputeFailed` if the computation fails. Whether
+    /// the error is cached, or the next call tries again, depends on
+    /// `T::ERROR_POLICY`. This also applies to an error cached by `get()` or
+    /// `ready()`.
+    pub fn try_get_or_compute(&mut self) -> Result<&T, CacheError<T::Error>> {
+        let failures = stale(&self.value);
+        if failures.is_some() {
+            self.update();
+        }
+        settle(&mut self.value, failures)
+    }
+}
+
+impl<'p, T: TryMemoize, R: Deref> MemoOnce<'p, Fallible<T>, R>
+where
+    T::Error: Clone,
+    R::Target: Borrow<T::Param>,
+{
+    /// If the value is not ready, try to compute it and cache it
+    ///
+    /// Returns `CacheError::ComputeFailed` if the computation fails. Whether
+    /// the error is cached depends on `T::ERROR_POLICY`.
+    pub fn try_ready(&mut self) -> Result<(), CacheError<T::Error>> {
+        self.try_get_or_compute().map(|_| ())
+    }
+
+    /// Get the value, trying to compute it if needed
+    ///
+    /// Returns `CacheError::ComputeFailed` if the computation fails. Whether
+    /// the error is cached, or the next call tries again, depends on
+    /// `T::ERROR_POLICY`.
+    pub fn try_get_or_compute(&mut self) -> Result<&T, CacheError<T::Error>> {
+        let failures = stale(&self.value);
+        if failures.is_some() {
+            self.update();
+        }
+        settle(&mut self.value, failures)
+    }
+}
+
+impl<'p, T: TryMemoize> MemoMut<'p, Fallible<T>>
+where
+    T::Error: Clone,
+{
+    /// If the value is not ready, try to compute it and cache it
+    ///
+    /// Returns `CacheError::ComputeFailed` if the computation fails. Whether
+    /// the error is cached depends on `T::ERROR_POLICY`.
+    pub fn try_ready(&mut self) -> Result<(), CacheError<T::Error>> {
+        self.try_get_or_compute().map(|_| ())
+    }
+
+    /// Get the value, trying to compute it if needed
+    ///
+    /// Returns `CacheError::ComputeFailed` if the computation fails. Whether
+    /// the error is cached, or the next call tries again, depends on
+    /// `T::ERROR_POLICY`.
+    pub fn try_get_or_compute(&mut self) -> Result<&T, CacheError<T::Error>> {
+        let failures = stale(&self.value);
+        if failures.is_some() {
+            self.update();
+        }
+        settle(&mut self.value, failures)
+    }
+}
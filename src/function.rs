@@ -0,0 +1,115 @@
+use core::fmt;
+
+use Invalidate;
+
+/// Memoized value computed by a closure
+///
+/// Like `Memo`, this holds ownership over the parameter for the computation,
+/// but the computation is a closure given to `new()`, instead of a `Memoize`
+/// impl. This is for computations which are local to one function, where
+/// defining a type and implementing `Memoize` for it would be overkill.
+///
+/// The closure is given a reference to the parameter, and can capture its
+/// environment, like any other.
+///
+/// ## Example
+///
+/// ```
+/// use core_memo::MemoFn;
+///
+/// let scale = 10;
+/// let mut memo = MemoFn::new(vec![1, 2, 3], |p: &Vec<i32>| p.iter().sum::<i32>() * scale);
+///
+/// assert_eq!(*memo.get(), 60);
+///
+/// memo.param_mut().push(4);
+/// assert!(!memo.is_ready());
+/// assert_eq!(*memo.get(), 100);
+/// ```
+///
+#[derive(Clone)]
+pub struct MemoFn<T, P, F> {
+    value: Option<T>,
+    param: P,
+    f: F,
+}
+
+impl<T, P, F: Fn(&P) -> T> MemoFn<T, P, F> {
+    /// Creates a new `MemoFn` instance, computing its value with `f`
+    pub fn new(p: P, f: F) -> Self {
+        Self {
+            value: None,
+            param: p,
+            f,
+        }
+    }
+
+    /// Get the value
+    ///
+    /// If the value has already been computed, this function returns the
+    /// cached value. If not, it is computed and cached for future use.
+    pub fn get(&mut self) -> &T {
+        if self.value.is_none() {
+            self.value = Some((self.f)(&self.param));
+        }
+        self.value.as_ref().unwrap()
+    }
+}
+
+impl<T, P, F> MemoFn<T, P, F> {
+    /// Clears any cached value
+    ///
+    /// The value will be reevaluated the next time it is needed.
+    pub fn clear(&mut self) {
+        self.value = None;
+    }
+
+    /// Check if there is a cached value
+    ///
+    /// If this method returns `true`, the next call to `get()` will return the
+    /// stored memoized value.
+    pub fn is_ready(&self) -> bool {
+        self.value.is_some()
+    }
+
+    /// Get the value if it is available
+    ///
+    /// If there is a cached value, returns it. If the value needs to be
+    /// computed, returns `None`.
+    pub fn try_get(&self) -> Option<&T> {
+        self.value.as_ref()
+    }
+
+    /// Get a reference to the parameter used for the computation
+    pub fn param(&self) -> &P {
+        &self.param
+    }
+
+    /// Get a mutable reference to the parameter used for the computation
+    ///
+    /// This clears any cached value.
+    pub fn param_mut(&mut self) -> &mut P {
+        self.clear();
+        &mut self.param
+    }
+
+    /// Get the parameter back, dropping the memo
+    pub fn into_param(self) -> P {
+        self.param
+    }
+}
+
+impl<T, P, F> Invalidate for MemoFn<T, P, F> {
+    fn invalidate(&mut self) {
+        self.clear()
+    }
+}
+
+impl<T: fmt::Debug, P: fmt::Debug, F> fmt::Debug for MemoFn<T, P, F> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("MemoFn")
+            .field("value", &self.value)
+            .field("param", &self.param)
+            .finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,93 @@
+use core::borrow::Borrow;
+
+use arbitrary::{Arbitrary, Result, Unstructured};
+
+use {Memo, MemoExt, Memoize};
+
+/// Generates a `MemoExt` which is either empty or holds an arbitrary value
+///
+/// Requires the `arbitrary` feature.
+///
+/// There is no parameter to check the value against, so it may or may not be
+/// the value the computation would return.
+impl<'a, T: Memoize + Arbitrary<'a>> Arbitrary<'a> for MemoExt<T> {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let mut memo = MemoExt::new();
+        memo.value = u.arbitrary()?;
+        Ok(memo)
+    }
+
+    fn size_hint(depth: usize) -> (usize, Option<usize>) {
+        <Option<T> as Arbitrary>::size_hint(depth)
+    }
+}
+
+/// Generates a `Memo` with an arbitrary parameter, in an arbitrary state
+///
+/// Requires the `arbitrary` feature.
+///
+/// The memo is either empty, ready (with the value computed from the
+/// parameter), or stale (with an arbitrary value, as if the parameter had
+/// been modified without clearing it). Stale memos cannot be created with the
+/// public API, but they let fuzz targets check how robust code built on top of
+/// memos is against bugs in computations (like non-deterministic ones).
+///
+/// ## Example
+///
+/// ```
+/// extern crate arbitrary;
+/// # extern crate core_memo;
+///
+/// use arbitrary::{Arbitrary, Unstructured};
+/// use core_memo::{Memoize, Memo};
+///
+/// struct Half(u32);
+///
+/// impl<'a> Arbitrary<'a> for Half {
+///     fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+///         Ok(Half(u.arbitrary()?))
+///     }
+/// }
+///
+/// impl Memoize for Half {
+///     type Param = u32;
+///     fn memoize(p: &u32) -> Self {
+///         Half(p / 2)
+///     }
+/// }
+///
+/// # fn main() {
+/// let data = [1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12];
+/// let mut u = Unstructured::new(&data);
+///
+/// let mut memo: Memo<Half> = Memo::arbitrary(&mut u).unwrap();
+/// // it may be stale, so start over
+/// memo.clear();
+/// let half = memo.param() / 2;
+/// assert_eq!(memo.get().0, half);
+/// # }
+/// ```
+///
+impl<'a, T, P> Arbitrary<'a> for Memo<T, P>
+where
+    T: Memoize + Arbitrary<'a>,
+    P: Borrow<T::Param> + Arbitrary<'a>,
+{
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let mut memo = Memo::new(u.arbitrary()?);
+        match u.choose_index(3)? {
+            0 => {}
+            1 => memo.ready(),
+            _ => memo.value = Some(u.arbitrary()?),
+        }
+        Ok(memo)
+    }
+
+    fn size_hint(depth: usize) -> (usize, Option<usize>) {
+        arbitrary::size_hint::and_all(&[
+            P::size_hint(depth),
+            <u32 as Arbitrary>::size_hint(depth),
+            arbitrary::size_hint::or((0, Some(0)), T::size_hint(depth)),
+        ])
+    }
+}
@@ -0,0 +1,323 @@
+use core::borrow::Borrow;
+use core::cell::UnsafeCell;
+use core::fmt;
+#[cfg(feature = "std")]
+use std::sync::{Mutex, PoisonError};
+
+use {Invalidate, Memoize};
+
+/// A lock for a `StaticMemo`
+///
+/// Implement this to plug your own locking into `StaticMemo`, like a spin
+/// lock, or disabling the interrupts of your platform. With the `std`
+/// feature, there is `MutexLock`, and with the `critical-section` feature,
+/// `CriticalSectionLock`.
+///
+/// ## Safety
+///
+/// `lock()` must not run `f` while it runs another closure given to `lock()`
+/// on the same lock, on another thread (or in an interrupt handler). Running
+/// it on the same thread, from within such a closure, is allowed (the lock
+/// can be reentrant): `StaticMemo` detects it, and panics.
+pub unsafe trait StaticLock {
+    /// An unlocked lock, for `StaticMemo::new()`
+    const NEW: Self;
+
+    /// Run `f` while holding the lock
+    fn lock<R, F: FnOnce() -> R>(&self, f: F) -> R;
+}
+
+/// A `StaticLock` using a `std::sync::Mutex`
+///
+/// Requires the `std` feature.
+///
+/// A panic while the lock is held does not poison the `StaticMemo`.
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub struct MutexLock(Mutex<()>);
+
+#[cfg(feature = "std")]
+unsafe impl StaticLock for MutexLock {
+    const NEW: Self = MutexLock(Mutex::new(()));
+
+    fn lock<R, F: FnOnce() -> R>(&self, f: F) -> R {
+        let _guard = self.0.lock().unwrap_or_else(PoisonError::into_inner);
+        f()
+    }
+}
+
+/// A `StaticLock` using a critical section
+///
+/// Requires the `critical-section` feature.
+///
+/// This uses whichever `critical-section` implementation the program links
+/// in, like disabling interrupts on a single-core microcontroller.
+#[cfg(feature = "critical-section")]
+#[derive(Debug)]
+pub struct CriticalSectionLock(());
+
+#[cfg(feature = "critical-section")]
+unsafe impl StaticLock for CriticalSectionLock {
+    const NEW: Self = CriticalSectionLock(());
+
+    fn lock<R, F: FnOnce() -> R>(&self, f: F) -> R {
+        critical_section::with(|_| f())
+    }
+}
+
+/// The `StaticLock` used by `static_memo!`
+///
+/// Requires the `std` or the `critical-section` feature.
+///
+/// This is `MutexLock` with the `std` feature, and `CriticalSectionLock`
+/// without it.
+#[cfg(feature = "std")]
+pub type DefaultLock = MutexLock;
+
+/// The `StaticLock` used by `static_memo!`
+///
+/// Requires the `std` or the `critical-section` feature.
+///
+/// This is `MutexLock` with the `std` feature, and `CriticalSectionLock`
+/// without it.
+#[cfg(all(feature = "critical-section", not(feature = "std")))]
+pub type DefaultLock = CriticalSectionLock;
+
+/// Declare a memoized value in a `static`
+///
+/// Requires the `std` or the `critical-section` feature.
+///
+/// `static_memo!(NAME: T = param)` declares a `static` `StaticMemo` for the
+/// computation `T`, starting with the parameter `param`, and locked with the
+/// `DefaultLock` for the platform. Its methods are the accessors: `with()`
+/// and `get()` to use the value, computing it if needed, and `clear()`,
+/// `set_param()`, and `update_param()` to invalidate it.
+///
+/// Like with `lazy_static!`, several of them can be declared at once, with
+/// attributes, doc comments, and visibility, in the form of `static` items.
+///
+/// ## Example
+///
+/// ```
+/// #[macro_use]
+/// extern crate core_memo;
+///
+/// use core_memo::Memoize;
+///
+/// #[derive(Clone)]
+/// struct Banner(String);
+///
+/// impl Memoize for Banner {
+///     type Param = &'static str;
+///     fn memoize(name: &&'static str) -> Self {
+///         Banner(format!("*** {} ***", name))
+///     }
+/// }
+///
+/// static_memo!(BANNER: Banner = "core_memo");
+///
+/// static_memo! {
+///     /// The banner, in the language of the user
+///     pub static LOCALIZED: Banner = "core_memo";
+/// }
+///
+/// fn main() {
+///     assert_eq!(BANNER.get().0, "*** core_memo ***");
+///
+///     LOCALIZED.set_param("mémo");
+///     assert_eq!(LOCALIZED.with(|b| b.0.chars().count()), 12);
+/// }
+/// ```
+///
+#[cfg(any(feature = "std", feature = "critical-section"))]
+#[macro_export]
+macro_rules! static_memo {
+    () => {};
+    ($(#[$attr:meta])* $vis:vis static $name:ident : $t:ty = $param:expr; $($rest:tt)*) => {
+        $(#[$attr])*
+        $vis static $name: $crate::StaticMemo<
+            $t,
+            <$t as $crate::Memoize>::Param,
+            $crate::DefaultLock,
+        > = $crate::StaticMemo::new($param);
+
+        $crate::static_memo!($($rest)*);
+    };
+    ($(#[$attr:meta])* $vis:vis $name:ident : $t:ty = $param:expr) => {
+        $crate::static_memo!($(#[$attr])* $vis static $name: $t = $param;);
+    };
+}
+
+/// Memoized value for use in a `static`
+///
+/// This can be created in a `const` context, and everything is done through
+/// `&self`, including clearing the value and modifying the parameter, so it
+/// can be a `static`, without any extra synchronization. All the accesses go
+/// through the lock `L`, so pick the one for your platform: `MutexLock` with
+/// `std`, `CriticalSectionLock` on embedded targets, or your own
+/// `StaticLock`.
+///
+/// Since the value can be cleared at any time, it is not returned by
+/// reference. Use it in a closure given to `with()`, which runs while the
+/// lock is held, or get a clone of it with `get()`. Don't access the same
+/// `StaticMemo` from inside that closure (or from inside the computation): it
+/// panics with a reentrant lock, like `CriticalSectionLock`, and deadlocks
+/// with `MutexLock`.
+///
+/// ## Example
+///
+#[cfg_attr(feature = "std", doc = "```")]
+#[cfg_attr(not(feature = "std"), doc = "```ignore")]
+/// use core_memo::{Memoize, MutexLock, StaticMemo};
+///
+/// struct Greeting(String);
+///
+/// impl Memoize for Greeting {
+///     type Param = &'static str;
+///     fn memoize(name: &&'static str) -> Self {
+///         Greeting(format!("hello, {}", name))
+///     }
+/// }
+///
+/// static GREETING: StaticMemo<Greeting, &str, MutexLock> = StaticMemo::new("world");
+///
+/// assert_eq!(GREETING.with(|g| g.0.len()), 12);
+///
+/// GREETING.set_param("static");
+/// assert!(!GREETING.is_ready());
+/// assert_eq!(GREETING.with(|g| g.0.clone()), "hello, static");
+/// ```
+///
+pub struct StaticMemo<T, P, L> {
+    lock: L,
+    /// Whether the state is being accessed, to detect reentrant accesses
+    busy: UnsafeCell<bool>,
+    value: UnsafeCell<Option<T>>,
+    param: UnsafeCell<P>,
+}
+
+// SAFETY: the value and the parameter are only accessed while the lock is
+// held, and reentrant accesses are caught by `busy`, so they are never
+// shared, just sent between threads, like in a `Mutex`.
+unsafe impl<T: Send, P: Send, L: StaticLock + Sync> Sync for StaticMemo<T, P, L> {}
+
+/// Resets `busy` at the end of an access, even if it panics
+struct Busy<'a>(&'a UnsafeCell<bool>);
+
+impl<'a> Drop for Busy<'a> {
+    fn drop(&mut self) {
+        // SAFETY: still under the lock
+        unsafe { *self.0.get() = false }
+    }
+}
+
+impl<T, P, L: StaticLock> StaticMemo<T, P, L> {
+    /// Creates a new `StaticMemo` instance
+    pub const fn new(p: P) -> Self {
+        Self {
+            lock: L::NEW,
+            busy: UnsafeCell::new(false),
+            value: UnsafeCell::new(None),
+            param: UnsafeCell::new(p),
+        }
+    }
+
+    /// Run `f` with exclusive access to the value and the parameter
+    fn access<R, F>(&self, f: F) -> R
+    where
+        F: FnOnce(&mut Option<T>, &mut P) -> R,
+    {
+        self.lock.lock(|| {
+            // SAFETY: `busy` is only accessed under the lock
+            let busy = unsafe { &mut *self.busy.get() };
+            assert!(
+                !*busy,
+                "core_memo: StaticMemo was accessed again while it was in use, \
+                 from its computation or from a closure given to `with()`"
+            );
+            *busy = true;
+            let _busy = Busy(&self.busy);
+
+            // SAFETY: the lock excludes other threads, and `busy` excludes
+            // other accesses on this one
+            unsafe { f(&mut *self.value.get(), &mut *self.param.get()) }
+        })
+    }
+
+    /// Clears any cached value
+    ///
+    /// The value will be reevaluated the next time it is needed.
+    pub fn clear(&self) {
+        self.access(|value, _| *value = None)
+    }
+
+    /// Check if there is a cached value
+    pub fn is_ready(&self) -> bool {
+        self.access(|value, _| value.is_some())
+    }
+
+    /// Replace the parameter used for the computation
+    ///
+    /// This clears any cached value.
+    pub fn set_param(&self, p: P) {
+        self.update_param(|param| *param = p)
+    }
+
+    /// Modify the parameter used for the computation via a closure
+    ///
+    /// This clears any cached value.
+    pub fn update_param<F: FnOnce(&mut P)>(&self, f: F) {
+        self.access(|value, param| {
+            *value = None;
+            f(param);
+        })
+    }
+
+    /// Get a clone of the parameter used for the computation
+    pub fn param(&self) -> P
+    where
+        P: Clone,
+    {
+        self.access(|_, param| param.clone())
+    }
+}
+
+impl<T: Memoize, P: Borrow<T::Param>, L: StaticLock> StaticMemo<T, P, L> {
+    /// Use the value
+    ///
+    /// If the value has not been computed yet, it is computed and cached for
+    /// future use. Then, it is given to `f`. Both happen while the lock is
+    /// held.
+    pub fn with<R, F: FnOnce(&T) -> R>(&self, f: F) -> R {
+        self.access(|value, param| {
+            if value.is_none() {
+                *value = Some(T::memoize((*param).borrow()));
+            }
+            f(value.as_ref().unwrap())
+        })
+    }
+
+    /// Get a clone of the value
+    ///
+    /// If the value has not been computed yet, it is computed and cached for
+    /// future use.
+    pub fn get(&self) -> T
+    where
+        T: Clone,
+    {
+        self.with(T::clone)
+    }
+}
+
+impl<T, P, L: StaticLock> Invalidate for StaticMemo<T, P, L> {
+    fn invalidate(&mut self) {
+        self.clear()
+    }
+}
+
+impl<T, P, L> fmt::Debug for StaticMemo<T, P, L> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // reading the value would need the lock, which `Debug` should not take
+        f.debug_struct("StaticMemo").finish_non_exhaustive()
+    }
+}
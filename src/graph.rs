@@ -0,0 +1,303 @@
+use alloc::collections::{BTreeMap, BTreeSet};
+use alloc::vec::Vec;
+use core::fmt;
+
+use Invalidate;
+
+/// Graph whose nodes can have memoized values, with `MemoGraph`
+///
+/// Requires the `graph` feature.
+///
+/// Implement this for your graph type. An implementation is provided for
+/// adjacency lists stored as a `BTreeMap` from each node to its successors.
+pub trait Graph {
+    /// Identifies a node in the graph
+    type Node: Copy + Ord;
+
+    /// Call `f` for each node with an edge from `n`
+    fn successors<F: FnMut(Self::Node)>(&self, n: Self::Node, f: F);
+
+    /// Call `f` for each node with an edge to `n`
+    fn predecessors<F: FnMut(Self::Node)>(&self, n: Self::Node, f: F);
+}
+
+impl<N: Copy + Ord> Graph for BTreeMap<N, Vec<N>> {
+    type Node = N;
+
+    fn successors<F: FnMut(N)>(&self, n: N, f: F) {
+        self.get(&n).into_iter().flatten().cloned().for_each(f);
+    }
+
+    fn predecessors<F: FnMut(N)>(&self, n: N, mut f: F) {
+        for (&from, to) in self {
+            if to.contains(&n) {
+                f(from);
+            }
+        }
+    }
+}
+
+/// Represents a computation of a value for a node of a graph
+///
+/// Requires the `graph` feature.
+///
+/// This is like `Memoize`, but the parameter is a node together with the graph
+/// it belongs to, so that the value can be derived from its neighbors.
+pub trait MemoizeNode<G: Graph>: Sized {
+    /// Compute the value for a node
+    ///
+    /// The values of the nodes it depends on (following the edges against the
+    /// `Direction` of the `MemoGraph`) are computed first, and can be looked
+    /// up in `values`, instead of being computed again.
+    fn memoize_node(graph: &G, node: G::Node, values: &NodeValues<Self, G>) -> Self;
+}
+
+/// The cached values of a `MemoGraph`, while the value of a node is computed
+///
+/// Requires the `graph` feature.
+///
+/// See `MemoizeNode::memoize_node()`.
+pub struct NodeValues<'a, T, G: Graph> {
+    values: &'a BTreeMap<G::Node, T>,
+}
+
+impl<'a, T, G: Graph> NodeValues<'a, T, G> {
+    /// Get the value for a node
+    ///
+    /// This is `None` only for nodes which are not depended on, and for the
+    /// nodes of a cycle which are not computed yet (as a cycle has to be
+    /// entered somewhere).
+    pub fn get(&self, node: G::Node) -> Option<&'a T> {
+        self.values.get(&node)
+    }
+}
+
+/// Direction in which values depend on each other, along the edges of a graph
+///
+/// Requires the `graph` feature.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    /// Values flow along the edges: a node's value depends on the nodes with
+    /// edges to it, so invalidating a node also invalidates its successors
+    Outgoing,
+    /// Values flow against the edges: a node's value depends on the nodes it
+    /// has edges to, so invalidating a node also invalidates its predecessors
+    Incoming,
+}
+
+/// Memoized values for the nodes of a graph
+///
+/// Requires the `graph` feature.
+///
+/// This caches one value per node, computed from the graph (like a keyed
+/// cache, where the keys are the nodes). The values of the nodes which a value
+/// depends on are computed and cached first, so that it can be derived from
+/// them. When a node changes, invalidate it with `invalidate_node()`, which
+/// also invalidates every node whose value depends on it, following the edges
+/// in the given `Direction`.
+///
+/// The graph is provided externally with every call that needs it, like the
+/// parameter of `MemoExt`. You must invalidate the affected nodes whenever you
+/// modify the graph.
+///
+/// ## Example
+///
+/// ```
+/// use std::collections::BTreeMap;
+/// use core_memo::{Direction, Graph, MemoGraph, MemoizeNode, NodeValues};
+///
+/// type Deps = BTreeMap<u32, Vec<u32>>;
+///
+/// /// The number of nodes which a node depends on, directly or indirectly
+/// struct Depth(usize);
+///
+/// impl MemoizeNode<Deps> for Depth {
+///     fn memoize_node(graph: &Deps, node: u32, values: &NodeValues<Depth, Deps>) -> Self {
+///         let mut depth = 0;
+///         graph.predecessors(node, |p| {
+///             let above = values.get(p).map_or(0, |d| d.0);
+///             depth = depth.max(1 + above);
+///         });
+///         Depth(depth)
+///     }
+/// }
+///
+/// // 1 -> 2 -> 3
+/// let mut graph: Deps = BTreeMap::new();
+/// graph.insert(1, vec![2]);
+/// graph.insert(2, vec![3]);
+///
+/// // 1 and 2 are computed first, and cached too
+/// let mut memo: MemoGraph<Depth, Deps> = MemoGraph::new(Direction::Outgoing);
+/// assert_eq!(memo.get(&graph, 3).0, 2);
+/// assert_eq!(memo.len(), 3);
+///
+/// // changing 2 invalidates 2 and 3, which depends on it, but not 1
+/// assert_eq!(memo.invalidate_node(&graph, 2), 2);
+/// assert!(memo.is_ready(1));
+/// assert!(!memo.is_ready(3));
+/// ```
+///
+pub struct MemoGraph<T, G: Graph> {
+    values: BTreeMap<G::Node, T>,
+    direction: Direction,
+}
+
+impl<T: MemoizeNode<G>, G: Graph> MemoGraph<T, G> {
+    /// Creates a new `MemoGraph` instance
+    ///
+    /// `direction` determines how invalidations cascade.
+    pub fn new(direction: Direction) -> Self {
+        Self {
+            values: BTreeMap::new(),
+            direction,
+        }
+    }
+
+    /// Get the direction in which invalidations cascade
+    pub fn direction(&self) -> Direction {
+        self.direction
+    }
+
+    /// Clears all cached values
+    pub fn clear(&mut self) {
+        self.values.clear();
+    }
+
+    /// Get the number of nodes with a cached value
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    /// Check if no node has a cached value
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// Check if there is a cached value for a node
+    pub fn is_ready(&self, node: G::Node) -> bool {
+        self.values.contains_key(&node)
+    }
+
+    /// If the value for a node is not ready, compute it and cache it
+    pub fn ready(&mut self, graph: &G, node: G::Node) {
+        self.get(graph, node);
+    }
+
+    /// Force the value for a node to be recomputed
+    ///
+    /// Nodes which depend on it are not invalidated. Use `invalidate_node()`
+    /// for that.
+    pub fn update(&mut self, graph: &G, node: G::Node) -> &T {
+        self.values.remove(&node);
+        self.get(graph, node)
+    }
+
+    /// Get the value for a node
+    ///
+    /// If the value has already been computed, this function returns the cached
+    /// value. If not, it is computed and cached for future use, after the
+    /// values of the nodes it depends on.
+    pub fn get(&mut self, graph: &G, node: G::Node) -> &T {
+        if !self.values.contains_key(&node) {
+            self.compute(graph, node);
+        }
+        &self.values[&node]
+    }
+
+    /// Compute and cache the value for a node, and first the missing values of
+    /// the nodes it depends on, directly or indirectly
+    fn compute(&mut self, graph: &G, node: G::Node) {
+        // depth-first, computing each node after the nodes it depends on
+        let mut visited = BTreeSet::new();
+        let mut stack = Vec::new();
+        stack.push((node, false));
+
+        while let Some((n, expanded)) = stack.pop() {
+            if expanded {
+                let value = T::memoize_node(
+                    graph,
+                    n,
+                    &NodeValues {
+                        values: &self.values,
+                    },
+                );
+                self.values.insert(n, value);
+                continue;
+            }
+            // a node can be pushed again before it is visited
+            if !visited.insert(n) {
+                continue;
+            }
+            stack.push((n, true));
+
+            let values = &self.values;
+            let visited = &visited;
+            let mut visit = |next| {
+                if !values.contains_key(&next) && !visited.contains(&next) {
+                    stack.push((next, false));
+                }
+            };
+            match self.direction {
+                Direction::Outgoing => graph.predecessors(n, &mut visit),
+                Direction::Incoming => graph.successors(n, &mut visit),
+            }
+        }
+    }
+
+    /// Get the value for a node if it is available
+    pub fn try_get(&self, node: G::Node) -> Option<&T> {
+        self.values.get(&node)
+    }
+
+    /// Clears the cached value for a node, and for all the nodes which depend
+    /// on it
+    ///
+    /// Follows the edges from the node in the direction of the `MemoGraph`,
+    /// visiting every reachable node once (so cycles are fine). Returns the
+    /// number of values that were cleared.
+    pub fn invalidate_node(&mut self, graph: &G, node: G::Node) -> usize {
+        let mut visited = BTreeSet::new();
+        let mut stack = Vec::new();
+        visited.insert(node);
+        stack.push(node);
+
+        let mut cleared = 0;
+        while let Some(n) = stack.pop() {
+            if self.values.remove(&n).is_some() {
+                cleared += 1;
+            }
+
+            let mut visit = |next| {
+                if visited.insert(next) {
+                    stack.push(next);
+                }
+            };
+            match self.direction {
+                Direction::Outgoing => graph.successors(n, &mut visit),
+                Direction::Incoming => graph.predecessors(n, &mut visit),
+            }
+        }
+        cleared
+    }
+}
+
+impl<T, G: Graph> Invalidate for MemoGraph<T, G> {
+    fn invalidate(&mut self) {
+        self.values.clear();
+    }
+}
+
+impl<T, G> fmt::Debug for MemoGraph<T, G>
+where
+    T: fmt::Debug,
+    G: Graph,
+    G::Node: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("MemoGraph")
+            .field("values", &self.values)
+            .field("direction", &self.direction)
+            .finish()
+    }
+}
@@ -0,0 +1,62 @@
+use core::hash::{Hash, Hasher};
+
+/// 64-bit FNV-1a, used because it is simple and its output is stable
+struct StableHasher(u64);
+
+impl StableHasher {
+    fn new() -> Self {
+        StableHasher(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl Hasher for StableHasher {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for b in bytes {
+            self.0 ^= u64::from(*b);
+            self.0 = self.0.wrapping_mul(0x0000_0100_0000_01b3);
+        }
+    }
+}
+
+/// Compute a hash of a parameter, which is stable across runs
+pub(crate) fn stable_hash<P: Hash + ?Sized>(p: &P) -> u64 {
+    let mut hasher = StableHasher::new();
+    p.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// A multiply-rotate hash, with a final mix, used where a second hash is
+/// needed which is independent of `StableHasher`
+#[cfg(feature = "disk")]
+struct MixHasher(u64);
+
+#[cfg(feature = "disk")]
+impl Hasher for MixHasher {
+    fn finish(&self) -> u64 {
+        // the finalizer of SplitMix64
+        let mut h = self.0;
+        h = (h ^ (h >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        h = (h ^ (h >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        h ^ (h >> 31)
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for b in bytes {
+            self.0 = (self.0.rotate_left(5) ^ u64::from(*b)).wrapping_mul(0x517c_c1b7_2722_0a95);
+        }
+    }
+}
+
+/// Compute a second hash of a parameter, which is stable across runs, and
+/// computed differently than `stable_hash()`, so that the two don't collide
+/// for the same parameters
+#[cfg(feature = "disk")]
+pub(crate) fn second_hash<P: Hash + ?Sized>(p: &P) -> u64 {
+    let mut hasher = MixHasher(0);
+    p.hash(&mut hasher);
+    hasher.finish()
+}
@@ -6,7 +6,7 @@
 //!
 //! You must define a custom type to represent your computation and implement
 //! the `Memoize` trait for it. Then, you can use it with the `Memo`, `MemoExt`,
-//! or `MemoOnce` types to lazily evaluate and cache the value.
+//! `MemoOnce`, or `MemoMut` types to lazily evaluate and cache the value.
 //!
 //! Here is an example:
 //!
@@ -49,7 +49,8 @@
 //! assert_eq!(memo.get(), &MemoSum(10)); // the vec is now `[1, 2, 3, 4]`
 //! ```
 //!
-//! There are 3 different wrapper types: `Memo`, `MemoExt`, and `MemoOnce`.
+//! There are 4 different wrapper types: `Memo`, `MemoExt`, `MemoOnce`, and
+//! `MemoMut`.
 //!
 //!   - `Memo` contains / holds ownership over the parameter for the computation.
 //!     This makes it the easiest and safest to use, but could limit your
@@ -65,6 +66,115 @@
 //!     manage the parameter externally, but you cannot mutate it as long as the
 //!     `MemoOnce` is alive. This could be useful for one-off computations.
 //!
+//!   - `MemoMut` holds a mutable reference to the parameter. Like `Memo`, you
+//!     can modify the parameter through it, which clears the cached value, but
+//!     the parameter is still owned by someone else.
+//!
+//! These each cache one value. To memoize the same computation over many
+//! different parameters, with one cached value per parameter, use
+//! `MemoMap` (with the `alloc` feature), which is a map from the parameters
+//! to the values (and another name for `MemoCache`).
+//!
+//! ## Optional Features
+//!
+//! By default, the library is `#![no_std]` and has no dependencies. The
+//! following cargo features enable additional functionality:
+//!
+//!   - `alloc`: types which need memory allocation, like `MemoCache`,
+//!     `InvalidationBus`, `Registry`, and `MemoDyn`.
+//!
+//!   - `std`: types which need the standard library, like `Listener`,
+//!     `MemoLock`, `MemoShared`, `MemoRevalidate`, and `MemoSpawn`. Implies
+//!     `alloc`.
+//!
+//!   - `stats`: per-entry access statistics for `MemoCache`, to find the
+//!     hottest and coldest entries, and `Registry::report()`. Implies `alloc`.
+//!
+//!   - `graph`: `MemoGraph`, for memoizing a value per node of a graph, with
+//!     invalidations cascading along the edges. Implies `alloc`.
+//!
+//!   - `test-util`: `MemoizeLaws`, `CallTracker`, and `CountingMemoize`, for
+//!     checking that your own memoization types and computations behave
+//!     correctly in your tests. With `std`, also `Overridable` and
+//!     `override_with()`, for replacing computations with fakes.
+//!
+//!   - `self-ref`: `MemoSelfRef`, for values which borrow from the parameter.
+//!
+//!   - `yoke`: caching zero-copy `Yoke` values, via `MemoizeYoke`.
+//!
+//!   - `serde`: `serde` serialization for `Memo` and `MemoExt`, and the
+//!     checksummed `Persisted` container for storing cached values.
+//!
+//!   - `disk`: `DiskCache`, for memoizing to files in a directory. Implies
+//!     `std` and `serde`.
+//!
+//!   - `mmap`: memory-mapped values from `DiskCache`, via `Mappable`. Implies
+//!     `disk`.
+//!
+//!   - `diagnostics`: in debug builds, report memos which were never used, or
+//!     which computed values that were never read, via `set_waste_hook()`.
+//!     With `std`, also report computations which exceed the budget set with
+//!     `set_budget()`, via `set_slow_hook()`, and record the history of the
+//!     computations of a memo, with `record_history()`.
+//!
+//!   - `zeroize`: memoizing `Zeroizing` values, which are wiped from memory
+//!     when they are cleared, recomputed, or dropped.
+//!
+//!   - `secrecy`: memoizing `SecretBox` values, which are redacted from
+//!     `Debug` output. Implies `zeroize` and `alloc`.
+//!
+//!   - `arbitrary`: `Arbitrary` impls for `Memo` and `MemoExt`, to generate
+//!     memos in different states in fuzz targets. Implies `std`.
+//!
+//!   - `loom`: compile the synchronization in the thread-safe memoization
+//!     types against `loom`, to model check them. This is only meant for
+//!     testing, as the types then only work inside `loom::model`.
+//!
+//!   - `capi`: a C API, with opaque `CoreMemo` handles and C callbacks for
+//!     the computations, for embedding in C/C++ programs. Implies `std`.
+//!
+//!   - `defmt`: `defmt::Format` for the memoization types, and `trace`-level
+//!     `defmt` events when values are computed or cleared, for logging over
+//!     RTT on embedded targets. The events are compiled out unless enabled
+//!     with `DEFMT_LOG` (like `DEFMT_LOG=core_memo=trace`).
+//!
+//!   - `ufmt`: `ufmt::uDebug` for the memoization types, and `ufmt::uDisplay`
+//!     for `CacheError` and the reports of the `diagnostics` feature, for
+//!     printing on targets where `core::fmt` is too big.
+//!
+//!   - `rayon`: `MemoCache::warm_par()`, `MemoCache::get_many_par()` and
+//!     `par_warm()`, for computing many values in parallel, and
+//!     `MemoizeParallel`, for computing one value in parallel chunks, using
+//!     `rayon`. Implies `std`.
+//!
+//!   - `atomic`: `MemoAtomic`, which can be shared between threads without
+//!     locks, in `no_std` programs on targets with atomics.
+//!
+//!   - `portable-atomic`: use `portable-atomic` instead of `core`'s atomics in
+//!     the thread-safe memoization types (and with the `diagnostics` feature),
+//!     so that they work on targets without native compare-and-swap, like
+//!     `thumbv6m`. On those, enable one of the `portable-atomic` features for
+//!     emulating it (like `critical-section`).
+//!
+//!   - `paranoid`: check the parameters given to a `MemoExt` after
+//!     `audit_params()`. This adds a field to every `MemoExt`.
+//!
+//!   - `arc-swap`: `MemoSwap`, which publishes its value through an `ArcSwap`,
+//!     for read-mostly sharing between threads. Implies `std`.
+//!
+//!   - `critical-section`: `MemoCritical`, which can be used from a `static`
+//!     by both the main program and interrupt handlers, on bare-metal targets.
+//!
+//!   - `derive`: `#[derive(Memoize)]`, for types which are computed field by
+//!     field from the parameter, and `#[memo]`, for memoizing a function.
+//!
+//!   - `tokio`: `MemoWatch`, which takes its parameter from a `tokio` watch
+//!     channel, and is invalidated whenever a new one is published. Implies
+//!     `std`.
+//!
+//!   - `rkyv`: `rkyv` serialization for `Memo` and `MemoExt`, so that cached
+//!     values can be persisted and later accessed zero-copy.
+//!
 //! ## Implementation Notes
 //!
 //! ### Why do the types not implement `Deref`/`DerefMut`?
@@ -86,18 +196,268 @@
 //! them in an immutable context.
 //!
 //! The current design of the library makes it as widely-useful as possible.
+//!
+//! ### How are errors reported?
+//!
+//! Operations which can fail return a `Result`. The error types of this
+//! library implement `Display` and `core::error::Error` (which does not need
+//! `std`), so that they work with `?` and with error-reporting crates, even
+//! in `no_std` programs. There is only one of them, `CacheError`, so that
+//! errors from different memoization types can be handled in one place.
+//! Operations which do I/O, like those of `DiskCache`, return
+//! `std::io::Error` instead.
 
 #![no_std]
 
-// enable std when testing
-#[cfg(test)]
-#[macro_use]
+// enable std when testing or with the `std` feature
+#[cfg(any(test, feature = "std"))]
+#[cfg_attr(test, macro_use)]
 extern crate std;
 
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
+#[cfg(feature = "arbitrary")]
+extern crate arbitrary;
+#[cfg(feature = "arc-swap")]
+extern crate arc_swap;
+#[cfg(feature = "disk")]
+extern crate bincode;
+#[cfg(feature = "derive")]
+extern crate core_memo_derive;
+#[cfg(feature = "critical-section")]
+extern crate critical_section;
+#[cfg(feature = "defmt")]
+extern crate defmt;
+#[cfg(feature = "loom")]
+extern crate loom;
+#[cfg(feature = "mmap")]
+extern crate memmap2;
+#[cfg(feature = "portable-atomic")]
+extern crate portable_atomic;
+#[cfg(feature = "rayon")]
+extern crate rayon;
+#[cfg(feature = "rkyv")]
+extern crate rkyv;
+#[cfg(feature = "secrecy")]
+extern crate secrecy;
+#[cfg(feature = "serde")]
+extern crate serde;
+#[cfg(any(feature = "self-ref", feature = "yoke"))]
+extern crate stable_deref_trait;
+#[cfg(feature = "tokio")]
+extern crate tokio;
+#[cfg(feature = "ufmt")]
+extern crate ufmt;
+#[cfg(feature = "yoke")]
+extern crate yoke;
+#[cfg(feature = "zeroize")]
+extern crate zeroize;
+
+// so that the code generated by `#[derive(Memoize)]` works in this crate too
+#[cfg(all(test, feature = "derive"))]
+extern crate self as core_memo;
+
 #[cfg(test)]
 mod tests;
 
+mod asynchronous;
+#[cfg(feature = "atomic")]
+mod atomic;
+#[cfg(feature = "paranoid")]
+mod audit;
+mod auxiliary;
+#[cfg(feature = "alloc")]
+mod bus;
+#[cfg(feature = "alloc")]
+mod cache;
+#[cfg(feature = "capi")]
+mod capi;
+mod cell;
+#[cfg(feature = "std")]
+mod channel;
+mod compact;
+mod compute;
+mod conversion;
+#[cfg(feature = "alloc")]
+mod cow;
+#[cfg(feature = "critical-section")]
+mod critical;
+#[cfg(feature = "defmt")]
+mod deferred;
+#[cfg(feature = "diagnostics")]
+mod diagnostics;
+#[cfg(feature = "disk")]
+mod disk;
+#[cfg(feature = "alloc")]
+mod dynamic;
+mod error;
+#[cfg(all(feature = "test-util", feature = "std"))]
+mod fake;
+mod fallible;
+mod function;
+#[cfg(feature = "arbitrary")]
+mod fuzzing;
+mod global;
+#[cfg(feature = "graph")]
+mod graph;
+mod hash;
+#[cfg(feature = "std")]
+mod lock;
+#[cfg(feature = "mmap")]
+mod mapped;
+mod memsize;
+#[cfg(feature = "ufmt")]
+mod micro;
+#[cfg(feature = "std")]
+mod oncelock;
+#[cfg(feature = "rayon")]
+mod parallel;
+mod params;
+#[cfg(feature = "alloc")]
+mod partial;
+#[cfg(feature = "serde")]
+mod persist;
+mod refresh;
+#[cfg(feature = "alloc")]
+mod registry;
+#[cfg(feature = "std")]
+mod revalidate;
+mod scan;
+#[cfg(feature = "secrecy")]
+mod secret;
+#[cfg(feature = "self-ref")]
+mod selfref;
+#[cfg(feature = "alloc")]
+mod shared;
+#[cfg(feature = "alloc")]
+mod sketch;
+#[cfg(feature = "alloc")]
+mod snapshot;
+#[cfg(feature = "std")]
+mod spawn;
+mod storage;
+mod strategy;
+#[cfg(feature = "arc-swap")]
+mod swap;
+mod sync;
+#[cfg(any(test, feature = "test-util"))]
+#[cfg_attr(not(feature = "test-util"), allow(dead_code))]
+mod test_util;
+mod typestate;
+#[cfg(feature = "tokio")]
+mod watch;
+#[cfg(feature = "alloc")]
+mod weak;
+#[cfg(feature = "yoke")]
+mod yoked;
+#[cfg(feature = "zeroize")]
+mod zeroizing;
+
+pub use asynchronous::{MemoAsync, MemoizeAsync};
+#[cfg(feature = "atomic")]
+pub use atomic::MemoAtomic;
+pub use auxiliary::{MemoizeAux, WithAux};
+#[cfg(feature = "alloc")]
+pub use bus::{InvalidationBus, Subscriber};
+#[cfg(feature = "stats")]
+pub use cache::EntryStats;
+#[cfg(feature = "alloc")]
+pub use cache::{CacheEntry, CacheIter, MemoCache, MemoMap};
+#[cfg(feature = "capi")]
+pub use capi::{
+    core_memo_clear, core_memo_create, core_memo_destroy, core_memo_get, core_memo_is_ready,
+    core_memo_set_param, core_memo_try_get, CoreMemo, CoreMemoComputeFn, CoreMemoFreeFn,
+};
+pub use cell::{MemoCell, MemoCopy, MemoRefCell};
+#[cfg(feature = "std")]
+pub use channel::Listener;
+pub use compact::Compact;
+pub use compute::{Compute, Output};
+pub use conversion::FromMemo;
+#[cfg(feature = "derive")]
+pub use core_memo_derive::{memo, Memoize};
+#[cfg(feature = "critical-section")]
+pub use critical::MemoCritical;
+#[cfg(all(feature = "diagnostics", feature = "std"))]
+pub use diagnostics::{set_slow_hook, ComputeEvent, SlowReport};
+#[cfg(feature = "diagnostics")]
+pub use diagnostics::{set_waste_hook, Waste, WasteReport};
+#[cfg(feature = "disk")]
+pub use disk::DiskCache;
+#[cfg(feature = "alloc")]
+pub use dynamic::{Computation, DynMemoize, MemoDyn};
+pub use error::CacheError;
+#[cfg(all(feature = "test-util", feature = "std"))]
+pub use fake::{override_with, Overridable, Override};
+pub use fallible::{ErrorPolicy, Fallible, TryMemoize};
+pub use function::MemoFn;
+#[cfg(feature = "critical-section")]
+pub use global::CriticalSectionLock;
+#[cfg(any(feature = "std", feature = "critical-section"))]
+pub use global::DefaultLock;
+#[cfg(feature = "std")]
+pub use global::MutexLock;
+pub use global::{StaticLock, StaticMemo};
+#[cfg(feature = "graph")]
+pub use graph::{Direction, Graph, MemoGraph, MemoizeNode, NodeValues};
+#[cfg(feature = "std")]
+pub use lock::{MemoLock, MemoReadGuard};
+#[cfg(feature = "mmap")]
+pub use mapped::Mappable;
+pub use memsize::MemSize;
+#[cfg(feature = "std")]
+pub use oncelock::MemoShared;
+#[cfg(feature = "rayon")]
+pub use parallel::{par_warm, MemoizeParallel, Parallel};
+pub use params::Params;
+#[cfg(feature = "alloc")]
+pub use partial::PartialMemo;
+#[cfg(feature = "serde")]
+pub use persist::Persisted;
+pub use refresh::Refreshed;
+#[cfg(feature = "alloc")]
+pub use registry::{Handle, Registry};
+#[cfg(feature = "stats")]
+pub use registry::{RegistryReport, TypeReport};
+#[cfg(feature = "std")]
+pub use revalidate::{MaybeStale, MemoRevalidate};
+pub use scan::MemoScan;
+#[cfg(feature = "self-ref")]
+pub use selfref::{MemoSelfRef, MemoizeBorrowing};
+#[cfg(feature = "alloc")]
+pub use snapshot::{MemoSnapshot, SharedPtr};
+#[cfg(feature = "std")]
+pub use spawn::MemoSpawn;
+#[cfg(feature = "self-ref")]
+pub use stable_deref_trait::StableDeref;
+pub use storage::{FixedMap, FixedMapIter, Lookup, Storage};
+pub use strategy::{MemoizeWith, Using};
+#[cfg(feature = "arc-swap")]
+pub use swap::MemoSwap;
+#[cfg(feature = "test-util")]
+pub use test_util::{CallTracker, CountingMemoize, MemoizeLaws, Tracked};
+pub use typestate::{Computed, Empty, MemoState, TypedMemo};
+#[cfg(feature = "tokio")]
+pub use watch::MemoWatch;
+#[cfg(feature = "alloc")]
+pub use weak::{Downgrade, MemoWeak};
+#[cfg(feature = "yoke")]
+pub use yoked::MemoizeYoke;
+
+#[cfg(feature = "alloc")]
+use alloc::boxed::Box;
+#[cfg(feature = "paranoid")]
+use core::any::type_name;
 use core::borrow::Borrow;
+use core::hash::Hash;
+use core::marker::PhantomData;
+use core::ops::Deref;
+
+#[cfg(feature = "paranoid")]
+use audit::Audit;
+#[cfg(feature = "diagnostics")]
+use diagnostics::Usage;
 
 /// Represents a computation that is to be memoized
 ///
@@ -141,10 +501,162 @@ use core::borrow::Borrow;
 /// assert_eq!(memo.get().0, "abcabcabc");
 /// ```
 ///
+/// ## Deriving
+///
+/// With the `derive` feature, simple cases, where each field of a struct is
+/// computed from the parameter, can be derived. The parameter type goes in
+/// `#[memoize(param = Type)]` on the struct, and the expression computing each
+/// field goes in `#[memoize(...)]` on the field, using the parameter as `p`.
+///
+#[cfg_attr(feature = "derive", doc = "```")]
+#[cfg_attr(not(feature = "derive"), doc = "```ignore")]
+/// use core_memo::{Memoize, Memo};
+///
+/// #[derive(Memoize)]
+/// #[memoize(param = [f64])]
+/// struct Summary {
+///     #[memoize(p.len())]
+///     count: usize,
+///     #[memoize(p.iter().sum::<f64>() / p.len() as f64)]
+///     mean: f64,
+///     #[memoize(p.iter().cloned().fold(f64::NAN, f64::max))]
+///     max: f64,
+/// }
+///
+/// let mut memo: Memo<Summary, _> = Memo::new(vec![1.0, 2.0, 6.0]);
+///
+/// assert_eq!(memo.get().count, 3);
+/// assert_eq!(memo.get().mean, 3.0);
+/// assert_eq!(memo.get().max, 6.0);
+/// ```
+///
+/// A function which takes the parameter by reference can also be turned into
+/// a computation, with `#[memo]`. It generates a newtype named after the
+/// function, which holds its result, and a `Memo` type for it.
+///
+#[cfg_attr(feature = "derive", doc = "```")]
+#[cfg_attr(not(feature = "derive"), doc = "```ignore")]
+/// use core_memo::memo;
+///
+/// #[memo]
+/// fn word_count(text: &str) -> usize {
+///     text.split_whitespace().count()
+/// }
+///
+/// let mut memo: WordCountMemo<String> = WordCountMemo::new("to be or not".into());
+///
+/// assert_eq!(memo.get().0, 4);
+/// ```
+///
 pub trait Memoize {
     type Param: ?Sized;
 
+    /// Approximate cost of the computation, relative to other computations
+    ///
+    /// This is only a hint, used to prioritize work. Caches prefer to keep
+    /// values which are expensive to recompute, and schedulers prefer to
+    /// recompute cheap values first. The unit is up to you (like microseconds),
+    /// but it should be consistent across the computations that you mix.
+    const COST: u32 = 1;
+
     fn memoize(p: &Self::Param) -> Self;
+
+    /// Approximate cost of the computation for a given parameter
+    ///
+    /// Override this if the cost depends on the parameter (like its length).
+    /// Defaults to `COST`.
+    fn cost(_p: &Self::Param) -> u32 {
+        Self::COST
+    }
+}
+
+/// Declare a computation from a closure
+///
+/// `memoize!(struct Name(Fields) from Param = |p| expression)` declares the
+/// tuple struct `Name`, and implements `Memoize` for it, with `Param` as the
+/// parameter type, and the closure as the computation. The closure is given
+/// a `&Param`, and returns a `Name`.
+///
+/// Attributes, doc comments, and visibility can be given, like on a `struct`
+/// item. This is plain `macro_rules!`, so it needs no features, and works in
+/// `no_std` programs.
+///
+/// ## Example
+///
+/// ```
+/// #[macro_use]
+/// extern crate core_memo;
+///
+/// use core_memo::Memo;
+///
+/// memoize!(struct Total(i32) from [i32] = |p| Total(p.iter().sum()));
+///
+/// memoize! {
+///     /// The longest line
+///     #[derive(Debug, PartialEq)]
+///     pub struct Widest(pub usize) from str = |text| {
+///         Widest(text.lines().map(str::len).max().unwrap_or(0))
+///     }
+/// }
+///
+/// fn main() {
+///     let mut total: Memo<Total, _> = Memo::new(vec![1, 2, 3]);
+///     assert_eq!(total.get().0, 6);
+///
+///     let mut widest: Memo<Widest, &str> = Memo::new("ab\nabcd\nabc");
+///     assert_eq!(widest.get(), &Widest(4));
+/// }
+/// ```
+///
+#[macro_export]
+macro_rules! memoize {
+    (
+        $(#[$attr:meta])*
+        $vis:vis struct $name:ident ($($field_vis:vis $field:ty),* $(,)*)
+        from $param:ty = |$p:pat| $body:expr $(;)*
+    ) => {
+        $(#[$attr])*
+        $vis struct $name($($field_vis $field),*);
+
+        impl $crate::Memoize for $name {
+            type Param = $param;
+
+            fn memoize($p: &$param) -> Self {
+                $body
+            }
+        }
+    };
+}
+
+/// Common interface for clearing the cached value of a memoization type
+///
+/// This is implemented for `Memo`, `MemoExt`, `MemoOnce`, and `MemoMut`, so that code
+/// which only needs to invalidate memos (such as `InvalidationBus`) can work
+/// with any of them.
+pub trait Invalidate {
+    /// Clears any cached value
+    ///
+    /// The value will be reevaluated the next time it is needed.
+    fn invalidate(&mut self);
+}
+
+/// Common interface for memoization types which can compute their own value
+///
+/// This is implemented for `Memo`, `MemoOnce`, and `MemoMut`, which have access to the
+/// parameter for the computation. It is not implemented for `MemoExt`.
+pub trait Ready {
+    /// Check if there is a cached value
+    fn is_ready(&self) -> bool;
+
+    /// If the value is not ready, compute it and cache it
+    fn ready(&mut self);
+
+    /// Approximate cost of computing the value
+    ///
+    /// See `Memoize::cost()`.
+    fn cost(&self) -> u32 {
+        1
+    }
 }
 
 /// Memoized value with a parameter provided externally
@@ -163,7 +675,8 @@ pub trait Memoize {
 ///
 /// It is very easy to introduce logic bugs in your program with this type. You
 /// should prefer the `Memo` or `MemoOnce` types, unless you need the extra
-/// flexibility provided by `MemoExt`.
+/// flexibility provided by `MemoExt`. If you do, call `audit_params()` to
+/// catch a forgotten `clear()` in your tests.
 ///
 /// ## Example
 ///
@@ -207,8 +720,21 @@ pub trait Memoize {
 /// ```
 ///
 #[derive(Debug)]
-pub struct MemoExt<T: Memoize> {
+#[cfg_attr(
+    feature = "rkyv",
+    derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)
+)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MemoExt<T> {
     value: Option<T>,
+    #[cfg(feature = "diagnostics")]
+    #[cfg_attr(feature = "serde", serde(skip))]
+    #[cfg_attr(feature = "rkyv", rkyv(with = rkyv::with::Skip))]
+    usage: Usage,
+    #[cfg(feature = "paranoid")]
+    #[cfg_attr(feature = "serde", serde(skip))]
+    #[cfg_attr(feature = "rkyv", rkyv(with = rkyv::with::Skip))]
+    audit: Audit,
 }
 
 /// Memoized value which holds ownership over the parameter for its computation
@@ -227,9 +753,18 @@ pub struct MemoExt<T: Memoize> {
 /// See the crate-level documentation for an example.
 ///
 #[derive(Debug)]
-pub struct Memo<T: Memoize, P: Borrow<T::Param> = <T as Memoize>::Param> {
+#[cfg_attr(
+    feature = "rkyv",
+    derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)
+)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Memo<T, P = <T as Memoize>::Param> {
     value: Option<T>,
     param: P,
+    #[cfg(feature = "diagnostics")]
+    #[cfg_attr(feature = "serde", serde(skip))]
+    #[cfg_attr(feature = "rkyv", rkyv(with = rkyv::with::Skip))]
+    usage: Usage,
 }
 
 /// Memoized value which holds a reference to the parameter for its computation
@@ -240,6 +775,11 @@ pub struct Memo<T: Memoize, P: Borrow<T::Param> = <T as Memoize>::Param> {
 /// to the input parameter for the computation, meaning that it cannot be
 /// mutated while the `MemoOnce` is alive.
 ///
+/// By default, the reference is a plain `&T::Param`, but it can be any pointer
+/// which derefs to (something that borrows as) the parameter, given as the
+/// `R` type parameter, like a `Ref` from a `RefCell`, or an `Rc`. This lets
+/// you memoize values for parameters you don't own, without cloning them.
+///
 /// ## Example
 ///
 /// ```
@@ -277,21 +817,83 @@ pub struct Memo<T: Memoize, P: Borrow<T::Param> = <T as Memoize>::Param> {
 /// // now our `MemoOnce` has been dropped, our String is no longer borrowed,
 /// // and we are free to mutate it:
 /// my_string.push_str(" Not anymore!");
+///
+/// // it can also hold a borrow from a `RefCell`, or any other pointer
+/// use std::cell::{Ref, RefCell};
+///
+/// let shared = RefCell::new(my_string);
+/// let mut len: MemoOnce<MemoLength, Ref<String>> = MemoOnce::new(shared.borrow());
+/// assert_eq!(len.get().0, 36);
 /// ```
 ///
 #[derive(Debug)]
-pub struct MemoOnce<'p, T: Memoize>
+pub struct MemoOnce<'p, T, R = &'p <T as Memoize>::Param> {
+    value: Option<T>,
+    param: R,
+    borrow: PhantomData<&'p ()>,
+    #[cfg(feature = "diagnostics")]
+    usage: Usage,
+}
+
+/// Memoized value which holds a mutable reference to the parameter for its
+/// computation
+///
+/// See the crate-level documentation for information how to use the library.
+///
+/// This is the middle ground between `Memo` and `MemoOnce`: the parameter is
+/// owned by someone else, like with `MemoOnce`, but it can be modified through
+/// the `MemoMut`, like with `Memo`. Any cached value is cleared when you do.
+///
+/// ## Example
+///
+/// ```
+/// use core_memo::{Memoize, MemoMut};
+///
+/// struct Total(u32);
+///
+/// impl Memoize for Total {
+///     type Param = Vec<u32>;
+///     fn memoize(p: &Vec<u32>) -> Self {
+///         Total(p.iter().sum())
+///     }
+/// }
+///
+/// let mut prices = vec![10, 20];
+///
+/// {
+///     let mut total: MemoMut<Total> = MemoMut::new(&mut prices);
+///     assert_eq!(total.get().0, 30);
+///
+///     // modifying the parameter clears the value
+///     total.param_mut().push(5);
+///     assert_eq!(total.get().0, 35);
+/// }
+///
+/// // the changes were made to our own vec
+/// assert_eq!(prices, [10, 20, 5]);
+/// ```
+///
+#[derive(Debug)]
+pub struct MemoMut<'p, T: Memoize>
 where
     T::Param: 'p,
 {
     value: Option<T>,
-    param: &'p T::Param,
+    param: &'p mut T::Param,
+    #[cfg(feature = "diagnostics")]
+    usage: Usage,
 }
 
-impl<T: Memoize> MemoExt<T> {
+impl<T> MemoExt<T> {
     /// Creates a new `MemoExt` instance
     pub fn new() -> Self {
-        Self { value: None }
+        Self {
+            value: None,
+            #[cfg(feature = "diagnostics")]
+            usage: Usage::new::<Self>(),
+            #[cfg(feature = "paranoid")]
+            audit: Audit::default(),
+        }
     }
 
     /// Clears any cached value
@@ -300,6 +902,8 @@ impl<T: Memoize> MemoExt<T> {
     ///
     /// The value will be reevaluated the next time it is needed.
     pub fn clear(&mut self) {
+        #[cfg(feature = "defmt")]
+        deferred::cleared::<T>();
         self.value = None
     }
 
@@ -311,16 +915,69 @@ impl<T: Memoize> MemoExt<T> {
     /// If this method returns `false`, the next call to `get()` will recompute
     /// the value.
     pub fn is_ready(&self) -> bool {
+        #[cfg(feature = "diagnostics")]
+        self.usage.used();
         self.value.is_some()
     }
 
+    /// Get the value if it is available
+    ///
+    /// If there is a cached value, returns it. If the value needs to be
+    /// computed, returns `None`.
+    pub fn try_get(&self) -> Option<&T> {
+        #[cfg(feature = "diagnostics")]
+        self.usage.read();
+        self.value.as_ref()
+    }
+
+    /// Set the cached value
+    ///
+    /// This stores `value` as if it had been computed, replacing any cached
+    /// value. It must be the value the computation would return for the
+    /// parameter which will be provided to `get()`.
+    ///
+    /// This does not require `T` to implement `Memoize`, so a `MemoExt` can
+    /// also hold values which are only ever provided with `set()`.
+    pub fn set(&mut self, value: T) {
+        self.value = Some(value);
+        #[cfg(feature = "diagnostics")]
+        self.usage.computed();
+        #[cfg(feature = "paranoid")]
+        self.audit.forget();
+    }
+
+    /// Take the cached value out, if there is one
+    ///
+    /// This leaves the `MemoExt` empty, as if `clear()` had been called.
+    pub fn take(&mut self) -> Option<T> {
+        #[cfg(feature = "diagnostics")]
+        self.usage.read();
+        self.value.take()
+    }
+
+    /// Set how long computing the value may take
+    ///
+    /// Requires the `diagnostics` and `std` features.
+    ///
+    /// In debug builds, computations which take longer are reported to the
+    /// hook set with `set_slow_hook()`.
+    #[cfg(all(feature = "diagnostics", feature = "std"))]
+    pub fn set_budget(&mut self, budget: core::time::Duration) {
+        self.usage.set_budget(budget);
+    }
+}
+
+impl<T: Memoize> MemoExt<T> {
     /// If the value is not ready, compute it and cache it
     ///
     /// Call this method if you want to make sure that future `get()` calls can
     /// return instantly without computing the value.
-    pub fn ready(&mut self, p: &T::Param) {
+    pub fn ready<B: Borrow<T::Param> + ?Sized>(&mut self, p: &B) {
+        #[cfg(feature = "paranoid")]
+        self.audit
+            .check::<T::Param>(p.borrow(), self.value.is_some(), type_name::<Self>());
         if self.value.is_none() {
-            self.value = Some(T::memoize(p));
+            self.compute(p.borrow());
         }
     }
 
@@ -330,8 +987,23 @@ impl<T: Memoize> MemoExt<T> {
     ///
     /// It is probably better to call `clear()` instead, to compute the value
     /// lazily when it is next needed.
-    pub fn update(&mut self, p: &T::Param) {
+    pub fn update<B: Borrow<T::Param> + ?Sized>(&mut self, p: &B) {
+        #[cfg(feature = "paranoid")]
+        self.audit
+            .check::<T::Param>(p.borrow(), false, type_name::<Self>());
+        self.compute(p.borrow());
+    }
+
+    /// Compute the value and cache it, running the hooks of the enabled
+    /// features around the computation
+    fn compute(&mut self, p: &T::Param) {
+        #[cfg(feature = "defmt")]
+        deferred::computing::<T>();
+        #[cfg(feature = "diagnostics")]
+        let start = self.usage.start();
         self.value = Some(T::memoize(p));
+        #[cfg(feature = "diagnostics")]
+        self.usage.computed_since::<T::Param>(start, p);
     }
 
     /// Get the value
@@ -341,29 +1013,392 @@ impl<T: Memoize> MemoExt<T> {
     ///
     /// If you need to make sure this method always returns quickly, call
     /// `ready()` beforehand or use `try_get()`.
-    pub fn get(&mut self, p: &T::Param) -> &T {
+    ///
+    /// Like with `ready()` and `update()`, the parameter can be anything which
+    /// borrows as `T::Param` (like a `String` for a `str` parameter, or a `Vec`
+    /// for a slice), not only `T::Param` itself.
+    pub fn get<B: Borrow<T::Param> + ?Sized>(&mut self, p: &B) -> &T {
         self.ready(p);
         self.try_get().unwrap()
     }
 
+    /// Get the value, taking the parameter by value
+    ///
+    /// Same as `get()`, but for parameters which are easier to produce on the
+    /// spot than to keep alive (like a temporary `String`). The parameter is
+    /// only used if the value needs to be computed, and is dropped afterwards.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// use core_memo::{Memoize, MemoExt};
+    ///
+    /// struct Upper(String);
+    ///
+    /// impl Memoize for Upper {
+    ///     type Param = str;
+    ///     fn memoize(p: &str) -> Self {
+    ///         Upper(p.to_uppercase())
+    ///     }
+    /// }
+    ///
+    /// let mut memo: MemoExt<Upper> = MemoExt::new();
+    /// let name = |first: &str, last: &str| format!("{} {}", first, last);
+    ///
+    /// assert_eq!(memo.get_owned(name("ada", "lovelace")).0, "ADA LOVELACE");
+    /// ```
+    ///
+    pub fn get_owned<P: Borrow<T::Param>>(&mut self, p: P) -> &T {
+        self.ready(&p);
+        self.try_get().unwrap()
+    }
+
+    /// Get the value, passing the parts of a tuple parameter individually
+    ///
+    /// Same as `get()`, but the parameter is only assembled from the arguments
+    /// (see `Params`) if the value needs to be computed. The arguments can also
+    /// be a closure, which builds the parameter (like `|| load_config()`), to
+    /// avoid that work entirely when the value is cached.
+    pub fn get_with<A: Params<T::Param>>(&mut self, args: A) -> &T
+    where
+        T::Param: Sized,
+    {
+        if self.value.is_none() {
+            self.ready(&args.into_param());
+        }
+        self.try_get().unwrap()
+    }
+
+    /// Turn this into a `Memo`, which owns the parameter
+    ///
+    /// Any cached value is kept, so `p` must be the parameter that it was
+    /// computed from. Use this to hand over ownership of the parameter, once
+    /// it no longer needs to be managed externally.
+    pub fn into_memo<P: Borrow<T::Param>>(self, p: P) -> Memo<T, P> {
+        Memo {
+            value: self.value,
+            param: p,
+            #[cfg(feature = "diagnostics")]
+            usage: self.usage,
+        }
+    }
+}
+
+impl<T> Default for MemoExt<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Memoize> MemoExt<T>
+where
+    T::Param: Hash,
+{
+    /// Check that the value is always requested with the same parameter
+    ///
+    /// Forgetting to call `clear()` when the parameter changes is the easiest
+    /// way to get a wrong answer out of a `MemoExt`. After calling this, with
+    /// the `paranoid` feature, the memo remembers a hash of the parameter its
+    /// value was computed from, and `ready()` and `get()` panic if they are
+    /// given a parameter which hashes differently while the value is cached.
+    /// Without the feature, this does nothing, and `MemoExt` has no room to
+    /// remember the hash, so enable it in your tests (like through a
+    /// dev-dependency), rather than in your releases.
+    ///
+    /// Values provided with `set()` are not checked, until the memo computes
+    /// one itself.
+    ///
+    /// ## Example
+    ///
+    #[cfg_attr(feature = "paranoid", doc = "```should_panic")]
+    #[cfg_attr(not(feature = "paranoid"), doc = "```ignore")]
+    /// use core_memo::{Memoize, MemoExt};
+    ///
+    /// struct Len(usize);
+    ///
+    /// impl Memoize for Len {
+    ///     type Param = str;
+    ///     fn memoize(p: &str) -> Self {
+    ///         Len(p.len())
+    ///     }
+    /// }
+    ///
+    /// let mut memo: MemoExt<Len> = MemoExt::new();
+    /// memo.audit_params();
+    ///
+    /// assert_eq!(memo.get("hello").0, 5);
+    ///
+    /// // WHOOPS: forgot to call `clear()`
+    /// memo.get("hello, world");
+    /// ```
+    ///
+    pub fn audit_params(&mut self) {
+        #[cfg(feature = "paranoid")]
+        self.audit.enable::<T::Param>();
+    }
+}
+
+impl<T, P> Memo<T, P> {
+    /// Creates a new `Memo` instance
+    ///
+    /// You must pass in the object which will be used as the parameter
+    /// for your computation. The `Memo` will take ownership over it.
+    pub fn new(p: P) -> Self {
+        Self {
+            value: None,
+            param: p,
+            #[cfg(feature = "diagnostics")]
+            usage: Usage::new::<Self>(),
+        }
+    }
+
+    /// Clears any cached value
+    ///
+    /// The value will be reevaluated the next time it is needed.
+    pub fn clear(&mut self) {
+        #[cfg(feature = "defmt")]
+        deferred::cleared::<T>();
+        self.value = None
+    }
+
+    /// Check if there is a cached value
+    ///
+    /// If this method returns `true`, the next call to `get()` will return a
+    /// stored memoized value.
+    ///
+    /// If this method returns `false`, the next call to `get()` will recompute
+    /// the value.
+    pub fn is_ready(&self) -> bool {
+        #[cfg(feature = "diagnostics")]
+        self.usage.used();
+        self.value.is_some()
+    }
+
     /// Get the value if it is available
     ///
     /// If there is a cached value, returns it. If the value needs to be
     /// computed, returns `None`.
     pub fn try_get(&self) -> Option<&T> {
+        #[cfg(feature = "diagnostics")]
+        self.usage.read();
         self.value.as_ref()
     }
+
+    /// Get a reference to the parameter used for the computation
+    pub fn param(&self) -> &P {
+        &self.param
+    }
+
+    /// Get a mutable reference to the parameter used for the computation
+    ///
+    /// This clears any cached value.
+    pub fn param_mut(&mut self) -> &mut P {
+        self.clear();
+        &mut self.param
+    }
+
+    /// Modify the parameter used for the computation
+    ///
+    /// Takes a closure and applies it to the parameter.
+    ///
+    /// This clears any cached value.
+    pub fn update_param<F>(&mut self, op: F)
+    where
+        F: FnOnce(&mut P),
+    {
+        self.clear();
+        op(&mut self.param);
+    }
+
+    /// Split this into the parameter and a `MemoExt`
+    ///
+    /// Any cached value is kept in the `MemoExt`. Use this to give back
+    /// ownership of the parameter, while keeping the value derived from it.
+    pub fn into_ext(self) -> (P, MemoExt<T>) {
+        let ext = MemoExt {
+            value: self.value,
+            #[cfg(feature = "diagnostics")]
+            usage: self.usage,
+            #[cfg(feature = "paranoid")]
+            audit: Audit::default(),
+        };
+        (self.param, ext)
+    }
+
+    /// Set the cached value
+    ///
+    /// This stores `value` as if it had been computed, replacing any cached
+    /// value. It must be the value the computation would return for the
+    /// parameter.
+    pub fn set(&mut self, value: T) {
+        self.value = Some(value);
+        #[cfg(feature = "diagnostics")]
+        self.usage.computed();
+    }
+
+    /// Take the cached value out, if there is one
+    ///
+    /// This leaves the `Memo` empty, as if `clear()` had been called.
+    pub fn take(&mut self) -> Option<T> {
+        #[cfg(feature = "diagnostics")]
+        self.usage.read();
+        self.value.take()
+    }
+
+    /// Set how long computing the value may take
+    ///
+    /// Requires the `diagnostics` and `std` features.
+    ///
+    /// In debug builds, computations which take longer are reported to the
+    /// hook set with `set_slow_hook()`.
+    #[cfg(all(feature = "diagnostics", feature = "std"))]
+    pub fn set_budget(&mut self, budget: core::time::Duration) {
+        self.usage.set_budget(budget);
+    }
 }
 
 impl<T: Memoize, P: Borrow<T::Param>> Memo<T, P> {
-    /// Creates a new `Memo` instance
+    /// If the value is not ready, compute it and cache it
     ///
-    /// You must pass in the object which will be used as the parameter
-    /// for your computation. The `Memo` will take ownership over it.
-    pub fn new(p: P) -> Self {
+    /// Call this method if you want to make sure that future `get()` calls can
+    /// return instantly without computing the value.
+    pub fn ready(&mut self) {
+        if self.value.is_none() {
+            self.compute();
+        }
+    }
+
+    /// Force the value to be recomputed
+    ///
+    /// This discards any stored value and computes a new one immediately.
+    ///
+    /// It is probably better to call `clear()` instead, to compute the value
+    /// lazily when it is next needed.
+    pub fn update(&mut self) {
+        self.compute();
+    }
+
+    /// Compute the value and cache it, running the hooks of the enabled
+    /// features around the computation
+    fn compute(&mut self) {
+        #[cfg(feature = "defmt")]
+        deferred::computing::<T>();
+        #[cfg(feature = "diagnostics")]
+        let start = self.usage.start();
+        self.value = Some(T::memoize(self.param.borrow()));
+        #[cfg(feature = "diagnostics")]
+        self.usage
+            .computed_since::<T::Param>(start, self.param.borrow());
+    }
+
+    /// Get the value
+    ///
+    /// If the value has already been computed, this function returns the cached
+    /// value. If not, it is computed and cached for future use.
+    ///
+    /// If you need to make sure this method always returns quickly, call
+    /// `ready()` beforehand or use `try_get()`.
+    pub fn get(&mut self) -> &T {
+        self.ready();
+        self.try_get().unwrap()
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<T: Memoize> Memo<T, Box<T::Param>> {
+    /// Creates a new `Memo` instance, owning the parameter in a `Box`
+    ///
+    /// Requires the `alloc` feature.
+    ///
+    /// This is the simplest way to own an unsized parameter (like `str` or
+    /// `[T]`), without picking a specific container for it. Anything that can
+    /// be converted into a `Box` of the parameter (like a `&str` or a `String`
+    /// for `str`) will do.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// use core_memo::{Memoize, Memo};
+    ///
+    /// struct Len(usize);
+    ///
+    /// impl Memoize for Len {
+    ///     type Param = str;
+    ///     fn memoize(p: &str) -> Self {
+    ///         Len(p.len())
+    ///     }
+    /// }
+    ///
+    /// let mut a: Memo<Len, Box<str>> = Memo::boxed("hello");
+    /// let mut b: Memo<Len, Box<str>> = Memo::boxed(String::from("hi"));
+    ///
+    /// assert_eq!(a.get().0, 5);
+    /// assert_eq!(b.get().0, 2);
+    /// ```
+    ///
+    pub fn boxed<B: Into<Box<T::Param>>>(p: B) -> Self {
+        Self::new(p.into())
+    }
+}
+
+impl<T, P: Default> Memo<T, P> {
+    /// Creates a new `Memo` instance, with the default value of the parameter
+    ///
+    /// This lets a `Memo` be part of a struct that derives `Default`, and be
+    /// populated later, using `param_mut()` or `update_param()`.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// use core_memo::{Memoize, Memo};
+    ///
+    /// struct Sum(i32);
+    ///
+    /// impl Memoize for Sum {
+    ///     type Param = Vec<i32>;
+    ///     fn memoize(p: &Vec<i32>) -> Self {
+    ///         Sum(p.iter().sum())
+    ///     }
+    /// }
+    ///
+    /// #[derive(Default)]
+    /// struct Config {
+    ///     weights: Memo<Sum>,
+    /// }
+    ///
+    /// let mut config = Config::default();
+    /// assert_eq!(config.weights.get().0, 0);
+    ///
+    /// config.weights.param_mut().extend(&[1, 2, 3]);
+    /// assert_eq!(config.weights.get().0, 6);
+    /// ```
+    ///
+    pub fn from_default() -> Self {
+        Self::new(P::default())
+    }
+}
+
+impl<T, P: Default> Default for Memo<T, P> {
+    fn default() -> Self {
+        Self::from_default()
+    }
+}
+
+impl<'p, T: Memoize, R: Deref> MemoOnce<'p, T, R>
+where
+    R::Target: Borrow<T::Param>,
+{
+    /// Creates a new `MemoOnce` instance
+    ///
+    /// You must pass a reference to the object which will be used as the
+    /// parameter for your computation.
+    pub fn new(p: R) -> Self {
         Self {
             value: None,
             param: p,
+            borrow: PhantomData,
+            #[cfg(feature = "diagnostics")]
+            usage: Usage::new::<Self>(),
         }
     }
 
@@ -371,6 +1406,8 @@ impl<T: Memoize, P: Borrow<T::Param>> Memo<T, P> {
     ///
     /// The value will be reevaluated the next time it is needed.
     pub fn clear(&mut self) {
+        #[cfg(feature = "defmt")]
+        deferred::cleared::<T>();
         self.value = None
     }
 
@@ -382,6 +1419,8 @@ impl<T: Memoize, P: Borrow<T::Param>> Memo<T, P> {
     /// If this method returns `false`, the next call to `get()` will recompute
     /// the value.
     pub fn is_ready(&self) -> bool {
+        #[cfg(feature = "diagnostics")]
+        self.usage.used();
         self.value.is_some()
     }
 
@@ -391,7 +1430,7 @@ impl<T: Memoize, P: Borrow<T::Param>> Memo<T, P> {
     /// return instantly without computing the value.
     pub fn ready(&mut self) {
         if self.value.is_none() {
-            self.value = Some(T::memoize(self.param.borrow()));
+            self.compute();
         }
     }
 
@@ -402,7 +1441,20 @@ impl<T: Memoize, P: Borrow<T::Param>> Memo<T, P> {
     /// It is probably better to call `clear()` instead, to compute the value
     /// lazily when it is next needed.
     pub fn update(&mut self) {
-        self.value = Some(T::memoize(self.param.borrow()));
+        self.compute();
+    }
+
+    /// Compute the value and cache it, running the hooks of the enabled
+    /// features around the computation
+    fn compute(&mut self) {
+        #[cfg(feature = "defmt")]
+        deferred::computing::<T>();
+        #[cfg(feature = "diagnostics")]
+        let start = self.usage.start();
+        self.value = Some(T::memoize((*self.param).borrow()));
+        #[cfg(feature = "diagnostics")]
+        self.usage
+            .computed_since::<T::Param>(start, (*self.param).borrow());
     }
 
     /// Get the value
@@ -422,45 +1474,108 @@ impl<T: Memoize, P: Borrow<T::Param>> Memo<T, P> {
     /// If there is a cached value, returns it. If the value needs to be
     /// computed, returns `None`.
     pub fn try_get(&self) -> Option<&T> {
+        #[cfg(feature = "diagnostics")]
+        self.usage.read();
         self.value.as_ref()
     }
 
-    /// Get a reference to the parameter used for the computation
-    pub fn param(&self) -> &P {
-        &self.param
+    /// Set the cached value
+    ///
+    /// This stores `value` as if it had been computed, replacing any cached
+    /// value. It must be the value the computation would return for the
+    /// parameter.
+    pub fn set(&mut self, value: T) {
+        self.value = Some(value);
+        #[cfg(feature = "diagnostics")]
+        self.usage.computed();
     }
 
-    /// Get a mutable reference to the parameter used for the computation
+    /// Take the cached value out, if there is one
     ///
-    /// This clears any cached value.
-    pub fn param_mut(&mut self) -> &mut P {
-        self.clear();
-        &mut self.param
+    /// This leaves the `MemoOnce` empty, as if `clear()` had been called.
+    pub fn take(&mut self) -> Option<T> {
+        #[cfg(feature = "diagnostics")]
+        self.usage.read();
+        self.value.take()
     }
 
-    /// Modify the parameter used for the computation
+    /// Set how long computing the value may take
     ///
-    /// Takes a closure and applies it to the parameter.
+    /// Requires the `diagnostics` and `std` features.
     ///
-    /// This clears any cached value.
-    pub fn update_param<F>(&mut self, op: F)
-    where
-        F: FnOnce(&mut P),
-    {
-        self.clear();
-        op(&mut self.param);
+    /// In debug builds, computations which take longer are reported to the
+    /// hook set with `set_slow_hook()`.
+    #[cfg(all(feature = "diagnostics", feature = "std"))]
+    pub fn set_budget(&mut self, budget: core::time::Duration) {
+        self.usage.set_budget(budget);
+    }
+
+    /// Get a reference to the parameter used for the computation
+    pub fn param(&self) -> &T::Param {
+        (*self.param).borrow()
+    }
+
+    /// Get the pointer back, releasing the parameter
+    pub fn into_ptr(self) -> R {
+        self.param
     }
 }
 
 impl<'p, T: Memoize> MemoOnce<'p, T> {
-    /// Creates a new `MemoOnce` instance
+    /// Creates a new `MemoOnce` instance from a raw pointer to the parameter
     ///
-    /// You must pass a reference to the object which will be used as the
-    /// parameter for your computation.
-    pub fn new(p: &'p T::Param) -> Self {
+    /// This is for FFI, when the parameter is owned by foreign code, and its
+    /// lifetime cannot be expressed as a Rust borrow. The lifetime of the
+    /// returned `MemoOnce` is unbounded, so it is up to you to not let it
+    /// outlive the parameter.
+    ///
+    /// # Safety
+    ///
+    /// `p` must be non-null, properly aligned, and point to a valid `T::Param`,
+    /// which is not mutated or freed for as long as the `MemoOnce` is alive,
+    /// like with `&*p`.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// use core_memo::{Memoize, MemoOnce};
+    ///
+    /// struct Total(u64);
+    ///
+    /// impl Memoize for Total {
+    ///     type Param = [u64];
+    ///     fn memoize(p: &[u64]) -> Self {
+    ///         Total(p.iter().sum())
+    ///     }
+    /// }
+    ///
+    /// // owned by "foreign code"
+    /// let raw: *mut [u64] = Box::into_raw(vec![1, 2, 3].into_boxed_slice());
+    ///
+    /// let mut memo: MemoOnce<Total> = unsafe { MemoOnce::new_unchecked(raw) };
+    /// assert_eq!(memo.get().0, 6);
+    /// drop(memo);
+    ///
+    /// // only free it once the memo is gone
+    /// drop(unsafe { Box::from_raw(raw) });
+    /// ```
+    ///
+    pub unsafe fn new_unchecked(p: *const T::Param) -> Self {
+        Self::new(&*p)
+    }
+}
+
+impl<'p, T: Memoize> MemoMut<'p, T> {
+    /// Creates a new `MemoMut` instance
+    ///
+    /// You must pass a mutable reference to the object which will be used as
+    /// the parameter for your computation.
+    pub fn new(p: &'p mut T::Param) -> Self {
         Self {
             value: None,
             param: p,
+            #[cfg(feature = "diagnostics")]
+            usage: Usage::new::<Self>(),
         }
     }
 
@@ -468,6 +1583,8 @@ impl<'p, T: Memoize> MemoOnce<'p, T> {
     ///
     /// The value will be reevaluated the next time it is needed.
     pub fn clear(&mut self) {
+        #[cfg(feature = "defmt")]
+        deferred::cleared::<T>();
         self.value = None
     }
 
@@ -479,6 +1596,8 @@ impl<'p, T: Memoize> MemoOnce<'p, T> {
     /// If this method returns `false`, the next call to `get()` will recompute
     /// the value.
     pub fn is_ready(&self) -> bool {
+        #[cfg(feature = "diagnostics")]
+        self.usage.used();
         self.value.is_some()
     }
 
@@ -488,7 +1607,7 @@ impl<'p, T: Memoize> MemoOnce<'p, T> {
     /// return instantly without computing the value.
     pub fn ready(&mut self) {
         if self.value.is_none() {
-            self.value = Some(T::memoize(self.param));
+            self.compute();
         }
     }
 
@@ -499,7 +1618,19 @@ impl<'p, T: Memoize> MemoOnce<'p, T> {
     /// It is probably better to call `clear()` instead, to compute the value
     /// lazily when it is next needed.
     pub fn update(&mut self) {
+        self.compute();
+    }
+
+    /// Compute the value and cache it, running the hooks of the enabled
+    /// features around the computation
+    fn compute(&mut self) {
+        #[cfg(feature = "defmt")]
+        deferred::computing::<T>();
+        #[cfg(feature = "diagnostics")]
+        let start = self.usage.start();
         self.value = Some(T::memoize(self.param));
+        #[cfg(feature = "diagnostics")]
+        self.usage.computed_since::<T::Param>(start, self.param);
     }
 
     /// Get the value
@@ -519,11 +1650,162 @@ impl<'p, T: Memoize> MemoOnce<'p, T> {
     /// If there is a cached value, returns it. If the value needs to be
     /// computed, returns `None`.
     pub fn try_get(&self) -> Option<&T> {
+        #[cfg(feature = "diagnostics")]
+        self.usage.read();
         self.value.as_ref()
     }
 
+    /// Set the cached value
+    ///
+    /// This stores `value` as if it had been computed, replacing any cached
+    /// value. It must be the value the computation would return for the
+    /// parameter.
+    pub fn set(&mut self, value: T) {
+        self.value = Some(value);
+        #[cfg(feature = "diagnostics")]
+        self.usage.computed();
+    }
+
+    /// Take the cached value out, if there is one
+    ///
+    /// This leaves the `MemoMut` empty, as if `clear()` had been called.
+    pub fn take(&mut self) -> Option<T> {
+        #[cfg(feature = "diagnostics")]
+        self.usage.read();
+        self.value.take()
+    }
+
+    /// Set how long computing the value may take
+    ///
+    /// Requires the `diagnostics` and `std` features.
+    ///
+    /// In debug builds, computations which take longer are reported to the
+    /// hook set with `set_slow_hook()`.
+    #[cfg(all(feature = "diagnostics", feature = "std"))]
+    pub fn set_budget(&mut self, budget: core::time::Duration) {
+        self.usage.set_budget(budget);
+    }
+
     /// Get a reference to the parameter used for the computation
     pub fn param(&self) -> &T::Param {
+        self.param
+    }
+
+    /// Get a mutable reference to the parameter used for the computation
+    ///
+    /// This clears any cached value.
+    pub fn param_mut(&mut self) -> &mut T::Param {
+        self.clear();
+        self.param
+    }
+
+    /// Modify the parameter used for the computation
+    ///
+    /// Takes a closure and applies it to the parameter.
+    ///
+    /// This clears any cached value.
+    pub fn update_param<F>(&mut self, op: F)
+    where
+        F: FnOnce(&mut T::Param),
+    {
+        self.clear();
+        op(self.param);
+    }
+}
+
+#[cfg(feature = "rkyv")]
+impl<T: Memoize + rkyv::Archive> ArchivedMemoExt<T> {
+    /// Get the archived value, if it was cached when the memo was serialized
+    pub fn try_get(&self) -> Option<&rkyv::Archived<T>> {
+        self.value.as_ref()
+    }
+}
+
+#[cfg(feature = "rkyv")]
+impl<T, P> ArchivedMemo<T, P>
+where
+    T: Memoize + rkyv::Archive,
+    P: Borrow<T::Param> + rkyv::Archive,
+{
+    /// Get the archived value, if it was cached when the memo was serialized
+    pub fn try_get(&self) -> Option<&rkyv::Archived<T>> {
+        self.value.as_ref()
+    }
+
+    /// Get the archived parameter used for the computation
+    pub fn param(&self) -> &rkyv::Archived<P> {
         &self.param
     }
 }
+
+impl<T> Invalidate for MemoExt<T> {
+    fn invalidate(&mut self) {
+        self.clear()
+    }
+}
+
+impl<T, P> Invalidate for Memo<T, P> {
+    fn invalidate(&mut self) {
+        self.clear()
+    }
+}
+
+impl<'p, T: Memoize, R: Deref> Invalidate for MemoOnce<'p, T, R>
+where
+    R::Target: Borrow<T::Param>,
+{
+    fn invalidate(&mut self) {
+        self.clear()
+    }
+}
+
+impl<'p, T: Memoize> Invalidate for MemoMut<'p, T> {
+    fn invalidate(&mut self) {
+        self.clear()
+    }
+}
+
+impl<T: Memoize, P: Borrow<T::Param>> Ready for Memo<T, P> {
+    fn is_ready(&self) -> bool {
+        self.is_ready()
+    }
+
+    fn ready(&mut self) {
+        self.ready()
+    }
+
+    fn cost(&self) -> u32 {
+        T::cost(self.param.borrow())
+    }
+}
+
+impl<'p, T: Memoize, R: Deref> Ready for MemoOnce<'p, T, R>
+where
+    R::Target: Borrow<T::Param>,
+{
+    fn is_ready(&self) -> bool {
+        self.is_ready()
+    }
+
+    fn ready(&mut self) {
+        self.ready()
+    }
+
+    fn cost(&self) -> u32 {
+        T::cost(self.param())
+    }
+}
+
+impl<'p, T: Memoize> Ready for MemoMut<'p, T> {
+    fn is_ready(&self) -> bool {
+        self.is_ready()
+    }
+
+    fn ready(&mut self) {
+        self.ready()
+    }
+
+    fn cost(&self) -> u32 {
+        T::cost(self.param)
+    }
+}
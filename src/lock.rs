@@ -0,0 +1,209 @@
+use core::borrow::Borrow;
+use core::fmt;
+use core::ops::Deref;
+use std::sync::PoisonError;
+#[cfg(not(feature = "loom"))]
+use std::sync::TryLockError;
+#[cfg(not(feature = "loom"))]
+use std::thread;
+#[cfg(not(feature = "loom"))]
+use std::time::{Duration, Instant};
+
+use sync::{RwLock, RwLockReadGuard};
+use {CacheError, Invalidate, Memoize};
+
+/// Memoized value which can be shared between threads
+///
+/// Requires the `std` feature.
+///
+/// Like `Memo`, this holds ownership over the parameter for the computation.
+/// But the value is behind a `RwLock`, so `get()` only needs `&self`, and
+/// many threads can read the cached value at the same time. On a miss, only
+/// one of them takes the write lock and computes the value; the others wait
+/// for it, and then read it, instead of computing it again.
+///
+/// The parameter cannot be modified through a shared reference, so the value
+/// is only recomputed after `clear()`, or after modifying the parameter with
+/// `param_mut()`.
+///
+/// If the computation panics, the lock is poisoned, and `get()` returns
+/// `CacheError::Poisoned` until the memo is cleared.
+///
+/// ## Example
+///
+#[cfg_attr(not(feature = "loom"), doc = "```")]
+#[cfg_attr(feature = "loom", doc = "```ignore")]
+/// use std::thread;
+/// use core_memo::{Memoize, MemoLock};
+///
+/// struct Primes(Vec<u32>);
+///
+/// impl Memoize for Primes {
+///     type Param = u32;
+///     fn memoize(n: &u32) -> Self {
+///         Primes((2..*n).filter(|i| (2..*i).all(|d| i % d != 0)).collect())
+///     }
+/// }
+///
+/// let memo: MemoLock<Primes> = MemoLock::new(100);
+///
+/// // computed by one of the threads, and read by all of them
+/// thread::scope(|s| {
+///     for _ in 0..4 {
+///         s.spawn(|| assert_eq!(memo.get().unwrap().0.len(), 25));
+///     }
+/// });
+/// ```
+///
+#[derive(Debug)]
+pub struct MemoLock<T, P = <T as Memoize>::Param> {
+    value: RwLock<Option<T>>,
+    param: P,
+}
+
+/// A read lock on the value of a `MemoLock`
+///
+/// Other threads can read the value at the same time, but it cannot be
+/// cleared while this is alive. It derefs to the value.
+pub struct MemoReadGuard<'a, T>(RwLockReadGuard<'a, Option<T>>);
+
+impl<T, P> MemoLock<T, P> {
+    /// Creates a new `MemoLock` instance
+    pub fn new(p: P) -> Self {
+        Self {
+            value: RwLock::new(None),
+            param: p,
+        }
+    }
+
+    /// Clears any cached value
+    ///
+    /// The value will be reevaluated the next time it is needed. This waits
+    /// for threads which are reading the value to release it, and recovers
+    /// from a computation which panicked.
+    pub fn clear(&self) {
+        let mut value = self.value.write().unwrap_or_else(PoisonError::into_inner);
+        *value = None;
+        drop(value);
+        // `loom` does not poison its locks
+        #[cfg(not(feature = "loom"))]
+        self.value.clear_poison();
+    }
+
+    /// Check if there is a cached value
+    pub fn is_ready(&self) -> bool {
+        self.value.read().is_ok_and(|value| value.is_some())
+    }
+
+    /// Get the value if it is available
+    ///
+    /// Returns `CacheError::NotReady` if the value needs to be computed, or
+    /// `CacheError::Poisoned` if a computation panicked.
+    pub fn try_get(&self) -> Result<MemoReadGuard<'_, T>, CacheError> {
+        let value = self.value.read().map_err(|_| CacheError::Poisoned)?;
+        match *value {
+            Some(_) => Ok(MemoReadGuard(value)),
+            None => Err(CacheError::NotReady),
+        }
+    }
+
+    /// Get a reference to the parameter used for the computation
+    pub fn param(&self) -> &P {
+        &self.param
+    }
+
+    /// Get a mutable reference to the parameter used for the computation
+    ///
+    /// This clears any cached value.
+    pub fn param_mut(&mut self) -> &mut P {
+        self.clear();
+        &mut self.param
+    }
+
+    /// Get the parameter back, dropping the memo
+    pub fn into_param(self) -> P {
+        self.param
+    }
+}
+
+impl<T: Memoize, P: Borrow<T::Param>> MemoLock<T, P> {
+    /// Get the value
+    ///
+    /// If the value has already been computed, this function returns the
+    /// cached value, under a read lock. If not, it takes the write lock,
+    /// computes the value unless another thread did so in the meantime, and
+    /// then reads it.
+    ///
+    /// Returns `CacheError::Poisoned` if a computation panicked.
+    pub fn get(&self) -> Result<MemoReadGuard<'_, T>, CacheError> {
+        loop {
+            match self.try_get() {
+                Err(CacheError::NotReady) => {}
+                result => return result,
+            }
+
+            let mut value = self.value.write().map_err(|_| CacheError::Poisoned)?;
+            // another thread may have computed it while we waited for the lock
+            if value.is_none() {
+                *value = Some(T::memoize(self.param.borrow()));
+            }
+            // read it on the next iteration, unless it is cleared again first
+        }
+    }
+
+    /// Get the value, waiting for the lock for at most `timeout`
+    ///
+    /// Same as `get()`, but returns `CacheError::TimedOut` if other threads
+    /// hold the lock (for example, to compute the value) for longer than
+    /// `timeout`. If this thread computes the value, the computation is not
+    /// interrupted.
+    #[cfg(not(feature = "loom"))]
+    pub fn get_timeout(&self, timeout: Duration) -> Result<MemoReadGuard<'_, T>, CacheError> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            match self.value.try_read() {
+                Ok(value) if value.is_some() => return Ok(MemoReadGuard(value)),
+                Ok(_) | Err(TryLockError::WouldBlock) => {}
+                Err(TryLockError::Poisoned(_)) => return Err(CacheError::Poisoned),
+            }
+
+            match self.value.try_write() {
+                Ok(mut value) => {
+                    if value.is_none() {
+                        *value = Some(T::memoize(self.param.borrow()));
+                    }
+                    continue;
+                }
+                Err(TryLockError::WouldBlock) => {}
+                Err(TryLockError::Poisoned(_)) => return Err(CacheError::Poisoned),
+            }
+
+            if Instant::now() >= deadline {
+                return Err(CacheError::TimedOut);
+            }
+            thread::yield_now();
+        }
+    }
+}
+
+impl<T, P> Invalidate for MemoLock<T, P> {
+    fn invalidate(&mut self) {
+        self.clear()
+    }
+}
+
+impl<'a, T> Deref for MemoReadGuard<'a, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        // only created for a cached value, which cannot be cleared while the
+        // lock is held
+        self.0.as_ref().unwrap()
+    }
+}
+
+impl<'a, T: fmt::Debug> fmt::Debug for MemoReadGuard<'a, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        (**self).fmt(f)
+    }
+}
@@ -0,0 +1,181 @@
+use core::hash::Hash;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Read, Write};
+use std::path::PathBuf;
+
+use memmap2::{Mmap, MmapOptions};
+
+use disk::temp_path;
+use hash::stable_hash;
+use {DiskCache, Memoize};
+
+/// Identifies the format of mapped files (the last byte is the version)
+const MAGIC: &[u8; 8] = b"CMEMMAP\x01";
+
+/// Magic followed by the parameter hash
+const HEADER_LEN: usize = 16;
+
+/// Values which are stored as raw bytes and can be viewed from any storage
+///
+/// Requires the `mmap` feature.
+///
+/// Implement this for computations with large outputs, to let `DiskCache`
+/// return them backed by a memory-mapped file with `get_mapped()`, instead of
+/// reading them fully into memory.
+///
+/// Your output type should be generic over where its bytes are stored. The
+/// computation produces a value with its bytes in memory (like a `Vec<u8>`).
+/// When it is loaded from the disk cache, the bytes are stored in an `Mmap`.
+///
+/// ## Example
+///
+/// ```no_run
+/// # extern crate core_memo;
+/// extern crate memmap2;
+///
+/// use core_memo::{DiskCache, Mappable, Memoize};
+///
+/// struct Texture<S = Vec<u8>>(S);
+///
+/// impl<S: AsRef<[u8]>> AsRef<[u8]> for Texture<S> {
+///     fn as_ref(&self) -> &[u8] {
+///         self.0.as_ref()
+///     }
+/// }
+///
+/// impl Memoize for Texture {
+///     type Param = str;
+///     fn memoize(path: &str) -> Self {
+///         // decode some huge image
+///         Texture(vec![0; 64 << 20])
+///     }
+/// }
+///
+/// impl Mappable for Texture {
+///     type Mapped<S: AsRef<[u8]>> = Texture<S>;
+///
+///     fn from_storage<S: AsRef<[u8]>>(bytes: S) -> Texture<S> {
+///         Texture(bytes)
+///     }
+/// }
+///
+/// # fn main() -> std::io::Result<()> {
+/// let cache: DiskCache<Texture> = DiskCache::new("target/textures")?;
+///
+/// // safe, because nothing else modifies the files in this directory
+/// let texture: Texture<memmap2::Mmap> = unsafe { cache.get_mapped("grass.png")? };
+/// assert_eq!(texture.as_ref().len(), 64 << 20);
+/// # Ok(())
+/// # }
+/// ```
+///
+pub trait Mappable: Memoize + AsRef<[u8]> {
+    /// The same kind of value, with its bytes stored in `S`
+    type Mapped<S: AsRef<[u8]>>;
+
+    /// Create a value from its bytes
+    fn from_storage<S: AsRef<[u8]>>(bytes: S) -> Self::Mapped<S>;
+}
+
+impl<T: Mappable> DiskCache<T>
+where
+    T::Param: Hash,
+{
+    /// Get the path of the memory-mapped file for a parameter
+    ///
+    /// Requires the `mmap` feature.
+    ///
+    /// The file may or may not exist.
+    pub fn mapped_path(&self, p: &T::Param) -> PathBuf {
+        self.file(p, "map")
+    }
+
+    /// Get the value, backed by a memory-mapped file
+    ///
+    /// Requires the `mmap` feature.
+    ///
+    /// If there is a valid file for the parameter, it is mapped into memory.
+    /// If not, the value is computed, written to the file, and then mapped.
+    ///
+    /// These files are separate from the ones used by `get()`.
+    ///
+    /// ## Safety
+    ///
+    /// The file must not be modified (by this or another process) while the
+    /// returned value is alive. `DiskCache` itself never modifies files in
+    /// place (it replaces them), so this is only a concern if something else
+    /// writes to the files in the cache directory.
+    pub unsafe fn get_mapped(&self, p: &T::Param) -> io::Result<T::Mapped<Mmap>> {
+        if let Some(value) = self.try_get_mapped(p) {
+            return Ok(value);
+        }
+
+        self.update_mapped(p)
+    }
+
+    /// Get the value, backed by a memory-mapped file, if it is available
+    ///
+    /// Requires the `mmap` feature.
+    ///
+    /// If there is a valid file for the parameter, it is mapped into memory.
+    /// If not, returns `None`.
+    ///
+    /// ## Safety
+    ///
+    /// See `get_mapped()`.
+    pub unsafe fn try_get_mapped(&self, p: &T::Param) -> Option<T::Mapped<Mmap>> {
+        self.open_mapped(p).ok().flatten()
+    }
+
+    /// Force the value to be recomputed, written to the file, and mapped
+    ///
+    /// Requires the `mmap` feature.
+    ///
+    /// ## Safety
+    ///
+    /// See `get_mapped()`.
+    pub unsafe fn update_mapped(&self, p: &T::Param) -> io::Result<T::Mapped<Mmap>> {
+        let value = T::memoize(p);
+        self.store_mapped(p, &value)?;
+        drop(value);
+
+        self.open_mapped(p)?
+            .ok_or_else(|| io::Error::other("cached file was replaced before it could be mapped"))
+    }
+
+    unsafe fn open_mapped(&self, p: &T::Param) -> io::Result<Option<T::Mapped<Mmap>>> {
+        let mut file = match File::open(self.mapped_path(p)) {
+            Ok(file) => file,
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e),
+        };
+
+        let mut header = [0; HEADER_LEN];
+        if file.read_exact(&mut header).is_err()
+            || header[..8] != MAGIC[..]
+            || header[8..] != stable_hash(p).to_le_bytes()
+        {
+            return Ok(None);
+        }
+
+        let map = MmapOptions::new().offset(HEADER_LEN as u64).map(&file)?;
+        Ok(Some(T::from_storage(map)))
+    }
+
+    fn store_mapped(&self, p: &T::Param, value: &T) -> io::Result<()> {
+        let path = self.mapped_path(p);
+        let tmp = temp_path(&path);
+
+        // write to a temporary file first, so that we never modify a file that
+        // could be mapped
+        {
+            let mut writer = BufWriter::new(File::create(&tmp)?);
+            writer.write_all(MAGIC)?;
+            writer.write_all(&stable_hash(p).to_le_bytes())?;
+            writer.write_all(value.as_ref())?;
+            writer.flush()?;
+        }
+
+        fs::rename(tmp, path)
+    }
+}
@@ -0,0 +1,160 @@
+#[cfg(feature = "alloc")]
+use alloc::boxed::Box;
+#[cfg(feature = "alloc")]
+use alloc::string::String;
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+use core::borrow::Borrow;
+#[cfg(feature = "alloc")]
+use core::mem;
+
+use {Memo, MemoExt, MemoMut, MemoOnce, Memoize};
+
+/// Values which can report how much heap memory they use
+///
+/// Implement this for your computations' outputs (and parameters), to let
+/// `MemoCache::memory_used()` and `Registry::memory_used()` account for them.
+/// The numbers don't have to be exact, but they should include the memory
+/// which dominates (like the buffers of large collections).
+///
+/// ## Example
+///
+/// ```
+/// use core_memo::MemSize;
+///
+/// struct Mesh {
+///     vertices: Vec<[f32; 3]>,
+///     name: String,
+/// }
+///
+/// impl MemSize for Mesh {
+///     fn heap_size(&self) -> usize {
+///         self.vertices.capacity() * 12 + self.name.capacity()
+///     }
+/// }
+///
+/// let mesh = Mesh {
+///     vertices: Vec::with_capacity(100),
+///     name: String::from("cube"),
+/// };
+/// assert_eq!(mesh.heap_size(), 100 * 12 + 4);
+/// ```
+///
+pub trait MemSize {
+    /// Get the number of bytes this value owns on the heap
+    ///
+    /// This does not include the size of the value itself (`size_of::<Self>()`).
+    fn heap_size(&self) -> usize;
+}
+
+macro_rules! impl_no_heap {
+    ($($t:ty),*) => {
+        $(
+            impl MemSize for $t {
+                fn heap_size(&self) -> usize {
+                    0
+                }
+            }
+        )*
+    };
+}
+
+impl_no_heap!(
+    (),
+    bool,
+    char,
+    u8,
+    u16,
+    u32,
+    u64,
+    u128,
+    usize,
+    i8,
+    i16,
+    i32,
+    i64,
+    i128,
+    isize,
+    f32,
+    f64,
+    str
+);
+
+impl<T: MemSize> MemSize for Option<T> {
+    fn heap_size(&self) -> usize {
+        self.as_ref().map_or(0, MemSize::heap_size)
+    }
+}
+
+impl<T: MemSize> MemSize for [T] {
+    fn heap_size(&self) -> usize {
+        self.iter().map(MemSize::heap_size).sum()
+    }
+}
+
+impl<T: MemSize, const N: usize> MemSize for [T; N] {
+    fn heap_size(&self) -> usize {
+        self[..].heap_size()
+    }
+}
+
+impl<A: MemSize, B: MemSize> MemSize for (A, B) {
+    fn heap_size(&self) -> usize {
+        self.0.heap_size() + self.1.heap_size()
+    }
+}
+
+impl<A: MemSize, B: MemSize, C: MemSize> MemSize for (A, B, C) {
+    fn heap_size(&self) -> usize {
+        self.0.heap_size() + self.1.heap_size() + self.2.heap_size()
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<T: MemSize> MemSize for Vec<T> {
+    fn heap_size(&self) -> usize {
+        self.capacity() * mem::size_of::<T>() + self[..].heap_size()
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl MemSize for String {
+    fn heap_size(&self) -> usize {
+        self.capacity()
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<T: MemSize + ?Sized> MemSize for Box<T> {
+    fn heap_size(&self) -> usize {
+        mem::size_of_val::<T>(self) + T::heap_size(self)
+    }
+}
+
+impl<T: Memoize + MemSize> MemSize for MemoExt<T> {
+    fn heap_size(&self) -> usize {
+        self.value.heap_size()
+    }
+}
+
+impl<T, P> MemSize for Memo<T, P>
+where
+    T: Memoize + MemSize,
+    P: Borrow<T::Param> + MemSize,
+{
+    fn heap_size(&self) -> usize {
+        self.value.heap_size() + self.param.heap_size()
+    }
+}
+
+impl<'p, T: Memoize + MemSize, R> MemSize for MemoOnce<'p, T, R> {
+    fn heap_size(&self) -> usize {
+        self.value.heap_size()
+    }
+}
+
+impl<'p, T: Memoize + MemSize> MemSize for MemoMut<'p, T> {
+    fn heap_size(&self) -> usize {
+        self.value.heap_size()
+    }
+}
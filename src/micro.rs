@@ -0,0 +1,172 @@
+use ufmt::{uDebug, uDisplay, uWrite, Formatter};
+
+#[cfg(all(feature = "diagnostics", feature = "std"))]
+use diagnostics::{ComputeEvent, SlowReport};
+#[cfg(feature = "diagnostics")]
+use diagnostics::{Waste, WasteReport};
+use {CacheError, Memo, MemoExt, MemoMut, MemoOnce, Memoize};
+
+/// Formats the cached value, if there is one
+///
+/// Requires the `ufmt` feature.
+///
+/// ## Example
+///
+/// ```
+/// extern crate ufmt;
+/// # extern crate core_memo;
+///
+/// use core_memo::{Memoize, Memo};
+/// use ufmt::derive::uDebug;
+///
+/// #[derive(uDebug)]
+/// struct Reading(u16);
+///
+/// impl Memoize for Reading {
+///     type Param = u16;
+///     fn memoize(p: &u16) -> Self {
+///         Reading(p * 4)
+///     }
+/// }
+///
+/// fn log<W: ufmt::uWrite>(w: &mut W, memo: &Memo<Reading>) -> Result<(), W::Error> {
+///     // like `Memo { value: Some(Reading(2048)), param: 512 }`
+///     ufmt::uwrite!(w, "{:?}", memo)
+/// }
+/// # fn main() {}
+/// ```
+///
+impl<T: uDebug> uDebug for MemoExt<T> {
+    fn fmt<W: uWrite + ?Sized>(&self, f: &mut Formatter<W>) -> Result<(), W::Error> {
+        f.debug_struct("MemoExt")?
+            .field("value", &self.value)?
+            .finish()
+    }
+}
+
+/// Formats the cached value, if there is one, and the parameter
+///
+/// Requires the `ufmt` feature.
+impl<T: uDebug, P: uDebug> uDebug for Memo<T, P> {
+    fn fmt<W: uWrite + ?Sized>(&self, f: &mut Formatter<W>) -> Result<(), W::Error> {
+        f.debug_struct("Memo")?
+            .field("value", &self.value)?
+            .field("param", &self.param)?
+            .finish()
+    }
+}
+
+/// Formats the cached value, if there is one, and the parameter
+///
+/// Requires the `ufmt` feature.
+impl<'p, T: uDebug, R: uDebug> uDebug for MemoOnce<'p, T, R> {
+    fn fmt<W: uWrite + ?Sized>(&self, f: &mut Formatter<W>) -> Result<(), W::Error> {
+        f.debug_struct("MemoOnce")?
+            .field("value", &self.value)?
+            .field("param", &self.param)?
+            .finish()
+    }
+}
+
+/// Formats the cached value, if there is one, and the parameter
+///
+/// Requires the `ufmt` feature.
+impl<'p, T: Memoize + uDebug> uDebug for MemoMut<'p, T>
+where
+    T::Param: uDebug,
+{
+    fn fmt<W: uWrite + ?Sized>(&self, f: &mut Formatter<W>) -> Result<(), W::Error> {
+        f.debug_struct("MemoMut")?
+            .field("value", &self.value)?
+            .field("param", &&*self.param)?
+            .finish()
+    }
+}
+
+/// Requires the `ufmt` feature.
+impl<E: uDebug> uDebug for CacheError<E> {
+    fn fmt<W: uWrite + ?Sized>(&self, f: &mut Formatter<W>) -> Result<(), W::Error> {
+        match self {
+            CacheError::NotReady => f.write_str("NotReady"),
+            CacheError::Expired => f.write_str("Expired"),
+            CacheError::Poisoned => f.write_str("Poisoned"),
+            CacheError::ComputeFailed(e) => f.debug_tuple("ComputeFailed")?.field(e)?.finish(),
+            CacheError::TimedOut => f.write_str("TimedOut"),
+            CacheError::AllocFailed => f.write_str("AllocFailed"),
+        }
+    }
+}
+
+/// The same message as `Display`
+///
+/// Requires the `ufmt` feature.
+impl<E: uDisplay> uDisplay for CacheError<E> {
+    fn fmt<W: uWrite + ?Sized>(&self, f: &mut Formatter<W>) -> Result<(), W::Error> {
+        match self {
+            CacheError::NotReady => f.write_str("the value is not ready"),
+            CacheError::Expired => f.write_str("the value has expired"),
+            CacheError::Poisoned => f.write_str("the lock on the value is poisoned"),
+            CacheError::ComputeFailed(e) => ufmt::uwrite!(f, "the computation failed: {}", e),
+            CacheError::TimedOut => f.write_str("timed out waiting for the value"),
+            CacheError::AllocFailed => f.write_str("memory allocation for the value failed"),
+        }
+    }
+}
+
+/// Requires the `ufmt` and `diagnostics` features.
+#[cfg(feature = "diagnostics")]
+impl uDebug for Waste {
+    fn fmt<W: uWrite + ?Sized>(&self, f: &mut Formatter<W>) -> Result<(), W::Error> {
+        match self {
+            Waste::NeverUsed => f.write_str("NeverUsed"),
+            Waste::NeverRead => f.write_str("NeverRead"),
+        }
+    }
+}
+
+/// The same message as `Display`
+///
+/// Requires the `ufmt` and `diagnostics` features.
+#[cfg(feature = "diagnostics")]
+impl uDisplay for WasteReport {
+    fn fmt<W: uWrite + ?Sized>(&self, f: &mut Formatter<W>) -> Result<(), W::Error> {
+        match self.waste {
+            Waste::NeverUsed => ufmt::uwrite!(f, "{} was dropped without being used", self.memo),
+            Waste::NeverRead => {
+                ufmt::uwrite!(f, "{} computed a value which was never read", self.memo)
+            }
+        }
+    }
+}
+
+/// Like `Display`, with the durations in microseconds
+///
+/// Requires the `ufmt`, `diagnostics` and `std` features.
+#[cfg(all(feature = "diagnostics", feature = "std"))]
+impl uDisplay for SlowReport {
+    fn fmt<W: uWrite + ?Sized>(&self, f: &mut Formatter<W>) -> Result<(), W::Error> {
+        ufmt::uwrite!(
+            f,
+            "{} took {}us to compute its value, over its budget of {}us",
+            self.memo,
+            self.elapsed.as_micros(),
+            self.budget.as_micros()
+        )
+    }
+}
+
+/// Like `Display`, with the duration in microseconds
+///
+/// Requires the `ufmt`, `diagnostics` and `std` features.
+#[cfg(all(feature = "diagnostics", feature = "std"))]
+impl uDisplay for ComputeEvent {
+    fn fmt<W: uWrite + ?Sized>(&self, f: &mut Formatter<W>) -> Result<(), W::Error> {
+        ufmt::uwrite!(
+            f,
+            "#{}: computed for {:016x} in {}us",
+            self.generation,
+            self.fingerprint,
+            self.elapsed.as_micros()
+        )
+    }
+}
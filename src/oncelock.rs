@@ -0,0 +1,117 @@
+use core::borrow::Borrow;
+use std::sync::OnceLock;
+
+use {Invalidate, Memoize};
+
+/// Memoized value which is computed once, and then read from many threads
+///
+/// Requires the `std` feature.
+///
+/// Like `Memo`, this holds ownership over the parameter for the computation.
+/// But the value is in a `OnceLock`, so `get()` only needs `&self`: the first
+/// call computes the value (if several threads call it at the same time, only
+/// one of them computes it, and the others wait for it), and all the calls
+/// after that read it without any locking. It is `Sync` when `T` and `P` are.
+///
+/// This is for values which are computed once and then used forever, like a
+/// `static` initialized at runtime. It can only be cleared through `&mut self`.
+/// If you need to clear it through a shared reference, use `MemoLock`.
+///
+/// ## Example
+///
+/// ```
+/// use std::thread;
+/// use core_memo::{Memoize, MemoShared};
+///
+/// struct Table(Vec<u64>);
+///
+/// impl Memoize for Table {
+///     type Param = usize;
+///     fn memoize(n: &usize) -> Self {
+///         Table((0..*n as u64).map(|i| i * i).collect())
+///     }
+/// }
+///
+/// let table: MemoShared<Table> = MemoShared::new(1000);
+///
+/// thread::scope(|s| {
+///     for i in 0..4 {
+///         let table = &table;
+///         s.spawn(move || assert_eq!(table.get().0[i], (i * i) as u64));
+///     }
+/// });
+/// ```
+///
+#[derive(Debug)]
+pub struct MemoShared<T, P = <T as Memoize>::Param> {
+    value: OnceLock<T>,
+    param: P,
+}
+
+impl<T, P> MemoShared<T, P> {
+    /// Creates a new `MemoShared` instance
+    pub const fn new(p: P) -> Self {
+        Self {
+            value: OnceLock::new(),
+            param: p,
+        }
+    }
+
+    /// Clears any cached value
+    ///
+    /// The value will be reevaluated the next time it is needed.
+    pub fn clear(&mut self) {
+        self.value.take();
+    }
+
+    /// Check if there is a cached value
+    ///
+    /// If this method returns `true`, the next call to `get()` will return the
+    /// stored memoized value.
+    pub fn is_ready(&self) -> bool {
+        self.value.get().is_some()
+    }
+
+    /// Get the value if it is available
+    ///
+    /// If there is a cached value, returns it. If the value needs to be
+    /// computed, returns `None`.
+    pub fn try_get(&self) -> Option<&T> {
+        self.value.get()
+    }
+
+    /// Get a reference to the parameter used for the computation
+    pub fn param(&self) -> &P {
+        &self.param
+    }
+
+    /// Get a mutable reference to the parameter used for the computation
+    ///
+    /// This clears any cached value.
+    pub fn param_mut(&mut self) -> &mut P {
+        self.clear();
+        &mut self.param
+    }
+
+    /// Get the parameter back, dropping the memo
+    pub fn into_param(self) -> P {
+        self.param
+    }
+}
+
+impl<T: Memoize, P: Borrow<T::Param>> MemoShared<T, P> {
+    /// Get the value
+    ///
+    /// If the value has already been computed, this function returns the
+    /// cached value, without locking. If not, it is computed and cached for
+    /// future use, blocking other threads which need it in the meantime.
+    pub fn get(&self) -> &T {
+        self.value.get_or_init(|| T::memoize(self.param.borrow()))
+    }
+}
+
+impl<T, P> Invalidate for MemoShared<T, P> {
+    fn invalidate(&mut self) {
+        self.clear()
+    }
+}
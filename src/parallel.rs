@@ -0,0 +1,148 @@
+use alloc::vec::Vec;
+use core::ops::Deref;
+use rayon::prelude::*;
+
+use {Memoize, Ready};
+
+/// Compute the values of many memos in advance, in parallel
+///
+/// Requires the `rayon` feature.
+///
+/// This calls `ready()` on every memo which is not ready yet, on the `rayon`
+/// thread pool, so that precomputing a large collection of memos (like before
+/// a hot phase of the program) scales with the number of cores. Pass anything
+/// which `rayon` can iterate over mutably, like `&mut Vec<Memo<T>>`, or a
+/// `Vec` of `&mut` memos. The memos must be `Send`, so their values and
+/// parameters must be too.
+///
+/// Returns the number of values computed.
+///
+/// ## Example
+///
+/// ```
+/// use core_memo::{par_warm, Memoize, Memo, Ready};
+///
+/// struct Sum(u64);
+///
+/// impl Memoize for Sum {
+///     type Param = u64;
+///     fn memoize(n: &u64) -> Self {
+///         Sum((0..=*n).sum())
+///     }
+/// }
+///
+/// let mut memos: Vec<Memo<Sum>> = (0..100).map(Memo::new).collect();
+/// memos[10].ready();
+///
+/// assert_eq!(par_warm(&mut memos), 99);
+/// assert!(memos.iter().all(|memo| memo.is_ready()));
+/// assert_eq!(memos[99].get().0, 4950);
+/// ```
+///
+pub fn par_warm<'a, I, R>(memos: I) -> usize
+where
+    I: IntoParallelIterator<Item = &'a mut R>,
+    R: Ready + Send + 'a,
+{
+    memos
+        .into_par_iter()
+        .filter(|memo| !memo.is_ready())
+        .map(|memo| memo.ready())
+        .count()
+}
+
+/// Represents a computation which can be split into chunks, to be computed in
+/// parallel
+///
+/// Requires the `rayon` feature.
+///
+/// This is like `Memoize`, for computations over large parameters (like a
+/// multi-megabyte slice), where a single thread would leave the other cores
+/// idle. The parameter is split into chunks, each chunk is computed on the
+/// `rayon` thread pool, and the partial results are combined into the value.
+/// Memoize it by using `Parallel<T>` as the value type of any of the
+/// memoization types.
+///
+/// The chunks are of the same type as the parameter, like subslices of a
+/// slice.
+///
+/// ## Example
+///
+/// ```
+/// use core_memo::{Memo, MemoizeParallel, Parallel};
+///
+/// struct Checksum(u64);
+///
+/// impl MemoizeParallel for Checksum {
+///     type Param = [u8];
+///     type Partial = u64;
+///
+///     fn split(data: &[u8]) -> Vec<&[u8]> {
+///         data.chunks(64 * 1024).collect()
+///     }
+///
+///     fn compute(chunk: &[u8]) -> u64 {
+///         chunk.iter().map(|&b| b as u64).sum()
+///     }
+///
+///     fn combine(partials: Vec<u64>) -> Self {
+///         Checksum(partials.into_iter().sum())
+///     }
+/// }
+///
+/// let mut memo: Memo<Parallel<Checksum>, Vec<u8>> = Memo::new(vec![1; 1 << 20]);
+/// assert_eq!(memo.get().0, 1 << 20);
+/// ```
+///
+pub trait MemoizeParallel: Sized {
+    /// The input type for the computation
+    type Param: ?Sized + Sync;
+
+    /// The result of the computation for one chunk
+    type Partial: Send;
+
+    /// Split the parameter into chunks
+    fn split(p: &Self::Param) -> Vec<&Self::Param>;
+
+    /// Compute the partial result for one chunk
+    fn compute(chunk: &Self::Param) -> Self::Partial;
+
+    /// Combine the partial results into the value
+    ///
+    /// They are in the same order as the chunks returned by `split()`.
+    fn combine(partials: Vec<Self::Partial>) -> Self;
+}
+
+/// The value of a `MemoizeParallel` computation, which can be memoized
+///
+/// Requires the `rayon` feature.
+///
+/// This implements `Memoize` for any `MemoizeParallel` type, by computing the
+/// chunks on the `rayon` thread pool. It derefs to the value. See
+/// `MemoizeParallel` for an example.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Parallel<T>(T);
+
+impl<T> Parallel<T> {
+    /// Get the value back
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> Deref for Parallel<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T: MemoizeParallel> Memoize for Parallel<T> {
+    type Param = T::Param;
+
+    fn memoize(p: &T::Param) -> Self {
+        let partials = T::split(p).into_par_iter().map(T::compute).collect();
+        Parallel(T::combine(partials))
+    }
+}
@@ -0,0 +1,65 @@
+/// Arguments which can be assembled into a tuple parameter
+///
+/// This is implemented for tuples (of up to 6 elements), where each element
+/// can be converted into the corresponding element of the parameter with
+/// `Into`. It lets you pass the arguments for a computation individually,
+/// in whatever form you have them (like a `&str` for a `String`), to methods
+/// like `MemoExt::get_with()`.
+///
+/// It is also implemented for closures which return the parameter, so that
+/// it is only constructed if the value actually needs to be computed.
+///
+/// ## Example
+///
+/// ```
+/// use core_memo::{Memoize, MemoExt};
+///
+/// struct Repeat(String);
+///
+/// impl Memoize for Repeat {
+///     type Param = (String, usize);
+///     fn memoize((s, count): &(String, usize)) -> Self {
+///         Repeat(s.repeat(*count))
+///     }
+/// }
+///
+/// let mut memo: MemoExt<Repeat> = MemoExt::new();
+///
+/// // no need to build a `(String, usize)` ourselves
+/// assert_eq!(memo.get_with(("ab", 3usize)).0, "ababab");
+///
+/// // or build it only when needed (this one is never called)
+/// memo.get_with(|| (String::from("cd"), 2));
+/// assert_eq!(memo.try_get().unwrap().0, "ababab");
+/// ```
+///
+pub trait Params<P> {
+    /// Assemble the parameter
+    fn into_param(self) -> P;
+}
+
+macro_rules! impl_params {
+    ($($a:ident $p:ident),*) => {
+        impl<$($a: Into<$p>, $p),*> Params<($($p,)*)> for ($($a,)*) {
+            #[allow(non_snake_case)]
+            fn into_param(self) -> ($($p,)*) {
+                let ($($a,)*) = self;
+                ($($a.into(),)*)
+            }
+        }
+    };
+}
+
+impl_params!(A PA);
+impl_params!(A PA, B PB);
+impl_params!(A PA, B PB, C PC);
+impl_params!(A PA, B PB, C PC, D PD);
+impl_params!(A PA, B PB, C PC, D PD, E PE);
+impl_params!(A PA, B PB, C PC, D PD, E PE, F PF);
+
+/// Builds the parameter by calling the closure
+impl<F: FnOnce() -> P, P> Params<P> for F {
+    fn into_param(self) -> P {
+        self()
+    }
+}
@@ -0,0 +1,135 @@
+use alloc::collections::BTreeMap;
+use core::fmt;
+
+use {Invalidate, Memoize};
+
+/// Memoized values for a parameter with one fixed and one varying part
+///
+/// Requires the `alloc` feature.
+///
+/// The parameter of the computation must be a pair `(F, V)`. The fixed part
+/// (`F`) is given when the `PartialMemo` is created, like the parameter of a
+/// `Memo`. The varying part (`V`) is given to `get()`, like the key of a keyed
+/// cache. One value is cached for each varying part. Modifying the fixed part
+/// clears all of them.
+///
+/// If you need more than one fixed or varying component, use tuples for them
+/// (like `((A, B), (C, D))`).
+///
+/// The fixed part is cloned for every computation, to assemble the parameter,
+/// so keep it cheap to clone (like an `Rc` or a reference).
+///
+/// ## Example
+///
+/// ```
+/// use core_memo::{Memoize, PartialMemo};
+///
+/// struct Scaled(Vec<f32>);
+///
+/// impl Memoize for Scaled {
+///     type Param = (&'static [f32], u32);
+///     fn memoize(&(points, factor): &(&'static [f32], u32)) -> Self {
+///         Scaled(points.iter().map(|p| p * factor as f32).collect())
+///     }
+/// }
+///
+/// static POINTS: [f32; 3] = [1.0, 2.0, 3.0];
+///
+/// let mut memo: PartialMemo<Scaled, &'static [f32], u32> = PartialMemo::new(&POINTS);
+///
+/// assert_eq!(memo.get(2).0, [2.0, 4.0, 6.0]);
+/// assert_eq!(memo.get(10).0, [10.0, 20.0, 30.0]);
+/// assert_eq!(memo.len(), 2);
+/// ```
+///
+pub struct PartialMemo<T, F, V> {
+    fixed: F,
+    values: BTreeMap<V, T>,
+}
+
+impl<T, F, V> PartialMemo<T, F, V>
+where
+    T: Memoize<Param = (F, V)>,
+    F: Clone,
+    V: Ord + Clone,
+{
+    /// Creates a new `PartialMemo` instance, with the fixed part of the
+    /// parameter
+    pub fn new(fixed: F) -> Self {
+        Self {
+            fixed,
+            values: BTreeMap::new(),
+        }
+    }
+
+    /// Clears all cached values
+    pub fn clear(&mut self) {
+        self.values.clear();
+    }
+
+    /// Clears the cached value for a varying part
+    ///
+    /// Returns the value, if there was one.
+    pub fn remove(&mut self, varying: &V) -> Option<T> {
+        self.values.remove(varying)
+    }
+
+    /// Get the number of cached values
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    /// Check if there are no cached values
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// Check if there is a cached value for a varying part
+    pub fn is_ready(&self, varying: &V) -> bool {
+        self.values.contains_key(varying)
+    }
+
+    /// Get the value for a varying part
+    ///
+    /// If the value has already been computed, this function returns the cached
+    /// value. If not, it is computed and cached for future use.
+    pub fn get(&mut self, varying: V) -> &T {
+        let fixed = &self.fixed;
+        self.values
+            .entry(varying)
+            .or_insert_with_key(|v| T::memoize(&(fixed.clone(), v.clone())))
+    }
+
+    /// Get the value for a varying part if it is available
+    pub fn try_get(&self, varying: &V) -> Option<&T> {
+        self.values.get(varying)
+    }
+
+    /// Get a reference to the fixed part of the parameter
+    pub fn fixed(&self) -> &F {
+        &self.fixed
+    }
+
+    /// Get a mutable reference to the fixed part of the parameter
+    ///
+    /// This clears all cached values.
+    pub fn fixed_mut(&mut self) -> &mut F {
+        self.clear();
+        &mut self.fixed
+    }
+}
+
+impl<T, F, V> Invalidate for PartialMemo<T, F, V> {
+    fn invalidate(&mut self) {
+        self.values.clear();
+    }
+}
+
+impl<T: fmt::Debug, F: fmt::Debug, V: fmt::Debug> fmt::Debug for PartialMemo<T, F, V> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("PartialMemo")
+            .field("fixed", &self.fixed)
+            .field("values", &self.values)
+            .finish()
+    }
+}
@@ -0,0 +1,121 @@
+use core::borrow::Borrow;
+use core::hash::Hash;
+
+use serde::{Deserialize, Serialize};
+
+use hash::stable_hash;
+use {Memo, Memoize};
+
+/// Versioned container for persisting a cached value
+///
+/// Requires the `serde` feature.
+///
+/// Stores a hash of the parameter alongside the value. When the container is
+/// loaded back, the value is only accepted if the hash matches the current
+/// parameter (and the container was written with the same format version).
+/// This way, stale caches (for example, on disk) degrade to recomputation,
+/// instead of giving you wrong answers.
+///
+/// The hash is computed with a fixed algorithm (64-bit FNV-1a), so it is
+/// stable across runs of your program. It relies on the `Hash` impl of the
+/// parameter type, which may differ between platforms (due to endianness and
+/// pointer width) and between versions of the libraries defining it.
+///
+/// ## Example
+///
+/// ```
+/// use core_memo::{Memoize, Memo, Persisted};
+///
+/// #[derive(Debug, PartialEq)]
+/// struct Sum(i32);
+///
+/// impl Memoize for Sum {
+///     type Param = [i32];
+///     fn memoize(p: &[i32]) -> Self {
+///         Sum(p.iter().sum())
+///     }
+/// }
+///
+/// let mut memo: Memo<Sum, _> = Memo::new(vec![1, 2, 3]);
+/// memo.get();
+///
+/// // we get a `Persisted<&Sum>`, which we can serialize with any `serde` format
+/// let saved = memo.persist().unwrap();
+/// assert!(saved.is_valid_for(&[1, 2, 3][..]));
+///
+/// // ... and later deserialize as a `Persisted<Sum>`
+/// let loaded = Persisted::new(Sum(6), &[1, 2, 3][..]);
+///
+/// // the parameter matches, so the value is restored
+/// let restored: Memo<Sum, _> = Memo::with_persisted(vec![1, 2, 3], loaded);
+/// assert_eq!(restored.try_get(), Some(&Sum(6)));
+///
+/// // the parameter has changed, so the value is discarded
+/// let loaded = Persisted::new(Sum(6), &[1, 2, 3][..]);
+/// let restored: Memo<Sum, _> = Memo::with_persisted(vec![4, 5], loaded);
+/// assert_eq!(restored.try_get(), None);
+/// ```
+///
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Persisted<T> {
+    version: u32,
+    param_hash: u64,
+    value: T,
+}
+
+impl<T> Persisted<T> {
+    /// The current version of the container format
+    pub const VERSION: u32 = 1;
+
+    /// Creates a new `Persisted` instance
+    ///
+    /// `param` should be the parameter that `value` was computed from.
+    pub fn new<P: Hash + ?Sized>(value: T, param: &P) -> Self {
+        Self {
+            version: Self::VERSION,
+            param_hash: stable_hash(param),
+            value,
+        }
+    }
+
+    /// Check if the value is valid for the given parameter
+    pub fn is_valid_for<P: Hash + ?Sized>(&self, param: &P) -> bool {
+        self.version == Self::VERSION && self.param_hash == stable_hash(param)
+    }
+
+    /// Get the value, if it is valid for the given parameter
+    pub fn restore<P: Hash + ?Sized>(self, param: &P) -> Option<T> {
+        if self.is_valid_for(param) {
+            Some(self.value)
+        } else {
+            None
+        }
+    }
+}
+
+impl<T: Memoize, P: Borrow<T::Param>> Memo<T, P>
+where
+    T::Param: Hash,
+{
+    /// Creates a new `Memo` instance, with a previously persisted value
+    ///
+    /// Requires the `serde` feature.
+    ///
+    /// If the persisted value was computed from a different parameter, it is
+    /// discarded, and will be recomputed when it is next needed.
+    pub fn with_persisted(p: P, persisted: Persisted<T>) -> Self {
+        let mut memo = Self::new(p);
+        memo.value = persisted.restore(memo.param.borrow());
+        memo
+    }
+
+    /// Get the cached value in a container suitable for persisting
+    ///
+    /// Requires the `serde` feature.
+    ///
+    /// Returns `None` if there is no cached value.
+    pub fn persist(&self) -> Option<Persisted<&T>> {
+        let param = self.param.borrow();
+        self.try_get().map(|v| Persisted::new(v, param))
+    }
+}
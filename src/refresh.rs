@@ -0,0 +1,157 @@
+use core::borrow::Borrow;
+use core::ops::Deref;
+
+use {Memo, MemoExt, MemoMut, MemoOnce, Memoize};
+
+/// The result of recomputing a value with `refresh()`
+///
+/// Tells whether the new value is different from the previously cached one,
+/// so that work which depends on the value can be skipped when an
+/// invalidation turns out to have changed nothing.
+///
+/// Modifying the parameter with `param_mut()` clears the cached value, so
+/// there would be nothing to compare to. Use `refresh_param()` instead, which
+/// keeps the old value around until the new one is computed.
+///
+/// ## Example
+///
+/// ```
+/// use core_memo::{Memoize, Memo, Refreshed};
+///
+/// #[derive(PartialEq)]
+/// struct Even(bool);
+///
+/// impl Memoize for Even {
+///     type Param = u32;
+///     fn memoize(p: &u32) -> Self {
+///         Even(p % 2 == 0)
+///     }
+/// }
+///
+/// let mut memo: Memo<Even> = Memo::new(2);
+/// memo.get();
+///
+/// let refreshed = memo.refresh_param(|p| *p = 4);
+/// assert!(matches!(refreshed, Refreshed::Same(&Even(true))));
+///
+/// assert!(memo.refresh_param(|p| *p = 5).is_changed());
+/// ```
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Refreshed<'a, T> {
+    /// The value is different, or there was no cached value before
+    Changed(&'a T),
+    /// The value is equal to the previously cached one
+    Same(&'a T),
+}
+
+impl<'a, T> Refreshed<'a, T> {
+    /// Check if the value is different from the previously cached one
+    pub fn is_changed(&self) -> bool {
+        match self {
+            Refreshed::Changed(_) => true,
+            Refreshed::Same(_) => false,
+        }
+    }
+
+    /// Get the value
+    pub fn value(&self) -> &'a T {
+        match *self {
+            Refreshed::Changed(value) | Refreshed::Same(value) => value,
+        }
+    }
+}
+
+/// Put the old value back if it is equal to the new one
+fn compare<T: PartialEq>(old: Option<T>, new: &mut T) -> Refreshed<'_, T> {
+    match old {
+        Some(old) if old == *new => {
+            *new = old;
+            Refreshed::Same(new)
+        }
+        _ => Refreshed::Changed(new),
+    }
+}
+
+impl<T: Memoize + PartialEq> MemoExt<T> {
+    /// Recompute the value, and tell if it changed
+    ///
+    /// Same as `update()`, but the new value is compared to the cached one.
+    /// If they are equal, the old value is kept (so any pointers or addresses
+    /// derived from it stay the same), and `Refreshed::Same` is returned.
+    pub fn refresh<B: Borrow<T::Param> + ?Sized>(&mut self, p: &B) -> Refreshed<'_, T> {
+        let old = self.value.take();
+        self.update(p);
+        compare(old, self.value.as_mut().unwrap())
+    }
+}
+
+impl<T: Memoize + PartialEq, P: Borrow<T::Param>> Memo<T, P> {
+    /// Recompute the value, and tell if it changed
+    ///
+    /// Same as `update()`, but the new value is compared to the cached one.
+    /// If they are equal, the old value is kept (so any pointers or addresses
+    /// derived from it stay the same), and `Refreshed::Same` is returned.
+    pub fn refresh(&mut self) -> Refreshed<'_, T> {
+        let old = self.value.take();
+        self.update();
+        compare(old, self.value.as_mut().unwrap())
+    }
+
+    /// Modify the parameter, recompute the value, and tell if it changed
+    ///
+    /// Like `update_param()` followed by `refresh()`, but the old value is
+    /// still there to compare to.
+    pub fn refresh_param<F>(&mut self, op: F) -> Refreshed<'_, T>
+    where
+        F: FnOnce(&mut P),
+    {
+        let old = self.value.take();
+        op(&mut self.param);
+        self.update();
+        compare(old, self.value.as_mut().unwrap())
+    }
+}
+
+impl<'p, T: Memoize + PartialEq, R: Deref> MemoOnce<'p, T, R>
+where
+    R::Target: Borrow<T::Param>,
+{
+    /// Recompute the value, and tell if it changed
+    ///
+    /// Same as `update()`, but the new value is compared to the cached one.
+    /// If they are equal, the old value is kept, and `Refreshed::Same` is
+    /// returned.
+    pub fn refresh(&mut self) -> Refreshed<'_, T> {
+        let old = self.value.take();
+        self.update();
+        compare(old, self.value.as_mut().unwrap())
+    }
+}
+
+impl<'p, T: Memoize + PartialEq> MemoMut<'p, T> {
+    /// Recompute the value, and tell if it changed
+    ///
+    /// Same as `update()`, but the new value is compared to the cached one.
+    /// If they are equal, the old value is kept, and `Refreshed::Same` is
+    /// returned.
+    pub fn refresh(&mut self) -> Refreshed<'_, T> {
+        let old = self.value.take();
+        self.update();
+        compare(old, self.value.as_mut().unwrap())
+    }
+
+    /// Modify the parameter, recompute the value, and tell if it changed
+    ///
+    /// Like `update_param()` followed by `refresh()`, but the old value is
+    /// still there to compare to.
+    pub fn refresh_param<F>(&mut self, op: F) -> Refreshed<'_, T>
+    where
+        F: FnOnce(&mut T::Param),
+    {
+        let old = self.value.take();
+        op(self.param);
+        self.update();
+        compare(old, self.value.as_mut().unwrap())
+    }
+}
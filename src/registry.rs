@@ -0,0 +1,489 @@
+use alloc::boxed::Box;
+#[cfg(feature = "stats")]
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+#[cfg(feature = "stats")]
+use core::any;
+use core::any::Any;
+use core::fmt;
+use core::marker::PhantomData;
+use core::mem;
+#[cfg(feature = "stats")]
+use core::time::Duration;
+
+#[cfg(all(feature = "std", not(feature = "stats")))]
+use std::time::Duration;
+#[cfg(feature = "std")]
+use std::time::Instant;
+
+use {Compact, MemSize, Ready};
+
+/// Collection of heterogeneous memos, which can be managed together
+///
+/// Requires the `alloc` feature.
+///
+/// Any type implementing `Ready` (such as `Memo`) can be inserted into the
+/// registry. You get back a typed `Handle`, which you can use to access it.
+///
+/// The registry keeps track of how often each memo is accessed through
+/// `get_mut()`, which is used to prioritize work in `run_idle()`.
+///
+/// ## Example
+///
+/// ```
+/// use core_memo::{Memoize, Memo, Registry};
+///
+/// struct Len(usize);
+///
+/// impl Memoize for Len {
+///     type Param = str;
+///     fn memoize(p: &str) -> Self {
+///         Len(p.len())
+///     }
+/// }
+///
+/// let mut registry = Registry::new();
+///
+/// let h = registry.insert(Memo::<Len, String>::new("hello".into()));
+///
+/// assert_eq!(registry.get_mut(h).unwrap().get().0, 5);
+///
+/// registry.get_mut(h).unwrap().param_mut().push_str(" world");
+///
+/// assert_eq!(registry.dirty(), 1);
+/// registry.ready_all();
+/// assert_eq!(registry.dirty(), 0);
+///
+/// assert_eq!(registry.get(h).unwrap().try_get().unwrap().0, 11);
+/// ```
+///
+pub struct Registry {
+    entries: Vec<Entry>,
+}
+
+/// Typed handle to a memo stored in a `Registry`
+///
+/// Requires the `alloc` feature.
+///
+/// A handle is only meaningful for the registry which returned it, but this
+/// is not checked: with another registry, it refers to the memo at the same
+/// position in that one, if there is one of the same type.
+pub struct Handle<M> {
+    index: usize,
+    marker: PhantomData<fn() -> M>,
+}
+
+/// Summary of the state of the memos in a `Registry`, by type
+///
+/// Requires the `stats` feature.
+///
+/// Created by `Registry::report()`. It implements `Display`, as a table with
+/// one line per type, which can be logged. With the `serde` feature, it can
+/// also be serialized (for example, to expose it over a debug endpoint).
+#[cfg(feature = "stats")]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct RegistryReport {
+    /// The statistics for each type of memo, ordered by type name
+    pub types: Vec<TypeReport>,
+}
+
+/// Statistics for all memos of one type in a `Registry`
+///
+/// Requires the `stats` feature.
+///
+/// Hits and misses are counted by `Registry::get_mut()`, depending on whether
+/// the memo had a cached value. Only computations done by the registry itself
+/// (in `ready_all()` and `run_idle()`) are timed, and only with the `std`
+/// feature.
+#[cfg(feature = "stats")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct TypeReport {
+    /// The name of the type of the memos
+    pub type_name: &'static str,
+    /// The number of memos of this type
+    pub count: usize,
+    /// The number of memos of this type which have a cached value
+    pub ready: usize,
+    /// The number of accesses to memos which had a cached value
+    pub hits: u64,
+    /// The number of accesses to memos which did not have a cached value
+    pub misses: u64,
+    /// The total time spent computing values in the registry
+    pub compute_time: Duration,
+}
+
+#[cfg(feature = "stats")]
+impl TypeReport {
+    /// Get the percentage of memos which have a cached value
+    pub fn ready_percent(&self) -> f64 {
+        if self.count == 0 {
+            return 0.0;
+        }
+        self.ready as f64 * 100.0 / self.count as f64
+    }
+}
+
+#[cfg(feature = "stats")]
+impl fmt::Display for RegistryReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for t in &self.types {
+            writeln!(
+                f,
+                "{}: {} memos, {:.0}% ready, {} hits, {} misses, {:?} computing",
+                t.type_name,
+                t.count,
+                t.ready_percent(),
+                t.hits,
+                t.misses,
+                t.compute_time
+            )?;
+        }
+        Ok(())
+    }
+}
+
+struct Entry {
+    memo: Box<dyn Slot>,
+    accesses: u64,
+    heap_size: fn(&dyn Any) -> usize,
+    compact: fn(&mut dyn Any),
+    #[cfg(feature = "stats")]
+    stats: EntryStats,
+}
+
+/// Statistics for one memo, to build a `RegistryReport`
+#[cfg(feature = "stats")]
+struct EntryStats {
+    type_name: &'static str,
+    hits: u64,
+    misses: u64,
+    compute_time: Duration,
+}
+
+impl Entry {
+    /// Compute the value of the memo if it is not ready (and time it, to
+    /// report it in the stats)
+    fn ready(&mut self) {
+        #[cfg(all(feature = "stats", feature = "std"))]
+        {
+            if !self.memo.is_ready() {
+                let start = Instant::now();
+                self.memo.ready();
+                self.stats.compute_time += start.elapsed();
+            }
+        }
+        #[cfg(not(all(feature = "stats", feature = "std")))]
+        self.memo.ready();
+    }
+}
+
+trait Slot: Ready {
+    fn as_any(&self) -> &dyn Any;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+impl<M: Ready + Any> Slot for M {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+impl Registry {
+    /// Creates a new empty `Registry` instance
+    pub fn new() -> Self {
+        Self {
+            entries: Vec::new(),
+        }
+    }
+
+    /// Add a memo to the registry
+    ///
+    /// The heap memory used by the memo is not accounted for in
+    /// `memory_used()`. Use `insert_sized()` for that.
+    pub fn insert<M: Ready + Any>(&mut self, memo: M) -> Handle<M> {
+        self.push(memo, |_| 0)
+    }
+
+    /// Add a memo to the registry, accounting for its heap memory
+    ///
+    /// Same as `insert()`, but `memory_used()` includes the heap memory used by
+    /// the memo, as reported by `MemSize`.
+    pub fn insert_sized<M: Ready + MemSize + Any>(&mut self, memo: M) -> Handle<M> {
+        fn heap_size<M: MemSize + Any>(memo: &dyn Any) -> usize {
+            memo.downcast_ref::<M>().map_or(0, MemSize::heap_size)
+        }
+
+        self.push(memo, heap_size::<M>)
+    }
+
+    fn push<M: Ready + Any>(&mut self, memo: M, heap_size: fn(&dyn Any) -> usize) -> Handle<M> {
+        self.entries.push(Entry {
+            memo: Box::new(memo),
+            accesses: 0,
+            heap_size,
+            compact: |_| {},
+            #[cfg(feature = "stats")]
+            stats: EntryStats {
+                type_name: any::type_name::<M>(),
+                hits: 0,
+                misses: 0,
+                compute_time: Duration::ZERO,
+            },
+        });
+
+        Handle {
+            index: self.entries.len() - 1,
+            marker: PhantomData,
+        }
+    }
+
+    /// Get a reference to a memo
+    ///
+    /// This does not count as an access.
+    ///
+    /// Returns `None` if there is no memo of type `M` for the handle (see
+    /// `Handle`).
+    pub fn get<M: Any>(&self, handle: Handle<M>) -> Option<&M> {
+        self.entries
+            .get(handle.index)
+            .and_then(|e| e.memo.as_any().downcast_ref())
+    }
+
+    /// Get a mutable reference to a memo
+    ///
+    /// This counts as an access.
+    ///
+    /// Returns `None` if there is no memo of type `M` for the handle (see
+    /// `Handle`).
+    pub fn get_mut<M: Any>(&mut self, handle: Handle<M>) -> Option<&mut M> {
+        let entry = self.entries.get_mut(handle.index)?;
+        #[cfg(feature = "stats")]
+        {
+            if entry.memo.as_any().is::<M>() {
+                if entry.memo.is_ready() {
+                    entry.stats.hits += 1;
+                } else {
+                    entry.stats.misses += 1;
+                }
+            }
+        }
+        let memo = entry.memo.as_any_mut().downcast_mut()?;
+        entry.accesses += 1;
+        Some(memo)
+    }
+
+    /// Get the number of times a memo has been accessed via `get_mut()`
+    pub fn accesses<M>(&self, handle: Handle<M>) -> u64 {
+        self.entries
+            .get(handle.index)
+            .map(|e| e.accesses)
+            .unwrap_or(0)
+    }
+
+    /// Get the number of memos in the registry
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Check if the registry is empty
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Get the approximate number of bytes of memory used by the memos
+    ///
+    /// This counts the size of each memo, plus its heap memory if it was added
+    /// with `insert_sized()`.
+    pub fn memory_used(&self) -> usize {
+        self.entries
+            .iter()
+            .map(|e| mem::size_of_val(&*e.memo) + (e.heap_size)(e.memo.as_any()))
+            .sum()
+    }
+
+    /// Include a memo in `compact_all()`
+    ///
+    /// Returns `false` if there is no memo of type `M` for the handle (see
+    /// `Handle`).
+    pub fn enable_compact<M: Compact + Any>(&mut self, handle: Handle<M>) -> bool {
+        fn compact<M: Compact + Any>(memo: &mut dyn Any) {
+            if let Some(memo) = memo.downcast_mut::<M>() {
+                memo.compact();
+            }
+        }
+
+        match self.entries.get_mut(handle.index) {
+            Some(entry) if entry.memo.as_any().is::<M>() => {
+                entry.compact = compact::<M>;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Compact the cached values of all memos which were registered with
+    /// `enable_compact()`
+    ///
+    /// Call this when memory is running low, to release the spare capacity of
+    /// the cached values, without invalidating them (see `Compact`).
+    pub fn compact_all(&mut self) {
+        for entry in self.entries.iter_mut() {
+            (entry.compact)(entry.memo.as_any_mut());
+        }
+    }
+
+    /// Get the number of memos that do not have a cached value
+    pub fn dirty(&self) -> usize {
+        self.entries.iter().filter(|e| !e.memo.is_ready()).count()
+    }
+
+    /// Compute the value of every memo that is not ready
+    pub fn ready_all(&mut self) {
+        for entry in self.entries.iter_mut() {
+            entry.ready();
+        }
+    }
+
+    /// Summarize the state of the memos, by type
+    ///
+    /// Requires the `stats` feature.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// use core_memo::{Memoize, Memo, Registry};
+    ///
+    /// struct Len(usize);
+    ///
+    /// impl Memoize for Len {
+    ///     type Param = str;
+    ///     fn memoize(p: &str) -> Self {
+    ///         Len(p.len())
+    ///     }
+    /// }
+    ///
+    /// let mut registry = Registry::new();
+    /// let a = registry.insert(Memo::<Len, String>::new("a".into()));
+    /// registry.insert(Memo::<Len, String>::new("b".into()));
+    ///
+    /// registry.get_mut(a).unwrap().get(); // miss
+    /// registry.get_mut(a).unwrap().get(); // hit
+    ///
+    /// let report = registry.report();
+    /// let len = &report.types[0];
+    /// assert_eq!((len.count, len.ready), (2, 1));
+    /// assert_eq!((len.hits, len.misses), (1, 1));
+    ///
+    /// println!("{}", report);
+    /// ```
+    ///
+    #[cfg(feature = "stats")]
+    pub fn report(&self) -> RegistryReport {
+        let mut types: BTreeMap<&'static str, TypeReport> = BTreeMap::new();
+        for entry in &self.entries {
+            let stats = &entry.stats;
+            let t = types.entry(stats.type_name).or_insert(TypeReport {
+                type_name: stats.type_name,
+                count: 0,
+                ready: 0,
+                hits: 0,
+                misses: 0,
+                compute_time: Duration::ZERO,
+            });
+            t.count += 1;
+            if entry.memo.is_ready() {
+                t.ready += 1;
+            }
+            t.hits += stats.hits;
+            t.misses += stats.misses;
+            t.compute_time += stats.compute_time;
+        }
+
+        RegistryReport {
+            types: types.into_values().collect(),
+        }
+    }
+
+    /// Recompute dirty memos, while staying within a time budget
+    ///
+    /// Requires the `std` feature.
+    ///
+    /// Meant to be called during idle time (such as at the end of a frame), to
+    /// heal caches before they are needed. The memos are prioritized by how
+    /// frequently they are accessed, relative to their cost (see
+    /// `Memoize::cost()`), so that frequently accessed and cheap memos are
+    /// recomputed first.
+    ///
+    /// Computations cannot be interrupted, so the budget is only checked
+    /// before starting each one. The last computation can overrun it.
+    ///
+    /// Returns the number of memos that were recomputed.
+    #[cfg(feature = "std")]
+    pub fn run_idle(&mut self, budget: Duration) -> usize {
+        let start = Instant::now();
+
+        let mut dirty: Vec<usize> = (0..self.entries.len())
+            .filter(|&i| !self.entries[i].memo.is_ready())
+            .collect();
+        // compare accesses per unit of cost, without dividing
+        let priority = |i: usize| {
+            let entry = &self.entries[i];
+            (
+                u128::from(entry.accesses),
+                u128::from(entry.memo.cost().max(1)),
+            )
+        };
+        dirty.sort_by(|&a, &b| {
+            let ((a_accesses, a_cost), (b_accesses, b_cost)) = (priority(a), priority(b));
+            (b_accesses * a_cost)
+                .cmp(&(a_accesses * b_cost))
+                .then(a_cost.cmp(&b_cost))
+        });
+
+        let mut count = 0;
+        for i in dirty {
+            if start.elapsed() >= budget {
+                break;
+            }
+            self.entries[i].ready();
+            count += 1;
+        }
+
+        count
+    }
+}
+
+impl Default for Registry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for Registry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Registry")
+            .field("len", &self.len())
+            .field("dirty", &self.dirty())
+            .finish()
+    }
+}
+
+impl<M> Clone for Handle<M> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<M> Copy for Handle<M> {}
+
+impl<M> fmt::Debug for Handle<M> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("Handle").field(&self.index).finish()
+    }
+}
@@ -0,0 +1,245 @@
+use core::borrow::Borrow;
+use core::ops::Deref;
+
+use spawn::Job;
+use {Invalidate, Memoize};
+
+/// A value which may be out of date
+///
+/// Returned by `MemoRevalidate::get_stale_while_revalidate()`. It derefs to
+/// the value, whether it is fresh or stale.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MaybeStale<T> {
+    /// The value is up to date
+    Fresh(T),
+    /// The value was computed from an old parameter, and a new one is being
+    /// computed
+    Stale(T),
+}
+
+impl<T> MaybeStale<T> {
+    /// Check if the value is out of date
+    pub fn is_stale(&self) -> bool {
+        match self {
+            MaybeStale::Fresh(_) => false,
+            MaybeStale::Stale(_) => true,
+        }
+    }
+
+    /// Get the value, whether it is fresh or stale
+    pub fn into_inner(self) -> T {
+        match self {
+            MaybeStale::Fresh(value) | MaybeStale::Stale(value) => value,
+        }
+    }
+}
+
+impl<T: Deref> Deref for MaybeStale<T> {
+    type Target = T::Target;
+
+    fn deref(&self) -> &T::Target {
+        match self {
+            MaybeStale::Fresh(value) | MaybeStale::Stale(value) => value,
+        }
+    }
+}
+
+/// Memoized value which keeps serving the old value while recomputing it
+///
+/// Requires the `std` feature.
+///
+/// Like `Memo`, this holds ownership over the parameter for the computation.
+/// But invalidating it (by modifying the parameter, or with `invalidate()`)
+/// only marks the value as stale. Then, `get_stale_while_revalidate()`
+/// returns the stale value immediately, and starts computing the new one on a
+/// background thread, with a clone of the parameter. The new value is swapped
+/// in by the first call after it is ready.
+///
+/// Use this where answering quickly with a slightly outdated value is better
+/// than waiting, like in latency-sensitive services. `get()` still waits for
+/// the up-to-date value.
+///
+/// ## Example
+///
+/// ```
+/// use core_memo::{Memoize, MemoRevalidate};
+///
+/// struct Report(String);
+///
+/// impl Memoize for Report {
+///     type Param = u32;
+///     fn memoize(day: &u32) -> Self {
+///         // imagine this takes a while
+///         Report(format!("report for day {}", day))
+///     }
+/// }
+///
+/// let mut memo: MemoRevalidate<Report> = MemoRevalidate::new(1);
+/// assert_eq!(memo.get().0, "report for day 1");
+///
+/// *memo.param_mut() = 2;
+///
+/// // the old report is served, while the new one is being computed
+/// let report = memo.get_stale_while_revalidate();
+/// assert!(report.is_stale());
+/// assert_eq!(report.0, "report for day 1");
+///
+/// assert_eq!(memo.get().0, "report for day 2");
+/// ```
+///
+#[derive(Debug)]
+pub struct MemoRevalidate<T, P = <T as Memoize>::Param> {
+    value: Option<T>,
+    param: P,
+    stale: bool,
+    /// Counts the invalidations, so that values computed in the background
+    /// for an old parameter are recognized
+    generation: u64,
+    /// The value being computed in the background, and the generation of the
+    /// parameter it is computed for
+    job: Option<(Job<T>, u64)>,
+}
+
+impl<T, P> MemoRevalidate<T, P> {
+    /// Creates a new `MemoRevalidate` instance
+    pub fn new(p: P) -> Self {
+        Self {
+            value: None,
+            param: p,
+            stale: false,
+            generation: 0,
+            job: None,
+        }
+    }
+
+    /// Mark the value as stale
+    ///
+    /// It is still returned by `get_stale_while_revalidate()`, until the new
+    /// one is ready. This is what `invalidate()` does.
+    pub fn mark_stale(&mut self) {
+        self.stale = true;
+        self.generation += 1;
+    }
+
+    /// Clears any cached value
+    ///
+    /// Unlike `mark_stale()`, there is nothing to return in the meantime, so
+    /// the next call to any of the getters computes the value.
+    pub fn clear(&mut self) {
+        self.mark_stale();
+        self.value = None;
+    }
+
+    /// Check if there is an up-to-date cached value
+    pub fn is_ready(&self) -> bool {
+        self.value.is_some() && !self.stale
+    }
+
+    /// Check if a new value is being computed in the background
+    pub fn is_revalidating(&self) -> bool {
+        self.job
+            .as_ref()
+            .is_some_and(|&(_, generation)| generation == self.generation)
+    }
+
+    /// Get the cached value if there is one, fresh or stale
+    ///
+    /// This does not swap in a value computed in the background.
+    pub fn try_get(&self) -> Option<MaybeStale<&T>> {
+        self.value.as_ref().map(|value| match self.stale {
+            false => MaybeStale::Fresh(value),
+            true => MaybeStale::Stale(value),
+        })
+    }
+
+    /// Get a reference to the parameter used for the computation
+    pub fn param(&self) -> &P {
+        &self.param
+    }
+
+    /// Get a mutable reference to the parameter used for the computation
+    ///
+    /// This marks the value as stale.
+    pub fn param_mut(&mut self) -> &mut P {
+        self.mark_stale();
+        &mut self.param
+    }
+
+    /// Get the parameter back, dropping the memo
+    ///
+    /// A computation which is still running in the background is detached,
+    /// and its result is discarded.
+    pub fn into_param(self) -> P {
+        self.param
+    }
+}
+
+impl<T, P> MemoRevalidate<T, P>
+where
+    T: Memoize + Send + 'static,
+    P: Borrow<T::Param> + Clone + Send + 'static,
+{
+    /// Swap in the value computed in the background, if it is ready (or if
+    /// `wait` is `true`, once it is)
+    fn collect(&mut self, wait: bool) {
+        let finished = self.job.as_ref().is_some_and(|&(ref job, generation)| {
+            let current = generation == self.generation;
+            (wait && current) || job.is_finished()
+        });
+        if finished {
+            let (job, generation) = self.job.take().unwrap();
+            self.value = Some(job.join());
+            self.stale = generation != self.generation;
+        }
+    }
+
+    /// Compute the value on this thread
+    fn compute(&mut self) {
+        self.job = None;
+        self.value = Some(T::memoize(self.param.borrow()));
+        self.stale = false;
+    }
+
+    /// Get the value, or the stale value while the new one is computed
+    ///
+    /// If there is an up-to-date value, it is returned as `Fresh`. If there is
+    /// a stale one, it is returned as `Stale`, and the new one is computed on
+    /// a background thread (unless that is already happening). If there is no
+    /// value at all, it is computed on this thread, like with `get()`.
+    pub fn get_stale_while_revalidate(&mut self) -> MaybeStale<&T> {
+        self.collect(false);
+
+        if self.value.is_none() {
+            return MaybeStale::Fresh(self.get());
+        }
+
+        if self.stale && !self.is_revalidating() {
+            let param = self.param.clone();
+            let job = Job::spawn(move || T::memoize(param.borrow()));
+            self.job = Some((job, self.generation));
+        }
+
+        self.try_get().unwrap()
+    }
+
+    /// Get the up-to-date value
+    ///
+    /// If it is being computed in the background, this waits for it. If not,
+    /// and the cached value is stale (or there is none), it is computed on
+    /// this thread.
+    ///
+    /// If the background computation panicked, the panic is resumed here.
+    pub fn get(&mut self) -> &T {
+        self.collect(true);
+        if !self.is_ready() {
+            self.compute();
+        }
+        self.value.as_ref().unwrap()
+    }
+}
+
+impl<T, P> Invalidate for MemoRevalidate<T, P> {
+    fn invalidate(&mut self) {
+        self.mark_stale()
+    }
+}
@@ -0,0 +1,117 @@
+use core::borrow::Borrow;
+
+use {MemoExt, Memoize};
+
+/// Memoized evaluation over a sequence of parameters
+///
+/// This type drives a computation across an iterator of parameters, producing
+/// the output for each of them in turn. The value is only recomputed when the
+/// parameter changes: if consecutive parameters compare equal, the previous
+/// output is reused.
+///
+/// This is useful for replaying event logs or time series, where long runs of
+/// steps share the same input.
+///
+/// The iterator can yield the parameters either by value or by reference.
+///
+/// `MemoScan` does not implement `Iterator`, because it yields references to
+/// the value it holds. Call `step()` in a loop instead.
+///
+/// ## Example
+///
+/// ```
+/// use core_memo::{Memoize, MemoScan};
+///
+/// struct Square(u64);
+///
+/// impl Memoize for Square {
+///     type Param = u64;
+///     fn memoize(p: &u64) -> Self {
+///         Square(p * p)
+///     }
+/// }
+///
+/// let log = [2, 2, 2, 3, 3, 2];
+/// let mut scan: MemoScan<Square, _> = MemoScan::new(log.iter());
+///
+/// let mut total = 0;
+/// while let Some(sq) = scan.step() {
+///     total += sq.0;
+/// }
+///
+/// assert_eq!(total, 4 + 4 + 4 + 9 + 9 + 4);
+///
+/// // the runs of equal values were only computed once each
+/// assert_eq!(scan.computations(), 3);
+/// ```
+///
+#[derive(Debug)]
+pub struct MemoScan<T: Memoize, I: Iterator> {
+    memo: MemoExt<T>,
+    params: I,
+    last: Option<I::Item>,
+    computations: usize,
+}
+
+impl<T: Memoize, I: Iterator> MemoScan<T, I>
+where
+    I::Item: Borrow<T::Param>,
+    T::Param: PartialEq,
+{
+    /// Creates a new `MemoScan` instance
+    ///
+    /// Nothing is computed until the first call to `step()`.
+    pub fn new<P>(params: P) -> Self
+    where
+        P: IntoIterator<IntoIter = I, Item = I::Item>,
+    {
+        Self {
+            memo: MemoExt::new(),
+            params: params.into_iter(),
+            last: None,
+            computations: 0,
+        }
+    }
+
+    /// Advance to the next parameter and get the value for it
+    ///
+    /// If the parameter compares equal to the previous one, the cached value
+    /// is returned. Otherwise, the value is recomputed.
+    ///
+    /// Returns `None` when the parameters have been exhausted.
+    pub fn step(&mut self) -> Option<&T> {
+        let param = self.params.next()?;
+
+        let unchanged = match self.last {
+            Some(ref last) => last.borrow() == param.borrow(),
+            None => false,
+        };
+
+        if !unchanged {
+            self.memo.update(param.borrow());
+            self.computations += 1;
+        }
+
+        self.last = Some(param);
+        self.memo.try_get()
+    }
+
+    /// Get the value for the current parameter
+    ///
+    /// Returns `None` if `step()` has not been called yet.
+    pub fn current(&self) -> Option<&T> {
+        self.memo.try_get()
+    }
+
+    /// Get the current parameter
+    ///
+    /// Returns `None` if `step()` has not been called yet.
+    pub fn param(&self) -> Option<&T::Param> {
+        self.last.as_ref().map(Borrow::borrow)
+    }
+
+    /// Get the number of times the value has been computed so far
+    pub fn computations(&self) -> usize {
+        self.computations
+    }
+}
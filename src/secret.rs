@@ -0,0 +1,70 @@
+use alloc::boxed::Box;
+
+use secrecy::SecretBox;
+use zeroize::Zeroize;
+
+use Memoize;
+
+/// Memoize secret values, which are kept out of logs and wiped from memory
+///
+/// Requires the `secrecy` feature.
+///
+/// With this, you can use `SecretBox<T>` as the value type of any of the
+/// memoization types, for any `T` which is `Zeroize`. The computation is the
+/// same as for `T`, and `get()` returns a `&SecretBox<T>`, which you have to
+/// call `expose_secret()` on to access the value. Like with `Zeroizing`, the
+/// value is wiped when it is cleared, recomputed, or dropped.
+///
+/// The `Debug` impls of the memoization types print the cached value with its
+/// own `Debug` impl, which redacts a `SecretBox`. They do print the parameter,
+/// though, so if it is also sensitive, it should be wrapped as well.
+///
+/// ## Example
+///
+/// ```
+/// extern crate secrecy;
+/// # extern crate core_memo;
+///
+/// use core_memo::{Memoize, Memo};
+/// use secrecy::{ExposeSecret, SecretBox};
+/// use secrecy::zeroize::Zeroize;
+///
+/// #[derive(Debug)]
+/// struct Token(u64);
+///
+/// impl Zeroize for Token {
+///     fn zeroize(&mut self) {
+///         self.0.zeroize();
+///     }
+/// }
+///
+/// impl Memoize for Token {
+///     type Param = u64;
+///     fn memoize(p: &u64) -> Self {
+///         Token(p.rotate_left(17))
+///     }
+/// }
+///
+/// # fn main() {
+/// let mut memo: Memo<SecretBox<Token>> = Memo::new(1);
+/// assert_eq!(memo.get().expose_secret().0, 1 << 17);
+///
+/// let debug = format!("{:?}", memo);
+/// assert!(debug.contains("REDACTED"));
+/// assert!(!debug.contains("131072"));
+/// # }
+/// ```
+///
+impl<T: Memoize + Zeroize> Memoize for SecretBox<T> {
+    type Param = T::Param;
+
+    const COST: u32 = T::COST;
+
+    fn memoize(p: &T::Param) -> Self {
+        SecretBox::new(Box::new(T::memoize(p)))
+    }
+
+    fn cost(p: &T::Param) -> u32 {
+        T::cost(p)
+    }
+}
@@ -0,0 +1,250 @@
+use core::fmt;
+use core::mem::{ManuallyDrop, MaybeUninit};
+use core::ptr;
+
+use stable_deref_trait::StableDeref;
+
+/// Represents a computation whose output borrows from its parameter
+///
+/// Requires the `self-ref` feature.
+///
+/// This is like `Memoize`, but the output is allowed to hold references into
+/// the parameter (like `&str` slices or a parsed AST referencing the source).
+/// Because the output type depends on the lifetime of the parameter, this
+/// trait is not implemented on the output type itself, but on a separate
+/// (usually zero-sized) type naming the computation.
+///
+/// Use it with `MemoSelfRef`, which owns the parameter and caches the output.
+///
+/// ## Covariance
+///
+/// The output type must be covariant in its lifetime. This is enforced by
+/// requiring you to implement `shorten()`, which you can always do by simply
+/// returning the argument. It will fail to compile if the output type is not
+/// covariant (for example, if it contains a `Cell<&'p str>`).
+///
+/// ## Example
+///
+/// ```
+/// use core_memo::{MemoizeBorrowing, MemoSelfRef};
+///
+/// struct Words;
+///
+/// impl MemoizeBorrowing for Words {
+///     type Param = str;
+///     type Output<'p> = Vec<&'p str>;
+///
+///     fn memoize(p: &str) -> Vec<&str> {
+///         p.split_whitespace().collect()
+///     }
+///
+///     fn shorten<'a, 'p: 'a>(v: &'a Vec<&'p str>) -> &'a Vec<&'a str> {
+///         v
+///     }
+/// }
+///
+/// let mut memo: MemoSelfRef<Words, String> = MemoSelfRef::new("hello big world".into());
+///
+/// assert_eq!(memo.get(), &["hello", "big", "world"]);
+///
+/// memo.param_mut().push_str(" again");
+///
+/// assert_eq!(memo.get().len(), 4);
+/// ```
+///
+pub trait MemoizeBorrowing {
+    type Param: ?Sized;
+    type Output<'p>
+    where
+        Self::Param: 'p;
+
+    fn memoize(p: &Self::Param) -> Self::Output<'_>;
+
+    /// Shorten the lifetime of the output
+    ///
+    /// Implement this by returning `v` unchanged.
+    fn shorten<'a, 'p: 'a>(v: &'a Self::Output<'p>) -> &'a Self::Output<'a>;
+}
+
+/// Memoized value which borrows from a parameter it owns
+///
+/// Requires the `self-ref` feature.
+///
+/// This is like `Memo`, but for computations implementing `MemoizeBorrowing`,
+/// whose output holds references into the parameter.
+///
+/// The parameter must be held by a pointer type implementing `StableDeref`
+/// (from the `stable_deref_trait` crate, re-exported here), such as `String`,
+/// `Vec<T>`, `Box<T>`, or `Rc<T>`.
+///
+/// You can modify the parameter using `param_mut()` or `update_param()`. Any
+/// cached value will be cleared and will be recomputed on the next access.
+///
+/// ## Example
+///
+/// See the documentation of `MemoizeBorrowing` for an example.
+///
+pub struct MemoSelfRef<C: MemoizeBorrowing, P: StableDeref<Target = C::Param>>
+where
+    C::Param: 'static,
+{
+    // NOTE: must be declared before `param`, so that it is dropped first
+    value: Option<C::Output<'static>>,
+    param: Unaliased<P>,
+}
+
+/// Holds the parameter, without asserting unique access to its target when
+/// it is moved
+///
+/// Moving a `Box` (or a struct holding one) does that, which would invalidate
+/// the references into it held by the value. A `MaybeUninit` does not.
+struct Unaliased<P>(MaybeUninit<P>);
+
+impl<P> Unaliased<P> {
+    fn new(p: P) -> Self {
+        Unaliased(MaybeUninit::new(p))
+    }
+
+    fn get(&self) -> &P {
+        // SAFETY: always initialized, until dropped or taken by `into_inner()`
+        unsafe { self.0.assume_init_ref() }
+    }
+
+    fn get_mut(&mut self) -> &mut P {
+        // SAFETY: as above
+        unsafe { self.0.assume_init_mut() }
+    }
+
+    fn into_inner(self) -> P {
+        let this = ManuallyDrop::new(self);
+        // SAFETY: as above, and `this` is not dropped, so it is only read once
+        unsafe { this.0.assume_init_read() }
+    }
+}
+
+impl<P> Drop for Unaliased<P> {
+    fn drop(&mut self) {
+        // SAFETY: as above, and it is never used again
+        unsafe { self.0.assume_init_drop() }
+    }
+}
+
+/// Extend the lifetime of an output to `'static`
+///
+/// ## Safety
+///
+/// The output must not be used after the parameter it borrows is dropped,
+/// moved, or mutated.
+unsafe fn extend<'p, C: MemoizeBorrowing>(v: C::Output<'p>) -> C::Output<'static>
+where
+    C::Param: 'static,
+{
+    let v = ManuallyDrop::new(v);
+    ptr::read((&*v as *const C::Output<'p>).cast::<C::Output<'static>>())
+}
+
+impl<C: MemoizeBorrowing, P: StableDeref<Target = C::Param>> MemoSelfRef<C, P>
+where
+    C::Param: 'static,
+{
+    /// Creates a new `MemoSelfRef` instance
+    ///
+    /// You must pass in the object which will be used as the parameter
+    /// for your computation. The `MemoSelfRef` will take ownership over it.
+    pub fn new(p: P) -> Self {
+        Self {
+            value: None,
+            param: Unaliased::new(p),
+        }
+    }
+
+    /// Clears any cached value
+    ///
+    /// The value will be reevaluated the next time it is needed.
+    pub fn clear(&mut self) {
+        self.value = None
+    }
+
+    /// Check if there is a cached value
+    pub fn is_ready(&self) -> bool {
+        self.value.is_some()
+    }
+
+    /// If the value is not ready, compute it and cache it
+    pub fn ready(&mut self) {
+        if self.value.is_none() {
+            self.update();
+        }
+    }
+
+    /// Force the value to be recomputed
+    pub fn update(&mut self) {
+        self.value = None;
+        let value = C::memoize(&**self.param.get());
+        // SAFETY: `param` derefs to a stable location, which cannot be mutated
+        // without going through `param_mut()` or `update_param()`, which clear
+        // the value first. The value is dropped before `param`. We never hand
+        // out the value with the `'static` lifetime.
+        self.value = Some(unsafe { extend::<C>(value) });
+    }
+
+    /// Get the value
+    ///
+    /// If the value has already been computed, this function returns the cached
+    /// value. If not, it is computed and cached for future use.
+    pub fn get(&mut self) -> &C::Output<'_> {
+        self.ready();
+        self.try_get().unwrap()
+    }
+
+    /// Get the value if it is available
+    pub fn try_get(&self) -> Option<&C::Output<'_>> {
+        self.value.as_ref().map(C::shorten)
+    }
+
+    /// Get a reference to the parameter used for the computation
+    pub fn param(&self) -> &P {
+        self.param.get()
+    }
+
+    /// Get a mutable reference to the parameter used for the computation
+    ///
+    /// This clears any cached value.
+    pub fn param_mut(&mut self) -> &mut P {
+        self.clear();
+        self.param.get_mut()
+    }
+
+    /// Modify the parameter used for the computation
+    ///
+    /// Takes a closure and applies it to the parameter.
+    ///
+    /// This clears any cached value.
+    pub fn update_param<F>(&mut self, op: F)
+    where
+        F: FnOnce(&mut P),
+    {
+        self.clear();
+        op(self.param.get_mut());
+    }
+
+    /// Drop any cached value and get the parameter back
+    pub fn into_param(mut self) -> P {
+        self.clear();
+        self.param.into_inner()
+    }
+}
+
+impl<C: MemoizeBorrowing, P: StableDeref<Target = C::Param>> fmt::Debug for MemoSelfRef<C, P>
+where
+    C::Param: 'static,
+    C::Output<'static>: fmt::Debug,
+    P: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("MemoSelfRef")
+            .field("value", &self.value)
+            .field("param", self.param.get())
+            .finish()
+    }
+}
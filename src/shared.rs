@@ -0,0 +1,152 @@
+use alloc::rc::Rc;
+use alloc::sync::Arc;
+use core::borrow::Borrow;
+use core::ops::Deref;
+
+use {Memo, MemoExt, MemoMut, MemoOnce, Memoize};
+
+/// Memoize values in an `Rc`, to share ownership of them
+///
+/// Requires the `alloc` feature.
+///
+/// With this, you can use `Rc<T>` as the value type of any of the memoization
+/// types. The computation is the same as for `T`. Then, `get_rc()` returns a
+/// clone of the pointer, which stays valid after the memo is cleared or its
+/// parameter is modified, without cloning the value itself.
+///
+/// ## Example
+///
+/// ```
+/// use std::rc::Rc;
+/// use core_memo::{Memoize, Memo};
+///
+/// struct Lines(Vec<String>);
+///
+/// impl Memoize for Lines {
+///     type Param = String;
+///     fn memoize(p: &String) -> Self {
+///         Lines(p.lines().map(String::from).collect())
+///     }
+/// }
+///
+/// let mut memo: Memo<Rc<Lines>> = Memo::new("a\nb".into());
+/// let before = memo.get_rc();
+///
+/// memo.param_mut().push_str("\nc");
+/// let after = memo.get_rc();
+///
+/// // the old value is still around, for as long as it is needed
+/// assert_eq!(before.0.len(), 2);
+/// assert_eq!(after.0.len(), 3);
+/// ```
+///
+impl<T: Memoize> Memoize for Rc<T> {
+    type Param = T::Param;
+
+    const COST: u32 = T::COST;
+
+    fn memoize(p: &T::Param) -> Self {
+        Rc::new(T::memoize(p))
+    }
+
+    fn cost(p: &T::Param) -> u32 {
+        T::cost(p)
+    }
+}
+
+/// Memoize values in an `Arc`, to share ownership of them across threads
+///
+/// Requires the `alloc` feature.
+///
+/// Same as for `Rc`, but `get_arc()` returns the clones of the pointer.
+impl<T: Memoize> Memoize for Arc<T> {
+    type Param = T::Param;
+
+    const COST: u32 = T::COST;
+
+    fn memoize(p: &T::Param) -> Self {
+        Arc::new(T::memoize(p))
+    }
+
+    fn cost(p: &T::Param) -> u32 {
+        T::cost(p)
+    }
+}
+
+impl<T: Memoize> MemoExt<Rc<T>> {
+    /// Get the value, sharing ownership of it
+    ///
+    /// Same as `get()`, but returns a clone of the pointer.
+    pub fn get_rc<B: Borrow<T::Param> + ?Sized>(&mut self, p: &B) -> Rc<T> {
+        Rc::clone(self.get(p))
+    }
+}
+
+impl<T: Memoize> MemoExt<Arc<T>> {
+    /// Get the value, sharing ownership of it
+    ///
+    /// Same as `get()`, but returns a clone of the pointer.
+    pub fn get_arc<B: Borrow<T::Param> + ?Sized>(&mut self, p: &B) -> Arc<T> {
+        Arc::clone(self.get(p))
+    }
+}
+
+impl<T: Memoize, P: Borrow<T::Param>> Memo<Rc<T>, P> {
+    /// Get the value, sharing ownership of it
+    ///
+    /// Same as `get()`, but returns a clone of the pointer.
+    pub fn get_rc(&mut self) -> Rc<T> {
+        Rc::clone(self.get())
+    }
+}
+
+impl<T: Memoize, P: Borrow<T::Param>> Memo<Arc<T>, P> {
+    /// Get the value, sharing ownership of it
+    ///
+    /// Same as `get()`, but returns a clone of the pointer.
+    pub fn get_arc(&mut self) -> Arc<T> {
+        Arc::clone(self.get())
+    }
+}
+
+impl<'p, T: Memoize, R: Deref> MemoOnce<'p, Rc<T>, R>
+where
+    R::Target: Borrow<T::Param>,
+{
+    /// Get the value, sharing ownership of it
+    ///
+    /// Same as `get()`, but returns a clone of the pointer.
+    pub fn get_rc(&mut self) -> Rc<T> {
+        Rc::clone(self.get())
+    }
+}
+
+impl<'p, T: Memoize, R: Deref> MemoOnce<'p, Arc<T>, R>
+where
+    R::Target: Borrow<T::Param>,
+{
+    /// Get the value, sharing ownership of it
+    ///
+    /// Same as `get()`, but returns a clone of the pointer.
+    pub fn get_arc(&mut self) -> Arc<T> {
+        Arc::clone(self.get())
+    }
+}
+
+impl<'p, T: Memoize> MemoMut<'p, Rc<T>> {
+    /// Get the value, sharing ownership of it
+    ///
+    /// Same as `get()`, but returns a clone of the pointer.
+    pub fn get_rc(&mut self) -> Rc<T> {
+        Rc::clone(self.get())
+    }
+}
+
+impl<'p, T: Memoize> MemoMut<'p, Arc<T>> {
+    /// Get the value, sharing ownership of it
+    ///
+    /// Same as `get()`, but returns a clone of the pointer.
+    pub fn get_arc(&mut self) -> Arc<T> {
+        Arc::clone(self.get())
+    }
+}
@@ -0,0 +1,129 @@
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// Number of hash functions (and rows of counters)
+const DEPTH: usize = 4;
+
+/// Counters saturate at this value
+const MAX_COUNT: u8 = 15;
+
+/// Approximate access frequencies of keys, in a fixed amount of memory
+///
+/// This is a count-min sketch with small saturating counters. To let the
+/// estimates adapt to changes in the access pattern, all counters are halved
+/// after a number of additions proportional to the size of the sketch.
+pub(crate) struct FrequencySketch {
+    counters: Vec<u8>,
+    mask: usize,
+    additions: usize,
+    sample_size: usize,
+}
+
+impl FrequencySketch {
+    /// Creates a sketch suitable for tracking about `capacity` hot keys
+    pub(crate) fn new(capacity: usize) -> Self {
+        let width = capacity.max(16).next_power_of_two();
+        Self {
+            counters: vec![0; width * DEPTH],
+            mask: width - 1,
+            additions: 0,
+            sample_size: width * 10,
+        }
+    }
+
+    /// Record an access to the key with the given hash
+    pub(crate) fn increment(&mut self, hash: u64) {
+        for slot in self.slots(hash) {
+            let c = &mut self.counters[slot];
+            if *c < MAX_COUNT {
+                *c += 1;
+            }
+        }
+
+        self.additions += 1;
+        if self.additions >= self.sample_size {
+            self.age();
+        }
+    }
+
+    /// Estimate the number of recent accesses to the key with the given hash
+    pub(crate) fn estimate(&self, hash: u64) -> u8 {
+        self.slots(hash)
+            .map(|slot| self.counters[slot])
+            .min()
+            .unwrap_or(0)
+    }
+
+    /// Halve all counters, so that old accesses count for less
+    fn age(&mut self) {
+        for c in &mut self.counters {
+            *c /= 2;
+        }
+        self.additions /= 2;
+    }
+
+    /// The counter for the key in each row, using double hashing
+    fn slots(&self, hash: u64) -> impl Iterator<Item = usize> {
+        let h1 = hash as usize;
+        let h2 = ((hash >> 32) as usize) | 1;
+        let (mask, width) = (self.mask, self.mask + 1);
+        (0..DEPTH).map(move |row| row * width + (h1.wrapping_add(row.wrapping_mul(h2)) & mask))
+    }
+}
+
+/// Bits per item, for a false positive rate of about 1%
+const BITS_PER_ITEM: usize = 10;
+
+/// Number of hash functions, optimal for `BITS_PER_ITEM`
+const HASHES: u64 = 7;
+
+/// Approximate set of keys, in a fixed amount of memory
+///
+/// This is a Bloom filter: it never forgets a key that was added, but it may
+/// also claim to contain keys that were never added (false positives).
+pub(crate) struct BloomFilter {
+    bits: Vec<u64>,
+    mask: u64,
+}
+
+impl BloomFilter {
+    /// Creates a filter with a false positive rate of about 1%, as long as no
+    /// more than `capacity` keys are added
+    pub(crate) fn new(capacity: usize) -> Self {
+        let bits = (capacity.max(1) * BITS_PER_ITEM)
+            .next_power_of_two()
+            .max(64);
+        Self {
+            bits: vec![0; bits / 64],
+            mask: bits as u64 - 1,
+        }
+    }
+
+    /// Add the key with the given hash
+    pub(crate) fn insert(&mut self, hash: u64) {
+        for bit in self.bits_for(hash) {
+            self.bits[(bit / 64) as usize] |= 1 << (bit % 64);
+        }
+    }
+
+    /// Check if the key with the given hash (might have) been added
+    pub(crate) fn contains(&self, hash: u64) -> bool {
+        self.bits_for(hash)
+            .all(|bit| self.bits[(bit / 64) as usize] & (1 << (bit % 64)) != 0)
+    }
+
+    /// Remove all keys
+    pub(crate) fn clear(&mut self) {
+        for word in &mut self.bits {
+            *word = 0;
+        }
+    }
+
+    /// The bit for the key for each hash function, using double hashing
+    fn bits_for(&self, hash: u64) -> impl Iterator<Item = u64> {
+        let h1 = hash;
+        let h2 = hash.rotate_left(32) | 1;
+        let mask = self.mask;
+        (0..HASHES).map(move |i| h1.wrapping_add(i.wrapping_mul(h2)) & mask)
+    }
+}
@@ -0,0 +1,163 @@
+use alloc::rc::Rc;
+use alloc::sync::Arc;
+use core::borrow::Borrow;
+use core::fmt;
+use core::ops::Deref;
+
+use {Invalidate, Memoize};
+
+/// Shared pointers which can be compared by identity
+///
+/// Requires the `alloc` feature.
+///
+/// Implemented for `Arc` and `Rc`.
+pub trait SharedPtr: Clone + Deref {
+    /// Check if two pointers point to the same allocation
+    fn ptr_eq(a: &Self, b: &Self) -> bool;
+}
+
+impl<T: ?Sized> SharedPtr for Arc<T> {
+    fn ptr_eq(a: &Self, b: &Self) -> bool {
+        Arc::ptr_eq(a, b)
+    }
+}
+
+impl<T: ?Sized> SharedPtr for Rc<T> {
+    fn ptr_eq(a: &Self, b: &Self) -> bool {
+        Rc::ptr_eq(a, b)
+    }
+}
+
+/// Memoized value for an immutable, shared parameter, which detects changes
+/// by pointer identity
+///
+/// Requires the `alloc` feature.
+///
+/// This is for architectures where state is never modified in place, and a
+/// change means a new snapshot (in a new `Arc` or `Rc`). Like `MemoExt`, the
+/// parameter is provided with every call to `get()`. But the memo also keeps
+/// the pointer it computed the value from, and recomputes the value when a
+/// different one is provided. Comparing pointers is cheap, so you get change
+/// detection for free, without comparing or hashing the parameter.
+///
+/// Holding the pointer keeps the old snapshot alive until the value is
+/// recomputed (which also guarantees that its address is not reused).
+///
+/// ## Example
+///
+/// ```
+/// use std::sync::Arc;
+/// use core_memo::{Memoize, MemoSnapshot};
+///
+/// struct WordCount(usize);
+///
+/// impl Memoize for WordCount {
+///     type Param = str;
+///     fn memoize(p: &str) -> Self {
+///         WordCount(p.split_whitespace().count())
+///     }
+/// }
+///
+/// let mut memo: MemoSnapshot<WordCount, Arc<str>> = MemoSnapshot::new();
+///
+/// let doc: Arc<str> = Arc::from("hello world");
+/// assert_eq!(memo.get(&doc).0, 2);
+///
+/// // the same snapshot, so the value is reused
+/// let same = Arc::clone(&doc);
+/// assert!(memo.is_fresh(&same));
+///
+/// // a new snapshot, even with equal contents, is a change
+/// let edited: Arc<str> = Arc::from("hello world");
+/// assert!(!memo.is_fresh(&edited));
+/// assert_eq!(memo.get(&edited).0, 2);
+/// ```
+///
+pub struct MemoSnapshot<T, P> {
+    value: Option<(P, T)>,
+}
+
+impl<T, P> MemoSnapshot<T, P>
+where
+    T: Memoize,
+    P: SharedPtr,
+    P::Target: Borrow<T::Param>,
+{
+    /// Creates a new `MemoSnapshot` instance
+    pub fn new() -> Self {
+        Self { value: None }
+    }
+
+    /// Clears any cached value, and releases the pointer it was computed from
+    pub fn clear(&mut self) {
+        self.value = None;
+    }
+
+    /// Check if there is a cached value
+    pub fn is_ready(&self) -> bool {
+        self.value.is_some()
+    }
+
+    /// Check if there is a cached value, computed from the same pointer
+    pub fn is_fresh(&self, p: &P) -> bool {
+        match self.value {
+            Some((ref snapshot, _)) => P::ptr_eq(snapshot, p),
+            None => false,
+        }
+    }
+
+    /// Get the value for a pointer
+    ///
+    /// If the cached value was computed from the same pointer, it is returned.
+    /// If not, the value is recomputed, and the pointer is kept.
+    pub fn get(&mut self, p: &P) -> &T {
+        if !self.is_fresh(p) {
+            let value = T::memoize((**p).borrow());
+            self.value = Some((p.clone(), value));
+        }
+        &self.value.as_ref().unwrap().1
+    }
+
+    /// Get the value for a pointer, if it is available
+    ///
+    /// Returns `None` if there is no cached value, or if it was computed from
+    /// a different pointer.
+    pub fn try_get(&self, p: &P) -> Option<&T> {
+        if self.is_fresh(p) {
+            self.value.as_ref().map(|(_, v)| v)
+        } else {
+            None
+        }
+    }
+
+    /// Get the pointer that the cached value was computed from
+    pub fn snapshot(&self) -> Option<&P> {
+        self.value.as_ref().map(|(p, _)| p)
+    }
+}
+
+impl<T, P> Default for MemoSnapshot<T, P>
+where
+    T: Memoize,
+    P: SharedPtr,
+    P::Target: Borrow<T::Param>,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, P> Invalidate for MemoSnapshot<T, P> {
+    fn invalidate(&mut self) {
+        self.value = None;
+    }
+}
+
+impl<T: fmt::Debug, P: fmt::Debug> fmt::Debug for MemoSnapshot<T, P> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("MemoSnapshot")
+            .field("value", &self.value.as_ref().map(|(_, v)| v))
+            .field("snapshot", &self.value.as_ref().map(|(p, _)| p))
+            .finish()
+    }
+}
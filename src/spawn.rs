@@ -0,0 +1,206 @@
+use core::borrow::Borrow;
+use std::panic;
+use std::thread::{self, JoinHandle};
+
+use {Invalidate, Memoize};
+
+/// A value computed on a background thread
+#[derive(Debug)]
+pub(crate) struct Job<T> {
+    handle: JoinHandle<T>,
+}
+
+impl<T: Send + 'static> Job<T> {
+    /// Start computing a value on a new thread
+    pub(crate) fn spawn<F>(compute: F) -> Self
+    where
+        F: FnOnce() -> T + Send + 'static,
+    {
+        Job {
+            handle: thread::spawn(compute),
+        }
+    }
+
+    /// Check if the value is ready, so that `join()` would not block
+    pub(crate) fn is_finished(&self) -> bool {
+        self.handle.is_finished()
+    }
+
+    /// Wait for the value
+    ///
+    /// If the computation panicked, the panic is resumed on this thread.
+    pub(crate) fn join(self) -> T {
+        match self.handle.join() {
+            Ok(value) => value,
+            Err(payload) => panic::resume_unwind(payload),
+        }
+    }
+}
+
+/// Memoized value which is computed on a worker thread
+///
+/// Requires the `std` feature.
+///
+/// Like `Memo`, this holds ownership over the parameter for the computation.
+/// But the value is never computed on the calling thread: it starts computing
+/// on a new thread, with a clone of the parameter, as soon as the memo is
+/// created, and every time it is invalidated. In the meantime, `poll()`
+/// returns `None`, so that the caller (like a UI thread) is never stalled by a
+/// slow computation. `wait()` blocks until the value is ready.
+///
+/// Modifying the parameter via `param_mut()` clears the value, and the new one
+/// starts computing on the next call to `poll()` or `wait()`. Use
+/// `update_param()` to start computing it right away.
+///
+/// ## Example
+///
+/// ```
+/// use core_memo::{Memoize, MemoSpawn};
+///
+/// struct Thumbnail(Vec<u8>);
+///
+/// impl Memoize for Thumbnail {
+///     type Param = String;
+///     fn memoize(path: &String) -> Self {
+///         // imagine this takes a while
+///         Thumbnail(path.bytes().rev().collect())
+///     }
+/// }
+///
+/// let mut memo: MemoSpawn<Thumbnail> = MemoSpawn::new("cat.png".into());
+///
+/// // draw a placeholder, until the thumbnail is ready
+/// let size = match memo.poll() {
+///     Some(thumbnail) => thumbnail.0.len(),
+///     None => 0,
+/// };
+/// # let _ = size;
+///
+/// assert_eq!(memo.wait().0, b"gnp.tac");
+/// assert!(memo.is_ready());
+/// ```
+///
+#[derive(Debug)]
+pub struct MemoSpawn<T, P = <T as Memoize>::Param> {
+    value: Option<T>,
+    param: P,
+    job: Option<Job<T>>,
+}
+
+impl<T, P> MemoSpawn<T, P> {
+    /// Check if the value has been computed
+    ///
+    /// A value which is computed, but has not been picked up by `poll()` or
+    /// `wait()` yet, does not count.
+    pub fn is_ready(&self) -> bool {
+        self.value.is_some()
+    }
+
+    /// Check if the value is being computed on a worker thread
+    pub fn is_computing(&self) -> bool {
+        self.job.is_some()
+    }
+
+    /// Get the value if it has been computed
+    ///
+    /// Unlike `poll()`, this does not pick up a value from the worker thread.
+    pub fn try_get(&self) -> Option<&T> {
+        self.value.as_ref()
+    }
+
+    /// Get a reference to the parameter used for the computation
+    pub fn param(&self) -> &P {
+        &self.param
+    }
+
+    /// Get a mutable reference to the parameter used for the computation
+    ///
+    /// This clears any cached value, and abandons a computation which is in
+    /// progress. The new one starts on the next call to `poll()` or `wait()`.
+    pub fn param_mut(&mut self) -> &mut P {
+        self.value = None;
+        self.job = None;
+        &mut self.param
+    }
+
+    /// Get the parameter back, dropping the memo
+    ///
+    /// A computation which is still in progress is detached, and its result is
+    /// discarded.
+    pub fn into_param(self) -> P {
+        self.param
+    }
+}
+
+impl<T, P> MemoSpawn<T, P>
+where
+    T: Memoize + Send + 'static,
+    P: Borrow<T::Param> + Clone + Send + 'static,
+{
+    /// Creates a new `MemoSpawn` instance, and starts computing the value
+    pub fn new(p: P) -> Self {
+        let mut memo = Self {
+            value: None,
+            param: p,
+            job: None,
+        };
+        memo.clear();
+        memo
+    }
+
+    /// Clears any cached value, and starts computing it again
+    ///
+    /// A computation which is already in progress is abandoned: it is left to
+    /// finish, and its result is discarded.
+    pub fn clear(&mut self) {
+        self.value = None;
+        let param = self.param.clone();
+        self.job = Some(Job::spawn(move || T::memoize(param.borrow())));
+    }
+
+    /// Modify the parameter via a closure, and start computing the value for
+    /// the new one
+    pub fn update_param<F: FnOnce(&mut P)>(&mut self, f: F) {
+        f(&mut self.param);
+        self.clear();
+    }
+
+    /// Get the value, if it has been computed
+    ///
+    /// This never blocks. If the worker thread has finished, its value is
+    /// picked up and cached. If the value has been cleared and is not being
+    /// computed, it starts computing.
+    ///
+    /// If the computation panicked, the panic is resumed here.
+    pub fn poll(&mut self) -> Option<&T> {
+        if self.job.as_ref().is_some_and(Job::is_finished) {
+            self.value = self.job.take().map(Job::join);
+        } else if self.value.is_none() && self.job.is_none() {
+            self.clear();
+        }
+        self.value.as_ref()
+    }
+
+    /// Get the value, blocking until it has been computed
+    ///
+    /// If the computation panicked, the panic is resumed here.
+    pub fn wait(&mut self) -> &T {
+        if self.value.is_none() && self.job.is_none() {
+            self.clear();
+        }
+        if let Some(job) = self.job.take() {
+            self.value = Some(job.join());
+        }
+        self.value.as_ref().unwrap()
+    }
+}
+
+impl<T, P> Invalidate for MemoSpawn<T, P>
+where
+    T: Memoize + Send + 'static,
+    P: Borrow<T::Param> + Clone + Send + 'static,
+{
+    fn invalidate(&mut self) {
+        self.clear()
+    }
+}
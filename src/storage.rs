@@ -0,0 +1,341 @@
+#[cfg(feature = "alloc")]
+use alloc::collections::{btree_map, BTreeMap};
+use core::borrow::Borrow;
+#[cfg(feature = "std")]
+use core::hash::BuildHasher;
+use core::hash::Hash;
+use core::{array, fmt, iter, mem, slice};
+#[cfg(feature = "std")]
+use std::collections::{hash_map, HashMap};
+
+use hash::stable_hash;
+
+/// Map from keys to values, used as the index of a keyed cache
+///
+/// Implement this to run `MemoCache` over a different kind of map. The cache
+/// keeps its own bookkeeping (recency, weights, tags, statistics), and only
+/// uses the storage to find its entries by key.
+///
+/// Implementations are provided for `FixedMap`, `BTreeMap` (with the `alloc`
+/// feature), and `HashMap` (with the `std` feature).
+///
+/// To look up entries by a borrowed form of the key (like `str` for `String`
+/// keys), the storage should also implement `Lookup` for that form. The
+/// `Lookup<K, V>` supertrait is for looking up entries by the key itself.
+pub trait Storage<K, V>: Lookup<K, V> {
+    /// Iterator over the entries of the storage
+    type Iter<'a>: Iterator<Item = (&'a K, &'a V)>
+    where
+        Self: 'a,
+        K: 'a,
+        V: 'a;
+
+    /// Get the number of entries
+    fn len(&self) -> usize;
+
+    /// Check if there are no entries
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Get the maximum number of entries, if the storage has a fixed size
+    ///
+    /// A cache never inserts more entries than this. It evicts entries instead.
+    fn capacity(&self) -> Option<usize> {
+        None
+    }
+
+    /// Insert an entry, returning the old value for the key, if any
+    fn insert(&mut self, key: K, value: V) -> Option<V>;
+
+    /// Iterate over the entries
+    fn iter(&self) -> Self::Iter<'_>;
+
+    /// Remove all entries
+    fn clear(&mut self);
+}
+
+/// Looking up entries in a `Storage`, by a (possibly borrowed) form of the key
+pub trait Lookup<Q: ?Sized, V> {
+    /// Get the value for a key
+    fn get(&self, key: &Q) -> Option<&V>;
+
+    /// Remove the entry for a key, returning its value
+    fn remove(&mut self, key: &Q) -> Option<V>;
+
+    /// Check if there is an entry for a key
+    fn contains_key(&self, key: &Q) -> bool {
+        self.get(key).is_some()
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<K: Ord, V> Storage<K, V> for BTreeMap<K, V> {
+    type Iter<'a>
+        = btree_map::Iter<'a, K, V>
+    where
+        K: 'a,
+        V: 'a;
+
+    fn len(&self) -> usize {
+        BTreeMap::len(self)
+    }
+
+    fn insert(&mut self, key: K, value: V) -> Option<V> {
+        BTreeMap::insert(self, key, value)
+    }
+
+    fn iter(&self) -> Self::Iter<'_> {
+        BTreeMap::iter(self)
+    }
+
+    fn clear(&mut self) {
+        BTreeMap::clear(self)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<K, Q, V> Lookup<Q, V> for BTreeMap<K, V>
+where
+    K: Ord + Borrow<Q>,
+    Q: Ord + ?Sized,
+{
+    fn get(&self, key: &Q) -> Option<&V> {
+        BTreeMap::get(self, key)
+    }
+
+    fn remove(&mut self, key: &Q) -> Option<V> {
+        BTreeMap::remove(self, key)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<K: Hash + Eq, V, S: BuildHasher> Storage<K, V> for HashMap<K, V, S> {
+    type Iter<'a>
+        = hash_map::Iter<'a, K, V>
+    where
+        K: 'a,
+        V: 'a,
+        S: 'a;
+
+    fn len(&self) -> usize {
+        HashMap::len(self)
+    }
+
+    fn insert(&mut self, key: K, value: V) -> Option<V> {
+        HashMap::insert(self, key, value)
+    }
+
+    fn iter(&self) -> Self::Iter<'_> {
+        HashMap::iter(self)
+    }
+
+    fn clear(&mut self) {
+        HashMap::clear(self)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<K, Q, V, S> Lookup<Q, V> for HashMap<K, V, S>
+where
+    K: Hash + Eq + Borrow<Q>,
+    Q: Hash + Eq + ?Sized,
+    S: BuildHasher,
+{
+    fn get(&self, key: &Q) -> Option<&V> {
+        HashMap::get(self, key)
+    }
+
+    fn remove(&mut self, key: &Q) -> Option<V> {
+        HashMap::remove(self, key)
+    }
+}
+
+/// Fixed-size hash map, which does not allocate
+///
+/// This is an open-addressing hash table with linear probing, with `N` slots
+/// stored inline. `N` must be a power of two. To keep the probe sequences
+/// short, it holds at most `3/4` of `N` entries. Use it as the `Storage` of a
+/// `MemoCache`, to bound the cache by the size of the table.
+///
+/// It only replaces the index of the cache, from the keys to the entries:
+/// `MemoCache` itself still requires `alloc`, for storing the entries, so this
+/// does not make a keyed cache usable without an allocator.
+///
+/// Keys are hashed with a fixed algorithm (64-bit FNV-1a), which is fast for
+/// small keys, but not resistant to collision attacks.
+///
+/// ## Example
+///
+/// ```
+/// use core_memo::{FixedMap, Lookup, Storage};
+///
+/// let mut map: FixedMap<u32, &str, 8> = FixedMap::new();
+/// assert_eq!(map.capacity(), Some(6));
+///
+/// map.insert(1, "one");
+/// map.insert(2, "two");
+/// assert_eq!(map.get(&1), Some(&"one"));
+///
+/// map.remove(&1);
+/// assert_eq!(map.get(&1), None);
+/// assert_eq!(map.len(), 1);
+/// ```
+///
+pub struct FixedMap<K, V, const N: usize> {
+    slots: [Option<(K, V)>; N],
+    len: usize,
+}
+
+/// Iterator over the entries of a `FixedMap`
+pub type FixedMapIter<'a, K, V> = iter::FilterMap<
+    slice::Iter<'a, Option<(K, V)>>,
+    fn(&'a Option<(K, V)>) -> Option<(&'a K, &'a V)>,
+>;
+
+impl<K, V, const N: usize> FixedMap<K, V, N> {
+    /// Fails to compile if `N` is not a power of two
+    const VALID: () = assert!(N.is_power_of_two(), "FixedMap size must be a power of two");
+
+    /// Creates a new empty `FixedMap` instance
+    pub fn new() -> Self {
+        #[allow(clippy::let_unit_value)]
+        let () = Self::VALID;
+        Self {
+            slots: array::from_fn(|_| None),
+            len: 0,
+        }
+    }
+}
+
+impl<K: Hash + Eq, V, const N: usize> FixedMap<K, V, N> {
+    /// Get the slot where the probe sequence for a key starts
+    fn home<Q: Hash + ?Sized>(key: &Q) -> usize {
+        stable_hash(key) as usize & (N - 1)
+    }
+
+    /// Find the slot holding a key
+    fn find<Q: Hash + Eq + ?Sized>(&self, key: &Q) -> Option<usize>
+    where
+        K: Borrow<Q>,
+    {
+        let mut i = Self::home(key);
+        for _ in 0..N {
+            match self.slots[i] {
+                Some((ref k, _)) if k.borrow() == key => return Some(i),
+                Some(_) => i = (i + 1) & (N - 1),
+                None => return None,
+            }
+        }
+        None
+    }
+
+    /// Empty a slot, moving later entries back to keep their probe sequences
+    /// unbroken
+    fn take(&mut self, mut i: usize) -> Option<(K, V)> {
+        let entry = self.slots[i].take();
+        self.len -= 1;
+
+        let mut j = i;
+        loop {
+            j = (j + 1) & (N - 1);
+            let home = match self.slots[j] {
+                Some((ref k, _)) => Self::home(k),
+                None => break,
+            };
+
+            // move the entry back if its home is not between the hole and it
+            if (j.wrapping_sub(home) & (N - 1)) >= (j.wrapping_sub(i) & (N - 1)) {
+                self.slots[i] = self.slots[j].take();
+                i = j;
+            }
+        }
+
+        entry
+    }
+}
+
+impl<K: fmt::Debug, V: fmt::Debug, const N: usize> fmt::Debug for FixedMap<K, V, N> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_map()
+            .entries(
+                self.slots
+                    .iter()
+                    .filter_map(|slot| slot.as_ref().map(|(k, v)| (k, v))),
+            )
+            .finish()
+    }
+}
+
+impl<K, V, const N: usize> Default for FixedMap<K, V, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: Hash + Eq, V, const N: usize> Storage<K, V> for FixedMap<K, V, N> {
+    type Iter<'a>
+        = FixedMapIter<'a, K, V>
+    where
+        K: 'a,
+        V: 'a;
+
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    fn capacity(&self) -> Option<usize> {
+        Some(N - N / 4)
+    }
+
+    /// Insert an entry, returning the old value for the key, if any
+    ///
+    /// ## Panics
+    ///
+    /// Panics if the map is full (holds `capacity()` entries).
+    fn insert(&mut self, key: K, value: V) -> Option<V> {
+        if let Some(i) = self.find(&key) {
+            let slot = self.slots[i].as_mut().unwrap();
+            return Some(mem::replace(&mut slot.1, value));
+        }
+
+        assert!(self.len < N - N / 4, "FixedMap is full");
+        let mut i = Self::home(&key);
+        while self.slots[i].is_some() {
+            i = (i + 1) & (N - 1);
+        }
+        self.slots[i] = Some((key, value));
+        self.len += 1;
+        None
+    }
+
+    fn iter(&self) -> Self::Iter<'_> {
+        fn entry<K, V>(slot: &Option<(K, V)>) -> Option<(&K, &V)> {
+            slot.as_ref().map(|(k, v)| (k, v))
+        }
+
+        self.slots.iter().filter_map(entry)
+    }
+
+    fn clear(&mut self) {
+        for slot in &mut self.slots {
+            *slot = None;
+        }
+        self.len = 0;
+    }
+}
+
+impl<K, Q, V, const N: usize> Lookup<Q, V> for FixedMap<K, V, N>
+where
+    K: Hash + Eq + Borrow<Q>,
+    Q: Hash + Eq + ?Sized,
+{
+    fn get(&self, key: &Q) -> Option<&V> {
+        self.find(key).map(|i| &self.slots[i].as_ref().unwrap().1)
+    }
+
+    fn remove(&mut self, key: &Q) -> Option<V> {
+        let i = self.find(key)?;
+        self.take(i).map(|(_, v)| v)
+    }
+}
@@ -0,0 +1,114 @@
+use core::fmt;
+use core::marker::PhantomData;
+use core::ops::Deref;
+
+use Memoize;
+
+/// Represents one of several computations of the same output type
+///
+/// `Memoize` allows one computation per output type. Implement this trait
+/// instead, once for every strategy (typically an empty marker struct, like
+/// `Exact` or `Approximate`), to have several of them. Memoize it by using
+/// `Using<T, S>` as the value type of any of the memoization types, which
+/// selects the strategy at the type level.
+///
+/// ## Example
+///
+/// ```
+/// use core_memo::{Memo, MemoizeWith, Using};
+///
+/// struct Exact;
+/// struct Approximate;
+///
+/// struct Root(f64);
+///
+/// impl MemoizeWith<Exact> for Root {
+///     type Param = f64;
+///     fn memoize_with(p: &f64) -> Self {
+///         Root(p.sqrt())
+///     }
+/// }
+///
+/// impl MemoizeWith<Approximate> for Root {
+///     type Param = f64;
+///     fn memoize_with(p: &f64) -> Self {
+///         // good enough near 1
+///         Root((1.0 + p) / 2.0)
+///     }
+/// }
+///
+/// let mut exact: Memo<Using<Root, Exact>> = Memo::new(1.21);
+/// let mut approx: Memo<Using<Root, Approximate>> = Memo::new(1.21);
+///
+/// assert!((exact.get().0 - 1.1).abs() < 1e-9);
+/// assert!((approx.get().0 - 1.105).abs() < 1e-9);
+/// ```
+///
+pub trait MemoizeWith<S>: Sized {
+    /// The input type for the computation
+    type Param: ?Sized;
+
+    /// The function to compute the value with this strategy
+    fn memoize_with(p: &Self::Param) -> Self;
+}
+
+/// A value computed with the strategy `S`
+///
+/// This implements `Memoize` for any `MemoizeWith<S>` type, and derefs to the
+/// value. See `MemoizeWith` for an example.
+pub struct Using<T, S> {
+    value: T,
+    strategy: PhantomData<fn() -> S>,
+}
+
+impl<T, S> Using<T, S> {
+    /// Get the value back
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+}
+
+impl<T: MemoizeWith<S>, S> Memoize for Using<T, S> {
+    type Param = T::Param;
+
+    fn memoize(p: &T::Param) -> Self {
+        Using {
+            value: T::memoize_with(p),
+            strategy: PhantomData,
+        }
+    }
+}
+
+impl<T, S> Deref for Using<T, S> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+impl<T: Clone, S> Clone for Using<T, S> {
+    fn clone(&self) -> Self {
+        Using {
+            value: self.value.clone(),
+            strategy: PhantomData,
+        }
+    }
+}
+
+impl<T: PartialEq, S> PartialEq for Using<T, S> {
+    fn eq(&self, other: &Self) -> bool {
+        self.value == other.value
+    }
+}
+
+impl<T: Eq, S> Eq for Using<T, S> {}
+
+impl<T: fmt::Debug, S> fmt::Debug for Using<T, S> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Using")
+            .field("value", &self.value)
+            .field("strategy", &core::any::type_name::<S>())
+            .finish()
+    }
+}
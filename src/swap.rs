@@ -0,0 +1,142 @@
+use alloc::sync::Arc;
+use arc_swap::ArcSwapOption;
+use core::borrow::Borrow;
+
+use {Invalidate, Memoize};
+
+/// Memoized value for read-mostly sharing between threads
+///
+/// Requires the `arc-swap` feature.
+///
+/// Like `Memo`, this holds ownership over the parameter for the computation.
+/// But the value is an `Arc`, published through an `ArcSwap`, so
+/// `get_shared()` only needs `&self`: readers get a clone of the `Arc`,
+/// without waiting for any lock. `refresh()` computes a new value and swaps
+/// it in atomically: readers keep using the old one until they ask again, and
+/// are never blocked by the computation.
+///
+/// This is for values which are read very often from many threads, and
+/// recomputed rarely. If several threads find the value missing at once, each
+/// of them computes it, and the first one to finish publishes it.
+///
+/// ## Example
+///
+/// ```
+/// use std::sync::Arc;
+/// use std::thread;
+/// use core_memo::{Memoize, MemoSwap};
+///
+/// struct Routes(Vec<String>);
+///
+/// impl Memoize for Routes {
+///     type Param = str;
+///     fn memoize(config: &str) -> Self {
+///         Routes(config.split(',').map(String::from).collect())
+///     }
+/// }
+///
+/// let routes: Arc<MemoSwap<Routes, String>> = Arc::new(MemoSwap::new("/a,/b".into()));
+///
+/// let reader = {
+///     let routes = routes.clone();
+///     thread::spawn(move || routes.get_shared().0.len())
+/// };
+///
+/// // recomputed and swapped in, without blocking the reader
+/// routes.refresh();
+/// assert_eq!(reader.join().unwrap(), 2);
+/// ```
+///
+#[derive(Debug)]
+pub struct MemoSwap<T, P = <T as Memoize>::Param> {
+    value: ArcSwapOption<T>,
+    param: P,
+}
+
+impl<T, P> MemoSwap<T, P> {
+    /// Creates a new `MemoSwap` instance
+    pub fn new(p: P) -> Self {
+        Self {
+            value: ArcSwapOption::empty(),
+            param: p,
+        }
+    }
+
+    /// Clears any cached value
+    ///
+    /// The value will be reevaluated the next time it is needed. Readers which
+    /// got the value before keep their `Arc`.
+    pub fn clear(&self) {
+        self.value.store(None);
+    }
+
+    /// Check if there is a cached value
+    pub fn is_ready(&self) -> bool {
+        self.value.load().is_some()
+    }
+
+    /// Get the value if it is available
+    ///
+    /// If there is a cached value, returns it. If the value needs to be
+    /// computed, returns `None`.
+    pub fn try_get(&self) -> Option<Arc<T>> {
+        self.value.load_full()
+    }
+
+    /// Get a reference to the parameter used for the computation
+    pub fn param(&self) -> &P {
+        &self.param
+    }
+
+    /// Get a mutable reference to the parameter used for the computation
+    ///
+    /// This clears any cached value.
+    pub fn param_mut(&mut self) -> &mut P {
+        self.clear();
+        &mut self.param
+    }
+
+    /// Get the parameter back, dropping the memo
+    pub fn into_param(self) -> P {
+        self.param
+    }
+}
+
+impl<T: Memoize, P: Borrow<T::Param>> MemoSwap<T, P> {
+    /// Get the value
+    ///
+    /// If the value has already been computed, this function returns the
+    /// cached value, without locking. If not, it is computed and published
+    /// for future use, unless another thread publishes a value first, which
+    /// is then returned instead.
+    pub fn get_shared(&self) -> Arc<T> {
+        if let Some(value) = self.try_get() {
+            return value;
+        }
+
+        let value = Arc::new(T::memoize(self.param.borrow()));
+        let previous = self
+            .value
+            .compare_and_swap(&None::<Arc<T>>, Some(value.clone()));
+        match *previous {
+            Some(ref winner) => winner.clone(),
+            None => value,
+        }
+    }
+
+    /// Compute a new value, and publish it
+    ///
+    /// Readers are not blocked while it is computed: until it is published,
+    /// they get the old value (if there is one). Returns the new value.
+    pub fn refresh(&self) -> Arc<T> {
+        let value = Arc::new(T::memoize(self.param.borrow()));
+        self.value.store(Some(value.clone()));
+        value
+    }
+}
+
+impl<T, P> Invalidate for MemoSwap<T, P> {
+    fn invalidate(&mut self) {
+        self.clear()
+    }
+}
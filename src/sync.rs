@@ -0,0 +1,70 @@
+// Synchronization primitives for the thread-safe memoization types.
+//
+// Everything that needs atomics or interior mutability shared across threads
+// goes through here, so that it can be compiled against `loom`'s instrumented
+// types with the `loom` feature, and model checked exhaustively, or against
+// `portable-atomic` with the `portable-atomic` feature, for targets without
+// native atomics.
+//
+// `UnsafeCell` follows `loom`'s API (closures over raw pointers), so that
+// `loom` can track every access to the contents.
+
+// not all of it is used by every combination of features
+#![allow(dead_code, unused_imports)]
+
+#[cfg(not(any(feature = "loom", feature = "portable-atomic")))]
+pub(crate) use core::sync::atomic::{AtomicBool, AtomicU8, AtomicUsize, Ordering};
+#[cfg(feature = "loom")]
+pub(crate) use loom::sync::atomic::{AtomicBool, AtomicU8, AtomicUsize, Ordering};
+// for targets without compare-and-swap instructions; `loom` takes precedence
+#[cfg(all(feature = "portable-atomic", not(feature = "loom")))]
+pub(crate) use portable_atomic::{AtomicBool, AtomicU8, AtomicUsize, Ordering};
+
+// Atomics outside of the thread-safe memoization types, like the flags of the
+// `diagnostics` feature. They are used outside of `loom::model`, so they are
+// never `loom`'s, but they do follow the `portable-atomic` feature.
+pub(crate) mod plain {
+    #[cfg(not(feature = "portable-atomic"))]
+    pub(crate) use core::sync::atomic::AtomicU8;
+    #[cfg(feature = "portable-atomic")]
+    pub(crate) use portable_atomic::AtomicU8;
+}
+
+#[cfg(feature = "loom")]
+pub(crate) use loom::cell::UnsafeCell;
+
+#[cfg(feature = "loom")]
+pub(crate) use loom::sync::{RwLock, RwLockReadGuard};
+#[cfg(all(feature = "std", not(feature = "loom")))]
+pub(crate) use std::sync::{RwLock, RwLockReadGuard};
+
+#[cfg(not(feature = "loom"))]
+pub(crate) use core::hint::spin_loop;
+#[cfg(feature = "loom")]
+pub(crate) use loom::hint::spin_loop;
+
+#[cfg(not(feature = "loom"))]
+#[derive(Debug)]
+pub(crate) struct UnsafeCell<T: ?Sized>(core::cell::UnsafeCell<T>);
+
+#[cfg(not(feature = "loom"))]
+impl<T> UnsafeCell<T> {
+    pub(crate) const fn new(data: T) -> Self {
+        UnsafeCell(core::cell::UnsafeCell::new(data))
+    }
+
+    pub(crate) fn into_inner(self) -> T {
+        self.0.into_inner()
+    }
+}
+
+#[cfg(not(feature = "loom"))]
+impl<T: ?Sized> UnsafeCell<T> {
+    pub(crate) fn with<R>(&self, f: impl FnOnce(*const T) -> R) -> R {
+        f(self.0.get())
+    }
+
+    pub(crate) fn with_mut<R>(&self, f: impl FnOnce(*mut T) -> R) -> R {
+        f(self.0.get())
+    }
+}
@@ -0,0 +1,320 @@
+use core::cell::Cell;
+use core::fmt::Debug;
+use core::ops::Deref;
+
+use {Invalidate, Memoize};
+
+/// Counts how many times something was called
+///
+/// Requires the `test-util` feature.
+///
+/// Use it as (part of) the parameter of a computation in your tests, and call
+/// `incr()` from `memoize()`, to check how many times the value was computed.
+/// To count the computations of an existing `Memoize` impl, use
+/// `CountingMemoize`.
+///
+/// ## Example
+///
+/// ```
+/// use core_memo::{CallTracker, Memoize, MemoExt};
+///
+/// struct Answer(u8);
+///
+/// impl Memoize for Answer {
+///     type Param = CallTracker;
+///     fn memoize(p: &CallTracker) -> Self {
+///         p.incr();
+///         Answer(42)
+///     }
+/// }
+///
+/// let track = CallTracker::new();
+/// let mut memo: MemoExt<Answer> = MemoExt::new();
+///
+/// memo.get(&track);
+/// memo.get(&track);
+/// assert_eq!(track.count(), 1);
+/// ```
+///
+#[derive(Debug, Default)]
+pub struct CallTracker {
+    count: Cell<usize>,
+}
+
+impl CallTracker {
+    /// Creates a new `CallTracker` instance, with a count of zero
+    pub fn new() -> Self {
+        Self {
+            count: Cell::new(0),
+        }
+    }
+
+    /// Get the number of calls so far
+    pub fn count(&self) -> usize {
+        self.count.get()
+    }
+
+    /// Count a call
+    pub fn incr(&self) {
+        self.count.set(self.count.get() + 1);
+    }
+
+    /// Set the count back to zero
+    pub fn reset(&self) {
+        self.count.set(0);
+    }
+}
+
+/// Parameter for `CountingMemoize`, which counts the computations
+///
+/// Requires the `test-util` feature.
+#[derive(Debug, Default)]
+pub struct Tracked<P: ?Sized> {
+    tracker: CallTracker,
+    param: P,
+}
+
+impl<P> Tracked<P> {
+    /// Creates a new `Tracked` instance, for a parameter
+    pub fn new(param: P) -> Self {
+        Self {
+            tracker: CallTracker::new(),
+            param,
+        }
+    }
+
+    /// Get the parameter back
+    pub fn into_inner(self) -> P {
+        self.param
+    }
+}
+
+impl<P: ?Sized> Tracked<P> {
+    /// Get the number of computations so far
+    pub fn count(&self) -> usize {
+        self.tracker.count()
+    }
+
+    /// Get the tracker which counts the computations
+    pub fn tracker(&self) -> &CallTracker {
+        &self.tracker
+    }
+
+    /// Get a reference to the parameter
+    pub fn param(&self) -> &P {
+        &self.param
+    }
+
+    /// Get a mutable reference to the parameter
+    pub fn param_mut(&mut self) -> &mut P {
+        &mut self.param
+    }
+}
+
+/// Wraps any computation, to count how many times it runs
+///
+/// Requires the `test-util` feature.
+///
+/// The computation is the same as for `T`, with the parameter wrapped in a
+/// `Tracked`, which counts the computations. The value can be accessed with
+/// `Deref`.
+///
+/// ## Example
+///
+/// ```
+/// use core_memo::{CountingMemoize, Memoize, Memo, Tracked};
+///
+/// struct Size {
+///     bytes: usize,
+/// }
+///
+/// impl Memoize for Size {
+///     type Param = String;
+///     fn memoize(p: &String) -> Self {
+///         Size { bytes: p.len() }
+///     }
+/// }
+///
+/// let mut memo: Memo<CountingMemoize<Size>> = Memo::new(Tracked::new("hello".into()));
+///
+/// assert_eq!(memo.get().bytes, 5);
+/// assert_eq!(memo.get().bytes, 5);
+/// assert_eq!(memo.param().count(), 1);
+///
+/// memo.param_mut().param_mut().push('!');
+/// assert_eq!(memo.get().bytes, 6);
+/// assert_eq!(memo.param().count(), 2);
+/// ```
+///
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct CountingMemoize<T>(pub T);
+
+impl<T> CountingMemoize<T> {
+    /// Get the value back
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> Deref for CountingMemoize<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T: Memoize> Memoize for CountingMemoize<T> {
+    type Param = Tracked<T::Param>;
+
+    const COST: u32 = T::COST;
+
+    fn memoize(p: &Tracked<T::Param>) -> Self {
+        p.tracker.incr();
+        CountingMemoize(T::memoize(&p.param))
+    }
+
+    fn cost(p: &Tracked<T::Param>) -> u32 {
+        T::cost(&p.param)
+    }
+}
+
+/// Checks that a memoization type and computation behave like a memo should
+///
+/// Requires the `test-util` feature.
+///
+/// This is for testing your own wrappers (anything that implements
+/// `Invalidate`) and `Memoize` impls. You provide the memo, a closure which
+/// gets its value, and a closure which returns how many times the value has
+/// been computed so far (typically from a counter in the computation or in
+/// the parameter). The checks panic, like `assert!`, if a law is broken:
+///
+///   - `check_idempotent()`: getting the value again returns an equal value,
+///     without computing it again.
+///
+///   - `check_one_compute_per_invalidation()`: after each invalidation, the
+///     value is computed exactly once, no matter how many times it is read.
+///
+///   - `check_clear_recomputes()`: getting the value after an invalidation
+///     computes it again.
+///
+/// `check_all()` runs all of them. To test many parameters, create a new
+/// `MemoizeLaws` for each of them.
+///
+/// ## Example
+///
+/// ```
+/// use std::cell::Cell;
+/// use core_memo::{Memoize, Memo, MemoizeLaws};
+///
+/// struct Doubled(u64);
+///
+/// impl Memoize for Doubled {
+///     type Param = (u64, Cell<usize>);
+///     fn memoize((x, calls): &(u64, Cell<usize>)) -> Self {
+///         calls.set(calls.get() + 1);
+///         Doubled(x * 2)
+///     }
+/// }
+///
+/// for x in 0..10 {
+///     let memo: Memo<Doubled> = Memo::new((x, Cell::new(0)));
+///     MemoizeLaws::new(memo, |m| m.get().0, |m| m.param().1.get()).check_all();
+/// }
+/// ```
+///
+pub struct MemoizeLaws<M, G, C> {
+    memo: M,
+    get: G,
+    computations: C,
+}
+
+impl<M, V, G, C> MemoizeLaws<M, G, C>
+where
+    M: Invalidate,
+    V: PartialEq + Debug,
+    G: FnMut(&mut M) -> V,
+    C: Fn(&M) -> usize,
+{
+    /// Creates a new `MemoizeLaws` instance
+    ///
+    /// `get` must return (a copy of) the value, computing it if needed.
+    /// `computations` must return how many times the value has been computed.
+    pub fn new(memo: M, get: G, computations: C) -> Self {
+        Self {
+            memo,
+            get,
+            computations,
+        }
+    }
+
+    /// Get the memo back
+    pub fn into_inner(self) -> M {
+        self.memo
+    }
+
+    fn get(&mut self) -> V {
+        (self.get)(&mut self.memo)
+    }
+
+    fn computations(&self) -> usize {
+        (self.computations)(&self.memo)
+    }
+
+    /// Check that getting the value twice returns equal values, and computes
+    /// it at most once
+    pub fn check_idempotent(&mut self) -> &mut Self {
+        let before = self.computations();
+        let first = self.get();
+        let second = self.get();
+        assert_eq!(first, second, "getting the value again changed it");
+        let computed = self.computations() - before;
+        assert!(
+            computed <= 1,
+            "getting the value twice computed it {} times",
+            computed
+        );
+        self
+    }
+
+    /// Check that the value is computed exactly once after an invalidation,
+    /// however many times it is read
+    pub fn check_one_compute_per_invalidation(&mut self) -> &mut Self {
+        for _ in 0..3 {
+            self.memo.invalidate();
+            let before = self.computations();
+            for _ in 0..3 {
+                self.get();
+            }
+            let computed = self.computations() - before;
+            assert_eq!(
+                computed, 1,
+                "the value was computed {} times after an invalidation",
+                computed
+            );
+        }
+        self
+    }
+
+    /// Check that getting the value after an invalidation computes it again,
+    /// and results in an equal value
+    pub fn check_clear_recomputes(&mut self) -> &mut Self {
+        let cached = self.get();
+        self.memo.invalidate();
+        let before = self.computations();
+        let recomputed = self.get();
+        assert!(
+            self.computations() > before,
+            "getting the value after an invalidation did not recompute it"
+        );
+        assert_eq!(cached, recomputed, "recomputing the value changed it");
+        self
+    }
+
+    /// Run all the checks
+    pub fn check_all(&mut self) -> &mut Self {
+        self.check_idempotent()
+            .check_one_compute_per_invalidation()
+            .check_clear_recomputes()
+    }
+}
@@ -1,31 +1,45 @@
+// the original tests compare `is_ready()` with booleans
+#![allow(clippy::bool_assert_comparison)]
+
+#[cfg(feature = "alloc")]
 use core::cell::Cell;
+use test_util::CallTracker;
 use Memoize;
 
 const MAGIC: i32 = -420;
 
-#[derive(Debug)]
-struct CallTracker {
-    count: Cell<usize>,
-}
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+struct TestOut(i32);
 
-impl CallTracker {
-    fn new() -> Self {
-        Self {
-            count: Cell::new(0),
-        }
+#[cfg(feature = "alloc")]
+impl ::MemSize for Square {
+    fn heap_size(&self) -> usize {
+        0
     }
+}
 
-    fn count(&self) -> usize {
-        self.count.get()
-    }
+#[cfg(feature = "alloc")]
+thread_local!(static SQUARES: Cell<usize> = const { Cell::new(0) });
+
+/// Counts its computations in `SQUARES`, for testing keyed caches
+#[cfg(feature = "alloc")]
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct Square(i64);
 
-    fn incr(&self) {
-        self.count.set(self.count.get() + 1);
+#[cfg(feature = "alloc")]
+impl Memoize for Square {
+    type Param = i64;
+    fn memoize(p: &i64) -> Self {
+        SQUARES.with(|c| c.set(c.get() + 1));
+        Square(p * p)
     }
 }
 
-#[derive(Debug, PartialEq, Eq, Clone, Copy)]
-struct TestOut(i32);
+#[cfg(feature = "alloc")]
+fn squares() -> usize {
+    SQUARES.with(|c| c.get())
+}
 
 impl Memoize for TestOut {
     type Param = CallTracker;
@@ -192,7 +206,66 @@ fn track_calls_mutation() {
     assert_eq!(memo.param().count(), 3);
 }
 
+#[test]
+fn ext_into_memo() {
+    use {Memo, MemoExt};
+
+    let track = CallTracker::new();
+    let mut ext: MemoExt<TestOut> = MemoExt::new();
+    ext.get(&track);
+
+    let mut memo: Memo<TestOut, &CallTracker> = ext.into_memo(&track);
+    assert!(memo.is_ready());
+    assert_eq!(memo.get(), &TestOut(MAGIC));
+    assert_eq!(track.count(), 1);
+
+    let memo: Memo<TestOut, &CallTracker> = MemoExt::new().into_memo(&track);
+    assert!(!memo.is_ready());
+}
+
+#[test]
+fn memo_mut() {
+    use {Invalidate, MemoMut};
+
+    let mut vals = [1, 2];
+    {
+        let mut memo: MemoMut<MemoSum> = MemoMut::new(&mut vals[..]);
+        assert_eq!(memo.get(), &MemoSum(3));
+
+        memo.param_mut()[0] = 3;
+        assert!(!memo.is_ready());
+        assert_eq!(memo.get(), &MemoSum(5));
+
+        memo.update_param(|p| p[1] = 4);
+        assert_eq!(memo.get(), &MemoSum(7));
+        assert_eq!(memo.param(), &[3, 4][..]);
+
+        memo.invalidate();
+        assert_eq!(memo.try_get(), None);
+    }
+    assert_eq!(vals, [3, 4]);
+}
+
+#[test]
+fn memo_into_ext() {
+    use {Memo, MemoExt};
+
+    let track = CallTracker::new();
+    let mut memo: Memo<TestOut, &CallTracker> = Memo::new(&track);
+    memo.get();
+
+    let (param, mut ext): (&CallTracker, MemoExt<TestOut>) = memo.into_ext();
+    assert!(ext.is_ready());
+    assert_eq!(ext.get(param), &TestOut(MAGIC));
+    assert_eq!(track.count(), 1);
+}
+
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(
+    feature = "rkyv",
+    derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)
+)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 struct MemoSum(i32);
 
 impl Memoize for MemoSum {
@@ -203,6 +276,12 @@ impl Memoize for MemoSum {
     }
 }
 
+impl ::MemSize for MemoSum {
+    fn heap_size(&self) -> usize {
+        0
+    }
+}
+
 #[test]
 fn sums() {
     use Memo;
@@ -220,3 +299,3542 @@ fn sums() {
 
     assert_eq!(memo.get(), &MemoSum(10));
 }
+
+#[cfg(feature = "alloc")]
+#[test]
+fn bus_invalidation() {
+    use {InvalidationBus, Memo};
+
+    let mut bus = InvalidationBus::new();
+    let mut a = bus.subscribe(Memo::<TestOut>::new(CallTracker::new()), vec![1, 2]);
+    let mut b = bus.subscribe(Memo::<TestOut>::new(CallTracker::new()), vec![2]);
+
+    a.sync(&bus).get();
+    b.sync(&bus).get();
+
+    assert!(!a.is_stale(&bus));
+    assert!(!b.is_stale(&bus));
+
+    bus.publish(1);
+
+    assert!(a.is_stale(&bus));
+    assert!(!b.is_stale(&bus));
+    assert!(!a.sync(&bus).is_ready());
+    assert!(b.sync(&bus).is_ready());
+
+    a.sync(&bus).get();
+    assert_eq!(a.memo().param().count(), 2);
+    assert!(!a.is_stale(&bus));
+
+    bus.publish(2);
+
+    assert!(!a.sync(&bus).is_ready());
+    assert!(!b.sync(&bus).is_ready());
+    assert_eq!(b.into_inner().param().count(), 1);
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn channel_invalidation() {
+    use std::sync::mpsc::channel;
+    use {Invalidate, Listener, Memo};
+
+    let (tx, rx) = channel();
+    let mut memo = Listener::with_filter(Memo::<TestOut>::new(CallTracker::new()), rx, |k| {
+        *k == "mine"
+    });
+
+    memo.sync().get();
+    assert!(memo.sync().is_ready());
+
+    tx.send("theirs").unwrap();
+    assert!(memo.sync().is_ready());
+
+    tx.send("mine").unwrap();
+    tx.send("mine").unwrap();
+    tx.send("theirs").unwrap();
+    assert!(!memo.sync().is_ready());
+
+    memo.sync().get();
+    assert_eq!(memo.memo().param().count(), 2);
+
+    drop(tx);
+    assert!(memo.sync().is_ready());
+
+    // a handler decides what a message means for the memo
+    let (tx, rx) = channel();
+    let mut memo = Listener::with_handler(
+        Memo::<TestOut>::new(CallTracker::new()),
+        rx,
+        |memo, calls: usize| {
+            if calls > memo.param().count() {
+                memo.invalidate();
+            }
+        },
+    );
+    memo.sync().get();
+    tx.send(1).unwrap();
+    assert!(memo.sync().is_ready());
+    tx.send(2).unwrap();
+    assert!(!memo.sync().is_ready());
+}
+
+#[test]
+fn scan_runs() {
+    use MemoScan;
+
+    let log: [&[i32]; 6] = [&[1, 2], &[1, 2], &[4], &[4], &[1, 2], &[1, 2]];
+    let mut scan: MemoScan<MemoSum, _> = MemoScan::new(log.iter().cloned());
+
+    assert_eq!(scan.current(), None);
+    assert_eq!(scan.param(), None);
+
+    assert_eq!(scan.step(), Some(&MemoSum(3)));
+    assert_eq!(scan.step(), Some(&MemoSum(3)));
+    assert_eq!(scan.computations(), 1);
+
+    assert_eq!(scan.step(), Some(&MemoSum(4)));
+    assert_eq!(scan.param(), Some(&[4][..]));
+    assert_eq!(scan.step(), Some(&MemoSum(4)));
+    assert_eq!(scan.computations(), 2);
+
+    assert_eq!(scan.step(), Some(&MemoSum(3)));
+    assert_eq!(scan.step(), Some(&MemoSum(3)));
+    assert_eq!(scan.computations(), 3);
+
+    assert_eq!(scan.step(), None);
+    assert_eq!(scan.current(), Some(&MemoSum(3)));
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn registry_access() {
+    use {Memo, Registry};
+
+    let mut registry = Registry::new();
+    let a = registry.insert(Memo::<TestOut>::new(CallTracker::new()));
+    let b = registry.insert(Memo::<MemoSum, _>::new(vec![1, 2]));
+
+    assert_eq!(registry.len(), 2);
+    assert_eq!(registry.dirty(), 2);
+
+    assert_eq!(registry.get_mut(b).unwrap().get(), &MemoSum(3));
+    assert_eq!(registry.dirty(), 1);
+    assert_eq!(registry.accesses(a), 0);
+    assert_eq!(registry.accesses(b), 1);
+
+    registry.ready_all();
+    assert_eq!(registry.dirty(), 0);
+    assert_eq!(registry.get(a).unwrap().param().count(), 1);
+    assert_eq!(registry.accesses(a), 0);
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn registry_idle() {
+    use std::time::Duration;
+    use {Memo, Registry};
+
+    let mut registry = Registry::new();
+    let handles: std::vec::Vec<_> = (0..4)
+        .map(|_| registry.insert(Memo::<TestOut>::new(CallTracker::new())))
+        .collect();
+
+    assert_eq!(registry.run_idle(Duration::from_secs(0)), 0);
+    assert_eq!(registry.dirty(), 4);
+
+    assert_eq!(registry.run_idle(Duration::from_secs(60)), 4);
+    assert_eq!(registry.dirty(), 0);
+
+    registry.get_mut(handles[2]).unwrap().clear();
+    assert_eq!(registry.run_idle(Duration::from_secs(60)), 1);
+    assert_eq!(registry.get(handles[2]).unwrap().param().count(), 2);
+}
+
+#[cfg(feature = "self-ref")]
+#[test]
+fn self_ref_words() {
+    use std::boxed::Box;
+    use std::rc::Rc;
+    use std::string::String;
+    use std::vec::Vec;
+    use {MemoSelfRef, MemoizeBorrowing};
+
+    struct Words;
+
+    impl MemoizeBorrowing for Words {
+        type Param = str;
+        type Output<'p> = Vec<&'p str>;
+
+        fn memoize(p: &str) -> Vec<&str> {
+            p.split(',').collect()
+        }
+
+        fn shorten<'a, 'p: 'a>(v: &'a Vec<&'p str>) -> &'a Vec<&'a str> {
+            v
+        }
+    }
+
+    let mut memo: MemoSelfRef<Words, String> = MemoSelfRef::new("a,b".into());
+    assert!(!memo.is_ready());
+    assert_eq!(memo.get(), &["a", "b"]);
+
+    // moving the memo must not invalidate the cached references
+    let mut moved = memo;
+    assert_eq!(moved.try_get(), Some(&vec!["a", "b"]));
+
+    moved.update_param(|p| p.push_str(",c"));
+    assert!(!moved.is_ready());
+    assert_eq!(moved.get(), &["a", "b", "c"]);
+    assert_eq!(moved.into_param(), "a,b,c");
+
+    let shared: Rc<str> = "x,y".into();
+    let mut memo: MemoSelfRef<Words, _> = MemoSelfRef::new(shared.clone());
+    assert_eq!(memo.get().len(), 2);
+    drop(shared);
+    assert_eq!(memo.get(), &["x", "y"]);
+
+    let mut memo: MemoSelfRef<Words, Box<str>> = MemoSelfRef::new("p,q".into());
+    assert_eq!(memo.get(), &["p", "q"]);
+    let moved = memo;
+    assert_eq!(moved.try_get(), Some(&vec!["p", "q"]));
+    assert_eq!(&*moved.into_param(), "p,q");
+}
+
+#[cfg(all(feature = "rkyv", feature = "alloc"))]
+#[test]
+fn rkyv_round_trip() {
+    use rkyv::rancor::Error;
+    use rkyv::Archived;
+    use std::vec::Vec;
+    use Memo;
+
+    let mut memo: Memo<MemoSum, _> = Memo::new(vec![1, 2, 3]);
+    memo.get();
+
+    let bytes = rkyv::to_bytes::<Error>(&memo).unwrap();
+    let archived = rkyv::access::<Archived<Memo<MemoSum, Vec<i32>>>, Error>(&bytes).unwrap();
+
+    assert_eq!(archived.try_get().map(|v| v.0.to_native()), Some(6));
+    assert_eq!(archived.param().len(), 3);
+
+    let restored: Memo<MemoSum, Vec<i32>> = rkyv::deserialize::<_, Error>(archived).unwrap();
+    assert_eq!(restored.try_get(), Some(&MemoSum(6)));
+    assert_eq!(restored.param(), &[1, 2, 3]);
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn persisted_checksum() {
+    use {Memo, Persisted};
+
+    let mut memo: Memo<MemoSum, _> = Memo::new(vec![1, 2]);
+    assert_eq!(memo.persist(), None);
+
+    memo.get();
+    let saved = memo.persist().unwrap();
+    assert!(saved.is_valid_for(&[1, 2][..]));
+    assert!(!saved.is_valid_for(&[2, 1][..]));
+    assert_eq!(saved.clone().restore(&[1, 2][..]), Some(&MemoSum(3)));
+    assert_eq!(saved.restore(&[1][..]), None);
+
+    let restored: Memo<MemoSum, _> =
+        Memo::with_persisted(vec![1, 2], Persisted::new(MemoSum(3), &[1, 2][..]));
+    assert_eq!(restored.try_get(), Some(&MemoSum(3)));
+
+    let mut stale: Memo<MemoSum, _> =
+        Memo::with_persisted(vec![5, 5], Persisted::new(MemoSum(3), &[1, 2][..]));
+    assert!(!stale.is_ready());
+    assert_eq!(stale.get(), &MemoSum(10));
+}
+
+#[cfg(feature = "disk")]
+#[test]
+fn disk_cache() {
+    use std::{env, fs, process};
+    use DiskCache;
+
+    let dir = env::temp_dir().join(format!("core_memo-disk_cache-{}", process::id()));
+    let cache: DiskCache<MemoSum> = DiskCache::new(&dir).unwrap();
+    let param = [1, 2, 3];
+
+    assert!(!cache.contains(&param));
+    assert_eq!(cache.try_get(&param), None);
+
+    assert_eq!(cache.get(&param).unwrap(), MemoSum(6));
+    assert!(cache.contains(&param));
+    assert_eq!(cache.try_get(&param), Some(MemoSum(6)));
+
+    // a corrupted file is treated as a miss
+    fs::write(cache.path(&param), b"garbage").unwrap();
+    assert_eq!(cache.try_get(&param), None);
+    assert_eq!(cache.get(&param).unwrap(), MemoSum(6));
+    assert_eq!(cache.try_get(&param), Some(MemoSum(6)));
+
+    cache.remove(&param).unwrap();
+    assert!(!cache.contains(&param));
+    cache.remove(&param).unwrap();
+
+    cache.get(&[4]).unwrap();
+    cache.get(&[5]).unwrap();
+
+    // leftovers of failed writes are cleared, other files are not
+    let tmp = cache.path(&[6]).with_extension("bin.1.2.tmp");
+    fs::write(&tmp, b"partial").unwrap();
+    fs::write(dir.join("notes.tmp"), b"mine").unwrap();
+
+    cache.clear().unwrap();
+    assert!(!cache.contains(&[4]));
+    assert!(!cache.contains(&[5]));
+    assert!(!tmp.exists());
+    assert!(dir.join("notes.tmp").exists());
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[cfg(feature = "mmap")]
+#[test]
+fn disk_cache_mapped() {
+    use std::string::String;
+    use std::vec::Vec;
+    use std::{env, fs, process};
+    use {DiskCache, Mappable};
+
+    struct Blob<S = Vec<u8>>(S);
+
+    impl<S: AsRef<[u8]>> AsRef<[u8]> for Blob<S> {
+        fn as_ref(&self) -> &[u8] {
+            self.0.as_ref()
+        }
+    }
+
+    impl Memoize for Blob {
+        type Param = String;
+        fn memoize(p: &String) -> Self {
+            Blob(p.bytes().rev().collect())
+        }
+    }
+
+    impl Mappable for Blob {
+        type Mapped<S: AsRef<[u8]>> = Blob<S>;
+
+        fn from_storage<S: AsRef<[u8]>>(bytes: S) -> Blob<S> {
+            Blob(bytes)
+        }
+    }
+
+    let dir = env::temp_dir().join(format!("core_memo-disk_mapped-{}", process::id()));
+    let cache: DiskCache<Blob> = DiskCache::new(&dir).unwrap();
+    let param = String::from("hello");
+
+    unsafe {
+        assert!(cache.try_get_mapped(&param).is_none());
+        assert_eq!(cache.get_mapped(&param).unwrap().as_ref(), b"olleh");
+        assert_eq!(cache.try_get_mapped(&param).unwrap().as_ref(), b"olleh");
+
+        // a file with the wrong header is treated as a miss
+        fs::write(cache.mapped_path(&param), b"garbage").unwrap();
+        assert!(cache.try_get_mapped(&param).is_none());
+        assert_eq!(cache.get_mapped(&param).unwrap().as_ref(), b"olleh");
+
+        let empty = String::new();
+        assert_eq!(cache.get_mapped(&empty).unwrap().as_ref(), b"");
+    }
+
+    // both kinds of files are removed
+    fs::write(cache.path(&param), b"garbage").unwrap();
+    cache.remove(&param).unwrap();
+    assert!(!cache.contains(&param));
+    assert!(!cache.mapped_path(&param).exists());
+    cache.remove(&param).unwrap();
+
+    cache.clear().unwrap();
+    assert!(!cache.mapped_path(&param).exists());
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn cache_unbounded() {
+    use {CacheError, MemoCache};
+
+    let mut cache: MemoCache<Square> = MemoCache::new();
+
+    assert_eq!(cache.capacity(), None);
+    assert!(cache.is_empty());
+    assert_eq!(cache.try_get(&3), None);
+    assert_eq!(cache.try_get_checked(&3), Err(CacheError::NotReady));
+    assert_eq!(cache.try_reserve(8), Ok(()));
+    assert_eq!(cache.try_reserve(usize::MAX), Err(CacheError::AllocFailed));
+
+    assert_eq!(cache.get(&3), &Square(9));
+    assert_eq!(cache.get(&3), &Square(9));
+    assert_eq!(cache.get(&4), &Square(16));
+    assert_eq!(squares(), 2);
+    assert_eq!(cache.len(), 2);
+
+    assert_eq!(cache.try_get(&4), Some(&Square(16)));
+    assert_eq!(cache.remove(&4), Some(Square(16)));
+    assert_eq!(cache.remove(&4), None);
+    assert!(!cache.is_ready(&4));
+
+    assert_eq!(cache.update(&3), &Square(9));
+    assert_eq!(squares(), 3);
+
+    cache.ready(&5);
+    cache.ready(&5);
+    assert_eq!(squares(), 4);
+
+    cache.clear();
+    assert!(cache.is_empty());
+    assert_eq!(cache.get(&3), &Square(9));
+    assert_eq!(squares(), 5);
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn cache_lru() {
+    use MemoCache;
+
+    let mut cache: MemoCache<Square> = MemoCache::bounded(3);
+
+    for i in 0..3 {
+        cache.get(&i);
+    }
+    assert_eq!(cache.len(), 3);
+
+    // make 0 the most recently used, so 1 is evicted next
+    cache.get(&0);
+    cache.get(&3);
+    assert!(cache.is_ready(&0));
+    assert!(!cache.is_ready(&1));
+    assert_eq!(cache.len(), 3);
+
+    // `try_get` does not affect recency, so 2 is evicted next
+    cache.try_get(&2);
+    cache.get(&4);
+    assert!(!cache.is_ready(&2));
+
+    // removed slots are reused
+    cache.remove(&0);
+    cache.get(&5);
+    cache.get(&6);
+    assert_eq!(cache.len(), 3);
+    assert!(cache.is_ready(&4));
+    assert!(cache.is_ready(&5));
+    assert!(cache.is_ready(&6));
+    assert!(!cache.is_ready(&3));
+    assert_eq!(squares(), 7);
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn cache_ordered() {
+    use MemoCache;
+
+    let mut cache: MemoCache<Square> = MemoCache::new();
+
+    for i in [5, 1, 4, 2, 3].iter() {
+        cache.get(i);
+    }
+
+    let keys: std::vec::Vec<i64> = cache.iter().map(|(k, _)| *k).collect();
+    assert_eq!(keys, [1, 2, 3, 4, 5]);
+
+    let values: std::vec::Vec<i64> = cache.range(2..4).map(|(_, v)| v.0).collect();
+    assert_eq!(values, [4, 9]);
+
+    assert_eq!(cache.invalidate_range(2..=4), 3);
+    let keys: std::vec::Vec<i64> = cache.iter().rev().map(|(k, _)| *k).collect();
+    assert_eq!(keys, [5, 1]);
+
+    // the removed entries are recomputed
+    cache.get(&3);
+    assert_eq!(squares(), 6);
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn cache_retain() {
+    use MemoCache;
+
+    let mut cache: MemoCache<Square> = MemoCache::bounded(8);
+
+    for i in 0..8 {
+        cache.get(&i);
+    }
+
+    cache.retain(|k, _| k % 2 == 0);
+    assert_eq!(cache.len(), 4);
+    assert!(cache.is_ready(&6));
+    assert!(!cache.is_ready(&7));
+
+    assert_eq!(cache.invalidate_where(|_, v| v.0 > 10), 2);
+    assert!(cache.is_ready(&2));
+    assert!(!cache.is_ready(&4));
+
+    // the freed slots are reused without evicting anything
+    for i in 10..16 {
+        cache.get(&i);
+    }
+    assert_eq!(cache.len(), 8);
+    assert!(cache.is_ready(&0));
+    assert_eq!(squares(), 14);
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn cache_prefix() {
+    use std::string::String;
+    use MemoCache;
+
+    struct Len(usize);
+
+    impl Memoize for Len {
+        type Param = str;
+        fn memoize(p: &str) -> Self {
+            Len(p.len())
+        }
+    }
+
+    let mut cache: MemoCache<Len, String> = MemoCache::new();
+
+    for path in [
+        "assets/sounds/boom.ogg",
+        "assets/textures",
+        "assets/textures/grass.png",
+        "assets/textures/stone.png",
+        "assets/texturesque.png",
+    ]
+    .iter()
+    {
+        cache.get(path);
+    }
+
+    assert_eq!(cache.invalidate_prefix("assets/textures/"), 2);
+    assert_eq!(cache.len(), 3);
+    assert!(cache.is_ready("assets/textures"));
+    assert_eq!(cache.try_get("assets/texturesque.png").unwrap().0, 22);
+
+    assert_eq!(cache.invalidate_prefix("models/"), 0);
+    assert_eq!(cache.invalidate_prefix(""), 3);
+    assert!(cache.is_empty());
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn cache_tags() {
+    use MemoCache;
+
+    let mut cache: MemoCache<Square> = MemoCache::new();
+
+    cache.get_tagged(&1, vec!["odd"]);
+    cache.get_tagged(&2, vec!["even", "prime"]);
+    cache.get_tagged(&3, vec!["odd", "prime"]);
+    cache.get(&4);
+    assert!(cache.tag(&4, "even"));
+    assert!(!cache.tag(&5, "odd"));
+    assert_eq!(cache.tags(&3), ["odd", "prime"]);
+
+    assert_eq!(cache.invalidate_tag(&"prime"), 2);
+    assert!(cache.is_ready(&1));
+    assert!(!cache.is_ready(&2));
+    assert!(!cache.is_ready(&3));
+    assert!(cache.is_ready(&4));
+
+    // the removed entries no longer count towards their other tags
+    assert_eq!(cache.invalidate_tag(&"odd"), 1);
+    assert_eq!(cache.invalidate_tag(&"prime"), 0);
+
+    // tags are not inherited by a reused slot
+    cache.get(&6);
+    assert!(cache.tags(&6).is_empty());
+    assert_eq!(cache.invalidate_tag(&"even"), 1);
+    assert!(cache.is_ready(&6));
+    assert_eq!(squares(), 5);
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn cache_weighted() {
+    use MemoCache;
+
+    let mut cache: MemoCache<Square> = MemoCache::weighted(20, |s| s.0 as usize);
+    assert_eq!(cache.max_weight(), Some(20));
+
+    cache.get(&1);
+    cache.get(&2);
+    cache.get(&3);
+    assert_eq!(cache.weight(), 14);
+
+    // 1 and 2 have to go to make room for 16
+    cache.get(&4);
+    assert_eq!(cache.len(), 1);
+    assert_eq!(cache.weight(), 16);
+    assert!(cache.is_ready(&4));
+
+    // an oversized value is still cached, on its own
+    cache.get(&5);
+    assert_eq!(cache.len(), 1);
+    assert_eq!(cache.weight(), 25);
+
+    cache.remove(&5);
+    assert_eq!(cache.weight(), 0);
+    assert_eq!(squares(), 5);
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn cache_cost() {
+    use MemoCache;
+
+    /// Negative numbers are expensive
+    struct Costly(i64);
+
+    impl Memoize for Costly {
+        type Param = i64;
+        fn memoize(p: &i64) -> Self {
+            Costly(*p)
+        }
+        fn cost(p: &i64) -> u32 {
+            if *p < 0 {
+                4
+            } else {
+                1
+            }
+        }
+    }
+
+    let mut cache: MemoCache<Costly> = MemoCache::bounded(2);
+    assert_eq!(cache.get(&-1).0, -1);
+    cache.get(&1);
+
+    // -1 is spared twice, even though it is the least recently used
+    cache.get(&2);
+    assert!(cache.is_ready(&-1));
+    assert!(!cache.is_ready(&1));
+    cache.get(&3);
+    assert!(cache.is_ready(&-1));
+    assert!(!cache.is_ready(&2));
+
+    cache.get(&4);
+    assert!(!cache.is_ready(&-1));
+    assert!(cache.is_ready(&3));
+
+    // entries which are inserted are spared as well
+    let mut cache: MemoCache<Costly> = MemoCache::bounded(2);
+    cache.entry(-1).or_insert_with(|| Costly(-1));
+    cache.extend(vec![(1, Costly(1)), (2, Costly(2))]);
+    assert!(cache.is_ready(&-1));
+    assert!(!cache.is_ready(&1));
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn cache_min_cost() {
+    use std::time::Duration;
+    use MemoCache;
+
+    // cheap enough, so nothing is cached
+    let mut cache: MemoCache<Square> = MemoCache::new().with_min_cost(Duration::from_secs(60));
+    assert!(!cache.is_bypassed());
+    assert_eq!(cache.get(&3).0, 9);
+    assert_eq!(cache.get(&3).0, 9);
+    assert!(cache.is_bypassed());
+    assert!(cache.is_empty());
+    assert_eq!(squares(), 2);
+
+    // too expensive, so caching goes on
+    let mut cache: MemoCache<Square> = MemoCache::new().with_min_cost(Duration::ZERO);
+    cache.get(&3);
+    cache.get(&3);
+    assert!(!cache.is_bypassed());
+    assert_eq!(cache.len(), 1);
+    assert_eq!(squares(), 3);
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn cache_ttl() {
+    use std::thread;
+    use std::time::Duration;
+    use {CacheError, MemoCache};
+
+    let mut cache: MemoCache<Square> = MemoCache::new().with_ttl(Duration::from_millis(50));
+    assert_eq!(cache.ttl(), Some(Duration::from_millis(50)));
+    cache.get(&2);
+    cache.get(&3);
+    assert!(cache.tag(&3, "three"));
+    assert_eq!(cache.gc(), 0);
+    assert_eq!(squares(), 2);
+
+    thread::sleep(Duration::from_millis(60));
+    cache.get(&4);
+
+    // expired, but still in memory until looked up or swept
+    assert!(!cache.is_ready(&2));
+    assert!(cache.try_get(&3).is_none());
+    assert!(cache.tags(&3).is_empty());
+    assert_eq!(cache.len(), 3);
+
+    assert_eq!(cache.try_get_checked(&3).unwrap_err(), CacheError::Expired);
+    assert_eq!(cache.try_get_checked(&9).unwrap_err(), CacheError::NotReady);
+    assert_eq!(cache.try_get_checked(&4).unwrap().0, 16);
+
+    // an expired entry can not be tagged or removed
+    assert!(!cache.tag(&2, "two"));
+    assert_eq!(cache.len(), 2);
+    assert!(cache.remove(&2).is_none());
+
+    // a lookup recomputes the value, with a fresh time-to-live
+    assert_eq!(cache.get(&2).0, 4);
+    assert_eq!(squares(), 4);
+    assert!(cache.is_ready(&2));
+
+    // only 3 has expired, and nothing else looks it up
+    assert_eq!(cache.gc(), 1);
+    assert_eq!(cache.len(), 2);
+    assert!(cache.is_ready(&4));
+
+    // without a time-to-live, nothing expires
+    let mut cache: MemoCache<Square> = MemoCache::new();
+    cache.get(&5);
+    assert_eq!(cache.gc(), 0);
+    assert!(cache.is_ready(&5));
+}
+
+#[cfg(feature = "rayon")]
+#[test]
+fn cache_warm_par() {
+    use core::sync::atomic::{AtomicUsize, Ordering};
+    use std::collections::HashMap;
+    use {FixedMap, MemoCache};
+
+    static COMPUTED: AtomicUsize = AtomicUsize::new(0);
+
+    struct Triple(u32);
+
+    impl Memoize for Triple {
+        type Param = u32;
+        fn memoize(p: &u32) -> Self {
+            COMPUTED.fetch_add(1, Ordering::Relaxed);
+            Triple(p * 3)
+        }
+    }
+
+    let mut cache: MemoCache<Triple> = MemoCache::bounded(100);
+    cache.get(&0);
+    assert_eq!(cache.warm_par((0..50).chain(0..50)), 49);
+    assert_eq!(COMPUTED.load(Ordering::Relaxed), 50);
+    assert_eq!(cache.len(), 50);
+    assert!((0..50).all(|p| cache.try_get(&p).unwrap().0 == p * 3));
+
+    // the keys count as used in order, so the first ones are evicted first
+    let mut cache: MemoCache<Triple> = MemoCache::bounded(2);
+    assert_eq!(cache.warm_par(vec![1, 2, 3]), 3);
+    assert!(!cache.is_ready(&1));
+    assert!(cache.is_ready(&2) && cache.is_ready(&3));
+
+    // keys which don't implement `Ord` are deduplicated too
+    let mut cache: MemoCache<Triple, u32, &str, HashMap<u32, usize>> = MemoCache::new();
+    assert_eq!(cache.warm_par(vec![1, 2, 1]), 2);
+
+    // with a fixed-size map, in batches which fit
+    let mut cache: MemoCache<Triple, u32, &str, FixedMap<u32, usize, 8>> = MemoCache::new();
+    assert_eq!(cache.warm_par(vec![1, 2, 3, 4, 5, 6, 7, 8, 9, 9]), 9);
+    assert_eq!(cache.len(), 6);
+    assert!(cache.is_ready(&9));
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn cache_ttl_misses() {
+    use std::thread;
+    use std::time::Duration;
+    use MemoCache;
+
+    let mut cache: MemoCache<Square> = MemoCache::new()
+        .with_ttl(Duration::from_millis(50))
+        .with_negative_filter(10, |s| s.0 % 2 == 0);
+    cache.get(&2);
+    cache.get(&4);
+    assert!(cache.is_known_miss(&2));
+
+    // known misses expire like entries, and are computed again
+    thread::sleep(Duration::from_millis(60));
+    assert!(!cache.is_known_miss(&2));
+    assert_eq!(cache.get(&2).0, 4);
+    assert!(cache.is_known_miss(&2));
+    assert_eq!(squares(), 3);
+
+    // only 4 has expired, and nothing else looks it up
+    assert_eq!(cache.gc(), 1);
+    assert_eq!(cache.gc(), 0);
+    assert!(cache.is_known_miss(&2));
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn cache_adaptive() {
+    use std::thread;
+    use std::time::Duration;
+    use MemoCache;
+
+    /// Takes as many microseconds as its parameter
+    struct Sleep(u64);
+
+    impl Memoize for Sleep {
+        type Param = u64;
+        fn memoize(p: &u64) -> Self {
+            thread::sleep(Duration::from_micros(*p));
+            Sleep(*p)
+        }
+    }
+
+    let mut cache: MemoCache<Sleep> = MemoCache::new().with_adaptive(Duration::from_micros(100));
+
+    // cheap and never repeated, so caching does not pay off
+    for p in 0..64 {
+        cache.get(&p);
+    }
+    assert!(cache.is_bypassed());
+    assert_eq!(cache.get(&1000).0, 1000);
+    assert!(!cache.is_ready(&1000));
+
+    // expensive and repeated, so caching is turned back on
+    for _ in 0..63 {
+        cache.get(&1000);
+    }
+    assert!(!cache.is_bypassed());
+    cache.get(&1000);
+    assert!(cache.is_ready(&1000));
+    assert_eq!(cache.len(), 64);
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn cache_entry() {
+    use MemoCache;
+
+    let mut cache: MemoCache<Square> = MemoCache::bounded(2);
+
+    assert!(!cache.entry(1).is_occupied());
+    assert_eq!(cache.entry(1).or_memoize().0, 1);
+    assert_eq!(cache.entry(1).and_modify(|s| s.0 += 1).or_memoize().0, 2);
+
+    // a vacant entry is not modified
+    let v = cache.entry(2).and_modify(|s| s.0 = 0).or_insert(Square(-4));
+    assert_eq!(v.0, -4);
+
+    assert_eq!(cache.entry(3).or_insert_with(|| Square(9)).0, 9);
+    assert!(!cache.is_ready(&1));
+    assert_eq!(*cache.entry(2).key(), 2);
+    assert_eq!(squares(), 1);
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn cache_inspect() {
+    use std::vec::Vec;
+    use MemoCache;
+
+    let mut cache: MemoCache<Square> = MemoCache::new();
+    assert_eq!(cache.keys_by_recency().count(), 0);
+
+    for i in [3, 1, 2].iter() {
+        cache.get(i);
+    }
+    cache.get(&3);
+
+    assert_eq!(cache.keys().cloned().collect::<Vec<_>>(), [1, 2, 3]);
+    assert_eq!(cache.values().map(|s| s.0).collect::<Vec<_>>(), [1, 4, 9]);
+    assert_eq!(
+        cache.keys_by_recency().cloned().collect::<Vec<_>>(),
+        [3, 2, 1]
+    );
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn cache_warm() {
+    use std::vec::Vec;
+    use MemoCache;
+
+    let mut cache: MemoCache<Square> = MemoCache::bounded(3);
+    cache.get(&2);
+
+    assert_eq!(cache.warm(vec![1, 2, 3, 4]), 3);
+    assert_eq!(squares(), 4);
+    assert!(!cache.is_ready(&1));
+
+    // hot keys can be carried over to a new cache
+    let hot: Vec<i64> = cache.keys_by_recency().cloned().collect();
+    let mut fresh: MemoCache<Square> = MemoCache::bounded(3);
+    fresh.warm(hot.into_iter().rev());
+    assert_eq!(
+        fresh.keys_by_recency().collect::<Vec<_>>(),
+        cache.keys_by_recency().collect::<Vec<_>>(),
+    );
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn cache_get_many() {
+    use std::vec::Vec;
+    use MemoCache;
+
+    let mut cache: MemoCache<Square> = MemoCache::bounded(2);
+    cache.get(&2);
+
+    let values: Vec<i64> = cache.get_many(&[3, 2, 1, 3]).iter().map(|s| s.0).collect();
+    assert_eq!(values, [9, 4, 1, 9]);
+    assert_eq!(squares(), 3);
+
+    // 1 didn't fit with the rest of the batch, so it was not cached
+    assert_eq!(cache.len(), 2);
+    assert!(!cache.is_ready(&1));
+    cache.get(&4);
+    assert!(cache.is_ready(&3));
+    assert!(!cache.is_ready(&2));
+
+    // misses are recorded, like with `get()`
+    let mut cache: MemoCache<Square> = MemoCache::new().with_negative_filter(10, |s| s.0 % 2 == 0);
+    cache.get_many(&[1, 2]);
+    assert!(cache.is_known_miss(&2));
+    assert_eq!(cache.len(), 1);
+}
+
+#[cfg(feature = "rayon")]
+#[test]
+fn cache_get_many_par() {
+    use std::vec::Vec;
+    use MemoCache;
+
+    let mut cache: MemoCache<Square> = MemoCache::bounded(2);
+    cache.get(&2);
+
+    // computed on other threads, so they are not counted by `squares()`
+    let values: Vec<i64> = cache
+        .get_many_par(&[3, 2, 1, 3])
+        .iter()
+        .map(|s| s.0)
+        .collect();
+    assert_eq!(values, [9, 4, 1, 9]);
+
+    assert_eq!(cache.len(), 2);
+    assert!(!cache.is_ready(&1));
+    cache.get(&4);
+    assert!(cache.is_ready(&3));
+    assert!(!cache.is_ready(&2));
+}
+
+#[cfg(feature = "stats")]
+#[test]
+fn cache_stats() {
+    use MemoCache;
+
+    let mut cache: MemoCache<Square> = MemoCache::new();
+
+    for &i in [1, 2, 3, 2, 3, 2, 4].iter() {
+        cache.get(&i);
+    }
+
+    assert_eq!(cache.stats_for(&2).unwrap().hits(), 2);
+    assert_eq!(cache.stats_for(&1).unwrap().hits(), 0);
+    assert!(cache.stats_for(&5).is_none());
+
+    let (last2, last3) = (
+        cache.stats_for(&2).unwrap().last_access(),
+        cache.stats_for(&3).unwrap().last_access(),
+    );
+    assert!(last2 > last3);
+
+    assert_eq!(cache.hottest(2), [&2, &3]);
+    // 1 and 4 have no hits, but 1 was accessed longer ago
+    assert_eq!(cache.coldest(3), [&1, &4, &3]);
+    assert_eq!(cache.hottest(10).len(), 4);
+
+    // `try_get` doesn't count as a hit
+    cache.try_get(&4);
+    assert_eq!(cache.stats_for(&4).unwrap().hits(), 0);
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn cache_admission() {
+    use MemoCache;
+
+    let mut cache: MemoCache<Square> = MemoCache::bounded(3).with_admission();
+
+    for _ in 0..4 {
+        for i in 0..3 {
+            cache.get(&i);
+        }
+    }
+
+    // a scan over one-off keys doesn't evict the hot entries
+    for i in 100..200 {
+        assert_eq!(cache.get(&i).0, i * i);
+    }
+    for i in 0..3 {
+        assert!(cache.is_ready(&i));
+    }
+    assert_eq!(cache.len(), 3);
+
+    // a key which becomes popular is admitted once it is used more often than
+    // the least recently used entry (4 times)
+    for _ in 0..4 {
+        cache.get(&7);
+    }
+    assert!(!cache.is_ready(&7));
+    cache.get(&7);
+    assert!(cache.is_ready(&7));
+    assert_eq!(squares(), 3 + 100 + 5);
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn cache_negative() {
+    use MemoCache;
+
+    let mut cache: MemoCache<Square> =
+        MemoCache::bounded(4).with_negative_filter(100, |s| s.0 % 2 == 0);
+
+    for i in 0..10 {
+        cache.get(&i);
+    }
+    assert_eq!(cache.len(), 4);
+    assert!(cache.is_known_miss(&4));
+    assert!(!cache.is_known_miss(&5));
+    assert_eq!(squares(), 10);
+
+    // known misses are not computed again, and give their own value
+    assert_eq!(cache.get(&2).0, 4);
+    assert_eq!(cache.get(&8).0, 64);
+    assert_eq!(squares(), 10);
+
+    // keys which are not known misses are always computed
+    for i in 10..200 {
+        assert_eq!(cache.get(&(2 * i + 1)).0, (2 * i + 1) * (2 * i + 1));
+    }
+    assert_eq!(squares(), 200);
+
+    // known misses are invalidated like entries
+    assert_eq!(cache.remove(&2).map(|s| s.0), Some(4));
+    assert!(!cache.is_known_miss(&2));
+    assert_eq!(cache.invalidate_where(|_, s| s.0 == 16), 1);
+    assert_eq!(cache.invalidate_range(6..=8), 2);
+    assert!(cache.is_known_miss(&0));
+    assert_eq!(cache.get(&2).0, 4);
+    assert_eq!(squares(), 201);
+
+    cache.clear_misses();
+    assert!(!cache.is_known_miss(&2));
+    assert_eq!(cache.get(&2).0, 4);
+    assert_eq!(cache.len(), 4);
+    assert_eq!(squares(), 202);
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn cache_negative_limit() {
+    use MemoCache;
+
+    let mut cache: MemoCache<Square> = MemoCache::new().with_negative_filter(2, |s| s.0 % 2 == 0);
+
+    for i in [0, 2, 4].iter() {
+        cache.get(i);
+    }
+    assert!(cache.is_known_miss(&2));
+    assert!(!cache.is_known_miss(&4));
+    assert_eq!(cache.get(&4).0, 16);
+    assert_eq!(squares(), 4);
+    assert!(cache.is_empty());
+}
+
+#[cfg(feature = "disk")]
+#[test]
+fn cache_serde() {
+    use std::vec::Vec;
+    use MemoCache;
+
+    let mut cache: MemoCache<Square> = MemoCache::new();
+    for i in [3, 1, 2].iter() {
+        cache.get(i);
+    }
+
+    let bytes = bincode::serialize(&cache).unwrap();
+
+    // restored into an unbounded cache
+    let restored: MemoCache<Square> = bincode::deserialize(&bytes).unwrap();
+    assert_eq!(
+        restored.iter().collect::<Vec<_>>(),
+        cache.iter().collect::<Vec<_>>()
+    );
+
+    // restored into a bounded cache, keeping the most recently used entries
+    let entries: Vec<(i64, Square)> = bincode::deserialize(&bytes).unwrap();
+    let mut bounded: MemoCache<Square> = MemoCache::bounded(2);
+    bounded.extend(entries);
+    assert_eq!(bounded.keys_by_recency().collect::<Vec<_>>(), [&2, &1]);
+    assert_eq!(squares(), 3);
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn cache_hash_storage() {
+    use std::collections::HashMap;
+    use std::string::String;
+    use MemoCache;
+
+    struct Len(usize);
+
+    impl Memoize for Len {
+        type Param = str;
+        fn memoize(p: &str) -> Self {
+            Len(p.len())
+        }
+    }
+
+    let mut cache: MemoCache<Len, String, &str, HashMap<String, usize>> = MemoCache::bounded(2);
+
+    assert_eq!(cache.get("a").0, 1);
+    assert_eq!(cache.get("bb").0, 2);
+    cache.get("a");
+    cache.get("ccc");
+
+    assert!(cache.is_ready("a"));
+    assert!(!cache.is_ready("bb"));
+    assert_eq!(cache.try_get("ccc").unwrap().0, 3);
+    assert_eq!(cache.iter().count(), 2);
+}
+
+#[test]
+fn fixed_map() {
+    use {FixedMap, Lookup, Storage};
+
+    let mut map: FixedMap<u32, u32, 16> = FixedMap::new();
+    assert_eq!(map.capacity(), Some(12));
+
+    for i in 0..12 {
+        assert_eq!(map.insert(i, i * 10), None);
+    }
+    assert_eq!(map.insert(3, 33), Some(30));
+    assert_eq!(map.len(), 12);
+
+    // removing entries keeps the others reachable
+    for i in (0..12).filter(|i| i % 3 == 0) {
+        assert_eq!(map.remove(&i), Some(if i == 3 { 33 } else { i * 10 }));
+    }
+    for i in 0..12 {
+        assert_eq!(map.get(&i).is_some(), i % 3 != 0);
+    }
+    assert_eq!(map.iter().count(), 8);
+
+    map.clear();
+    assert!(map.is_empty());
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn cache_fixed_storage() {
+    use {FixedMap, MemoCache};
+
+    let mut cache: MemoCache<Square, i64, &str, FixedMap<i64, usize, 4>> = MemoCache::new();
+
+    // the storage holds 3 entries, so the cache evicts to stay within it
+    for i in 0..5 {
+        cache.get(&i);
+    }
+    assert_eq!(cache.len(), 3);
+    assert!(!cache.is_ready(&1));
+    assert!(cache.is_ready(&4));
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn cache_get_many_fixed_storage() {
+    use std::vec::Vec;
+    use {FixedMap, MemoCache};
+
+    let mut cache: MemoCache<Square, i64, &str, FixedMap<i64, usize, 4>> = MemoCache::new();
+
+    // more keys than the storage holds, so they are computed in batches, and
+    // the ones which don't fit are not cached
+    let values: Vec<i64> = cache
+        .get_many(&[1, 2, 3, 4, 5, 1])
+        .iter()
+        .map(|s| s.0)
+        .collect();
+    assert_eq!(values, [1, 4, 9, 16, 25, 1]);
+    assert_eq!(squares(), 5);
+    assert_eq!(cache.len(), 3);
+    assert!(cache.is_ready(&3));
+    assert!(!cache.is_ready(&4));
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn memo_dyn() {
+    use alloc::boxed::Box;
+    use {Computation, MemoDyn};
+
+    let mut memos: [MemoDyn<i64>; 2] = [
+        MemoDyn::of::<Square>(),
+        MemoDyn::new(Box::new(Computation::<Square>::new())),
+    ];
+
+    assert_eq!(
+        memos[0].computation().output_type_name(),
+        core::any::type_name::<Square>()
+    );
+    assert!(memos[0].try_get().is_none());
+
+    // the wrong type is rejected without computing anything
+    assert_eq!(memos[0].get_as::<TestOut>(&3), None);
+    assert!(!memos[0].is_ready());
+    assert_eq!(squares(), 0);
+
+    assert_eq!(memos[0].get_as::<Square>(&3), Some(&Square(9)));
+    assert_eq!(memos[0].get_as::<Square>(&3), Some(&Square(9)));
+    assert_eq!(memos[1].get(&4).downcast_ref::<Square>(), Some(&Square(16)));
+    assert_eq!(squares(), 2);
+
+    memos[0].clear();
+    assert!(!memos[0].is_ready());
+    assert_eq!(memos[1].try_get_as::<Square>(), Some(&Square(16)));
+}
+
+#[test]
+fn typed_memo() {
+    use {Computed, Empty, Memo, TypedMemo};
+
+    let track = CallTracker::new();
+    let memo: TypedMemo<TestOut, &CallTracker, Empty> = TypedMemo::new(&track);
+    assert_eq!(track.count(), 0);
+
+    let memo: TypedMemo<TestOut, &CallTracker, Computed> = memo.ready();
+    assert_eq!(memo.get(), &TestOut(MAGIC));
+    assert_eq!(memo.get(), &TestOut(MAGIC));
+    assert_eq!(track.count(), 1);
+
+    let memo = memo.update();
+    assert_eq!(track.count(), 2);
+
+    let mut memo: Memo<TestOut, &CallTracker> = memo.into();
+    assert!(memo.is_ready());
+    memo.get();
+    assert_eq!(track.count(), 2);
+
+    let memo: Memo<TestOut, &CallTracker> = TypedMemo::new(&track).into();
+    assert!(!memo.is_ready());
+}
+
+// the reports are only emitted in debug builds
+#[cfg(all(feature = "diagnostics", debug_assertions))]
+#[test]
+fn waste_reports() {
+    use std::vec::Vec;
+    use {set_waste_hook, Memo, MemoExt, Waste, WasteReport};
+
+    thread_local!(static REPORTS: core::cell::RefCell<Vec<Waste>> = const {
+        core::cell::RefCell::new(Vec::new())
+    });
+
+    fn record(report: &WasteReport) {
+        // other tests drop memos on their own threads
+        REPORTS.with(|r| r.borrow_mut().push(report.waste));
+    }
+
+    fn reports() -> Vec<Waste> {
+        REPORTS.with(|r| r.borrow_mut().drain(..).collect())
+    }
+
+    set_waste_hook(record);
+    let track = CallTracker::new();
+
+    drop(MemoExt::<TestOut>::new());
+    assert_eq!(reports(), [Waste::NeverUsed]);
+
+    let mut memo: Memo<TestOut, &CallTracker> = Memo::new(&track);
+    memo.ready();
+    drop(memo);
+    assert_eq!(reports(), [Waste::NeverRead]);
+
+    let mut memo: Memo<TestOut, &CallTracker> = Memo::new(&track);
+    memo.get();
+    memo.update();
+    memo.try_get();
+    drop(memo);
+    assert_eq!(reports(), []);
+
+    let memo: Memo<TestOut, &CallTracker> = Memo::new(&track);
+    assert!(!memo.is_ready());
+    drop(memo);
+    assert_eq!(reports(), []);
+}
+
+// the reports are only emitted in debug builds
+#[cfg(all(feature = "diagnostics", feature = "std", debug_assertions))]
+#[test]
+fn slow_reports() {
+    use core::time::Duration;
+    use std::vec::Vec;
+    use {set_slow_hook, Memo, Memoize, SlowReport};
+
+    struct Sleep;
+
+    impl Memoize for Sleep {
+        type Param = u64;
+
+        fn memoize(p: &u64) -> Self {
+            std::thread::sleep(Duration::from_millis(*p));
+            Sleep
+        }
+    }
+
+    thread_local!(static REPORTS: core::cell::RefCell<Vec<SlowReport>> = const {
+        core::cell::RefCell::new(Vec::new())
+    });
+
+    fn record(report: &SlowReport) {
+        REPORTS.with(|r| r.borrow_mut().push(*report));
+    }
+
+    fn reports() -> Vec<SlowReport> {
+        REPORTS.with(|r| r.borrow_mut().drain(..).collect())
+    }
+
+    set_slow_hook(record);
+
+    // no budget, no timing
+    let mut memo: Memo<Sleep> = Memo::new(20);
+    memo.get();
+    assert!(reports().is_empty());
+
+    memo.set_budget(Duration::from_millis(5));
+    memo.update();
+    let slow = reports();
+    assert_eq!(slow.len(), 1);
+    assert!(slow[0].memo.contains("Sleep"));
+    assert!(slow[0].elapsed >= Duration::from_millis(20));
+    assert_eq!(slow[0].budget, Duration::from_millis(5));
+
+    memo.set_budget(Duration::from_secs(60));
+    memo.update();
+    assert!(reports().is_empty());
+}
+
+#[test]
+fn refreshed() {
+    use {Memo, MemoExt, MemoMut, MemoOnce, Refreshed};
+
+    #[derive(Debug, PartialEq)]
+    struct Sign(i32);
+
+    impl Memoize for Sign {
+        type Param = i32;
+        fn memoize(p: &i32) -> Self {
+            Sign(p.signum())
+        }
+    }
+
+    let mut memo: Memo<Sign> = Memo::new(5);
+
+    // nothing to compare to
+    assert_eq!(memo.refresh(), Refreshed::Changed(&Sign(1)));
+    let before: *const Sign = memo.try_get().unwrap();
+
+    assert_eq!(memo.refresh_param(|p| *p = 7), Refreshed::Same(&Sign(1)));
+    assert!(!memo.refresh().is_changed());
+
+    // the old value is kept
+    assert!(core::ptr::eq(before, memo.try_get().unwrap()));
+
+    let refreshed = memo.refresh_param(|p| *p = -7);
+    assert!(refreshed.is_changed());
+    assert_eq!(refreshed.value(), &Sign(-1));
+
+    // modifying the parameter separately leaves nothing to compare to
+    *memo.param_mut() = -8;
+    assert!(memo.refresh().is_changed());
+
+    let mut memo: MemoExt<Sign> = MemoExt::new();
+    memo.get(&1);
+    assert_eq!(memo.refresh(&0), Refreshed::Changed(&Sign(0)));
+
+    let n = 3;
+    let mut memo: MemoOnce<Sign> = MemoOnce::new(&n);
+    memo.get();
+    assert_eq!(memo.refresh(), Refreshed::Same(&Sign(1)));
+
+    let mut n = 3;
+    let mut memo: MemoMut<Sign> = MemoMut::new(&mut n);
+    memo.get();
+    assert_eq!(memo.refresh_param(|p| *p = 0), Refreshed::Changed(&Sign(0)));
+    assert_eq!(memo.refresh(), Refreshed::Same(&Sign(0)));
+}
+
+#[cfg(feature = "defmt")]
+#[test]
+fn defmt_format() {
+    use defmt::Format;
+    use {Memo, MemoExt, MemoMut, MemoOnce, Memoize};
+
+    #[derive(Format)]
+    struct Half(u32);
+
+    impl Memoize for Half {
+        type Param = u32;
+
+        fn memoize(p: &u32) -> Self {
+            Half(p / 2)
+        }
+    }
+
+    fn is_format<F: Format>(_: &F) {}
+
+    let mut n = 8;
+    let mut memo: Memo<Half> = Memo::new(n);
+    is_format(&MemoExt::<Half>::new());
+    is_format(&memo);
+    is_format(&MemoOnce::<Half>::new(&n));
+    is_format(&MemoMut::<Half>::new(&mut n));
+
+    // the events are compiled out by default, and must not get in the way
+    assert_eq!(memo.get().0, 4);
+    memo.clear();
+    assert!(!memo.is_ready());
+}
+
+#[cfg(all(feature = "ufmt", feature = "alloc"))]
+#[test]
+fn ufmt_format() {
+    use alloc::string::String;
+    use core::convert::Infallible;
+    use ufmt::derive::uDebug;
+    use ufmt::{uWrite, uwrite};
+    use {CacheError, Memo, MemoExt, MemoMut, MemoOnce, Memoize};
+
+    #[derive(uDebug)]
+    struct Half(u32);
+
+    impl Memoize for Half {
+        type Param = u32;
+
+        fn memoize(p: &u32) -> Self {
+            Half(p / 2)
+        }
+    }
+
+    struct Out(String);
+
+    impl uWrite for Out {
+        type Error = Infallible;
+
+        fn write_str(&mut self, s: &str) -> Result<(), Infallible> {
+            self.0.push_str(s);
+            Ok(())
+        }
+    }
+
+    let mut out = Out(String::new());
+    let mut n = 8;
+    let mut memo: Memo<Half> = Memo::new(n);
+    memo.get();
+    uwrite!(out, "{:?}", memo).unwrap();
+    assert_eq!(out.0, "Memo { value: Some(Half(4)), param: 8 }");
+
+    out.0.clear();
+    uwrite!(out, "{:?}", MemoExt::<Half>::new()).unwrap();
+    assert_eq!(out.0, "MemoExt { value: None }");
+
+    out.0.clear();
+    uwrite!(out, "{:?}", MemoOnce::<Half>::new(&n)).unwrap();
+    assert_eq!(out.0, "MemoOnce { value: None, param: 8 }");
+
+    out.0.clear();
+    uwrite!(out, "{:?}", MemoMut::<Half>::new(&mut n)).unwrap();
+    assert_eq!(out.0, "MemoMut { value: None, param: 8 }");
+
+    out.0.clear();
+    let e: CacheError<u8> = CacheError::ComputeFailed(3);
+    uwrite!(out, "{:?}; {}", e, e).unwrap();
+    assert_eq!(out.0, "ComputeFailed(3); the computation failed: 3");
+}
+
+#[cfg(all(feature = "ufmt", feature = "diagnostics", feature = "std"))]
+#[test]
+fn ufmt_diagnostics() {
+    use alloc::string::String;
+    use core::convert::Infallible;
+    use core::time::Duration;
+    use ufmt::{uWrite, uwrite};
+    use {ComputeEvent, SlowReport, Waste, WasteReport};
+
+    struct Out(String);
+
+    impl uWrite for Out {
+        type Error = Infallible;
+
+        fn write_str(&mut self, s: &str) -> Result<(), Infallible> {
+            self.0.push_str(s);
+            Ok(())
+        }
+    }
+
+    let mut out = Out(String::new());
+    let report = WasteReport {
+        waste: Waste::NeverRead,
+        memo: "Memo<Half>",
+    };
+    uwrite!(out, "{}", report).unwrap();
+    assert_eq!(out.0, "Memo<Half> computed a value which was never read");
+
+    out.0.clear();
+    let report = SlowReport {
+        memo: "Memo<Half>",
+        elapsed: Duration::from_millis(3),
+        budget: Duration::from_millis(1),
+    };
+    uwrite!(out, "{}", report).unwrap();
+    assert_eq!(
+        out.0,
+        "Memo<Half> took 3000us to compute its value, over its budget of 1000us"
+    );
+
+    out.0.clear();
+    let event = ComputeEvent {
+        generation: 2,
+        fingerprint: 0xbeef,
+        elapsed: Duration::from_micros(15),
+    };
+    uwrite!(out, "{}", event).unwrap();
+    assert_eq!(out.0, "#2: computed for 000000000000beef in 15us");
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn compact() {
+    use alloc::string::String;
+    use std::vec::Vec;
+    use {Compact, Memo, MemoExt, Memoize, Registry};
+
+    struct Padded(String);
+
+    impl Memoize for Padded {
+        type Param = str;
+        fn memoize(p: &str) -> Self {
+            let mut s = String::with_capacity(64);
+            s.push_str(p);
+            Padded(s)
+        }
+    }
+
+    impl Compact for Padded {
+        fn compact(&mut self) {
+            self.0.compact();
+        }
+    }
+
+    // nothing to compact yet
+    let mut memo: MemoExt<Padded> = MemoExt::new();
+    memo.compact();
+    assert!(!memo.is_ready());
+
+    let mut registry = Registry::new();
+    let a = registry.insert(Memo::<Padded, String>::new("abc".into()));
+    let b = registry.insert(Memo::<Padded, String>::new("def".into()));
+    assert!(registry.enable_compact(a));
+    registry.ready_all();
+    registry.compact_all();
+
+    // only the registered memo is compacted, and neither is invalidated
+    let a = registry.get(a).unwrap().try_get().unwrap();
+    let b = registry.get(b).unwrap().try_get().unwrap();
+    assert_eq!((&*a.0, a.0.capacity()), ("abc", 3));
+    assert_eq!((&*b.0, b.0.capacity()), ("def", 64));
+
+    let mut v: Option<Vec<u8>> = Some(Vec::with_capacity(8));
+    v.compact();
+    assert_eq!(v.unwrap().capacity(), 0);
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn cow_values() {
+    use alloc::borrow::Cow;
+    use {Memo, MemoExt, MemoMut, MemoOnce};
+
+    #[derive(Clone)]
+    struct Cube(i64);
+
+    impl Memoize for Cube {
+        type Param = i64;
+        fn memoize(p: &i64) -> Self {
+            Cube(p * p * p)
+        }
+    }
+
+    let mut memo: Memo<Cube> = Memo::new(2);
+    assert!(matches!(memo.get_cow(), Cow::Owned(Cube(8))));
+    assert!(!memo.is_ready());
+    memo.ready();
+    assert!(matches!(memo.get_cow(), Cow::Borrowed(&Cube(8))));
+
+    let mut memo: MemoExt<Cube> = MemoExt::new();
+    assert!(matches!(memo.get_cow(&3), Cow::Owned(Cube(27))));
+    memo.ready(&3);
+    assert!(matches!(memo.get_cow(&3), Cow::Borrowed(&Cube(27))));
+
+    let n = 4;
+    let memo: MemoOnce<Cube> = MemoOnce::new(&n);
+    assert_eq!(memo.get_cow().0, 64);
+
+    let mut n = 5;
+    let memo: MemoMut<Cube> = MemoMut::new(&mut n);
+    assert_eq!(memo.get_cow().into_owned().0, 125);
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn shared_values() {
+    use alloc::rc::Rc;
+    use alloc::sync::Arc;
+    use {Memo, MemoExt, MemoMut, MemoOnce};
+
+    let mut memo: Memo<Rc<Square>> = Memo::new(3);
+    let first = memo.get_rc();
+    assert!(Rc::ptr_eq(&first, &memo.get_rc()));
+    assert_eq!(squares(), 1);
+
+    // the value outlives the parameter it was computed from
+    *memo.param_mut() = 4;
+    assert_eq!((first.0, memo.get_rc().0), (9, 16));
+    assert_eq!(Rc::strong_count(&first), 1);
+
+    let mut memo: MemoExt<Arc<Square>> = MemoExt::new();
+    let value = memo.get_arc(&5);
+    memo.clear();
+    assert_eq!(value.0, 25);
+
+    let n = 6;
+    let mut memo: MemoOnce<Arc<Square>> = MemoOnce::new(&n);
+    assert_eq!(memo.get_arc().0, 36);
+
+    let mut n = 7;
+    let mut memo: MemoMut<Rc<Square>> = MemoMut::new(&mut n);
+    assert_eq!(memo.get_rc().0, 49);
+    assert_eq!(squares(), 5);
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn weak_values() {
+    use alloc::rc::Rc;
+    use alloc::sync::Arc;
+    use {Invalidate, MemoWeak};
+
+    let mut memo: MemoWeak<Square> = MemoWeak::new(3);
+    assert!(!memo.is_ready());
+
+    let value = memo.get();
+    assert_eq!(value.0, 9);
+    assert!(Rc::ptr_eq(&value, &memo.get()));
+    assert!(Rc::ptr_eq(&value, &memo.try_get().unwrap()));
+    assert_eq!(squares(), 1);
+
+    // the memo does not keep the value alive
+    assert_eq!(Rc::strong_count(&value), 1);
+    drop(value);
+    assert!(memo.try_get().is_none());
+    assert_eq!(memo.get().0, 9);
+    assert_eq!(squares(), 2);
+
+    // changing the parameter forgets the value, even if it is still held
+    let old = memo.get();
+    *memo.param_mut() = 4;
+    let new = memo.get();
+    assert_eq!((old.0, new.0), (9, 16));
+    assert_eq!(squares(), 4);
+
+    let mut memo: MemoWeak<Square, i64, Arc<Square>> = MemoWeak::new(5);
+    let value = memo.get();
+    memo.invalidate();
+    assert!(!memo.is_ready());
+    assert_eq!(value.0, 25);
+    assert_eq!(memo.into_param(), 5);
+}
+
+#[cfg(all(feature = "diagnostics", feature = "std"))]
+#[test]
+fn compute_history() {
+    use std::vec::Vec;
+    use {Memo, MemoExt, MemoMut};
+
+    let mut memo: Memo<Square> = Memo::new(2);
+    memo.get();
+    assert_eq!(memo.history().count(), 0);
+
+    memo.record_history(3);
+    for p in [3, 4, 3, 4] {
+        *memo.param_mut() = p;
+        memo.get();
+    }
+
+    // only the last 3 are kept
+    let events: Vec<_> = memo.history().collect();
+    let generations: Vec<_> = events.iter().map(|e| e.generation).collect();
+    assert_eq!(generations, [1, 2, 3]);
+    assert_eq!(events[0].fingerprint, events[2].fingerprint);
+    assert_ne!(events[0].fingerprint, events[1].fingerprint);
+
+    // set() is not a computation
+    memo.set(Square(0));
+    assert_eq!(memo.history().count(), 3);
+
+    memo.record_history(0);
+    memo.update();
+    assert_eq!(memo.history().count(), 0);
+
+    // the same parameter has the same fingerprint in every memo
+    let mut ext: MemoExt<Square> = MemoExt::new();
+    ext.record_history(1);
+    ext.get(&3);
+    let mut n = 3;
+    let mut mem: MemoMut<Square> = MemoMut::new(&mut n);
+    mem.record_history(1);
+    mem.get();
+    assert_eq!(
+        ext.history().next().unwrap().fingerprint,
+        events[1].fingerprint
+    );
+    assert_eq!(
+        mem.history().next().unwrap().fingerprint,
+        events[1].fingerprint
+    );
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn memory_used() {
+    use alloc::string::String;
+    use alloc::vec::Vec;
+    use core::mem::size_of;
+    use {MemSize, Memo, MemoCache, Registry};
+
+    assert_eq!(String::with_capacity(10).heap_size(), 10);
+    assert_eq!(Vec::<u32>::with_capacity(4).heap_size(), 16);
+    assert_eq!(Some(String::with_capacity(3)).heap_size(), 3);
+
+    let mut cache: MemoCache<Square> = MemoCache::new();
+    let empty = cache.memory_used();
+    cache.get(&1);
+    cache.get(&2);
+    assert!(cache.memory_used() >= empty + 2 * size_of::<(i64, usize)>());
+
+    let mut registry = Registry::new();
+    registry.insert(Memo::<Square>::new(3));
+    let plain = registry.memory_used();
+    assert_eq!(plain, size_of::<Memo<Square>>());
+
+    let h = registry.insert_sized(Memo::<MemoSum, Vec<i32>>::new(Vec::with_capacity(8)));
+    assert_eq!(
+        registry.memory_used(),
+        plain + size_of::<Memo<MemoSum, Vec<i32>>>() + 32
+    );
+    assert!(registry.get(h).is_some());
+}
+
+#[cfg(all(feature = "stats", feature = "std"))]
+#[test]
+fn registry_report() {
+    use std::string::ToString;
+    use {Memo, Registry};
+
+    let mut registry = Registry::new();
+    let a = registry.insert(Memo::<Square>::new(2));
+    registry.insert(Memo::<Square>::new(3));
+    let s = registry.insert(Memo::<MemoSum, _>::new(vec![1, 2]));
+
+    registry.get_mut(a).unwrap().get();
+    registry.get_mut(a).unwrap().get();
+    registry.get_mut(s).unwrap();
+    registry.ready_all();
+
+    let report = registry.report();
+    assert_eq!(report.types.len(), 2);
+
+    let sums = &report.types[0];
+    assert!(sums.type_name.contains("MemoSum"));
+    assert_eq!(
+        (sums.count, sums.ready, sums.hits, sums.misses),
+        (1, 1, 0, 1)
+    );
+
+    let squares = &report.types[1];
+    assert!(squares.type_name.contains("Square"));
+    assert_eq!((squares.count, squares.ready), (2, 2));
+    assert_eq!((squares.hits, squares.misses), (1, 1));
+    assert_eq!(squares.ready_percent(), 100.0);
+
+    assert_eq!(report.to_string().lines().count(), 2);
+}
+
+#[cfg(feature = "graph")]
+#[test]
+fn graph_invalidation() {
+    use alloc::collections::BTreeMap;
+    use alloc::vec::Vec;
+    use {Direction, Graph, MemoGraph, MemoizeNode, NodeValues};
+
+    type Deps = BTreeMap<u8, Vec<u8>>;
+
+    /// The sum of a node and its successors
+    struct Total(u32);
+
+    impl MemoizeNode<Deps> for Total {
+        fn memoize_node(graph: &Deps, node: u8, _: &NodeValues<Total, Deps>) -> Self {
+            let mut total = u32::from(node);
+            graph.successors(node, |s| total += u32::from(s));
+            Total(total)
+        }
+    }
+
+    // 1 -> 2 -> 3 -> 1, and 4 -> 3
+    let mut graph: Deps = BTreeMap::new();
+    graph.insert(1, vec![2]);
+    graph.insert(2, vec![3]);
+    graph.insert(3, vec![1]);
+    graph.insert(4, vec![3]);
+
+    let mut memo: MemoGraph<Total, Deps> = MemoGraph::new(Direction::Incoming);
+    for n in 1..=4 {
+        memo.ready(&graph, n);
+    }
+    assert_eq!(memo.try_get(4).unwrap().0, 7);
+
+    // everything depends on 3, through the cycle
+    assert_eq!(memo.invalidate_node(&graph, 3), 4);
+    assert!(memo.is_empty());
+
+    let mut memo: MemoGraph<Total, Deps> = MemoGraph::new(Direction::Outgoing);
+    for n in 1..=4 {
+        memo.ready(&graph, n);
+    }
+    // 4 is not reachable from 3
+    assert_eq!(memo.invalidate_node(&graph, 3), 3);
+    assert_eq!(memo.len(), 1);
+    assert!(memo.is_ready(4));
+    assert_eq!(memo.update(&graph, 4).0, 7);
+
+    // the nodes which a node depends on are computed and cached with it
+    memo.clear();
+    memo.ready(&graph, 1);
+    assert_eq!(memo.len(), 4);
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn snapshot_identity() {
+    use alloc::rc::Rc;
+    use MemoSnapshot;
+
+    let mut memo: MemoSnapshot<Square, Rc<i64>> = MemoSnapshot::new();
+    let a = Rc::new(3);
+    assert_eq!(memo.try_get(&a), None);
+    assert_eq!(memo.get(&a), &Square(9));
+    assert_eq!(memo.get(&Rc::clone(&a)), &Square(9));
+    assert_eq!(squares(), 1);
+
+    // equal, but a different allocation
+    let b = Rc::new(3);
+    assert_eq!(memo.try_get(&b), None);
+    assert_eq!(memo.get(&b), &Square(9));
+    assert_eq!(squares(), 2);
+
+    // the memo keeps the snapshot alive
+    assert_eq!(Rc::strong_count(&b), 2);
+    memo.clear();
+    assert_eq!(Rc::strong_count(&b), 1);
+    assert!(memo.snapshot().is_none());
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn boxed_param() {
+    use alloc::boxed::Box;
+    use Memo;
+
+    let mut memo: Memo<MemoSum, Box<[i32]>> = Memo::boxed(vec![1, 2, 3]);
+    assert_eq!(memo.get(), &MemoSum(6));
+
+    let mut memo: Memo<MemoSum, Box<[i32]>> = Memo::boxed(&[4, 5][..]);
+    assert_eq!(memo.get(), &MemoSum(9));
+    memo.param_mut()[0] = 1;
+    assert_eq!(memo.get(), &MemoSum(6));
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn tuple_params() {
+    use alloc::string::String;
+    use {MemoCache, MemoExt, Memoize};
+
+    #[derive(Debug, PartialEq)]
+    struct Label(String);
+
+    impl Memoize for Label {
+        type Param = (String, u32, char);
+        fn memoize((name, n, sep): &(String, u32, char)) -> Self {
+            Label(format!("{}{}{}", name, sep, n))
+        }
+    }
+
+    let mut memo: MemoExt<Label> = MemoExt::new();
+    assert_eq!(memo.get_with(("item", 3u32, '#')).0, "item#3");
+    // cached, so the arguments are ignored
+    assert_eq!(memo.get_with(("other", 4u32, '-')).0, "item#3");
+
+    let mut cache: MemoCache<Label> = MemoCache::new();
+    assert_eq!(cache.get_with(("item", 3u8, '#')).0, "item#3");
+    assert_eq!(cache.get_with(("item", 4u8, '#')).0, "item#4");
+    assert_eq!(cache.len(), 2);
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn partial_memo() {
+    use {Memoize, PartialMemo};
+
+    struct Power(i64);
+
+    impl Memoize for Power {
+        type Param = (i64, u32);
+        fn memoize(&(base, exp): &(i64, u32)) -> Self {
+            Power(base.pow(exp))
+        }
+    }
+
+    let mut memo: PartialMemo<Power, i64, u32> = PartialMemo::new(2);
+    assert_eq!(memo.get(3).0, 8);
+    assert_eq!(memo.get(10).0, 1024);
+    assert!(memo.is_ready(&3));
+    assert_eq!(memo.try_get(&4).map(|p| p.0), None);
+
+    *memo.fixed_mut() = 3;
+    assert!(memo.is_empty());
+    assert_eq!(memo.get(3).0, 27);
+    assert_eq!(memo.remove(&3).map(|p| p.0), Some(27));
+    assert_eq!(memo.fixed(), &3);
+}
+
+#[test]
+fn memo_from_default() {
+    use {Memo, Memoize};
+
+    struct Double(i64);
+
+    impl Memoize for Double {
+        type Param = i64;
+        fn memoize(p: &i64) -> Self {
+            Double(p * 2)
+        }
+    }
+
+    let mut memo: Memo<Double> = Memo::from_default();
+    assert_eq!(memo.get().0, 0);
+
+    *memo.param_mut() = 21;
+    assert_eq!(memo.get().0, 42);
+
+    let memo: Memo<Double> = Default::default();
+    assert!(!memo.is_ready());
+    assert_eq!(memo.param(), &0);
+}
+
+#[cfg(feature = "zeroize")]
+#[test]
+fn zeroize_values() {
+    use core::sync::atomic::{AtomicUsize, Ordering};
+    use zeroize::{Zeroize, Zeroizing};
+    use {Memo, MemoExt, Memoize};
+
+    static WIPED: AtomicUsize = AtomicUsize::new(0);
+
+    struct Secret(u64);
+
+    impl Zeroize for Secret {
+        fn zeroize(&mut self) {
+            self.0 = 0;
+            WIPED.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    impl Memoize for Secret {
+        type Param = u64;
+        fn memoize(p: &u64) -> Self {
+            Secret(p ^ 0xdead_beef)
+        }
+    }
+
+    let wiped = || WIPED.load(Ordering::SeqCst);
+
+    let mut memo: Memo<Zeroizing<Secret>> = Memo::new(1);
+    assert_eq!(memo.get().0, 0xdead_beee);
+    assert_eq!(wiped(), 0);
+
+    memo.clear();
+    assert_eq!(wiped(), 1);
+
+    memo.get();
+    memo.update();
+    assert_eq!(wiped(), 2);
+
+    drop(memo);
+    assert_eq!(wiped(), 3);
+
+    let mut ext: MemoExt<Secret> = MemoExt::new();
+    ext.get(&2);
+    ext.zeroize();
+    assert!(!ext.is_ready());
+    assert_eq!(wiped(), 4);
+}
+
+#[cfg(feature = "secrecy")]
+#[test]
+fn secret_values() {
+    use secrecy::{ExposeSecret, SecretBox};
+    use zeroize::Zeroize;
+    use {MemoExt, Memoize};
+
+    #[derive(Debug)]
+    struct Password(u32);
+
+    impl Zeroize for Password {
+        fn zeroize(&mut self) {
+            self.0.zeroize();
+        }
+    }
+
+    impl Memoize for Password {
+        type Param = u32;
+        fn memoize(p: &u32) -> Self {
+            Password(p * 1_000_003)
+        }
+    }
+
+    let mut memo: MemoExt<SecretBox<Password>> = MemoExt::new();
+    assert_eq!(memo.get(&7).expose_secret().0, 7_000_021);
+    assert!(!format!("{:?}", memo).contains("7000021"));
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn audit_params() {
+    use MemoExt;
+
+    let mut memo: MemoExt<Square> = MemoExt::new();
+    memo.audit_params();
+
+    assert_eq!(memo.get(&3).0, 9);
+    assert_eq!(memo.get(&3).0, 9);
+
+    // a new parameter is fine once the value is cleared
+    memo.clear();
+    assert_eq!(memo.get(&4).0, 16);
+    memo.update(&5);
+    assert_eq!(memo.get(&5).0, 25);
+
+    // values which were not computed by the memo are not checked
+    memo.set(Square(36));
+    assert_eq!(memo.get(&7).0, 36);
+}
+
+#[cfg(all(feature = "alloc", feature = "paranoid"))]
+#[test]
+#[should_panic(expected = "was given a different parameter")]
+fn audit_params_mismatch() {
+    use MemoExt;
+
+    let mut memo: MemoExt<Square> = MemoExt::new();
+    memo.audit_params();
+
+    memo.get(&3);
+    memo.get(&4);
+}
+
+#[cfg(feature = "test-util")]
+#[test]
+fn memoize_laws() {
+    use {Memo, MemoExt, MemoOnce, MemoizeLaws};
+
+    let memo: Memo<TestOut> = Memo::new(CallTracker::new());
+    MemoizeLaws::new(memo, |m| *m.get(), |m| m.param().count()).check_all();
+
+    let track = CallTracker::new();
+    let memo: MemoExt<TestOut> = MemoExt::new();
+    MemoizeLaws::new(memo, |m| *m.get(&track), |_| track.count()).check_all();
+
+    let track = CallTracker::new();
+    let memo: MemoOnce<TestOut> = MemoOnce::new(&track);
+    MemoizeLaws::new(memo, |m| *m.get(), |_| track.count()).check_all();
+}
+
+#[cfg(feature = "test-util")]
+#[test]
+#[should_panic(expected = "computed 0 times after an invalidation")]
+fn memoize_laws_broken() {
+    use {Invalidate, MemoizeLaws};
+
+    /// Never forgets its value
+    struct Stubborn(Option<TestOut>);
+
+    impl Invalidate for Stubborn {
+        fn invalidate(&mut self) {}
+    }
+
+    let track = CallTracker::new();
+    let memo = Stubborn(None);
+    MemoizeLaws::new(
+        memo,
+        |m| *m.0.get_or_insert_with(|| TestOut::memoize(&track)),
+        |_| track.count(),
+    )
+    .check_all();
+}
+
+#[cfg(feature = "test-util")]
+#[test]
+fn counting_memoize() {
+    use {CountingMemoize, MemoExt, MemoizeLaws, Tracked};
+
+    let param = Tracked::new(CallTracker::new());
+    let mut memo: MemoExt<CountingMemoize<TestOut>> = MemoExt::new();
+
+    assert_eq!(**memo.get(&param), TestOut(MAGIC));
+    memo.get(&param);
+    assert_eq!(param.count(), 1);
+    assert_eq!(param.param().count(), 1);
+
+    param.tracker().reset();
+    MemoizeLaws::new(memo, |m| **m.get(&param), |_| param.count()).check_all();
+    assert!(param.count() > 0);
+}
+
+#[cfg(all(feature = "test-util", feature = "std"))]
+#[test]
+fn override_global() {
+    use {override_with, MemoExt, MemoOnce, Overridable};
+
+    let param = CallTracker::new();
+    let mut memo: MemoExt<Overridable<TestOut>> = MemoExt::new();
+    {
+        let _outer = override_with::<TestOut>(|_| TestOut(1));
+        assert_eq!(**memo.get(&param), TestOut(1));
+
+        {
+            let _inner = override_with::<TestOut>(|_| TestOut(2));
+            memo.clear();
+            assert_eq!(**memo.get(&param), TestOut(2));
+
+            // memos of the computation itself are not affected
+            let mut once: MemoOnce<TestOut> = MemoOnce::new(&param);
+            assert_eq!(*once.get(), TestOut(MAGIC));
+        }
+
+        memo.clear();
+        assert_eq!(**memo.get(&param), TestOut(1));
+
+        // other threads are not affected
+        let other = std::thread::spawn(|| {
+            let mut memo: MemoExt<Overridable<TestOut>> = MemoExt::new();
+            memo.get(&CallTracker::new()).clone().into_inner()
+        });
+        assert_eq!(other.join().unwrap(), TestOut(MAGIC));
+    }
+    assert_eq!(param.count(), 1);
+
+    memo.clear();
+    assert_eq!(**memo.get(&param), TestOut(MAGIC));
+    assert_eq!(param.count(), 2);
+}
+
+#[cfg(feature = "arbitrary")]
+#[test]
+fn arbitrary_memos() {
+    use arbitrary::{Arbitrary, Unstructured};
+    use {Memo, MemoExt};
+
+    #[derive(Debug, PartialEq)]
+    struct Neg(i16);
+
+    impl<'a> Arbitrary<'a> for Neg {
+        fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+            Ok(Neg(u.arbitrary()?))
+        }
+    }
+
+    impl Memoize for Neg {
+        type Param = i16;
+        fn memoize(p: &i16) -> Self {
+            Neg(p.wrapping_neg())
+        }
+    }
+
+    let (mut empty, mut ready, mut stale) = (0, 0, 0);
+    for seed in 0..64u8 {
+        let data: std::vec::Vec<u8> = (0..32)
+            .map(|i| seed.wrapping_mul(31).wrapping_add(i * 7))
+            .collect();
+        let mut u = Unstructured::new(&data);
+
+        let mut memo: Memo<Neg> = Memo::arbitrary(&mut u).unwrap();
+        let expected = Neg::memoize(memo.param());
+        match memo.try_get() {
+            None => empty += 1,
+            Some(v) if *v == expected => ready += 1,
+            Some(_) => stale += 1,
+        }
+        memo.clear();
+        assert_eq!(*memo.get(), expected);
+
+        let ext: MemoExt<Neg> = MemoExt::arbitrary(&mut u).unwrap();
+        ext.try_get();
+    }
+    assert!(empty > 0 && ready > 0 && stale > 0);
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn ext_borrowed_params() {
+    use std::rc::Rc;
+    use std::string::String;
+    use std::vec::Vec;
+    use MemoExt;
+
+    struct Words(usize);
+
+    impl Memoize for Words {
+        type Param = str;
+        fn memoize(p: &str) -> Self {
+            Words(p.split_whitespace().count())
+        }
+    }
+
+    let owned = String::from("one two three");
+    let shared: Rc<str> = Rc::from("four five");
+
+    let mut memo: MemoExt<Words> = MemoExt::new();
+    assert_eq!(memo.get(&owned).0, 3);
+    memo.update(&shared);
+    assert_eq!(memo.get("ignored").0, 2);
+
+    let values: Vec<i32> = (1..=4).collect();
+    let mut sum: MemoExt<MemoSum> = MemoExt::new();
+    sum.ready(&values);
+    assert_eq!(sum.get(&[0]).0, 10);
+}
+
+#[test]
+fn ext_owned_param() {
+    use MemoExt;
+
+    let mut memo: MemoExt<TestOut> = MemoExt::new();
+    assert_eq!(memo.get_owned(CallTracker::new()), &TestOut(MAGIC));
+
+    // the parameter is not even looked at on a hit
+    let track = CallTracker::new();
+    memo.get_owned(&track);
+    assert_eq!(track.count(), 0);
+
+    memo.clear();
+    memo.get_owned(&track);
+    assert_eq!(track.count(), 1);
+}
+
+#[test]
+fn ext_deferred_param() {
+    use MemoExt;
+
+    let built = CallTracker::new();
+    let build = || {
+        built.incr();
+        CallTracker::new()
+    };
+
+    let mut memo: MemoExt<TestOut> = MemoExt::new();
+    assert_eq!(memo.get_with(build), &TestOut(MAGIC));
+    assert_eq!(memo.get_with(build), &TestOut(MAGIC));
+    assert_eq!(built.count(), 1);
+
+    memo.clear();
+    memo.get_with(build);
+    assert_eq!(built.count(), 2);
+}
+
+#[test]
+fn set_and_take() {
+    use {Memo, MemoExt, MemoOnce};
+
+    /// Not a computation, only ever provided with `set()`
+    #[derive(Debug, PartialEq)]
+    struct External(&'static str);
+
+    let mut slot: MemoExt<External> = MemoExt::default();
+    assert_eq!(slot.take(), None);
+    slot.set(External("hi"));
+    assert_eq!(slot.try_get(), Some(&External("hi")));
+    assert_eq!(slot.take(), Some(External("hi")));
+    assert!(!slot.is_ready());
+
+    let track = CallTracker::new();
+    let mut memo: Memo<TestOut, &CallTracker> = Memo::new(&track);
+    memo.set(TestOut(MAGIC));
+    assert_eq!(memo.get(), &TestOut(MAGIC));
+    assert_eq!(track.count(), 0);
+    assert_eq!(memo.take(), Some(TestOut(MAGIC)));
+    memo.get();
+    assert_eq!(track.count(), 1);
+
+    let mut once: MemoOnce<TestOut> = MemoOnce::new(&track);
+    once.set(TestOut(0));
+    assert_eq!(once.get(), &TestOut(0));
+    assert_eq!(track.count(), 1);
+}
+
+#[test]
+fn compute_output() {
+    use {Compute, Memo, MemoExt, Output};
+
+    struct Halves;
+
+    impl Compute for Halves {
+        type Param = [i32];
+        type Output = (i32, i32);
+        fn compute(p: &[i32]) -> (i32, i32) {
+            let (a, b) = p.split_at(p.len() / 2);
+            (a.iter().sum(), b.iter().sum())
+        }
+    }
+
+    let mut memo: MemoExt<Output<Halves>> = MemoExt::new();
+    assert_eq!(**memo.get(&[1, 2, 3, 4]), (3, 7));
+    assert_eq!(memo.take().map(Output::into_inner), Some((3, 7)));
+
+    // `Memoize` types are `Compute` types too
+    let track = CallTracker::new();
+    assert_eq!(<TestOut as Compute>::compute(&track), TestOut(MAGIC));
+    let mut memo: Memo<Output<TestOut>, &CallTracker> = Memo::new(&track);
+    assert_eq!(**memo.get(), TestOut(MAGIC));
+    assert_eq!(track.count(), 2);
+}
+
+#[test]
+fn aux_side_products() {
+    use {MemoExt, MemoizeAux, WithAux};
+
+    #[derive(Debug, PartialEq)]
+    struct Clamped(i32);
+
+    impl MemoizeAux for Clamped {
+        type Param = i32;
+        type Aux = bool;
+        fn memoize_aux(p: &i32) -> (Self, bool) {
+            (Clamped((*p).clamp(0, 100)), !(0..=100).contains(p))
+        }
+    }
+
+    let mut memo: MemoExt<WithAux<Clamped>> = MemoExt::new();
+    assert_eq!(memo.take_aux(), None);
+    assert_eq!(**memo.get(&150), Clamped(100));
+    assert_eq!(memo.try_get().and_then(WithAux::aux), Some(&true));
+    assert_eq!(memo.take_aux(), Some(true));
+    assert_eq!(memo.take_aux(), None);
+
+    memo.update(&50);
+    assert_eq!(memo.take_aux(), Some(false));
+    assert_eq!(memo.take().map(WithAux::into_inner), Some(Clamped(50)));
+}
+
+#[test]
+fn strategies() {
+    use {MemoExt, MemoizeWith, Using};
+
+    struct Floor;
+    struct Round;
+
+    #[derive(Debug, PartialEq)]
+    struct Whole(i64);
+
+    impl MemoizeWith<Floor> for Whole {
+        type Param = f64;
+        fn memoize_with(p: &f64) -> Self {
+            Whole(p.floor() as i64)
+        }
+    }
+
+    impl MemoizeWith<Round> for Whole {
+        type Param = f64;
+        fn memoize_with(p: &f64) -> Self {
+            Whole(p.round() as i64)
+        }
+    }
+
+    let mut floor: MemoExt<Using<Whole, Floor>> = MemoExt::new();
+    let mut round: MemoExt<Using<Whole, Round>> = MemoExt::new();
+    assert_eq!(**floor.get(&2.7), Whole(2));
+    assert_eq!(**round.get(&2.7), Whole(3));
+    assert_eq!(round.take().map(Using::into_inner), Some(Whole(3)));
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn memo_once_pointers() {
+    use core::cell::{Ref, RefCell};
+    use std::rc::Rc;
+    use std::vec::Vec;
+    use MemoOnce;
+
+    let cell = RefCell::new(CallTracker::new());
+    {
+        let mut memo: MemoOnce<TestOut, Ref<CallTracker>> = MemoOnce::new(cell.borrow());
+        memo.get();
+        memo.get();
+        assert_eq!(memo.param().count(), 1);
+    }
+    // the borrow is released with the memo
+    *cell.borrow_mut() = CallTracker::new();
+
+    let shared: Rc<Vec<i32>> = Rc::new(vec![1, 2, 3]);
+    let mut memo: MemoOnce<MemoSum, Rc<Vec<i32>>> = MemoOnce::new(Rc::clone(&shared));
+    assert_eq!(memo.get().0, 6);
+    assert_eq!(Rc::strong_count(&shared), 2);
+    drop(memo.into_ptr());
+    assert_eq!(Rc::strong_count(&shared), 1);
+}
+
+#[test]
+fn memo_once_unchecked() {
+    use MemoOnce;
+
+    let track = CallTracker::new();
+    let raw: *const CallTracker = &track;
+
+    {
+        let mut memo: MemoOnce<TestOut> = unsafe { MemoOnce::new_unchecked(raw) };
+        assert_eq!(memo.get(), &TestOut(MAGIC));
+        memo.get();
+    }
+    assert_eq!(track.count(), 1);
+}
+
+#[cfg(feature = "capi")]
+#[test]
+fn c_api() {
+    use core::ffi::c_void;
+    use core::ptr;
+    use std::boxed::Box;
+    use {
+        core_memo_clear, core_memo_create, core_memo_destroy, core_memo_get, core_memo_is_ready,
+        core_memo_set_param, core_memo_try_get,
+    };
+
+    /// Counts live values in `ctx`
+    unsafe extern "C" fn triple(param: *const c_void, ctx: *mut c_void) -> *mut c_void {
+        *(ctx as *mut i32) += 1;
+        Box::into_raw(Box::new(*(param as *const i64) * 3)) as *mut c_void
+    }
+
+    unsafe extern "C" fn free(value: *mut c_void, ctx: *mut c_void) {
+        *(ctx as *mut i32) -= 1;
+        drop(Box::from_raw(value as *mut i64));
+    }
+
+    let mut live = 0i32;
+    let (a, b) = (5i64, 7i64);
+    let ctx = &mut live as *mut i32 as *mut c_void;
+
+    unsafe {
+        let memo = core_memo_create(triple, Some(free), ctx, &a as *const i64 as *const c_void);
+        assert!(!core_memo_is_ready(memo));
+        assert!(core_memo_try_get(memo).is_null());
+
+        assert_eq!(*(core_memo_get(memo) as *const i64), 15);
+        assert_eq!(core_memo_get(memo), core_memo_try_get(memo));
+        assert_eq!(*(ctx as *const i32), 1);
+
+        core_memo_clear(memo);
+        assert_eq!(*(ctx as *const i32), 0);
+
+        core_memo_set_param(memo, &b as *const i64 as *const c_void);
+        assert_eq!(*(core_memo_get(memo) as *const i64), 21);
+
+        core_memo_destroy(memo);
+        core_memo_destroy(ptr::null_mut());
+    }
+    assert_eq!(live, 0);
+}
+
+#[test]
+fn cache_error() {
+    use core::error::Error;
+    use core::fmt;
+    use std::boxed::Box;
+    use std::string::ToString;
+    use CacheError;
+
+    #[derive(Debug)]
+    struct Overflow;
+
+    impl fmt::Display for Overflow {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.write_str("overflow")
+        }
+    }
+
+    impl Error for Overflow {}
+
+    let e: Box<dyn Error> = Box::new(CacheError::<Overflow>::AllocFailed);
+    assert_eq!(e.to_string(), "memory allocation for the value failed");
+    assert!(e.source().is_none());
+    let e: CacheError = CacheError::NotReady;
+    assert_eq!(e.to_string(), "the value is not ready");
+
+    let e: CacheError<Overflow> = CacheError::ComputeFailed(Overflow);
+    assert_eq!(e.to_string(), "the computation failed: overflow");
+    assert!(e.source().unwrap().is::<Overflow>());
+    assert!(e.compute_error().is_some());
+
+    let e: CacheError<Overflow> = CacheError::Expired.widen();
+    assert!(e.source().is_none());
+    assert_eq!(e.to_string(), "the value has expired");
+
+    let e = CacheError::ComputeFailed(3).map(|n| n * 2);
+    assert_eq!(e, CacheError::ComputeFailed(6));
+    assert_eq!(
+        CacheError::<u8>::TimedOut.map(u32::from),
+        CacheError::TimedOut
+    );
+}
+
+#[test]
+fn try_memoize() {
+    use {CacheError, Fallible, Memo, MemoExt, MemoMut, MemoOnce, TryMemoize};
+
+    #[derive(Debug, PartialEq)]
+    struct Half(u32);
+
+    impl TryMemoize for Half {
+        type Param = (u32, CallTracker);
+        type Error = u32;
+        fn try_memoize(p: &(u32, CallTracker)) -> Result<Self, u32> {
+            p.1.incr();
+            if p.0.is_multiple_of(2) {
+                Ok(Half(p.0 / 2))
+            } else {
+                Err(p.0)
+            }
+        }
+    }
+
+    let mut memo: Memo<Fallible<Half>> = Memo::new((3, CallTracker::new()));
+    assert_eq!(memo.try_get_or_compute(), Err(CacheError::ComputeFailed(3)));
+    assert!(!memo.is_ready());
+    assert_eq!(memo.try_ready(), Err(CacheError::ComputeFailed(3)));
+    assert_eq!(memo.param().1.count(), 2);
+
+    memo.param_mut().0 = 4;
+    assert_eq!(memo.try_get_or_compute(), Ok(&Half(2)));
+    assert_eq!(memo.try_get_or_compute(), Ok(&Half(2)));
+    assert_eq!(memo.param().1.count(), 3);
+
+    // `get()` caches the error, but trying again does not
+    let mut memo: MemoExt<Fallible<Half>> = MemoExt::new();
+    let param = (5, CallTracker::new());
+    assert_eq!(memo.get(&param).as_result(), Err(&5));
+    assert!(memo.is_ready());
+    assert_eq!(memo.try_ready(&param), Err(CacheError::ComputeFailed(5)));
+    assert!(!memo.is_ready());
+    assert_eq!(param.1.count(), 2);
+
+    let param = (6, CallTracker::new());
+    let mut memo: MemoOnce<Fallible<Half>> = MemoOnce::new(&param);
+    assert_eq!(memo.try_get_or_compute(), Ok(&Half(3)));
+
+    let mut param = (7, CallTracker::new());
+    let mut memo: MemoMut<Fallible<Half>> = MemoMut::new(&mut param);
+    assert!(memo.try_ready().is_err());
+    memo.param_mut().0 = 8;
+    assert_eq!(memo.try_get_or_compute(), Ok(&Half(4)));
+}
+
+#[test]
+fn error_policy() {
+    use {CacheError, ErrorPolicy, Fallible, Memo, MemoExt, TryMemoize};
+
+    #[derive(Debug, PartialEq)]
+    struct Flaky<const N: u32>;
+
+    impl<const N: u32> TryMemoize for Flaky<N> {
+        type Param = CallTracker;
+        type Error = usize;
+        const ERROR_POLICY: ErrorPolicy = if N == 0 {
+            ErrorPolicy::Cache
+        } else {
+            ErrorPolicy::RetryUpTo(N)
+        };
+        // fails the first 3 times
+        fn try_memoize(p: &CallTracker) -> Result<Self, usize> {
+            p.incr();
+            if p.count() > 3 {
+                Ok(Flaky)
+            } else {
+                Err(p.count())
+            }
+        }
+    }
+
+    let mut memo: Memo<Fallible<Flaky<0>>> = Memo::new(CallTracker::new());
+    assert_eq!(memo.try_get_or_compute(), Err(CacheError::ComputeFailed(1)));
+    assert_eq!(memo.try_get_or_compute(), Err(CacheError::ComputeFailed(1)));
+    assert!(memo.is_ready());
+    assert_eq!(memo.param().count(), 1);
+
+    // modifying the parameter clears the error
+    memo.param_mut();
+    assert_eq!(memo.try_ready(), Err(CacheError::ComputeFailed(2)));
+
+    let mut memo: Memo<Fallible<Flaky<1>>> = Memo::new(CallTracker::new());
+    assert_eq!(memo.try_get_or_compute(), Err(CacheError::ComputeFailed(1)));
+    assert_eq!(memo.try_get_or_compute(), Err(CacheError::ComputeFailed(2)));
+    assert_eq!(memo.try_get_or_compute(), Err(CacheError::ComputeFailed(2)));
+    assert_eq!(memo.try_get().unwrap().failures(), 2);
+
+    let param = CallTracker::new();
+    let mut memo: MemoExt<Fallible<Flaky<5>>> = MemoExt::new();
+    for i in 1..=3 {
+        assert_eq!(
+            memo.try_get_or_compute(&param),
+            Err(CacheError::ComputeFailed(i))
+        );
+    }
+    assert_eq!(memo.try_get_or_compute(&param), Ok(&Flaky));
+    assert_eq!(memo.try_get().unwrap().failures(), 0);
+    assert_eq!(memo.try_get_or_compute(&param), Ok(&Flaky));
+    assert_eq!(param.count(), 4);
+}
+
+/// Poll a future to completion, counting how many times it was pending
+fn block_on<F: core::future::Future>(future: F) -> (F::Output, usize) {
+    use core::task::{Context, Poll};
+    use std::task::Waker;
+
+    let mut future = core::pin::pin!(future);
+    let mut cx = Context::from_waker(Waker::noop());
+    let mut pending = 0;
+    loop {
+        match future.as_mut().poll(&mut cx) {
+            Poll::Ready(output) => return (output, pending),
+            Poll::Pending => pending += 1,
+        }
+    }
+}
+
+/// A future which is pending once, then completes with its value
+struct YieldOnce<T>(Option<T>, bool);
+
+impl<T: Unpin> core::future::Future for YieldOnce<T> {
+    type Output = T;
+
+    fn poll(
+        mut self: core::pin::Pin<&mut Self>,
+        cx: &mut core::task::Context,
+    ) -> core::task::Poll<T> {
+        if self.1 {
+            core::task::Poll::Ready(self.0.take().unwrap())
+        } else {
+            self.1 = true;
+            cx.waker().wake_by_ref();
+            core::task::Poll::Pending
+        }
+    }
+}
+
+#[test]
+fn memo_async() {
+    use core::future::Future;
+    use {Invalidate, MemoAsync, MemoizeAsync};
+
+    #[derive(Debug, PartialEq)]
+    struct Remote(i32);
+
+    impl MemoizeAsync for Remote {
+        type Param = (i32, CallTracker);
+        fn memoize_async(p: &(i32, CallTracker)) -> impl Future<Output = Self> {
+            p.1.incr();
+            YieldOnce(Some(Remote(p.0 * 10)), false)
+        }
+    }
+
+    let mut memo: MemoAsync<Remote> = MemoAsync::new((4, CallTracker::new()));
+    assert_eq!(block_on(memo.get()), (&Remote(40), 1));
+    assert_eq!(block_on(memo.get()), (&Remote(40), 0));
+    assert_eq!(memo.param().1.count(), 1);
+
+    // dropping the future before it completes caches nothing
+    memo.param_mut().0 = 5;
+    {
+        let mut get = core::pin::pin!(memo.get());
+        let mut cx = core::task::Context::from_waker(std::task::Waker::noop());
+        assert!(get.as_mut().poll(&mut cx).is_pending());
+    }
+    assert!(!memo.is_ready());
+    assert_eq!(block_on(memo.get()), (&Remote(50), 1));
+    assert_eq!(memo.param().1.count(), 3);
+
+    memo.invalidate();
+    assert_eq!(memo.try_get(), None);
+}
+
+#[test]
+fn into_future() {
+    use core::future::IntoFuture;
+    use Memo;
+
+    let mut memo: Memo<TestOut> = Memo::new(CallTracker::new());
+    assert_eq!(block_on((&mut memo).into_future()), (&TestOut(MAGIC), 0));
+    assert_eq!(block_on((&mut memo).into_future()), (&TestOut(MAGIC), 0));
+    assert_eq!(memo.param().count(), 1);
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn into_future_async() {
+    use core::future::{Future, IntoFuture};
+    use {MemoAsync, MemoizeAsync};
+
+    struct Doubled(u8);
+
+    impl MemoizeAsync for Doubled {
+        type Param = u8;
+        fn memoize_async(p: &u8) -> impl Future<Output = Self> {
+            YieldOnce(Some(Doubled(p * 2)), false)
+        }
+    }
+
+    let mut memo: MemoAsync<Doubled> = MemoAsync::new(21);
+    assert_eq!(block_on((&mut memo).into_future()).0 .0, 42);
+    assert_eq!(block_on((&mut memo).into_future()).1, 0);
+}
+
+#[cfg(feature = "tokio")]
+#[test]
+fn memo_watch() {
+    use tokio::sync::watch;
+    use {Invalidate, MemoWatch};
+
+    let (tx, rx) = watch::channel(3);
+    let mut memo: MemoWatch<Square> = MemoWatch::new(rx);
+    let before = squares();
+
+    assert_eq!(memo.get().0, 9);
+    assert_eq!(memo.get().0, 9);
+    assert_eq!(squares() - before, 1);
+
+    tx.send(4).unwrap();
+    assert!(!memo.is_ready());
+    assert!(memo.try_get().is_none());
+    assert!(block_on(memo.changed()).0.is_ok());
+    assert_eq!(memo.get().0, 16);
+    assert_eq!(squares() - before, 2);
+
+    // the last parameter stays current once the sender is gone
+    drop(tx);
+    assert!(memo.is_ready());
+    assert!(block_on(memo.changed()).0.is_err());
+
+    memo.invalidate();
+    assert_eq!(memo.get().0, 16);
+    assert_eq!(*memo.into_receiver().borrow(), 4);
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn memo_revalidate() {
+    use {Invalidate, MaybeStale, MemoRevalidate};
+
+    let mut memo: MemoRevalidate<Square> = MemoRevalidate::new(3);
+    let before = squares();
+
+    // nothing to serve yet, so it is computed on this thread
+    assert!(memo.try_get().is_none());
+    assert!(!memo.get_stale_while_revalidate().is_stale());
+    assert_eq!(memo.get().0, 9);
+    assert_eq!(squares() - before, 1);
+
+    *memo.param_mut() = 4;
+    assert!(!memo.is_ready());
+    assert_eq!(memo.try_get(), Some(MaybeStale::Stale(&Square(9))));
+
+    // the stale value is served, and the new one computed in the background
+    assert_eq!(memo.get_stale_while_revalidate().0, 9);
+    assert!(memo.is_revalidating());
+    assert_eq!(memo.get_stale_while_revalidate().0, 9);
+    assert_eq!(memo.get().0, 16);
+    assert!(!memo.is_revalidating());
+    assert_eq!(squares() - before, 1);
+
+    // a value computed for an old parameter stays stale
+    memo.invalidate();
+    assert!(memo.get_stale_while_revalidate().is_stale());
+    *memo.param_mut() = 5;
+    assert!(!memo.is_revalidating());
+    assert_eq!(memo.get().0, 25);
+    assert_eq!(
+        memo.get_stale_while_revalidate(),
+        MaybeStale::Fresh(&Square(25))
+    );
+
+    memo.clear();
+    assert_eq!(memo.get_stale_while_revalidate().into_inner().0, 25);
+    assert_eq!(memo.into_param(), 5);
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn memo_spawn() {
+    use {Invalidate, MemoSpawn};
+
+    let before = squares();
+    let mut memo: MemoSpawn<Square> = MemoSpawn::new(3);
+    assert!(memo.is_computing());
+    assert_eq!(memo.wait().0, 9);
+    assert_eq!(memo.poll(), Some(&Square(9)));
+    assert_eq!(memo.try_get(), Some(&Square(9)));
+    assert!(!memo.is_computing());
+
+    // the new value starts computing on the next poll
+    *memo.param_mut() = 4;
+    assert!(!memo.is_ready() && !memo.is_computing());
+    assert_eq!(memo.poll(), None);
+    assert!(memo.is_computing());
+    while memo.poll().is_none() {}
+    assert_eq!(memo.try_get(), Some(&Square(16)));
+
+    memo.update_param(|p| *p = 5);
+    assert!(memo.is_computing());
+    assert_eq!(memo.wait().0, 25);
+
+    memo.invalidate();
+    assert!(memo.try_get().is_none());
+    assert_eq!(memo.wait().0, 25);
+
+    // nothing was computed on this thread
+    assert_eq!(squares() - before, 0);
+    assert_eq!(memo.into_param(), 5);
+}
+
+#[cfg(feature = "rayon")]
+#[test]
+fn par_warm() {
+    use core::sync::atomic::{AtomicUsize, Ordering};
+    use std::vec::Vec;
+    use {Memo, MemoMut};
+
+    static COMPUTED: AtomicUsize = AtomicUsize::new(0);
+
+    struct Double(u32);
+
+    impl Memoize for Double {
+        type Param = u32;
+        fn memoize(p: &u32) -> Self {
+            COMPUTED.fetch_add(1, Ordering::Relaxed);
+            Double(p * 2)
+        }
+    }
+
+    let mut memos: Vec<Memo<Double>> = (0..50).map(Memo::new).collect();
+    memos[0].ready();
+    assert_eq!(::par_warm(&mut memos), 49);
+    assert_eq!(::par_warm(&mut memos[..]), 0);
+    assert_eq!(COMPUTED.load(Ordering::Relaxed), 50);
+    assert!((0..50).all(|p| memos[p as usize].try_get().unwrap().0 == p * 2));
+
+    let mut params = [1, 2, 3];
+    let mut memos: Vec<MemoMut<Double>> = params.iter_mut().map(MemoMut::new).collect();
+    assert_eq!(::par_warm(&mut memos), 3);
+    assert_eq!(memos[2].get().0, 6);
+}
+
+#[cfg(feature = "rayon")]
+#[test]
+fn memoize_parallel() {
+    use std::vec::Vec;
+    use {MemoExt, MemoizeParallel, Parallel};
+
+    /// The chunks, in the order they were combined
+    struct Chunks(Vec<u32>);
+
+    impl MemoizeParallel for Chunks {
+        type Param = [u32];
+        type Partial = u32;
+
+        fn split(p: &[u32]) -> Vec<&[u32]> {
+            p.chunks(10).collect()
+        }
+
+        fn compute(chunk: &[u32]) -> u32 {
+            chunk.iter().sum()
+        }
+
+        fn combine(partials: Vec<u32>) -> Self {
+            Chunks(partials)
+        }
+    }
+
+    let data: Vec<u32> = (0..100).collect();
+    let mut memo: MemoExt<Parallel<Chunks>> = MemoExt::new();
+    let Chunks(ref sums) = **memo.get(&data[..]);
+    assert_eq!(sums.len(), 10);
+    assert!(sums.windows(2).all(|w| w[1] - w[0] == 100));
+    assert_eq!(sums.iter().sum::<u32>(), 4950);
+
+    let empty: &[u32] = &[];
+    assert!(Parallel::<Chunks>::memoize(empty).into_inner().0.is_empty());
+}
+
+#[cfg(all(feature = "std", not(feature = "loom")))]
+#[test]
+fn memo_lock() {
+    use core::sync::atomic::{AtomicUsize, Ordering};
+    use std::panic;
+    use std::thread;
+    use std::time::Duration;
+    use {CacheError, Invalidate, MemoLock};
+
+    static COMPUTED: AtomicUsize = AtomicUsize::new(0);
+
+    #[derive(Debug)]
+    struct Half(u32);
+
+    impl Memoize for Half {
+        type Param = u32;
+        fn memoize(p: &u32) -> Self {
+            COMPUTED.fetch_add(1, Ordering::Relaxed);
+            assert!(p.is_multiple_of(2), "odd");
+            Half(p / 2)
+        }
+    }
+
+    let mut memo: MemoLock<Half> = MemoLock::new(8);
+    assert_eq!(memo.try_get().unwrap_err(), CacheError::NotReady);
+
+    thread::scope(|s| {
+        for _ in 0..8 {
+            s.spawn(|| assert_eq!(memo.get().unwrap().0, 4));
+        }
+    });
+    assert_eq!(COMPUTED.load(Ordering::Relaxed), 1);
+    assert!(memo.is_ready());
+
+    memo.clear();
+    assert!(!memo.is_ready());
+    *memo.param_mut() = 10;
+    assert_eq!(memo.get().unwrap().0, 5);
+    memo.invalidate();
+    assert_eq!(COMPUTED.load(Ordering::Relaxed), 2);
+
+    // a panic in the computation poisons the lock
+    *memo.param_mut() = 3;
+    assert!(panic::catch_unwind(panic::AssertUnwindSafe(|| memo.get().is_ok())).is_err());
+    assert_eq!(memo.get().unwrap_err(), CacheError::Poisoned);
+    assert_eq!(
+        memo.get_timeout(Duration::ZERO).unwrap_err(),
+        CacheError::Poisoned
+    );
+    assert!(!memo.is_ready());
+
+    // until it is cleared
+    memo.clear();
+    assert!(panic::catch_unwind(panic::AssertUnwindSafe(|| memo.get().is_ok())).is_err());
+    *memo.param_mut() = 12;
+    assert_eq!(memo.get().unwrap().0, 6);
+    assert_eq!(memo.into_param(), 12);
+}
+
+#[cfg(all(feature = "std", not(feature = "loom")))]
+#[test]
+fn memo_lock_timeout() {
+    use core::sync::atomic::{AtomicBool, Ordering};
+    use std::thread;
+    use std::time::Duration;
+    use {CacheError, MemoLock};
+
+    static STARTED: AtomicBool = AtomicBool::new(false);
+
+    #[derive(Debug)]
+    struct Slow(u32);
+
+    impl Memoize for Slow {
+        type Param = u32;
+        fn memoize(p: &u32) -> Self {
+            STARTED.store(true, Ordering::Release);
+            thread::sleep(Duration::from_millis(200));
+            Slow(*p)
+        }
+    }
+
+    let memo: MemoLock<Slow> = MemoLock::new(7);
+    thread::scope(|s| {
+        s.spawn(|| memo.get().unwrap().0);
+        while !STARTED.load(Ordering::Acquire) {
+            thread::yield_now();
+        }
+
+        // the other thread holds the lock while it computes the value
+        let e = memo.get_timeout(Duration::from_millis(10)).unwrap_err();
+        assert_eq!(e, CacheError::TimedOut);
+        assert_eq!(memo.get_timeout(Duration::from_secs(10)).unwrap().0, 7);
+    });
+}
+
+#[cfg(feature = "loom")]
+#[test]
+fn memo_lock_loom() {
+    use loom::sync::atomic::{AtomicUsize, Ordering};
+    use loom::sync::Arc;
+    use loom::thread;
+    use MemoLock;
+
+    /// Counts the computations in its parameter
+    struct Count(usize);
+
+    impl Memoize for Count {
+        type Param = AtomicUsize;
+        fn memoize(p: &AtomicUsize) -> Self {
+            Count(p.fetch_add(1, Ordering::Relaxed) + 1)
+        }
+    }
+
+    loom::model(|| {
+        let memo: Arc<MemoLock<Count>> = Arc::new(MemoLock::new(AtomicUsize::new(0)));
+        let other = memo.clone();
+        let thread = thread::spawn(move || other.get().unwrap().0);
+        assert_eq!(memo.get().unwrap().0, 1);
+        assert_eq!(thread.join().unwrap(), 1);
+    });
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn memo_shared() {
+    use core::sync::atomic::{AtomicUsize, Ordering};
+    use std::thread;
+    use {Invalidate, MemoShared};
+
+    static COMPUTED: AtomicUsize = AtomicUsize::new(0);
+
+    struct Negated(i32);
+
+    impl Memoize for Negated {
+        type Param = i32;
+        fn memoize(p: &i32) -> Self {
+            COMPUTED.fetch_add(1, Ordering::Relaxed);
+            Negated(-p)
+        }
+    }
+
+    let mut memo: MemoShared<Negated> = MemoShared::new(5);
+    assert!(!memo.is_ready());
+    assert!(memo.try_get().is_none());
+
+    thread::scope(|s| {
+        for _ in 0..8 {
+            s.spawn(|| assert_eq!(memo.get().0, -5));
+        }
+    });
+    assert_eq!(COMPUTED.load(Ordering::Relaxed), 1);
+    assert_eq!(memo.try_get().unwrap().0, -5);
+
+    *memo.param_mut() = 6;
+    assert!(!memo.is_ready());
+    assert_eq!(memo.get().0, -6);
+    memo.invalidate();
+    assert!(!memo.is_ready());
+    assert_eq!(COMPUTED.load(Ordering::Relaxed), 2);
+    assert_eq!(memo.into_param(), 6);
+}
+
+#[test]
+fn memo_cell() {
+    use {Invalidate, MemoCell};
+
+    let mut memo: MemoCell<TestOut> = MemoCell::new(CallTracker::new());
+    assert!(!memo.is_ready());
+    assert!(memo.try_get().is_none());
+
+    {
+        let shared = &memo;
+        assert_eq!(shared.get(), &TestOut(MAGIC));
+        assert_eq!(shared.get(), &TestOut(MAGIC));
+    }
+    assert!(memo.is_ready());
+    assert_eq!(memo.param().count(), 1);
+
+    memo.param_mut();
+    assert!(!memo.is_ready());
+    memo.get();
+    memo.invalidate();
+    assert_eq!(memo.try_get(), None);
+    assert_eq!(memo.get(), &TestOut(MAGIC));
+    assert_eq!(memo.into_param().count(), 3);
+}
+
+#[test]
+fn memo_ref_cell() {
+    use {Invalidate, MemoRefCell};
+
+    let mut memo: MemoRefCell<TestOut> = MemoRefCell::new(CallTracker::new());
+    assert!(memo.try_get().is_none());
+
+    {
+        let shared = &memo;
+        let a = shared.get();
+        let b = shared.get();
+        assert_eq!(*a, TestOut(MAGIC));
+        assert_eq!(*b, TestOut(MAGIC));
+    }
+    assert_eq!(memo.param().count(), 1);
+
+    memo.clear();
+    assert!(!memo.is_ready());
+    assert_eq!(*memo.get(), TestOut(MAGIC));
+    memo.invalidate();
+    assert!(memo.try_get().is_none());
+    memo.get();
+    memo.param_mut();
+    assert!(!memo.is_ready());
+    assert_eq!(memo.into_param().count(), 3);
+}
+
+#[test]
+#[should_panic]
+fn memo_ref_cell_clear_borrowed() {
+    use MemoRefCell;
+
+    let memo: MemoRefCell<TestOut> = MemoRefCell::new(CallTracker::new());
+    let _value = memo.get();
+    memo.clear();
+}
+
+#[test]
+fn memo_copy() {
+    use {Invalidate, MemoCopy};
+
+    let mut memo: MemoCopy<TestOut> = MemoCopy::new(CallTracker::new());
+    assert_eq!(memo.try_get(), None);
+
+    {
+        let shared = &memo;
+        assert_eq!(shared.get(), TestOut(MAGIC));
+        assert_eq!(shared.get(), TestOut(MAGIC));
+        shared.clear();
+        assert!(!shared.is_ready());
+        assert_eq!(shared.get(), TestOut(MAGIC));
+    }
+    assert_eq!(memo.param().count(), 2);
+
+    memo.invalidate();
+    assert_eq!(memo.try_get(), None);
+    memo.get();
+    memo.param_mut();
+    assert!(!memo.is_ready());
+    assert_eq!(memo.into_param().count(), 3);
+}
+
+#[cfg(all(feature = "atomic", not(feature = "loom")))]
+#[test]
+fn memo_atomic() {
+    use core::sync::atomic::{AtomicUsize, Ordering};
+    use std::panic;
+    use std::thread;
+    use {Invalidate, MemoAtomic};
+
+    static COMPUTED: AtomicUsize = AtomicUsize::new(0);
+
+    #[derive(Debug)]
+    struct Root(u32);
+
+    impl Memoize for Root {
+        type Param = u32;
+        fn memoize(p: &u32) -> Self {
+            COMPUTED.fetch_add(1, Ordering::Relaxed);
+            let root = (0..).find(|r| r * r >= *p).unwrap();
+            assert!(root * root == *p, "not a square");
+            Root(root)
+        }
+    }
+
+    let mut memo: MemoAtomic<Root> = MemoAtomic::new(49);
+    assert!(memo.try_get().is_none());
+
+    thread::scope(|s| {
+        for _ in 0..8 {
+            s.spawn(|| assert_eq!(memo.get().0, 7));
+        }
+    });
+    assert_eq!(COMPUTED.load(Ordering::Relaxed), 1);
+    assert!(memo.is_ready());
+
+    *memo.param_mut() = 64;
+    assert!(!memo.is_ready());
+    assert_eq!(memo.get().0, 8);
+    memo.invalidate();
+    assert!(memo.try_get().is_none());
+
+    // a panic in the computation gives up the claim on the value
+    *memo.param_mut() = 2;
+    assert!(panic::catch_unwind(panic::AssertUnwindSafe(|| memo.get().0)).is_err());
+    assert!(!memo.is_ready());
+    *memo.param_mut() = 81;
+    assert_eq!(memo.get().0, 9);
+    assert_eq!(COMPUTED.load(Ordering::Relaxed), 4);
+    assert_eq!(memo.into_param(), 81);
+}
+
+#[cfg(all(feature = "atomic", feature = "loom"))]
+#[test]
+fn memo_atomic_loom() {
+    use loom::sync::atomic::{AtomicUsize, Ordering};
+    use loom::sync::Arc;
+    use loom::thread;
+    use MemoAtomic;
+
+    /// Counts the computations in its parameter
+    struct Count(usize);
+
+    impl Memoize for Count {
+        type Param = AtomicUsize;
+        fn memoize(p: &AtomicUsize) -> Self {
+            Count(p.fetch_add(1, Ordering::Relaxed) + 1)
+        }
+    }
+
+    loom::model(|| {
+        let memo: Arc<MemoAtomic<Count>> = Arc::new(MemoAtomic::new(AtomicUsize::new(0)));
+        let other = memo.clone();
+        let thread = thread::spawn(move || other.get().0);
+        assert_eq!(memo.get().0, 1);
+        assert_eq!(thread.join().unwrap(), 1);
+    });
+}
+
+#[cfg(feature = "arc-swap")]
+#[test]
+fn memo_swap() {
+    use std::sync::Arc;
+    use std::thread;
+    use {Invalidate, MemoSwap};
+
+    let mut memo: MemoSwap<Square> = MemoSwap::new(3);
+    let before = squares();
+    assert!(memo.try_get().is_none());
+
+    let first = memo.get_shared();
+    assert_eq!(first.0, 9);
+    assert!(Arc::ptr_eq(&first, &memo.get_shared()));
+    assert_eq!(squares() - before, 1);
+
+    // readers keep the old value after a refresh
+    let refreshed = memo.refresh();
+    assert!(!Arc::ptr_eq(&first, &refreshed));
+    assert!(Arc::ptr_eq(&refreshed, &memo.try_get().unwrap()));
+    assert_eq!(squares() - before, 2);
+
+    thread::scope(|s| {
+        for _ in 0..4 {
+            s.spawn(|| assert_eq!(memo.get_shared().0, 9));
+        }
+    });
+
+    memo.clear();
+    assert!(!memo.is_ready());
+    assert_eq!(first.0, 9);
+    *memo.param_mut() = 4;
+    assert_eq!(memo.get_shared().0, 16);
+    memo.invalidate();
+    assert!(memo.try_get().is_none());
+    assert_eq!(memo.into_param(), 4);
+}
+
+#[cfg(feature = "critical-section")]
+#[test]
+fn memo_critical() {
+    use core::sync::atomic::{AtomicUsize, Ordering};
+    use std::thread;
+    use {Invalidate, MemoCritical};
+
+    static COMPUTED: AtomicUsize = AtomicUsize::new(0);
+
+    #[derive(Debug, PartialEq)]
+    struct Inverted(u8);
+
+    impl Memoize for Inverted {
+        type Param = u8;
+        fn memoize(p: &u8) -> Self {
+            COMPUTED.fetch_add(1, Ordering::Relaxed);
+            Inverted(!p)
+        }
+    }
+
+    static MEMO: MemoCritical<Inverted> = MemoCritical::new(0x0f);
+
+    assert!(!MEMO.is_ready());
+    thread::scope(|s| {
+        for _ in 0..4 {
+            s.spawn(|| assert_eq!(MEMO.get().0, 0xf0));
+        }
+    });
+    assert_eq!(MEMO.try_get(), Some(&Inverted(0xf0)));
+    assert!(COMPUTED.load(Ordering::Relaxed) >= 1);
+
+    let mut memo: MemoCritical<Inverted> = MemoCritical::new(0);
+    assert_eq!(memo.get().0, 0xff);
+    *memo.param_mut() = 0xff;
+    assert!(!memo.is_ready());
+    assert_eq!(memo.get().0, 0);
+    memo.invalidate();
+    assert!(memo.try_get().is_none());
+    assert_eq!(memo.into_param(), 0xff);
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn static_memo() {
+    use core::sync::atomic::{AtomicUsize, Ordering};
+    use std::thread;
+    use {MutexLock, StaticMemo};
+
+    static COMPUTED: AtomicUsize = AtomicUsize::new(0);
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct Doubled(u64);
+
+    impl Memoize for Doubled {
+        type Param = u64;
+        fn memoize(p: &u64) -> Self {
+            COMPUTED.fetch_add(1, Ordering::Relaxed);
+            Doubled(p * 2)
+        }
+    }
+
+    static MEMO: StaticMemo<Doubled, u64, MutexLock> = StaticMemo::new(21);
+
+    assert!(!MEMO.is_ready());
+    thread::scope(|s| {
+        for _ in 0..8 {
+            s.spawn(|| assert_eq!(MEMO.get(), Doubled(42)));
+        }
+    });
+    assert_eq!(COMPUTED.load(Ordering::Relaxed), 1);
+
+    MEMO.update_param(|p| *p += 1);
+    assert!(!MEMO.is_ready());
+    assert_eq!(MEMO.with(|d| d.0), 44);
+    assert_eq!(MEMO.param(), 22);
+    MEMO.set_param(1);
+    MEMO.clear();
+    assert_eq!(MEMO.get().0, 2);
+    assert_eq!(COMPUTED.load(Ordering::Relaxed), 3);
+}
+
+#[cfg(feature = "critical-section")]
+#[test]
+#[should_panic(expected = "accessed again")]
+fn static_memo_reentrant() {
+    use {CriticalSectionLock, StaticMemo};
+
+    struct Byte(u8);
+
+    impl Memoize for Byte {
+        type Param = u8;
+        fn memoize(p: &u8) -> Self {
+            Byte(*p)
+        }
+    }
+
+    static MEMO: StaticMemo<Byte, u8, CriticalSectionLock> = StaticMemo::new(0);
+
+    MEMO.with(|byte| {
+        assert_eq!(byte.0, 0);
+        MEMO.clear();
+    });
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn static_memo_macro() {
+    ::static_memo!(SHORT: Square = 3);
+
+    ::static_memo! {
+        /// Doc comments are allowed
+        static FIRST: Square = 4;
+        pub(crate) static SECOND: Square = 5;
+    }
+
+    assert_eq!(SHORT.get(), Square(9));
+    assert_eq!(FIRST.with(|s| s.0), 16);
+    SECOND.set_param(6);
+    assert_eq!(SECOND.get(), Square(36));
+    SECOND.clear();
+    assert!(!SECOND.is_ready());
+}
+
+#[cfg(feature = "derive")]
+#[test]
+fn derive_memoize() {
+    use std::string::{String, ToString};
+    use Memo;
+
+    #[derive(Memoize)]
+    #[memoize(param = (i64, i64))]
+    struct Stats {
+        #[memoize(p.0 + p.1)]
+        sum: i64,
+        #[memoize((p.0 * p.1).to_string())]
+        product: String,
+    }
+
+    #[derive(Memoize)]
+    #[memoize(param = str)]
+    struct Lengths(
+        #[memoize(p.len())] usize,
+        #[memoize(p.chars().count())] usize,
+    );
+
+    #[derive(Memoize)]
+    #[memoize(param = T)]
+    struct Twice<T: Clone>(#[memoize((p.clone(), p.clone()))] (T, T));
+
+    let mut stats: Memo<Stats, _> = Memo::new((3, 4));
+    assert_eq!(stats.get().sum, 7);
+    assert_eq!(stats.get().product, "12");
+    stats.param_mut().1 = 5;
+    assert_eq!(stats.get().sum, 8);
+
+    let lengths = Lengths::memoize("mémo");
+    assert_eq!((lengths.0, lengths.1), (5, 4));
+
+    let mut twice: Memo<Twice<char>, _> = Memo::new('a');
+    assert_eq!(twice.get().0, ('a', 'a'));
+}
+
+#[cfg(feature = "derive")]
+#[test]
+fn memo_attribute() {
+    use memo;
+
+    #[memo]
+    fn sum_of_squares(p: &[i64]) -> i64 {
+        p.iter().map(|x| x * x).sum()
+    }
+
+    #[memo(name = Halved)]
+    fn half(p: &i64) -> i64 {
+        p / 2
+    }
+
+    let mut memo: SumOfSquaresMemo<std::vec::Vec<i64>> = SumOfSquaresMemo::new(vec![1, 2, 3]);
+    assert_eq!(memo.get().0, 14);
+    memo.param_mut().push(4);
+    assert_eq!(memo.get().0, 30);
+    assert_eq!(sum_of_squares(&[5]), 25);
+
+    let mut half: HalvedMemo = HalvedMemo::new(9);
+    assert_eq!(half.get().0, 4);
+}
+
+#[test]
+fn memoize_macro() {
+    use Memo;
+
+    ::memoize!(struct Total(i64) from [i64] = |p| Total(p.iter().sum()));
+
+    ::memoize! {
+        /// Attributes and trailing commas are allowed
+        #[derive(Debug, PartialEq)]
+        pub(crate) struct MinMax(pub i64, pub i64,) from (i64, i64) = |&(a, b)| {
+            MinMax(a.min(b), a.max(b))
+        };
+    }
+
+    let mut total: Memo<Total, std::vec::Vec<i64>> = Memo::new(vec![1, 2, 3]);
+    assert_eq!(total.get().0, 6);
+    total.param_mut().push(4);
+    assert_eq!(total.get().0, 10);
+
+    let mut min_max: Memo<MinMax> = Memo::new((7, -2));
+    assert_eq!(min_max.get(), &MinMax(-2, 7));
+}
+
+#[test]
+fn memo_fn() {
+    use core::cell::Cell;
+    use {Invalidate, MemoFn};
+
+    let calls = Cell::new(0);
+    let mut memo = MemoFn::new(3i64, |p: &i64| {
+        calls.set(calls.get() + 1);
+        p * p
+    });
+
+    assert!(memo.try_get().is_none());
+    assert_eq!(*memo.get(), 9);
+    assert_eq!(*memo.get(), 9);
+    assert_eq!(calls.get(), 1);
+
+    *memo.param_mut() = 4;
+    assert!(!memo.is_ready());
+    assert_eq!(*memo.get(), 16);
+    assert_eq!(calls.get(), 2);
+
+    memo.invalidate();
+    assert_eq!(memo.try_get(), None);
+    assert_eq!(*memo.get(), 16);
+    assert_eq!(calls.get(), 3);
+
+    assert_eq!(
+        format!("{:?}", memo),
+        "MemoFn { value: Some(16), param: 4, .. }"
+    );
+    assert_eq!(memo.into_param(), 4);
+}
+
+#[test]
+fn from_memo() {
+    use {FromMemo, Memo};
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct Digits(u32);
+
+    impl From<&u64> for Digits {
+        fn from(n: &u64) -> Self {
+            let mut digits = 1;
+            let mut n = *n;
+            while n >= 10 {
+                n /= 10;
+                digits += 1;
+            }
+            Digits(digits)
+        }
+    }
+
+    let mut memo: Memo<FromMemo<Digits, u64>> = Memo::new(12_345);
+    assert_eq!(memo.get().0, 5);
+    *memo.param_mut() = 7;
+    assert_eq!(**memo.get(), Digits(1));
+    assert_eq!(format!("{:?}", memo.get()), "FromMemo(Digits(1))");
+    assert_eq!(memo.get().clone().into_inner(), Digits(1));
+}
@@ -0,0 +1,192 @@
+use core::borrow::Borrow;
+use core::fmt;
+
+use {Memo, Memoize};
+
+/// State of a `TypedMemo`: whether its value has been computed
+///
+/// Implemented by `Empty` and `Computed`. It cannot be implemented outside of
+/// this crate.
+pub trait MemoState: sealed::Sealed {
+    /// What the memo holds for a value of type `T` in this state
+    type Value<T>;
+}
+
+/// State of a `TypedMemo` without a value
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Empty {}
+
+/// State of a `TypedMemo` with a computed value
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Computed {}
+
+impl MemoState for Empty {
+    type Value<T> = ();
+}
+
+impl MemoState for Computed {
+    type Value<T> = T;
+}
+
+mod sealed {
+    pub trait Sealed {}
+
+    impl Sealed for super::Empty {}
+    impl Sealed for super::Computed {}
+}
+
+/// Memoized value which tracks whether it has been computed in its type
+///
+/// Like `Memo`, this holds ownership over the parameter for its computation.
+/// But instead of checking at runtime whether there is a cached value, the
+/// state is part of the type: `ready()` consumes a `TypedMemo<_, _, Empty>`
+/// and returns a `TypedMemo<_, _, Computed>`, which has a `get()` that takes
+/// `&self` and cannot fail. Changing the parameter goes back to `Empty`.
+///
+/// This suits pipeline-style code, where a value is computed once in one
+/// stage and then shared with the next stages, which don't need to mutate it.
+///
+/// ## Example
+///
+/// ```
+/// use core_memo::{Computed, Memoize, TypedMemo};
+///
+/// struct Sum(i32);
+///
+/// impl Memoize for Sum {
+///     type Param = [i32];
+///     fn memoize(p: &[i32]) -> Self {
+///         Sum(p.iter().sum())
+///     }
+/// }
+///
+/// fn report(memo: &TypedMemo<Sum, Vec<i32>, Computed>) -> String {
+///     format!("{:?} adds up to {}", memo.param(), memo.get().0)
+/// }
+///
+/// let memo: TypedMemo<Sum, Vec<i32>> = TypedMemo::new(vec![1, 2]);
+/// let memo = memo.ready();
+/// assert_eq!(report(&memo), "[1, 2] adds up to 3");
+///
+/// let memo = memo.update_param(|p| p.push(3)).ready();
+/// assert_eq!(report(&memo), "[1, 2, 3] adds up to 6");
+/// ```
+///
+pub struct TypedMemo<T, P = <T as Memoize>::Param, S = Empty>
+where
+    T: Memoize,
+    P: Borrow<T::Param>,
+    S: MemoState,
+{
+    value: S::Value<T>,
+    param: P,
+}
+
+impl<T: Memoize, P: Borrow<T::Param>, S: MemoState> TypedMemo<T, P, S> {
+    /// Get a reference to the parameter used for the computation
+    pub fn param(&self) -> &P {
+        &self.param
+    }
+
+    /// Clears any cached value
+    pub fn clear(self) -> TypedMemo<T, P, Empty> {
+        TypedMemo {
+            value: (),
+            param: self.param,
+        }
+    }
+
+    /// Modify the parameter used for the computation
+    ///
+    /// This clears any cached value.
+    pub fn update_param<F>(self, op: F) -> TypedMemo<T, P, Empty>
+    where
+        F: FnOnce(&mut P),
+    {
+        let mut memo = self.clear();
+        op(&mut memo.param);
+        memo
+    }
+
+    /// Take the parameter out of the memo
+    pub fn into_param(self) -> P {
+        self.param
+    }
+}
+
+impl<T: Memoize, P: Borrow<T::Param>> TypedMemo<T, P, Empty> {
+    /// Creates a new `TypedMemo` instance, without a value
+    pub fn new(p: P) -> Self {
+        Self {
+            value: (),
+            param: p,
+        }
+    }
+
+    /// Get a mutable reference to the parameter used for the computation
+    ///
+    /// This is only possible without a value, so that it cannot get stale.
+    pub fn param_mut(&mut self) -> &mut P {
+        &mut self.param
+    }
+
+    /// Compute the value
+    pub fn ready(self) -> TypedMemo<T, P, Computed> {
+        TypedMemo {
+            value: T::memoize(self.param.borrow()),
+            param: self.param,
+        }
+    }
+}
+
+impl<T: Memoize, P: Borrow<T::Param>> TypedMemo<T, P, Computed> {
+    /// Get the value
+    pub fn get(&self) -> &T {
+        &self.value
+    }
+
+    /// Force the value to be recomputed
+    pub fn update(self) -> Self {
+        self.clear().ready()
+    }
+
+    /// Take the value and the parameter out of the memo
+    pub fn into_parts(self) -> (T, P) {
+        (self.value, self.param)
+    }
+}
+
+impl<T: Memoize, P: Borrow<T::Param>> From<TypedMemo<T, P, Empty>> for Memo<T, P> {
+    fn from(memo: TypedMemo<T, P, Empty>) -> Self {
+        Memo::new(memo.param)
+    }
+}
+
+impl<T: Memoize, P: Borrow<T::Param>> From<TypedMemo<T, P, Computed>> for Memo<T, P> {
+    fn from(memo: TypedMemo<T, P, Computed>) -> Self {
+        let mut m = Memo::new(memo.param);
+        m.value = Some(memo.value);
+        m
+    }
+}
+
+impl<T: Memoize, P: Borrow<T::Param> + fmt::Debug> fmt::Debug for TypedMemo<T, P, Empty> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("TypedMemo")
+            .field("param", &self.param)
+            .finish()
+    }
+}
+
+impl<T, P> fmt::Debug for TypedMemo<T, P, Computed>
+where
+    T: Memoize + fmt::Debug,
+    P: Borrow<T::Param> + fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("TypedMemo")
+            .field("value", &self.value)
+            .field("param", &self.param)
+            .finish()
+    }
+}
@@ -0,0 +1,242 @@
+use core::fmt;
+use core::future::Future;
+use core::pin::Pin;
+use core::task::{Context, Poll};
+use tokio::sync::watch::error::RecvError;
+use tokio::sync::watch::Receiver;
+
+use {Invalidate, Memoize};
+
+/// Memoized value whose parameter is published over a `tokio` watch channel
+///
+/// Requires the `tokio` feature.
+///
+/// This holds the receiving end of a `tokio::sync::watch` channel, which
+/// carries the parameter for the computation. Whenever the sender publishes a
+/// new parameter, the cached value is invalidated, and the next call to
+/// `get()` computes it for the new parameter.
+///
+/// The parameter is borrowed from the channel while the value is computed,
+/// which blocks the sender from publishing a new one in the meantime (see
+/// `Receiver::borrow()`). Keep that in mind for slow computations.
+///
+/// ## Example
+///
+/// ```
+/// extern crate tokio;
+/// # extern crate core_memo;
+///
+/// use core_memo::{Memoize, MemoWatch};
+/// use tokio::sync::watch;
+///
+/// struct Greeting(String);
+///
+/// impl Memoize for Greeting {
+///     type Param = String;
+///     fn memoize(name: &String) -> Self {
+///         Greeting(format!("hello, {}", name))
+///     }
+/// }
+///
+/// # fn main() {
+/// let (tx, rx) = watch::channel(String::from("world"));
+/// let mut memo: MemoWatch<Greeting> = MemoWatch::new(rx);
+///
+/// assert_eq!(memo.get().0, "hello, world");
+///
+/// tx.send(String::from("tokio")).unwrap();
+/// assert!(!memo.is_ready());
+/// assert_eq!(memo.get().0, "hello, tokio");
+/// # }
+/// ```
+///
+pub struct MemoWatch<T: Memoize>
+where
+    T::Param: Sized,
+{
+    value: Option<T>,
+    receiver: Receiver<T::Param>,
+}
+
+impl<T: Memoize> MemoWatch<T>
+where
+    T::Param: Sized,
+{
+    /// Creates a new `MemoWatch` instance
+    ///
+    /// The value is computed from the current parameter in the channel, when
+    /// it is first needed.
+    pub fn new(receiver: Receiver<T::Param>) -> Self {
+        Self {
+            value: None,
+            receiver,
+        }
+    }
+
+    /// Clears any cached value
+    ///
+    /// The value will be reevaluated the next time it is needed.
+    pub fn clear(&mut self) {
+        self.value = None;
+    }
+
+    /// Check if a new parameter has been published, which the cached value
+    /// was not computed from
+    ///
+    /// If the sender has been dropped, the last parameter stays current.
+    fn is_stale(&self) -> bool {
+        self.receiver.has_changed().unwrap_or(false)
+    }
+
+    /// Check if there is a cached value for the current parameter
+    ///
+    /// If this method returns `true`, the next call to `get()` will return a
+    /// stored memoized value, unless a new parameter is published first.
+    pub fn is_ready(&self) -> bool {
+        self.value.is_some() && !self.is_stale()
+    }
+
+    /// Get the value if it is available
+    ///
+    /// Returns `None` if the value needs to be computed, including when a new
+    /// parameter has been published.
+    pub fn try_get(&self) -> Option<&T> {
+        if self.is_stale() {
+            return None;
+        }
+        self.value.as_ref()
+    }
+
+    /// If the value is not ready, compute it and cache it
+    ///
+    /// If a new parameter has been published, any cached value is cleared
+    /// first.
+    pub fn ready(&mut self) {
+        if self.is_stale() {
+            self.clear();
+        }
+        if self.value.is_none() {
+            let param = self.receiver.borrow_and_update();
+            self.value = Some(T::memoize(&param));
+        }
+    }
+
+    /// Get the value
+    ///
+    /// If the value has already been computed for the current parameter, this
+    /// function returns the cached value. If not, it is computed and cached
+    /// for future use.
+    pub fn get(&mut self) -> &T {
+        self.ready();
+        self.value.as_ref().unwrap()
+    }
+
+    /// Wait for a new parameter to be published
+    ///
+    /// The returned future completes immediately if there already is one,
+    /// which the cached value was not computed from, and clears the value. It
+    /// fails if the sender has been dropped.
+    ///
+    /// Use this to recompute the value whenever the parameter changes:
+    ///
+    /// ```
+    /// # extern crate tokio;
+    /// # extern crate core_memo;
+    /// # use tokio::sync::watch;
+    /// # use core_memo::{Memoize, MemoWatch};
+    /// # struct Len(usize);
+    /// # impl Memoize for Len {
+    /// #     type Param = String;
+    /// #     fn memoize(p: &String) -> Self {
+    /// #         Len(p.len())
+    /// #     }
+    /// # }
+    /// # fn block_on<F: std::future::Future>(f: F) -> F::Output {
+    /// #     let mut f = std::pin::pin!(f);
+    /// #     let mut cx = std::task::Context::from_waker(std::task::Waker::noop());
+    /// #     loop {
+    /// #         if let std::task::Poll::Ready(v) = f.as_mut().poll(&mut cx) {
+    /// #             return v;
+    /// #         }
+    /// #     }
+    /// # }
+    /// # fn main() {
+    /// let (tx, rx) = watch::channel(String::new());
+    /// let mut memo: MemoWatch<Len> = MemoWatch::new(rx);
+    ///
+    /// tx.send(String::from("abc")).unwrap();
+    /// drop(tx);
+    ///
+    /// let mut lengths = vec![];
+    /// while block_on(memo.changed()).is_ok() {
+    ///     lengths.push(memo.get().0);
+    /// }
+    /// assert_eq!(lengths, [3]);
+    /// # }
+    /// ```
+    pub fn changed(&mut self) -> impl Future<Output = Result<(), RecvError>> + '_ {
+        Changed {
+            value: &mut self.value,
+            future: self.receiver.changed(),
+        }
+    }
+
+    /// Get a reference to the receiver
+    pub fn receiver(&self) -> &Receiver<T::Param> {
+        &self.receiver
+    }
+
+    /// Stop watching and get the receiver back, dropping the memo
+    pub fn into_receiver(self) -> Receiver<T::Param> {
+        self.receiver
+    }
+}
+
+impl<T: Memoize> Invalidate for MemoWatch<T>
+where
+    T::Param: Sized,
+{
+    fn invalidate(&mut self) {
+        self.clear()
+    }
+}
+
+impl<T: Memoize + fmt::Debug> fmt::Debug for MemoWatch<T>
+where
+    T::Param: Sized + fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("MemoWatch")
+            .field("value", &self.value)
+            .field("receiver", &self.receiver)
+            .finish()
+    }
+}
+
+/// The future returned by `MemoWatch::changed()`
+///
+/// Waiting for a change marks the new parameter as seen, so the value must be
+/// cleared here, or `get()` would not know that it is stale.
+struct Changed<'a, T, F> {
+    value: &'a mut Option<T>,
+    future: F,
+}
+
+impl<'a, T, F: Future<Output = Result<(), RecvError>>> Future for Changed<'a, T, F> {
+    type Output = Result<(), RecvError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        // SAFETY: `future` is pinned along with `self`, and never moved
+        let this = unsafe { self.get_unchecked_mut() };
+        let future = unsafe { Pin::new_unchecked(&mut this.future) };
+
+        let result = match future.poll(cx) {
+            Poll::Ready(result) => result,
+            Poll::Pending => return Poll::Pending,
+        };
+        if result.is_ok() {
+            *this.value = None;
+        }
+        Poll::Ready(result)
+    }
+}
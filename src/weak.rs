@@ -0,0 +1,187 @@
+use alloc::rc::{self, Rc};
+use alloc::sync::{self, Arc};
+use core::borrow::Borrow;
+use core::fmt;
+
+use {Invalidate, Memoize, SharedPtr};
+
+/// Shared pointers which can be downgraded to weak pointers
+///
+/// Requires the `alloc` feature.
+///
+/// Implemented for `Arc` and `Rc`.
+pub trait Downgrade: SharedPtr
+where
+    Self::Target: Sized,
+{
+    /// The weak counterpart of the pointer
+    type Weak;
+
+    /// Allocate a value, and get a pointer to it
+    fn new(value: Self::Target) -> Self;
+
+    /// Get a weak pointer to the same allocation
+    fn downgrade(this: &Self) -> Self::Weak;
+
+    /// Get a strong pointer back, if the value has not been dropped
+    fn upgrade(weak: &Self::Weak) -> Option<Self>;
+}
+
+impl<T> Downgrade for Arc<T> {
+    type Weak = sync::Weak<T>;
+
+    fn new(value: T) -> Self {
+        Arc::new(value)
+    }
+
+    fn downgrade(this: &Self) -> sync::Weak<T> {
+        Arc::downgrade(this)
+    }
+
+    fn upgrade(weak: &sync::Weak<T>) -> Option<Self> {
+        weak.upgrade()
+    }
+}
+
+impl<T> Downgrade for Rc<T> {
+    type Weak = rc::Weak<T>;
+
+    fn new(value: T) -> Self {
+        Rc::new(value)
+    }
+
+    fn downgrade(this: &Self) -> rc::Weak<T> {
+        Rc::downgrade(this)
+    }
+
+    fn upgrade(weak: &rc::Weak<T>) -> Option<Self> {
+        weak.upgrade()
+    }
+}
+
+/// Memoized value which is owned by its users, and only weakly referenced
+///
+/// Requires the `alloc` feature.
+///
+/// Like `Memo`, this owns the parameter for the computation. But `get()`
+/// returns the value in a shared pointer (of type `S`, an `Rc` by default),
+/// and the memo only keeps a weak pointer to it. As long as someone holds on
+/// to the value, `get()` returns it again. Once all of them have dropped it,
+/// it is freed, and `get()` computes it again.
+///
+/// Use this for huge values, which should stay in memory only for as long as
+/// they are in use: the cache is never the reason a value is kept alive.
+///
+/// ## Example
+///
+/// ```
+/// use std::rc::Rc;
+/// use core_memo::{Memoize, MemoWeak};
+///
+/// struct Texture(Vec<u8>);
+///
+/// impl Memoize for Texture {
+///     type Param = usize;
+///     fn memoize(size: &usize) -> Self {
+///         Texture(vec![0; size * size * 4])
+///     }
+/// }
+///
+/// let mut memo: MemoWeak<Texture> = MemoWeak::new(512);
+///
+/// let texture = memo.get();
+/// let again = memo.get();
+/// assert!(Rc::ptr_eq(&texture, &again));
+///
+/// // all users are done with it, so it is freed
+/// drop((texture, again));
+/// assert!(!memo.is_ready());
+/// ```
+///
+pub struct MemoWeak<T, P = <T as Memoize>::Param, S: Downgrade<Target = T> = Rc<T>> {
+    value: Option<S::Weak>,
+    param: P,
+}
+
+impl<T, P, S: Downgrade<Target = T>> MemoWeak<T, P, S> {
+    /// Creates a new `MemoWeak` instance
+    pub fn new(p: P) -> Self {
+        Self {
+            value: None,
+            param: p,
+        }
+    }
+
+    /// Forgets the value
+    ///
+    /// The value is not dropped while its users hold on to it, but the next
+    /// call to `get()` computes a new one.
+    pub fn clear(&mut self) {
+        self.value = None;
+    }
+
+    /// Check if the value is still held by someone
+    ///
+    /// If this method returns `true`, the next call to `get()` will return the
+    /// same value, unless all of its users drop it first.
+    pub fn is_ready(&self) -> bool {
+        self.try_get().is_some()
+    }
+
+    /// Get the value if it is still held by someone
+    pub fn try_get(&self) -> Option<S> {
+        self.value.as_ref().and_then(S::upgrade)
+    }
+
+    /// Get a reference to the parameter used for the computation
+    pub fn param(&self) -> &P {
+        &self.param
+    }
+
+    /// Get a mutable reference to the parameter used for the computation
+    ///
+    /// This forgets the value.
+    pub fn param_mut(&mut self) -> &mut P {
+        self.clear();
+        &mut self.param
+    }
+
+    /// Get the parameter back, dropping the memo
+    pub fn into_param(self) -> P {
+        self.param
+    }
+}
+
+impl<T: Memoize, P: Borrow<T::Param>, S: Downgrade<Target = T>> MemoWeak<T, P, S> {
+    /// Get the value
+    ///
+    /// If the last value is still held by someone, returns it. If not, a new
+    /// one is computed, which is only kept alive by the returned pointer.
+    pub fn get(&mut self) -> S {
+        if let Some(value) = self.try_get() {
+            return value;
+        }
+
+        let value = S::new(T::memoize(self.param.borrow()));
+        self.value = Some(S::downgrade(&value));
+        value
+    }
+}
+
+impl<T, P, S: Downgrade<Target = T>> Invalidate for MemoWeak<T, P, S> {
+    fn invalidate(&mut self) {
+        self.clear()
+    }
+}
+
+impl<T: fmt::Debug, P: fmt::Debug, S> fmt::Debug for MemoWeak<T, P, S>
+where
+    S: Downgrade<Target = T> + fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("MemoWeak")
+            .field("value", &self.try_get())
+            .field("param", &self.param)
+            .finish()
+    }
+}
@@ -0,0 +1,71 @@
+use core::ops::Deref;
+
+use stable_deref_trait::StableDeref;
+use yoke::{Yoke, Yokeable};
+
+use Memoize;
+
+/// Represents a zero-copy computation, whose output borrows from a cart
+///
+/// Requires the `yoke` feature.
+///
+/// Implement this on the `'static` form of a `Yokeable` type, to make
+/// `Yoke<Self, C>` implement `Memoize`, with the cart `C` as the parameter.
+/// This lets you cache zero-copy views (such as deserialized data borrowing
+/// from a shared or memory-mapped buffer) in any of the memoization types.
+///
+/// Every computation clones the cart, so it should be cheap to clone (like
+/// `Rc` or `Arc`).
+///
+/// ## Example
+///
+/// ```
+/// extern crate yoke;
+/// # extern crate core_memo;
+///
+/// use core_memo::{Memo, MemoizeYoke};
+/// use std::rc::Rc;
+/// use yoke::{Yoke, Yokeable};
+///
+/// #[derive(Yokeable)]
+/// struct Header<'a> {
+///     title: &'a str,
+///     fields: Vec<&'a str>,
+/// }
+///
+/// impl MemoizeYoke<Rc<str>> for Header<'static> {
+///     fn memoize(p: &str) -> Header<'_> {
+///         let mut lines = p.lines();
+///         Header {
+///             title: lines.next().unwrap_or(""),
+///             fields: lines.collect(),
+///         }
+///     }
+/// }
+///
+/// # fn main() {
+/// let data: Rc<str> = "Title\nfoo\nbar".into();
+/// let mut memo: Memo<Yoke<Header<'static>, Rc<str>>> = Memo::new(data);
+///
+/// let header = memo.get().get();
+/// assert_eq!(header.title, "Title");
+/// assert_eq!(header.fields, ["foo", "bar"]);
+/// # }
+/// ```
+///
+pub trait MemoizeYoke<C: Deref>: for<'a> Yokeable<'a> {
+    fn memoize(p: &C::Target) -> <Self as Yokeable<'_>>::Output;
+}
+
+impl<Y, C> Memoize for Yoke<Y, C>
+where
+    Y: MemoizeYoke<C>,
+    C: StableDeref + Clone,
+    C::Target: 'static,
+{
+    type Param = C;
+
+    fn memoize(cart: &C) -> Self {
+        Yoke::attach_to_cart(cart.clone(), |p| Y::memoize(p))
+    }
+}
@@ -0,0 +1,102 @@
+use core::borrow::Borrow;
+
+use zeroize::{Zeroize, Zeroizing};
+
+use {Memo, MemoExt, MemoMut, MemoOnce, Memoize};
+
+/// Memoize sensitive values, which are wiped from memory when discarded
+///
+/// Requires the `zeroize` feature.
+///
+/// With this, you can use `Zeroizing<T>` as the value type of any of the
+/// memoization types, for any `T` which is `Zeroize`. The computation is the
+/// same as for `T`. When the cached value is cleared, recomputed, or dropped
+/// together with the memo, it is zeroized, instead of just dropped.
+///
+/// The value may still be moved (and therefore copied) while it is being
+/// computed and stored. See the `zeroize` documentation for the caveats.
+///
+/// ## Example
+///
+/// ```
+/// extern crate zeroize;
+/// # extern crate core_memo;
+///
+/// use core_memo::{Memoize, Memo};
+/// use zeroize::{Zeroize, Zeroizing};
+///
+/// struct Key([u8; 4]);
+///
+/// impl Zeroize for Key {
+///     fn zeroize(&mut self) {
+///         self.0.zeroize();
+///     }
+/// }
+///
+/// impl Memoize for Key {
+///     type Param = u32;
+///     fn memoize(p: &u32) -> Self {
+///         Key(p.to_le_bytes())
+///     }
+/// }
+///
+/// # fn main() {
+/// let mut memo: Memo<Zeroizing<Key>> = Memo::new(0x04030201);
+/// assert_eq!(memo.get().0, [1, 2, 3, 4]);
+///
+/// // the old key is wiped, before being dropped
+/// memo.clear();
+/// # }
+/// ```
+///
+impl<T: Memoize + Zeroize> Memoize for Zeroizing<T> {
+    type Param = T::Param;
+
+    const COST: u32 = T::COST;
+
+    fn memoize(p: &T::Param) -> Self {
+        Zeroizing::new(T::memoize(p))
+    }
+
+    fn cost(p: &T::Param) -> u32 {
+        T::cost(p)
+    }
+}
+
+/// Wipes the cached value and clears it
+///
+/// Requires the `zeroize` feature.
+impl<T: Memoize + Zeroize> Zeroize for MemoExt<T> {
+    fn zeroize(&mut self) {
+        self.value.zeroize();
+    }
+}
+
+/// Wipes the cached value and clears it
+///
+/// Requires the `zeroize` feature.
+///
+/// The parameter is left untouched.
+impl<T: Memoize + Zeroize, P: Borrow<T::Param>> Zeroize for Memo<T, P> {
+    fn zeroize(&mut self) {
+        self.value.zeroize();
+    }
+}
+
+/// Wipes the cached value and clears it
+///
+/// Requires the `zeroize` feature.
+impl<'p, T: Memoize + Zeroize, R> Zeroize for MemoOnce<'p, T, R> {
+    fn zeroize(&mut self) {
+        self.value.zeroize();
+    }
+}
+
+/// Wipes the cached value and clears it
+///
+/// Requires the `zeroize` feature.
+impl<'p, T: Memoize + Zeroize> Zeroize for MemoMut<'p, T> {
+    fn zeroize(&mut self) {
+        self.value.zeroize();
+    }
+}